@@ -1,8 +1,14 @@
 CREATE SCHEMA IF NOT EXISTS kickstarter;
 
+-- Unsubscribe tokens are drawn from pgcrypto's cryptographically secure gen_random_bytes.
+CREATE EXTENSION IF NOT EXISTS pgcrypto;
+
 -- Use line breaks to delineate between SQL commands; this allows the ksr program
 -- to read each command and run them when the --build flag is passed in.
 
+-- Every object lives in the kickstarter schema. When building a different schema,
+-- ksr replaces each whole-word occurrence of kickstarter with the configured name.
+
 -- Only use these domains when the ORM model generator supports domain interpretation.
 -- Otherwise, use _alnum and _numtext check constraints.
 
@@ -11,18 +17,38 @@ CREATE SCHEMA IF NOT EXISTS kickstarter;
 
 -- TODO: Add numeric(30,2), decimal(30,2) or money type support to rust-postgres to replace DOUBLE.
 
--- User:    indexes on user_id and name.
+-- User:    indexes on user_id, name, email and unsubscribe_token.
 -- Project: indexes on project_id and name.
 -- Pledge:  indexes on user_id, project_id, and card.
+-- Reward:  indexes on reward_id and (project_id, title).
+-- Comment: indexes on comment_id.
+-- Refund:  indexes on refund_id.
+-- Audit:   indexes on audit_id, (entity, entity_id), actor and date_created.
+-- Event:   indexes on event_id and date_created.
+-- Webhook: indexes on webhook_id and project_id.
+-- Webhook delivery: indexes on delivery_id and (status, next_attempt).
+-- Webhook attempt:  indexes on attempt_id and delivery_id.
+-- Mail:    indexes on mail_id and status.
+-- Mail preference: indexes on (user_id, kind).
+-- Token:   indexes on token_id, digest and user_id.
 
 CREATE TABLE IF NOT EXISTS kickstarter.user (
     user_id SERIAL NOT NULL,
     name text NOT NULL,
     date_created timestamp DEFAULT localtimestamp NOT NULL,
+    role text DEFAULT 'backer' NOT NULL,
+    email text,
+    unsubscribe_token text DEFAULT encode(gen_random_bytes(16), 'hex') NOT NULL,
+    password_hash text,
+    shipping_address text,
     PRIMARY KEY (user_id),
     CONSTRAINT user_name_uniq UNIQUE (name),
+    CONSTRAINT user_email_uniq UNIQUE (email),
+    CONSTRAINT user_unsubscribe_token_uniq UNIQUE (unsubscribe_token),
+    CONSTRAINT user_role_chk CHECK (role IN ('backer', 'creator', 'moderator', 'admin')),
     CONSTRAINT user_name_alnum_chk CHECK (name ~ '^[a-zA-Z0-9_-]+$'),
-    CONSTRAINT user_name_length_chk CHECK (char_length(name) >= 4 AND char_length(name) <= 20)
+    CONSTRAINT user_name_length_chk CHECK (char_length(name) >= 4 AND char_length(name) <= 20),
+    CONSTRAINT user_shipping_address_length_chk CHECK (char_length(shipping_address) >= 1 AND char_length(shipping_address) <= 500)
 );
 
 CREATE TABLE IF NOT EXISTS kickstarter.project (
@@ -30,10 +56,14 @@ CREATE TABLE IF NOT EXISTS kickstarter.project (
     name text NOT NULL,
     goal double precision NOT NULL,
     date_created timestamp DEFAULT localtimestamp NOT NULL,
+    comments_backers_only boolean DEFAULT false NOT NULL,
+    creator_id integer,
+    date_closed timestamp,
     PRIMARY KEY (project_id),
     CONSTRAINT project_name_uniq UNIQUE (name),
     CONSTRAINT project_name_alnum_chk CHECK (name ~ '^[a-zA-Z0-9_-]+$'),
-    CONSTRAINT project_name_length_chk CHECK (char_length(name) >= 4 AND char_length(name) <= 20)
+    CONSTRAINT project_name_length_chk CHECK (char_length(name) >= 4 AND char_length(name) <= 20),
+    CONSTRAINT project_creator_fkey FOREIGN KEY ("creator_id") REFERENCES kickstarter.user ("user_id") ON DELETE SET NULL
 );
 
 CREATE TABLE IF NOT EXISTS kickstarter.pledge (
@@ -42,7 +72,9 @@ CREATE TABLE IF NOT EXISTS kickstarter.pledge (
     amount double precision NOT NULL,
     card text NOT NULL,
     date_created timestamp DEFAULT localtimestamp NOT NULL,
+    refunded double precision DEFAULT 0 NOT NULL,
     PRIMARY KEY (user_id, project_id),
+    CONSTRAINT pledge_refunded_chk CHECK (refunded >= 0 AND refunded <= amount),
     CONSTRAINT pledge_card_numtext_chk CHECK (card ~ '^[0-9]+$'),
     CONSTRAINT pledge_card_length_chk CHECK (char_length(card) <= 19),
     CONSTRAINT pledge_project_card UNIQUE (project_id, card),
@@ -50,7 +82,246 @@ CREATE TABLE IF NOT EXISTS kickstarter.pledge (
     CONSTRAINT pledge_project_fkey FOREIGN KEY ("project_id") REFERENCES kickstarter.project ("project_id") ON DELETE CASCADE
 );
 
-CREATE OR REPLACE FUNCTION upsert_user(_name text) RETURNS integer AS $$
+-- A project's reward tiers. Backers are owed the richest tier that what remains
+-- of their pledge covers.
+CREATE TABLE IF NOT EXISTS kickstarter.reward (
+    reward_id SERIAL NOT NULL,
+    project_id integer NOT NULL,
+    title text NOT NULL,
+    minimum double precision NOT NULL,
+    date_created timestamp DEFAULT localtimestamp NOT NULL,
+    PRIMARY KEY (reward_id),
+    CONSTRAINT reward_project_title UNIQUE (project_id, title),
+    CONSTRAINT reward_minimum_chk CHECK (minimum > 0),
+    CONSTRAINT reward_title_length_chk CHECK (char_length(title) >= 1 AND char_length(title) <= 100),
+    CONSTRAINT reward_project_fkey FOREIGN KEY ("project_id") REFERENCES kickstarter.project ("project_id") ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS kickstarter.comment (
+    comment_id SERIAL NOT NULL,
+    project_id integer NOT NULL,
+    user_id integer NOT NULL,
+    parent_id integer,
+    body text NOT NULL,
+    hidden boolean DEFAULT false NOT NULL,
+    flagged boolean DEFAULT false NOT NULL,
+    deleted boolean DEFAULT false NOT NULL,
+    date_created timestamp DEFAULT localtimestamp NOT NULL,
+    date_edited timestamp,
+    PRIMARY KEY (comment_id),
+    CONSTRAINT comment_body_length_chk CHECK (char_length(body) >= 1 AND char_length(body) <= 2000),
+    CONSTRAINT comment_user_fkey FOREIGN KEY ("user_id") REFERENCES kickstarter.user ("user_id") ON DELETE CASCADE,
+    CONSTRAINT comment_project_fkey FOREIGN KEY ("project_id") REFERENCES kickstarter.project ("project_id") ON DELETE CASCADE,
+    CONSTRAINT comment_parent_fkey FOREIGN KEY ("parent_id") REFERENCES kickstarter.comment ("comment_id") ON DELETE CASCADE
+);
+
+-- Refunds and chargebacks are kept even if the pledge is later removed, so that
+-- every movement of money remains auditable.
+CREATE TABLE IF NOT EXISTS kickstarter.refund (
+    refund_id SERIAL NOT NULL,
+    user_id integer NOT NULL,
+    project_id integer NOT NULL,
+    amount double precision NOT NULL,
+    kind text NOT NULL,
+    reason text NOT NULL,
+    reference text,
+    date_created timestamp DEFAULT localtimestamp NOT NULL,
+    PRIMARY KEY (refund_id),
+    CONSTRAINT refund_amount_chk CHECK (amount > 0),
+    CONSTRAINT refund_kind_chk CHECK (kind IN ('refund', 'chargeback')),
+    CONSTRAINT refund_reason_chk CHECK (reason IN ('requested', 'duplicate', 'fraudulent', 'cancelled', 'disputed', 'other')),
+    CONSTRAINT refund_user_fkey FOREIGN KEY ("user_id") REFERENCES kickstarter.user ("user_id") ON DELETE CASCADE,
+    CONSTRAINT refund_project_fkey FOREIGN KEY ("project_id") REFERENCES kickstarter.project ("project_id") ON DELETE CASCADE
+);
+
+-- Every mutation appends a row here in the same transaction as the change.
+-- Values are JSON encodings of the affected row before and after the change.
+CREATE TABLE IF NOT EXISTS kickstarter.audit (
+    audit_id SERIAL NOT NULL,
+    actor text NOT NULL,
+    action text NOT NULL,
+    entity text NOT NULL,
+    entity_id text NOT NULL,
+    before_value text,
+    after_value text,
+    date_created timestamp DEFAULT localtimestamp NOT NULL,
+    PRIMARY KEY (audit_id)
+);
+
+CREATE INDEX audit_entity_idx ON kickstarter.audit (entity, entity_id);
+
+CREATE INDEX audit_actor_idx ON kickstarter.audit (actor);
+
+CREATE INDEX audit_date_created_idx ON kickstarter.audit (date_created);
+
+-- The audit log is append-only: reject any attempt to rewrite history.
+CREATE OR REPLACE FUNCTION kickstarter.audit_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'The audit log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_append_only BEFORE UPDATE OR DELETE ON kickstarter.audit
+    FOR EACH ROW EXECUTE PROCEDURE kickstarter.audit_append_only();
+
+-- Domain events are the source of truth for users, projects, pledges and refunds;
+-- those tables are projections that can be rebuilt by replaying this log.
+CREATE TABLE IF NOT EXISTS kickstarter.event (
+    event_id SERIAL NOT NULL,
+    kind text NOT NULL,
+    payload text NOT NULL,
+    date_created timestamp DEFAULT localtimestamp NOT NULL,
+    PRIMARY KEY (event_id)
+);
+
+CREATE INDEX event_date_created_idx ON kickstarter.event (date_created);
+
+-- Webhooks are registered for a single project, or for every project when project_id is null.
+CREATE TABLE IF NOT EXISTS kickstarter.webhook (
+    webhook_id SERIAL NOT NULL,
+    project_id integer,
+    url text NOT NULL,
+    secret text NOT NULL,
+    active boolean DEFAULT true NOT NULL,
+    date_created timestamp DEFAULT localtimestamp NOT NULL,
+    PRIMARY KEY (webhook_id),
+    CONSTRAINT webhook_url_chk CHECK (url ~ '^https?://'),
+    CONSTRAINT webhook_project_fkey FOREIGN KEY ("project_id") REFERENCES kickstarter.project ("project_id") ON DELETE CASCADE
+);
+
+CREATE INDEX webhook_project_idx ON kickstarter.webhook (project_id);
+
+-- The webhook outbox: deliveries are queued in the same transaction as the change they
+-- describe, and are sent later with exponential backoff until they succeed or give up.
+CREATE TABLE IF NOT EXISTS kickstarter.webhook_delivery (
+    delivery_id SERIAL NOT NULL,
+    webhook_id integer NOT NULL,
+    event text NOT NULL,
+    payload text NOT NULL,
+    status text DEFAULT 'pending' NOT NULL,
+    attempts integer DEFAULT 0 NOT NULL,
+    next_attempt timestamp DEFAULT localtimestamp NOT NULL,
+    date_created timestamp DEFAULT localtimestamp NOT NULL,
+    date_delivered timestamp,
+    PRIMARY KEY (delivery_id),
+    CONSTRAINT webhook_delivery_status_chk CHECK (status IN ('pending', 'delivered', 'failed')),
+    CONSTRAINT webhook_delivery_webhook_fkey FOREIGN KEY ("webhook_id") REFERENCES kickstarter.webhook ("webhook_id") ON DELETE CASCADE
+);
+
+CREATE INDEX webhook_delivery_pending_idx ON kickstarter.webhook_delivery (status, next_attempt);
+
+-- Every attempt to send a delivery, successful or not.
+CREATE TABLE IF NOT EXISTS kickstarter.webhook_attempt (
+    attempt_id SERIAL NOT NULL,
+    delivery_id integer NOT NULL,
+    status_code integer,
+    error text,
+    date_created timestamp DEFAULT localtimestamp NOT NULL,
+    PRIMARY KEY (attempt_id),
+    CONSTRAINT webhook_attempt_delivery_fkey FOREIGN KEY ("delivery_id") REFERENCES kickstarter.webhook_delivery ("delivery_id") ON DELETE CASCADE
+);
+
+CREATE INDEX webhook_attempt_delivery_idx ON kickstarter.webhook_attempt (delivery_id);
+
+-- The mail outbox: messages are rendered and queued in the same transaction as the change
+-- that prompted them, and are sent later through the configured transport.
+CREATE TABLE IF NOT EXISTS kickstarter.mail (
+    mail_id SERIAL NOT NULL,
+    user_id integer NOT NULL,
+    kind text NOT NULL,
+    recipient text NOT NULL,
+    subject text NOT NULL,
+    body_text text NOT NULL,
+    body_html text NOT NULL,
+    status text DEFAULT 'pending' NOT NULL,
+    attempts integer DEFAULT 0 NOT NULL,
+    error text,
+    date_created timestamp DEFAULT localtimestamp NOT NULL,
+    date_sent timestamp,
+    PRIMARY KEY (mail_id),
+    CONSTRAINT mail_status_chk CHECK (status IN ('pending', 'sent', 'failed')),
+    CONSTRAINT mail_user_fkey FOREIGN KEY ("user_id") REFERENCES kickstarter.user ("user_id") ON DELETE CASCADE
+);
+
+CREATE INDEX mail_status_idx ON kickstarter.mail (status);
+
+-- Users receive every kind of mail unless they have turned it off here.
+CREATE TABLE IF NOT EXISTS kickstarter.mail_preference (
+    user_id integer NOT NULL,
+    kind text NOT NULL,
+    enabled boolean DEFAULT true NOT NULL,
+    PRIMARY KEY (user_id, kind),
+    CONSTRAINT mail_preference_user_fkey FOREIGN KEY ("user_id") REFERENCES kickstarter.user ("user_id") ON DELETE CASCADE
+);
+
+-- API tokens issued when a user logs in. Only a SHA-256 digest of each token is stored,
+-- so a leaked table cannot be used to sign in.
+CREATE TABLE IF NOT EXISTS kickstarter.token (
+    token_id SERIAL NOT NULL,
+    user_id integer NOT NULL,
+    digest text NOT NULL,
+    date_created timestamp DEFAULT localtimestamp NOT NULL,
+    date_expires timestamp NOT NULL,
+    date_used timestamp,
+    date_revoked timestamp,
+    PRIMARY KEY (token_id),
+    CONSTRAINT token_digest_uniq UNIQUE (digest),
+    CONSTRAINT token_user_fkey FOREIGN KEY ("user_id") REFERENCES kickstarter.user ("user_id") ON DELETE CASCADE
+);
+
+CREATE INDEX token_user_idx ON kickstarter.token (user_id);
+
+-- Changes to projects and pledges are announced on a channel named after the schema,
+-- so that dashboards can follow them live with LISTEN instead of polling.
+CREATE OR REPLACE FUNCTION kickstarter.notify_project() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('kickstarter', json_build_object(
+        'event', CASE TG_OP WHEN 'INSERT' THEN 'ProjectCreated' ELSE 'ProjectUpdated' END,
+        'project', NEW.name,
+        'goal', NEW.goal)::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER project_notify AFTER INSERT OR UPDATE ON kickstarter.project
+    FOR EACH ROW EXECUTE PROCEDURE kickstarter.notify_project();
+
+CREATE OR REPLACE FUNCTION kickstarter.notify_pledge() RETURNS trigger AS $$
+DECLARE
+    _row kickstarter.pledge;
+    _event text;
+    _amount double precision;
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        _row := NEW;
+        _event := 'PledgeCreated';
+        _amount := NEW.amount - NEW.refunded;
+    ELSIF TG_OP = 'UPDATE' THEN
+        _row := NEW;
+        _event := 'PledgeRefunded';
+        _amount := NEW.refunded - OLD.refunded;
+    ELSE
+        _row := OLD;
+        _event := 'PledgeCancelled';
+        _amount := OLD.amount - OLD.refunded;
+    END IF;
+    -- Only changes that move money are worth announcing.
+    IF TG_OP = 'UPDATE' AND _amount = 0 THEN
+        RETURN NULL;
+    END IF;
+    PERFORM pg_notify('kickstarter', json_build_object(
+        'event', _event,
+        'project', (SELECT name FROM kickstarter.project WHERE project_id = _row.project_id),
+        'user', (SELECT name FROM kickstarter.user WHERE user_id = _row.user_id),
+        'amount', _amount)::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER pledge_notify AFTER INSERT OR UPDATE OR DELETE ON kickstarter.pledge
+    FOR EACH ROW EXECUTE PROCEDURE kickstarter.notify_pledge();
+
+CREATE OR REPLACE FUNCTION kickstarter.upsert_user(_name text) RETURNS integer AS $$
 DECLARE
     return_id integer;
 BEGIN
@@ -0,0 +1,393 @@
+//! Module for user accounts, passwords and API tokens.
+//!
+//! Users register with an email address and a password, which is stored as a bcrypt hash.
+//! Logging in issues a random API token that is shown once; only its SHA-256 digest is kept.
+//! Presenting a token that has not expired or been revoked yields a `Principal`, which the
+//! client requires for anything done on a user's behalf.
+//!
+//! Each user has a role, which decides what the client lets their principal do. Roles are
+//! ordered, and each includes everything allowed to the roles below it:
+//!
+//! * `backer` may back projects, comment, and manage their own pledges and mail.
+//! * `creator` may also create projects, and close, refund and moderate their own projects.
+//! * `moderator` may also hide and flag any comment, and restrict comments on any project.
+//! * `admin` may do anything, including rebuilding the database, replaying events,
+//!   managing webhooks and mail, reading the audit log and assigning roles.
+//!
+//! Every registered account starts as a backer. The first admin of a schema is registered
+//! separately with `register_admin`, which only succeeds while the schema has no admin.
+pub use models::Token;
+
+use {validate, Client, Error, Result};
+use db::{column, table};
+use event::{self, DomainEvent};
+use models::{Audit, Event, Project, User};
+
+use crypto::bcrypt::bcrypt;
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+use crypto::util::fixed_time_eq;
+use rand::{OsRng, Rng};
+use rustc_serialize::hex::{FromHex, ToHex};
+use rustc_serialize::json::ToJson;
+use rustorm::dao::{IsDao, Value};
+use rustorm::query::{Equality, Query};
+
+use std::convert::From;
+use std::fmt;
+use std::str::FromStr;
+
+/// The bcrypt work factor used for new password hashes. Each increment doubles the work.
+pub const BCRYPT_COST: u32 = 10;
+/// Number of days that a token stays valid after it is issued.
+pub const TOKEN_LIFETIME: i32 = 30;
+
+/// Prefix identifying the scheme of a stored password hash.
+const HASH_SCHEME: &'static str = "bcrypt";
+const SALT_LEN: usize = 16;
+const HASH_LEN: usize = 24;
+const TOKEN_LEN: usize = 32;
+
+const ERR_ADMIN_EXISTS: &'static str = "The database already has an admin, who can give other accounts the admin role.";
+
+/// What a user is allowed to do. Each role includes the permissions of those before it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Backer,
+    Creator,
+    Moderator,
+    Admin,
+}
+
+impl Role {
+    /// Returns the code stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Role::Backer    => "backer",
+            Role::Creator   => "creator",
+            Role::Moderator => "moderator",
+            Role::Admin     => "admin",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = validate::Error;
+
+    fn from_str(s: &str) -> validate::Result<Role> {
+        match s {
+            "backer"    => Ok(Role::Backer),
+            "creator"   => Ok(Role::Creator),
+            "moderator" => Ok(Role::Moderator),
+            "admin"     => Ok(Role::Admin),
+            _           => Err(validate::Error::InvalidRole(s.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.as_str())
+    }
+}
+
+/// An authenticated user, on whose behalf the client acts.
+/// Principals can only be obtained by logging in or presenting a valid token.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Principal {
+    user_id: i32,
+    name: String,
+    role: Role,
+}
+
+impl Principal {
+
+    /// Returns the ID of the authenticated user.
+    pub fn user_id(&self) -> i32 {
+        self.user_id
+    }
+
+    /// Returns the name of the authenticated user.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the authenticated user's role.
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    /// Returns true if the authenticated user is a moderator or admin.
+    pub fn is_moderator(&self) -> bool {
+        self.role >= Role::Moderator
+    }
+
+    /// Returns true if the authenticated user is an admin.
+    pub fn is_admin(&self) -> bool {
+        self.role == Role::Admin
+    }
+
+    /// Ensures that the principal has at least the provided role.
+    pub fn require(&self, role: Role) -> Result<()> {
+        if self.role >= role {
+            Ok(())
+        } else {
+            Err(Error::PermissionDenied(format!("{} needs the {} role to do that.", self.name, role)))
+        }
+    }
+
+    /// Ensures that the principal created the project, or has at least the provided role.
+    pub fn require_creator(&self, project: &Project, role: Role) -> Result<()> {
+        if project.creator_id == Some(self.user_id) || self.role >= role {
+            Ok(())
+        } else {
+            Err(Error::PermissionDenied(format!("Only the creator of {} or a user with the {} role can do that.",
+                                                project.name, role)))
+        }
+    }
+
+    fn from_user(user: &User) -> Principal {
+        Principal {
+            user_id: user.user_id,
+            name: user.name.clone(),
+            role: user.role(),
+        }
+    }
+}
+
+/// Hashes a password with bcrypt and a random salt, in the form stored for users.
+pub fn hash_password(password: &str) -> Result<String> {
+    try!(validate::password(password));
+
+    let mut salt = [0u8; SALT_LEN];
+    try!(OsRng::new()).fill_bytes(&mut salt);
+
+    let mut hash = [0u8; HASH_LEN];
+    bcrypt(BCRYPT_COST, &salt, password.as_bytes(), &mut hash);
+    Ok(format!("{}${}${}${}", HASH_SCHEME, BCRYPT_COST, salt.to_hex(), hash.to_hex()))
+}
+
+/// Returns true if the password matches the stored hash.
+pub fn verify_password(password: &str, stored: &str) -> bool {
+    if validate::password(password).is_err() {
+        return false;
+    }
+
+    let parts: Vec<&str> = stored.split('$').collect();
+    if parts.len() != 4 || parts[0] != HASH_SCHEME {
+        return false;
+    }
+
+    let cost = match parts[1].parse::<u32>() {
+        Ok(cost) if cost >= 4 && cost <= 31 => cost,
+        _ => return false,
+    };
+
+    let (salt, expected) = match (parts[2].from_hex(), parts[3].from_hex()) {
+        (Ok(salt), Ok(expected)) => (salt, expected),
+        _ => return false,
+    };
+
+    if salt.len() != SALT_LEN || expected.len() != HASH_LEN {
+        return false;
+    }
+
+    let mut hash = [0u8; HASH_LEN];
+    bcrypt(cost, &salt, password.as_bytes(), &mut hash);
+    fixed_time_eq(&hash, &expected)
+}
+
+/// Returns the digest under which a token is stored.
+fn digest(token: &str) -> String {
+    let mut sha = Sha256::new();
+    sha.input_str(token);
+    sha.result_str()
+}
+
+impl User {
+
+    /// Registers a new backer account with a unique name and email address.
+    pub fn register(client: &Client, user: &str, email: &str, password: &str) -> Result<User> {
+        User::create_account(client, user, email, password, Role::Backer)
+    }
+
+    /// Registers the first admin of a schema, such as one that was just built.
+    /// Fails if the schema already has an admin, who can grant the role to others instead.
+    pub fn register_admin(client: &Client, user: &str, email: &str, password: &str) -> Result<User> {
+        User::create_account(client, user, email, password, Role::Admin)
+    }
+
+    fn create_account(client: &Client, user: &str, email: &str, password: &str, role: Role) -> Result<User> {
+        try!(validate::length(user, 4, 20));
+        try!(validate::alphanumeric(user));
+        try!(validate::email(email));
+        let password_hash = try!(hash_password(password));
+
+        let sql = format!("SELECT * FROM {} WHERE name = $1 OR email = $2", client.table(table::user));
+
+        client.transaction(|| {
+            if role == Role::Admin {
+                // Concurrent bootstraps wait here until the first commits, so only one finds no admin.
+                let lock = format!("LOCK TABLE {} IN SHARE ROW EXCLUSIVE MODE", client.table(table::user));
+                try!(client.db().execute_sql(&lock, &vec![]));
+                if try!(User::has_admin(client)) {
+                    return Err(Error::PermissionDenied(ERR_ADMIN_EXISTS.to_owned()));
+                }
+            }
+
+            let daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::String(user.to_owned()),
+                                                                             Value::String(email.to_owned())]));
+            if let Some(existing) = daos.iter().map(User::from_dao).next() {
+                let err = if existing.name == user { validate::Error::UserExists } else { validate::Error::EmailExists };
+                return Err(From::from(err));
+            }
+
+            let account: User = try!(Query::insert()
+                .set(column::name, &user)
+                .set(column::email, &email)
+                .set(column::password_hash, &password_hash)
+                .set(column::role, &role.as_str())
+                .into_table(&client.table(table::user))
+                .return_all()
+                .collect_one(client.db()));
+
+            try!(Audit::record(client, "register", table::user, &account.user_id.to_string(),
+                               None, Some(account.redacted_json())));
+            try!(Event::record(client, DomainEvent::UserCreated(event::UserCreated {
+                user_id: account.user_id,
+                name: account.name.clone(),
+                password_hash: account.password_hash.clone(),
+            })));
+            try!(Event::record(client, DomainEvent::EmailChanged(event::EmailChanged {
+                user_id: account.user_id,
+                email: account.email.clone(),
+            })));
+            try!(Event::record(client, DomainEvent::RoleChanged(event::RoleChanged {
+                user_id: account.user_id,
+                role: account.role.clone(),
+            })));
+            Ok(account)
+        })
+    }
+}
+
+impl Token {
+
+    /// Checks a user's password and issues a new token for them.
+    /// Returns the principal and the token, which cannot be retrieved again.
+    pub fn login(client: &Client, user: &str, password: &str) -> Result<(Principal, String)> {
+        // Unknown users and wrong passwords are indistinguishable to the caller.
+        let user = match User::get(client, user) {
+            Ok(user) => user,
+            Err(_) => return Err(From::from(validate::Error::InvalidCredentials)),
+        };
+
+        let verified = match user.password_hash {
+            Some(ref hash) => verify_password(password, hash),
+            None => false,
+        };
+
+        if !verified {
+            return Err(From::from(validate::Error::InvalidCredentials));
+        }
+
+        let (_, token) = try!(Token::issue(client, &user));
+        Ok((Principal::from_user(&user), token))
+    }
+
+    /// Issues a new token for the user, valid for `TOKEN_LIFETIME` days.
+    /// Returns the stored record and the token itself.
+    pub fn issue(client: &Client, user: &User) -> Result<(Token, String)> {
+        let mut bytes = [0u8; TOKEN_LEN];
+        try!(OsRng::new()).fill_bytes(&mut bytes);
+        let secret = bytes.to_hex();
+
+        let sql = format!("INSERT INTO {} (user_id, digest, date_expires) \
+                           VALUES ($1, $2, localtimestamp + $3 * interval '1 day') RETURNING *",
+                          client.table(table::token));
+
+        client.transaction(|| {
+            let mut daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::I32(user.user_id),
+                                                                                 Value::String(digest(&secret)),
+                                                                                 Value::I32(TOKEN_LIFETIME)]));
+            let token = match daos.pop() {
+                Some(dao) => Token::from_dao(&dao),
+                None => return Err(From::from(validate::Error::UserDoesNotExist)),
+            };
+
+            try!(Audit::record(client, "login", table::token, &token.token_id.to_string(),
+                               None, Some(token.to_json())));
+            Ok((token, secret.clone()))
+        })
+    }
+
+    /// Returns the principal that a token was issued to, if it has not expired or been revoked.
+    pub fn authenticate(client: &Client, secret: &str) -> Result<Principal> {
+        let sql = format!("UPDATE {} SET date_used = localtimestamp \
+                           WHERE digest = $1 AND date_revoked IS NULL AND date_expires > localtimestamp \
+                           RETURNING *", client.table(table::token));
+
+        let mut daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::String(digest(secret))]));
+        let token = match daos.pop() {
+            Some(dao) => Token::from_dao(&dao),
+            None => return Err(From::from(validate::Error::InvalidSession)),
+        };
+
+        let mut users: Vec<User> = try!(Query::select_all()
+            .from_table(&client.table(table::user))
+            .filter(column::user_id, Equality::EQ, &token.user_id)
+            .collect(client.db()));
+
+        match users.pop() {
+            Some(user) => Ok(Principal::from_user(&user)),
+            None => Err(From::from(validate::Error::InvalidSession)),
+        }
+    }
+
+    /// Revokes a token so that it can no longer be used.
+    pub fn revoke(client: &Client, secret: &str) -> Result<Token> {
+        let sql = format!("UPDATE {} SET date_revoked = localtimestamp \
+                           WHERE digest = $1 AND date_revoked IS NULL RETURNING *",
+                          client.table(table::token));
+
+        client.transaction(|| {
+            let mut daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::String(digest(secret))]));
+            let token = match daos.pop() {
+                Some(dao) => Token::from_dao(&dao),
+                None => return Err(From::from(validate::Error::InvalidSession)),
+            };
+
+            try!(Audit::record(client, "revoke", table::token, &token.token_id.to_string(),
+                               None, Some(token.to_json())));
+            Ok(token)
+        })
+    }
+
+    /// Revokes every token issued to the principal. Returns the revoked tokens.
+    pub fn revoke_all(client: &Client, principal: &Principal) -> Result<Vec<Token>> {
+        let sql = format!("UPDATE {} SET date_revoked = localtimestamp \
+                           WHERE user_id = $1 AND date_revoked IS NULL RETURNING *",
+                          client.table(table::token));
+
+        client.transaction(|| {
+            let daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::I32(principal.user_id)]));
+            let tokens: Vec<Token> = daos.iter().map(Token::from_dao).collect();
+
+            for token in &tokens {
+                try!(Audit::record(client, "revoke", table::token, &token.token_id.to_string(),
+                                   None, Some(token.to_json())));
+            }
+            Ok(tokens)
+        })
+    }
+
+    /// Returns the principal's tokens that have not expired or been revoked, oldest first.
+    pub fn list(client: &Client, principal: &Principal) -> Result<Vec<Token>> {
+        let sql = format!("SELECT * FROM {} WHERE user_id = $1 AND date_revoked IS NULL \
+                           AND date_expires > localtimestamp ORDER BY token_id",
+                          client.table(table::token));
+
+        let daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::I32(principal.user_id)]));
+        Ok(daos.iter().map(Token::from_dao).collect())
+    }
+}
@@ -0,0 +1,469 @@
+//! Module for serving projects, pledges and users as a JSON REST API.
+//!
+//! Every request is answered by a `Client` operation, so the API enforces the same
+//! validations and permissions as the library. Requests that act on a user's behalf
+//! present the API token from `POST /tokens` as a bearer token:
+//!
+//! ```text
+//! Authorization: Bearer <token>
+//! ```
+//!
+//! Responses are JSON. Errors have the form `{"error": ..., "status": ...}`, with a status
+//! code chosen by `status`. Lists are paginated with the `offset` and `limit` query parameters,
+//! and wrapped as `{"items": [...], "offset": ..., "limit": ..., "total": ...}`.
+//! The routes are described by the OpenAPI document served at `/openapi.json`.
+//!
+//! `POST /graphql` runs GraphQL documents from `{"query": ..., "variables": ..., "operationName": ...}`
+//! against the `graphql` module, answering with its response format rather than the one above.
+use {graphql, validate, Client, Error, Result};
+use account::Principal;
+use config::Config;
+use models::{Pledge, Project, User};
+
+use hyper::{self, Server};
+use hyper::method::Method;
+use hyper::server::{Listening, Request, Response};
+use hyper::status::StatusCode;
+use hyper::uri::RequestUri;
+use postgres::error::SqlState;
+use rustc_serialize::json::{self, Json, ToJson};
+
+use std::cmp;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::sync::Mutex;
+
+/// The OpenAPI description of every route.
+pub const OPENAPI: &'static str = include_str!("../data/openapi.json");
+
+/// Number of items returned in a page when no limit is requested.
+pub const DEFAULT_LIMIT: usize = 20;
+/// Largest number of items returned in a single page.
+pub const MAX_LIMIT:     usize = 100;
+/// Largest request body read, in bytes. Larger requests are refused without reading the rest.
+pub const MAX_BODY:      u64 = 1 << 20;
+
+/// A response to an API request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reply {
+    pub status: StatusCode,
+    /// The JSON body, or Null for responses without one.
+    pub body: Json,
+}
+
+/// The body of `POST /users`.
+#[derive(RustcDecodable)]
+struct NewUser {
+    name: String,
+    email: String,
+    password: String,
+}
+
+/// The body of `POST /tokens`.
+#[derive(RustcDecodable)]
+struct Credentials {
+    name: String,
+    password: String,
+}
+
+/// The body of `POST /projects`.
+#[derive(RustcDecodable)]
+struct NewProject {
+    name: String,
+    goal: f64,
+}
+
+/// The body of `POST /projects/{name}/pledges`.
+#[derive(RustcDecodable)]
+struct NewPledge {
+    card: String,
+    amount: f64,
+}
+
+/// Returns the HTTP status that describes an error.
+pub fn status(err: &Error) -> StatusCode {
+    match *err {
+        Error::InvalidData(ref inner) => match *inner {
+            validate::Error::ProjectDoesNotExist |
+            validate::Error::UserDoesNotExist    |
+            validate::Error::PledgeDoesNotExist  |
+            validate::Error::CommentDoesNotExist |
+            validate::Error::WebhookDoesNotExist => StatusCode::NotFound,
+            validate::Error::UserExists     |
+            validate::Error::EmailExists    |
+            validate::Error::CardExists     |
+            validate::Error::RewardExists   |
+            validate::Error::ProjectClosed  |
+            validate::Error::SchemaNotEmpty |
+            validate::Error::SchemaDrift(_) |
+            validate::Error::ConcurrentChange => StatusCode::Conflict,
+            validate::Error::InvalidCredentials |
+            validate::Error::InvalidSession     |
+            validate::Error::NotAuthenticated   => StatusCode::Unauthorized,
+            validate::Error::NotCommentAuthor |
+            validate::Error::NotModerator     |
+            validate::Error::BackersOnly      => StatusCode::Forbidden,
+            _ => StatusCode::BadRequest,
+        },
+        Error::PermissionDenied(_) => StatusCode::Forbidden,
+        Error::Decode(_) => StatusCode::BadRequest,
+        Error::Database(ref inner) => match inner.code {
+            Some(SqlState::UniqueViolation) => StatusCode::Conflict,
+            _ => StatusCode::InternalServerError,
+        },
+        Error::Config(_) | Error::IO(_) => StatusCode::InternalServerError,
+    }
+}
+
+/// Answers a single request. The URI is the path and query string, and the token is
+/// the bearer token from the Authorization header, if one was sent.
+pub fn handle(client: &Client, method: &Method, uri: &str, token: Option<&str>, body: &str) -> Reply {
+    client.act_as(None);
+    let (path, query) = match uri.find('?') {
+        Some(i) => (&uri[..i], &uri[i + 1..]),
+        None => (uri, ""),
+    };
+
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let segment = |i: usize| segments.get(i).map(|s| *s);
+    if segments.len() > 4 {
+        return Reply::error(StatusCode::NotFound, "No such route.");
+    }
+
+    let result = match (method, segment(0), segment(1), segment(2), segment(3)) {
+        (&Method::Get, Some("openapi.json"), None, None, None) => {
+            Json::from_str(OPENAPI)
+                .map(|doc| Reply::new(StatusCode::Ok, doc))
+                .map_err(|err| Error::Config(err.to_string()))
+        }
+
+        (&Method::Post, Some("graphql"), None, None, None) => {
+            graphql_request(body).and_then(|(query, operation, variables)| {
+                let principal = match token {
+                    Some(token) => Some(try!(client.authenticate(token))),
+                    None => None,
+                };
+
+                let response = graphql::execute(client, principal.as_ref(), &query,
+                                                operation.as_ref().map(|o| &o[..]), &variables);
+                let status = if response.data == Json::Null { StatusCode::BadRequest } else { StatusCode::Ok };
+                Ok(Reply::new(status, response.to_json()))
+            })
+        }
+
+        (&Method::Post, Some("users"), None, None, None) => {
+            decode::<NewUser>(body).and_then(|new| {
+                let user = try!(client.register(&new.name, &new.email, &new.password));
+                Ok(Reply::new(StatusCode::Created, user_json(&user)))
+            })
+        }
+
+        (&Method::Get, Some("users"), Some(name), None, None) => {
+            client.get_user(name).map(|user| Reply::new(StatusCode::Ok, user_json(&user)))
+        }
+
+        (&Method::Get, Some("users"), Some(name), Some("pledges"), None) => {
+            page(query).and_then(|(offset, limit)| {
+                let (pledges, total) = try!(client.list_backed_projects_page(name, offset, limit));
+                let items = pledges.iter()
+                    .map(|p| pledge_json(p, name, &p.get_project().name))
+                    .collect();
+                Ok(Reply::page(items, offset, limit, total))
+            })
+        }
+
+        (&Method::Post, Some("tokens"), None, None, None) => {
+            decode::<Credentials>(body).and_then(|credentials| {
+                let (principal, token) = try!(client.login(&credentials.name, &credentials.password));
+                let mut json = BTreeMap::new();
+                json.insert("token".to_owned(), token.to_json());
+                json.insert("user".to_owned(), principal_json(&principal));
+                Ok(Reply::new(StatusCode::Created, Json::Object(json)))
+            })
+        }
+
+        (&Method::Delete, Some("tokens"), None, None, None) => {
+            authenticate(client, token)
+                .and_then(|_| client.logout(token.unwrap()))
+                .map(|_| Reply::new(StatusCode::NoContent, Json::Null))
+        }
+
+        (&Method::Get, Some("projects"), None, None, None) => {
+            page(query).and_then(|(offset, limit)| {
+                let (projects, total) = try!(client.list_projects_page(offset, limit));
+                let items = projects.iter().map(project_json).collect();
+                Ok(Reply::page(items, offset, limit, total))
+            })
+        }
+
+        (&Method::Post, Some("projects"), None, None, None) => {
+            authenticate(client, token).and_then(|principal| {
+                let new = try!(decode::<NewProject>(body));
+                let project = try!(client.create_project(&principal, &new.name, new.goal));
+                Ok(Reply::new(StatusCode::Created, project_json(&project)))
+            })
+        }
+
+        (&Method::Get, Some("projects"), Some(name), None, None) => {
+            client.get_project(name).and_then(|project| {
+                let (pledges, _) = try!(client.list_backers(name));
+                let pledged = pledges.iter().fold(0f64, |total, p| total + p.net_amount());
+
+                let mut json = project_json(&project);
+                if let Json::Object(ref mut map) = json {
+                    map.insert("pledged".to_owned(), pledged.to_json());
+                    map.insert("backers".to_owned(), pledges.len().to_json());
+                }
+                Ok(Reply::new(StatusCode::Ok, json))
+            })
+        }
+
+        (&Method::Get, Some("projects"), Some(name), Some("pledges"), None) => {
+            page(query).and_then(|(offset, limit)| {
+                let (pledges, total) = try!(client.list_backers_page(name, offset, limit));
+                let items = pledges.iter()
+                    .map(|p| pledge_json(p, &p.get_user().name, name))
+                    .collect();
+                Ok(Reply::page(items, offset, limit, total))
+            })
+        }
+
+        (&Method::Post, Some("projects"), Some(name), Some("pledges"), None) => {
+            authenticate(client, token).and_then(|principal| {
+                let new = try!(decode::<NewPledge>(body));
+                let pledge = try!(client.back_project(&principal, name, &new.card, new.amount));
+                Ok(Reply::new(StatusCode::Created, pledge_json(&pledge, principal.name(), name)))
+            })
+        }
+
+        (&Method::Delete, Some("projects"), Some(name), Some("pledges"), Some(user)) => {
+            authenticate(client, token).and_then(|principal| {
+                if user != principal.name() {
+                    return Err(Error::PermissionDenied(format!("{} cannot withdraw {}'s pledge.",
+                                                               principal.name(), user)));
+                }
+
+                let pledge = try!(client.cancel_pledge(&principal, name));
+                Ok(Reply::new(StatusCode::Ok, pledge_json(&pledge, user, name)))
+            })
+        }
+
+        _ if route_exists(&segments) => {
+            Ok(Reply::error(StatusCode::MethodNotAllowed, &format!("{} is not allowed here.", method)))
+        }
+
+        _ => Ok(Reply::error(StatusCode::NotFound, "No such route.")),
+    };
+
+    result.unwrap_or_else(|err| Reply::error(status(&err), &err.to_string()))
+}
+
+/// Listens for requests on the address, such as "127.0.0.1:3000", in the background.
+/// The configuration file is read once. Requests answered on several threads at once each
+/// take a client of their own from a pool, which opens more connections as they're needed.
+pub fn serve(config: &str, addr: &str) -> Result<Listening> {
+    listen(config, None, addr)
+}
+
+/// Listens for requests like `serve`, but answers them from the named schema rather than
+/// the configured one, such as a client's ephemeral schema.
+pub fn serve_schema(config: &str, schema: &str, addr: &str) -> Result<Listening> {
+    listen(config, Some(schema.to_owned()), addr)
+}
+
+fn listen(config: &str, schema: Option<String>, addr: &str) -> Result<Listening> {
+    let mut config = try!(Config::load(config));
+    if let Some(schema) = schema {
+        config.schema = schema;
+    }
+
+    let idle: Mutex<Vec<Client>> = Mutex::new(vec![]);
+    let server = try!(Server::http(addr).map_err(|err| http_error(addr, err)));
+
+    let handling = server.handle(move |mut req: Request, mut res: Response| {
+        let uri = match req.uri {
+            RequestUri::AbsolutePath(ref path) => path.clone(),
+            _ => String::new(),
+        };
+
+        let token = req.headers.get_raw("Authorization")
+            .and_then(|values| values.get(0))
+            .and_then(|value| String::from_utf8(value.clone()).ok())
+            .and_then(|value| if value.starts_with("Bearer ") { Some(value[7..].trim().to_owned()) } else { None });
+
+        let mut body = String::new();
+        let read = req.by_ref().take(MAX_BODY + 1).read_to_string(&mut body);
+        let reply = match read {
+            Err(err) => Reply::error(StatusCode::BadRequest, &err.to_string()),
+            Ok(n) if n as u64 > MAX_BODY => {
+                Reply::error(StatusCode::PayloadTooLarge, &format!("Request bodies are limited to {} bytes.", MAX_BODY))
+            }
+            Ok(_) => {
+                let pooled = idle.lock().unwrap().pop();
+                match pooled.map_or_else(|| Client::from_config(&config), Ok) {
+                    Ok(client) => {
+                        let reply = handle(&client, &req.method, &uri, token.as_ref().map(|t| &t[..]), &body);
+                        // A client whose connection failed isn't reused.
+                        if reply.status != StatusCode::InternalServerError {
+                            idle.lock().unwrap().push(client);
+                        }
+                        reply
+                    }
+                    Err(err) => Reply::error(status(&err), &err.to_string()),
+                }
+            }
+        };
+
+        *res.status_mut() = reply.status;
+        if reply.status == StatusCode::Unauthorized {
+            res.headers_mut().set_raw("WWW-Authenticate", vec![b"Bearer".to_vec()]);
+        }
+
+        if reply.body == Json::Null {
+            let _ = res.send(b"");
+        } else {
+            res.headers_mut().set_raw("Content-Type", vec![b"application/json".to_vec()]);
+            let _ = res.send(reply.body.to_string().as_bytes());
+        }
+    });
+
+    handling.map_err(|err| http_error(addr, err))
+}
+
+impl Reply {
+
+    /// Creates a reply with a status and body.
+    pub fn new(status: StatusCode, body: Json) -> Reply {
+        Reply { status: status, body: body }
+    }
+
+    /// Creates an error reply.
+    pub fn error(status: StatusCode, message: &str) -> Reply {
+        let mut json = BTreeMap::new();
+        json.insert("error".to_owned(), message.to_json());
+        json.insert("status".to_owned(), status.to_u16().to_json());
+        Reply::new(status, Json::Object(json))
+    }
+
+    /// Creates a reply with one page of items, out of a total number of items.
+    fn page(items: Vec<Json>, offset: usize, limit: usize, total: usize) -> Reply {
+        let mut json = BTreeMap::new();
+        json.insert("items".to_owned(), Json::Array(items));
+        json.insert("offset".to_owned(), offset.to_json());
+        json.insert("limit".to_owned(), limit.to_json());
+        json.insert("total".to_owned(), total.to_json());
+        Reply::new(StatusCode::Ok, Json::Object(json))
+    }
+}
+
+/// Returns true if some method is routed to the path.
+fn route_exists(segments: &[&str]) -> bool {
+    match segments.len() {
+        1 => ["projects", "tokens", "graphql", "openapi.json"].contains(&segments[0]),
+        2 => segments[0] == "users" || segments[0] == "projects",
+        3 => (segments[0] == "users" || segments[0] == "projects") && segments[2] == "pledges",
+        4 => segments[0] == "projects" && segments[2] == "pledges",
+        _ => false,
+    }
+}
+
+/// Authenticates the bearer token sent with the request.
+fn authenticate(client: &Client, token: Option<&str>) -> Result<Principal> {
+    match token {
+        Some(token) => client.authenticate(token),
+        None => Err(From::from(validate::Error::NotAuthenticated)),
+    }
+}
+
+/// Decodes a JSON request body.
+fn decode<T: ::rustc_serialize::Decodable>(body: &str) -> Result<T> {
+    Ok(try!(json::decode(body)))
+}
+
+/// Reads the query, operation name and variables from the body of `POST /graphql`.
+fn graphql_request(body: &str) -> Result<(String, Option<String>, Json)> {
+    let json = try!(Json::from_str(body).map_err(json::DecoderError::ParseError));
+    let query = match json.find("query").and_then(|q| q.as_string()) {
+        Some(query) => query.to_owned(),
+        None => return Err(From::from(json::DecoderError::MissingFieldError("query".to_owned()))),
+    };
+
+    let operation = json.find("operationName").and_then(|o| o.as_string()).map(|o| o.to_owned());
+    let variables = json.find("variables").cloned().unwrap_or(Json::Null);
+    Ok((query, operation, variables))
+}
+
+/// Reads the offset and limit of a page from the query string.
+/// Limits are capped at MAX_LIMIT.
+fn page(query: &str) -> Result<(usize, usize)> {
+    let mut offset = 0;
+    let mut limit = DEFAULT_LIMIT;
+
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let mut parts = pair.splitn(2, '=');
+        let key = parts.next().unwrap();
+        let value = parts.next().unwrap_or("");
+
+        if key == "offset" || key == "limit" {
+            try!(validate::numtext(value));
+            let n = try!(value.parse::<usize>().map_err(|_| validate::Error::NotNumeric(value.to_owned())));
+            if key == "offset" {
+                offset = n;
+            } else {
+                limit = cmp::min(cmp::max(n, 1), MAX_LIMIT);
+            }
+        }
+    }
+
+    Ok((offset, limit))
+}
+
+/// Returns a project as JSON.
+pub fn project_json(project: &Project) -> Json {
+    let mut json = BTreeMap::new();
+    json.insert("project_id".to_owned(), project.project_id.to_json());
+    json.insert("name".to_owned(), project.name.to_json());
+    json.insert("goal".to_owned(), project.goal.to_json());
+    json.insert("creator_id".to_owned(), project.creator_id.to_json());
+    json.insert("comments_backers_only".to_owned(), project.comments_backers_only.to_json());
+    json.insert("date_created".to_owned(), project.date_created.to_string().to_json());
+    json.insert("date_closed".to_owned(), project.date_closed.map(|d| d.to_string()).to_json());
+    Json::Object(json)
+}
+
+/// Returns a pledge as JSON. Card numbers are masked.
+pub fn pledge_json(pledge: &Pledge, user: &str, project: &str) -> Json {
+    let mut json = BTreeMap::new();
+    json.insert("user".to_owned(), user.to_json());
+    json.insert("project".to_owned(), project.to_json());
+    json.insert("card".to_owned(), pledge.masked_card().to_json());
+    json.insert("amount".to_owned(), pledge.amount.to_json());
+    json.insert("refunded".to_owned(), pledge.refunded.to_json());
+    json.insert("pledged".to_owned(), pledge.net_amount().to_json());
+    json.insert("date_created".to_owned(), pledge.date_created.to_string().to_json());
+    Json::Object(json)
+}
+
+/// Returns the public parts of a user as JSON.
+pub fn user_json(user: &User) -> Json {
+    let mut json = BTreeMap::new();
+    json.insert("user_id".to_owned(), user.user_id.to_json());
+    json.insert("name".to_owned(), user.name.to_json());
+    json.insert("role".to_owned(), user.role.to_json());
+    json.insert("date_created".to_owned(), user.date_created.to_string().to_json());
+    Json::Object(json)
+}
+
+/// Returns an authenticated user as JSON.
+pub fn principal_json(principal: &Principal) -> Json {
+    let mut json = BTreeMap::new();
+    json.insert("user_id".to_owned(), principal.user_id().to_json());
+    json.insert("name".to_owned(), principal.name().to_json());
+    json.insert("role".to_owned(), principal.role().as_str().to_json());
+    Json::Object(json)
+}
+
+/// Describes a server that could not be started.
+fn http_error(addr: &str, err: hyper::Error) -> Error {
+    Error::Config(format!("Could not serve the API on {}: {}", addr, err))
+}
@@ -0,0 +1,107 @@
+//! Module for recording and searching the append-only audit log.
+pub use models::Audit;
+
+use {Client, Result};
+use db::{column, table};
+
+use chrono::naive::datetime::NaiveDateTime;
+use rustc_serialize::json::Json;
+use rustorm::dao::{IsDao, Value};
+use rustorm::query::Query;
+
+/// Criteria for searching the audit log. Unset fields match every entry.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    /// Only entries affecting this kind of entity, such as `project` or `pledge`.
+    pub entity: Option<String>,
+    /// Only entries affecting the entity with this ID.
+    pub entity_id: Option<String>,
+    /// Only entries made by this actor.
+    pub actor: Option<String>,
+    /// Only entries made at or after this time.
+    pub since: Option<NaiveDateTime>,
+    /// Only entries made before this time.
+    pub until: Option<NaiveDateTime>,
+}
+
+impl Audit {
+
+    /// Appends an entry to the audit log on behalf of the client's actor.
+    /// Callers should run this in the same transaction as the change being recorded.
+    pub fn record(client: &Client, action: &str, entity: &str, entity_id: &str,
+                  before: Option<Json>, after: Option<Json>) -> Result<Audit> {
+
+        let mut query = Query::insert();
+        query.set(column::actor, &client.actor())
+            .set(column::action, &action)
+            .set(column::entity, &entity)
+            .set(column::entity_id, &entity_id);
+
+        if let Some(ref before) = before {
+            query.set(column::before_value, &before.to_string());
+        }
+
+        if let Some(ref after) = after {
+            query.set(column::after_value, &after.to_string());
+        }
+
+        let audit = try!(query
+            .into_table(&client.table(table::audit))
+            .return_all()
+            .collect_one(client.db()));
+
+        Ok(audit)
+    }
+
+    /// Returns all audit log entries that match the filter, oldest first.
+    pub fn search(client: &Client, filter: &Filter) -> Result<Vec<Audit>> {
+        let mut conditions = vec![];
+        let mut params = vec![];
+
+        {
+            let mut condition = |sql: &str, value: Value| {
+                params.push(value);
+                conditions.push(format!("{} ${}", sql, params.len()));
+            };
+
+            if let Some(ref entity) = filter.entity {
+                condition("entity =", Value::String(entity.clone()));
+            }
+
+            if let Some(ref entity_id) = filter.entity_id {
+                condition("entity_id =", Value::String(entity_id.clone()));
+            }
+
+            if let Some(ref actor) = filter.actor {
+                condition("actor =", Value::String(actor.clone()));
+            }
+
+            if let Some(since) = filter.since {
+                condition("date_created >=", Value::NaiveDateTime(since));
+            }
+
+            if let Some(until) = filter.until {
+                condition("date_created <", Value::NaiveDateTime(until));
+            }
+        }
+
+        let mut sql = format!("SELECT * FROM {}", client.table(table::audit));
+        if !conditions.is_empty() {
+            sql = format!("{} WHERE {}", sql, conditions.join(" AND "));
+        }
+        sql = format!("{} ORDER BY audit_id", sql);
+
+        let daos = try!(client.db().execute_sql_with_return(&sql, &params));
+        Ok(daos.iter().map(Audit::from_dao).collect())
+    }
+
+    /// Parses the recorded value before the change, if there was one.
+    pub fn before(&self) -> Option<Json> {
+        self.before_value.as_ref().and_then(|s| Json::from_str(s).ok())
+    }
+
+    /// Parses the recorded value after the change, if there is one.
+    pub fn after(&self) -> Option<Json> {
+        self.after_value.as_ref().and_then(|s| Json::from_str(s).ok())
+    }
+}
@@ -0,0 +1,350 @@
+//! Module for load testing a schema with concurrent workers, for `ksr bench`.
+//!
+//! Each worker opens its own client and runs a weighted mix of operations: creating projects,
+//! backing them and listing their backers. Backers are drawn from seeded users, and pay with
+//! cards drawn from the same users, so that workers sometimes race to back a project with the
+//! same card or as the same user; those show up as errors, counted by what caused them.
+//! Operations run as the generated users, without logging each of them in.
+use {validate, Client, Error, Result};
+use models::{Pledge, Project};
+use seed::Dataset;
+
+use postgres::error::SqlState;
+use rand::{Rng, SeedableRng, XorShiftRng};
+use rustc_serialize::json::{Json, ToJson};
+
+use std::collections::BTreeMap;
+use std::error::Error as ErrorTrait;
+use std::sync::{Arc, Barrier, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Pledge amounts chosen when backing a project.
+static AMOUNTS: &'static [f64] = &[5f64, 10f64, 25f64, 50f64, 100f64, 250f64];
+
+/// An operation run by the workers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Op {
+    /// Create a project, as `Client::create_project` would.
+    Create,
+    /// Back a project, as `Client::back_project` would.
+    Back,
+    /// List a project's backers, with `Client::list_backers`.
+    List,
+}
+
+impl Op {
+    /// Returns every operation, in the order they are reported.
+    pub fn all() -> &'static [Op] {
+        &[Op::Create, Op::Back, Op::List]
+    }
+
+    /// Returns the name of the operation, as written in a mix.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Op::Create => "create",
+            Op::Back   => "back",
+            Op::List   => "list",
+        }
+    }
+}
+
+/// How often each operation is run, relative to the others.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mix {
+    pub create: u32,
+    pub back: u32,
+    pub list: u32,
+}
+
+impl Default for Mix {
+    fn default() -> Mix {
+        Mix { create: 1, back: 6, list: 3 }
+    }
+}
+
+impl Mix {
+
+    /// Parses weights written as `op=weight` pairs separated by commas, such as `back=8,list=2`.
+    /// Operations left out aren't run.
+    pub fn parse(s: &str) -> validate::Result<Mix> {
+        let mut mix = Mix { create: 0, back: 0, list: 0 };
+        for pair in s.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            let parts: Vec<&str> = pair.splitn(2, '=').map(|p| p.trim()).collect();
+            let weight = match parts.get(1).and_then(|w| w.parse::<u32>().ok()) {
+                Some(weight) => weight,
+                None => return Err(validate::Error::InvalidMix(pair.to_owned())),
+            };
+
+            match parts[0] {
+                "create" => mix.create = weight,
+                "back"   => mix.back = weight,
+                "list"   => mix.list = weight,
+                _ => return Err(validate::Error::InvalidMix(pair.to_owned())),
+            }
+        }
+
+        if mix.create + mix.back + mix.list == 0 {
+            return Err(validate::Error::InvalidMix(s.to_owned()));
+        }
+        Ok(mix)
+    }
+
+    /// Returns an operation chosen at random, in proportion to its weight.
+    fn choose<R: Rng>(&self, rng: &mut R) -> Op {
+        let point = rng.gen_range(0, self.create + self.back + self.list);
+        if point < self.create {
+            Op::Create
+        } else if point < self.create + self.back {
+            Op::Back
+        } else {
+            Op::List
+        }
+    }
+}
+
+/// How a benchmark is run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Options {
+    /// The number of workers running operations at once.
+    pub workers: usize,
+    /// The number of operations run by all of the workers together.
+    pub operations: usize,
+    pub mix: Mix,
+    /// The seed that decides which operations are run, and with what.
+    pub seed: u64,
+}
+
+impl Default for Options {
+    /// The benchmark that `ksr bench` runs unless told otherwise.
+    fn default() -> Options {
+        Options { workers: 8, operations: 1000, mix: Mix::default(), seed: 1 }
+    }
+}
+
+/// The results of one kind of operation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub op: Op,
+    /// The number of times the operation ran.
+    pub count: usize,
+    /// The number of times it failed, by what caused each failure.
+    pub errors: BTreeMap<String, usize>,
+    /// Latencies at the 50th, 95th and 99th percentiles, in milliseconds.
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+/// The results of a benchmark.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub workers: usize,
+    /// How long the workers took, from when they all started to when the last one finished.
+    pub elapsed: Duration,
+    pub operations: Vec<Summary>,
+}
+
+impl Summary {
+
+    /// Returns the number of times the operation failed.
+    pub fn failed(&self) -> usize {
+        self.errors.values().fold(0, |sum, n| sum + n)
+    }
+}
+
+impl Report {
+
+    /// Returns the number of operations run each second, by all of the workers together.
+    pub fn throughput(&self, count: usize) -> f64 {
+        count as f64 / self.seconds().max(1e-9)
+    }
+
+    /// Returns how long the workers took, in seconds.
+    pub fn seconds(&self) -> f64 {
+        seconds(self.elapsed)
+    }
+
+    /// Returns the number of operations run.
+    pub fn count(&self) -> usize {
+        self.operations.iter().fold(0, |sum, s| sum + s.count)
+    }
+}
+
+impl ToJson for Report {
+    fn to_json(&self) -> Json {
+        let operations: Vec<Json> = self.operations.iter().map(|summary| {
+            let mut json = BTreeMap::new();
+            json.insert("operation".to_owned(), summary.op.as_str().to_json());
+            json.insert("count".to_owned(), summary.count.to_json());
+            json.insert("failed".to_owned(), summary.failed().to_json());
+            json.insert("throughput".to_owned(), self.throughput(summary.count).to_json());
+            json.insert("p50_ms".to_owned(), summary.p50.to_json());
+            json.insert("p95_ms".to_owned(), summary.p95.to_json());
+            json.insert("p99_ms".to_owned(), summary.p99.to_json());
+            json.insert("errors".to_owned(), summary.errors.to_json());
+            Json::Object(json)
+        }).collect();
+
+        let mut json = BTreeMap::new();
+        json.insert("workers".to_owned(), self.workers.to_json());
+        json.insert("seconds".to_owned(), self.seconds().to_json());
+        json.insert("count".to_owned(), self.count().to_json());
+        json.insert("throughput".to_owned(), self.throughput(self.count()).to_json());
+        json.insert("operations".to_owned(), Json::Array(operations));
+        Json::Object(json)
+    }
+}
+
+/// A finished operation: what it was, how long it took, and what it failed with, if it did.
+type Sample = (Op, Duration, Option<String>);
+
+/// Runs the operations against a schema already seeded with the dataset, spread across workers
+/// that each open a connection of their own, and reports how they went.
+pub fn run(uri: &str, schema: &str, dataset: &Dataset, options: &Options) -> Result<Report> {
+    let workers = options.workers.max(1);
+    let users = Arc::new(dataset.users.clone());
+    let projects = Arc::new(Mutex::new(dataset.projects.iter().map(|p| p.0.clone()).collect::<Vec<String>>()));
+
+    // Workers wait for each other to connect, so that connecting isn't timed.
+    let barrier = Arc::new(Barrier::new(workers + 1));
+
+    let mut handles = vec![];
+    for worker in 0..workers {
+        let (uri, schema) = (uri.to_owned(), schema.to_owned());
+        let (users, projects, barrier) = (users.clone(), projects.clone(), barrier.clone());
+        let operations = options.operations / workers + if worker < options.operations % workers { 1 } else { 0 };
+        let mix = options.mix;
+        let seed = options.seed.wrapping_add(worker as u64);
+
+        handles.push(thread::spawn(move || -> Result<Vec<Sample>> {
+            let client = Client::new(&uri, &schema);
+            barrier.wait();
+            let client = try!(client);
+
+            let mut rng = XorShiftRng::from_seed([seed as u32 ^ 0x193a6754, (seed >> 32) as u32 ^ 0xa8a7d469,
+                                                  0x97830e05, 0x113ba7bb]);
+            let mut samples = vec![];
+            for i in 0..operations {
+                let op = mix.choose(&mut rng);
+                let start = Instant::now();
+                let result = run_op(&client, op, worker, i, &users, &projects, &mut rng);
+                samples.push((op, start.elapsed(), result.err().map(|err| classify(&err))));
+            }
+            Ok(samples)
+        }));
+    }
+
+    barrier.wait();
+    let start = Instant::now();
+
+    let mut samples = vec![];
+    let mut failure = None;
+    for handle in handles {
+        match handle.join() {
+            Ok(Ok(worker_samples)) => samples.extend(worker_samples),
+            Ok(Err(err)) => failure = Some(err),
+            Err(_) => failure = Some(Error::Config("A benchmark worker panicked.".to_owned())),
+        }
+    }
+    let elapsed = start.elapsed();
+
+    if let Some(err) = failure {
+        return Err(err);
+    }
+
+    Ok(Report { workers: workers, elapsed: elapsed, operations: summarize(samples) })
+}
+
+/// Runs a single operation as a random user.
+fn run_op<R: Rng>(client: &Client, op: Op, worker: usize, i: usize, users: &[(String, String)],
+                  projects: &Mutex<Vec<String>>, rng: &mut R) -> Result<()> {
+    let user = &rng.choose(users).unwrap().0;
+    let project = {
+        let projects = projects.lock().unwrap();
+        rng.choose(&projects[..]).cloned()
+    };
+
+    match op {
+        Op::Create => {
+            let name = format!("Bench_{}_{}", worker, i);
+            let goal = (rng.gen_range(10, 1000) * 100) as f64;
+            try!(Project::create_by(client, Some(&user[..]), &name, goal));
+            projects.lock().unwrap().push(name);
+        }
+        Op::Back => {
+            // Cards are drawn from every user's, so that backers sometimes collide on a card.
+            let card = &rng.choose(users).unwrap().1;
+            let amount = *rng.choose(AMOUNTS).unwrap();
+            let project = try!(project.ok_or(validate::Error::ProjectDoesNotExist));
+            try!(Pledge::create(client, user, &project, card, amount));
+        }
+        Op::List => {
+            let project = try!(project.ok_or(validate::Error::ProjectDoesNotExist));
+            try!(client.list_backers(&project));
+        }
+    }
+    Ok(())
+}
+
+/// Names what caused an operation to fail, so that failures with the same cause are counted
+/// together. Unique violations name the constraint that `Pledge::create` reports them for.
+fn classify(err: &Error) -> String {
+    match *err {
+        Error::Database(ref inner) => match inner.code {
+            Some(SqlState::UniqueViolation) if inner.description().starts_with("Credit card") => {
+                "pledge_project_card".to_owned()
+            }
+            Some(SqlState::UniqueViolation) if inner.description().contains("has already backed") => {
+                "pledge_pkey".to_owned()
+            }
+            Some(ref code) => format!("{:?}", code),
+            None => inner.description().to_owned(),
+        },
+        ref err => err.to_string(),
+    }
+}
+
+/// Groups samples by operation, with their latency percentiles and failures.
+fn summarize(samples: Vec<Sample>) -> Vec<Summary> {
+    Op::all().iter().filter_map(|&op| {
+        let mut latencies: Vec<f64> = vec![];
+        let mut errors = BTreeMap::new();
+        for &(sample_op, duration, ref error) in &samples {
+            if sample_op != op {
+                continue;
+            }
+            latencies.push(seconds(duration) * 1000f64);
+            if let Some(ref error) = *error {
+                *errors.entry(error.clone()).or_insert(0) += 1;
+            }
+        }
+
+        if latencies.is_empty() {
+            return None;
+        }
+
+        latencies.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        Some(Summary {
+            op: op,
+            count: latencies.len(),
+            errors: errors,
+            p50: percentile(&latencies, 50f64),
+            p95: percentile(&latencies, 95f64),
+            p99: percentile(&latencies, 99f64),
+        })
+    }).collect()
+}
+
+/// Returns the nearest-rank percentile of sorted values.
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0f64;
+    }
+    let rank = (p / 100f64 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.max(1).min(sorted.len()) - 1]
+}
+
+fn seconds(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1e9
+}
@@ -1,47 +1,223 @@
 extern crate docopt;
 extern crate rustc_serialize;
 extern crate kickstarter;
+extern crate rpassword;
+extern crate rustyline;
 
 use docopt::Docopt;
-use kickstarter::{Client, Result, Error};
+use kickstarter::{validate, Client, Result, Error, SCHEMA_VAR};
+use kickstarter::account::{Principal, Role};
+use kickstarter::api;
+use kickstarter::audit::Filter;
+use kickstarter::comment::Thread;
+use kickstarter::config::Config;
+use kickstarter::graphql::{self, parser};
+use kickstarter::graphql::parser::OperationKind;
+use kickstarter::import::{self, Mapping};
+use kickstarter::mail::Kind;
+use kickstarter::notify::Notice;
+use kickstarter::refund::Reason;
+use kickstarter::testing::Ephemeral;
+use kickstarter::{bench, db, fulfillment, golden, repl, rpc, seed};
+use kickstarter::output::{self, Format, Table};
+use rustc_serialize::json::{self, Json, ToJson};
+use rustyline::Editor;
+use rustyline::error::ReadlineError;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::env;
 use std::fs::File;
-use std::io::{self, BufRead, Read, Write};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::{self, Command, Stdio};
+use std::rc::Rc;
+use std::thread;
 
 const USAGE: &'static str = "
 The Real Kickstarter.
 
 Usage:
-    ksr run     [<file>]                       [--config=<cfile>]
-    ksr project <name> <amount>                [--config=<cfile>]
-    ksr back    <user> <name> <card> <amount>  [--config=<cfile>]
-    ksr list    <name>                         [--config=<cfile>]
-    ksr backer  <user>                         [--config=<cfile>]
-    ksr listall                                [--config=<cfile>]
-    ksr (-b | --build)                         [--config=<cfile>]
-    ksr (-s | --sync)                          [--config=<cfile>]
+    ksr run     [<file>] [--dry-run] [--atomic | --continue-on-error] [--config=<cfile>]
+    ksr run     --record=<rfile> [--timestamps] [--outcomes] [--config=<cfile>]
+    ksr rpc                                    [--token=<t>] [--config=<cfile>]
+    ksr test    <script>... [--bless]          [--config=<cfile>]
+    ksr register   <user> <address> [--admin]  [--config=<cfile>] [--format=<f>]
+    ksr login      <user>                      [--config=<cfile>] [--format=<f>]
+    ksr logout     [--all]                     [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr whoami                                 [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr project <name> <amount>                [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr back    <name> <card> <amount>         [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr list    <name>                         [--config=<cfile>] [--format=<f>]
+    ksr backer  <user>                         [--config=<cfile>] [--format=<f>]
+    ksr listall                                [--config=<cfile>] [--format=<f>]
+    ksr watch   <name>                         [--config=<cfile>]
+    ksr serve   [--bind=<addr>]                [--config=<cfile>]
+    ksr graphql <query> [--variables=<json>] [--operation=<op>] [--token=<t>] [--config=<cfile>]
+    ksr close      <name>                      [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr cancel     <name>                      [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr refund     <user> <name> [<amount>]    [--reason=<code>] [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr chargeback <name> <card> [<amount>]    [--reason=<code>] [--reference=<ref>] [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr refunds    <name>                      [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr replay     <schema> [--until=<t>]      [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr email      [<address>]                 [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr ship-to    [<shipping>]                [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr mute       <kind>                      [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr unmute     <kind>                      [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr unsubscribe <token>                    [--kind=<kind>] [--config=<cfile>] [--format=<f>]
+    ksr mail                                   [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr hook       <url> <secret> [--project=<name>] [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr unhook     <id>                        [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr hooks                                  [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr deliver                                [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr deliveries <id>                        [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr audit      [--entity=<e>] [--entity-id=<id>] [--actor=<a>] [--since=<t>] [--until=<t>] [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr comment    <name> <body>...            [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr reply      <id> <body>...              [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr edit       <id> <body>...              [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr delete     <id>                        [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr hide       <id>                        [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr unhide     <id>                        [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr flag       <id>                        [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr comments   <name>                      [--config=<cfile>] [--format=<f>]
+    ksr restrict   <name>                      [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr unrestrict <name>                      [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr role       <user> <role>               [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr dump       [--snapshot]                [--token=<t>] [--config=<cfile>]
+    ksr restore    <snapshot>                  [--config=<cfile>] [--format=<f>]
+    ksr import     (projects | pledges) <csv> [--map=<m>] [--rejects=<rfile>] [--skip-rejected] [--dry-run] [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr seed       [--projects=<n>] [--users=<n>] [--pledges=<n>] [--seed=<s>] [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr bench      <schema> [--workers=<n>] [--ops=<n>] [--mix=<m>] [--projects=<n>] [--users=<n>] [--seed=<s>] [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr reward     <name> <title> <amount>     [--token=<t>] [--config=<cfile>] [--format=<f>]
+    ksr rewards    <name>                      [--config=<cfile>] [--format=<f>]
+    ksr export-backers <name> [--output=<ofile>] [--token=<t>] [--config=<cfile>]
+    ksr schema check                           [--config=<cfile>] [--format=<f>]
+    ksr config check                           [--config=<cfile>] [--format=<f>]
+    ksr (-b | --build)                         [--token=<t>] [--config=<cfile>]
+    ksr (-s | --sync)                          [--token=<t>] [--config=<cfile>]
     ksr (-h | --help)
     ksr (-v | --version)
 
 Options:
     -h --help          Show this message
     -v --version       Show version
-    -s --sync          Sync generated models with db tables
+    -s --sync          Sync generated models with db tables (admins only)
     -b --build         Build tables and models from configured .sql file
+                       (admins only, once the schema has been built)
     --config=<cfile>   The .toml configuration file [default: data/config.toml]
+    --token=<t>        The API token printed by login, if $KSR_TOKEN isn't set
+    --format=<f>       Print results as text, table, json, csv, or tsv [default: text]
+    --admin            Register the first admin of a schema that has none
+    --dry-run          Check every line of a script, or row of an import, without running
+                       or importing any of them
+    --atomic           Run a script in one transaction, rolled back at the first failure
+    --continue-on-error  Keep running a script after a line fails
+    --record=<rfile>   Append each command run at the prompt to a script that replays the session
+    --timestamps       Note when each recorded command ran
+    --outcomes         Record failed commands too, commented out along with their errors
+    --map=<m>          Read fields from differently named CSV columns, as field=column pairs
+                       separated by commas, such as goal=Target,name=Title
+    --rejects=<rfile>  Write rejected rows to a CSV file, with why each was rejected
+    --skip-rejected    Import the rows that pass even if others are rejected
+    --workers=<n>      The number of benchmark workers running at once [default: 8]
+    --ops=<n>          The number of operations the benchmark workers run in all [default: 1000]
+    --mix=<m>          How often the benchmark runs each operation, as op=weight pairs of create,
+                       back and list [default: create=1,back=6,list=3]
+    --output=<ofile>   Write to a file instead of stdout; a name ending in .xlsx writes a spreadsheet
+    --projects=<n>     The number of projects to seed [default: 10]
+    --users=<n>        The number of users to seed [default: 100]
+    --pledges=<n>      The number of pledges to seed [default: 500]
+    --seed=<s>         The seed that decides the data generated; the same seed gives the same data [default: 1]
+    --snapshot         Dump a JSON snapshot of every row, instead of a .ksr script
+    --bless            Rewrite a test script's expectations to match its output
+    --bind=<addr>      The address to serve the API on [default: 127.0.0.1:3000]
+    --variables=<json> A JSON object of variables for a GraphQL query
+    --operation=<op>   The operation to run, if a GraphQL document has several
+    --all              Revoke every token you have, not just this one
+    --kind=<kind>      Only unsubscribe from this kind of mail: receipt, new_backer,
+                       campaign_succeeded, or campaign_failed
+    --reason=<code>    Why money is being returned: requested, duplicate,
+                       fraudulent, cancelled, disputed, or other
+    --reference=<ref>  The payment processor's reference for a dispute
+    --project=<name>   Only send the webhook changes to this project
+    --entity=<e>       Only audit entries for this kind of entity (project, pledge, user, comment)
+    --entity-id=<id>   Only audit entries for the entity with this ID
+    --actor=<a>        Only audit entries made by this actor
+    --since=<t>        Only audit entries made at or after this date or timestamp
+    --until=<t>        Only audit entries made before this date or timestamp,
+                       or only replay events up to this date or timestamp
 
 Commands:
-    project    Create a new project
+    register   Create a backer account, or with --admin the schema's first admin;
+               the password is read from $KSR_PASSWORD or prompted for
+    login      Log in and print an API token for the other commands
+    logout     Revoke your API token
+    whoami     Show who your API token belongs to
+    project    Create a new project that you run (creators only)
     back       Back a project
     list       List all pledges towards a project
     backer     List all pledges that a backer has made
     listall    List all existing projects
-    run        Streaming CLI
+    watch      Follow pledges towards a project as they arrive
+    serve      Serve projects, pledges and users as a JSON REST API
+    graphql    Run a GraphQL query, mutation or subscription; `graphql schema` prints the schema
+    close      End your project's campaign and tell its backers how it went
+    cancel     Withdraw a pledge
+    refund     Refund some or all of a pledge to your project
+    chargeback Record a chargeback reported by a payment processor (admins only)
+    refunds    List all refunds and chargebacks for your project
+    replay     Rebuild a fresh schema by replaying the event log (admins only)
+    email      Set or clear the address a user's mail is sent to
+    ship-to    Set or clear the address your rewards are shipped to
+    mute       Stop sending a user one kind of mail
+    unmute     Resume sending a user one kind of mail
+    unsubscribe Stop sending mail to the user with an unsubscribe token
+    mail       Send all queued mail through the configured transport (admins only)
+    hook       Register a webhook, signed with a shared secret (admins only)
+    unhook     Remove a webhook (admins only)
+    hooks      List all webhooks (admins only)
+    deliver    Send all webhook deliveries that are due (admins only)
+    deliveries List all deliveries made to a webhook (admins only)
+    audit      Search the audit log of every change (admins only)
+    comment    Comment on a project
+    reply      Reply to a comment
+    edit       Edit your comment
+    delete     Delete your comment
+    hide       Hide a comment (moderators only)
+    unhide     Reveal a hidden comment (moderators only)
+    flag       Flag a comment for review (moderators only)
+    comments   List all comment threads on a project
+    restrict   Only allow backers to comment on your project
+    unrestrict Allow anyone to comment on your project
+    role       Make a user a backer, creator, moderator, or admin (admins only)
+    dump       Print a .ksr script that rebuilds the projects and pledges, or a JSON
+               snapshot of every row with its IDs and timestamps (admins only)
+    restore    Load a JSON snapshot into a freshly built, empty schema
+    import     Import projects or pledges from a CSV file in one transaction (admins only)
+    seed       Fill the database with generated users, projects and pledges (admins only)
+    bench      Rebuild a schema, seed it, and time concurrent creates, backs and lists against it
+               (admins only)
+    reward     Offer a reward tier on your project, earned by pledging at least the amount
+    rewards    List a project's reward tiers, cheapest first
+    export-backers Export the backers of your project for fulfillment, as CSV or XLSX,
+               with their reward tiers and shipping addresses, and card numbers masked
+    schema     `schema check` compares the database's tables with the generated models,
+               and fails if their columns, types, nullability or foreign keys differ
+    config     `config check` reads the configuration, with its profile and `KSR_*` overrides
+               applied, shows where each setting came from, and connects with it
+    run        Run commands from a prompt with history and completion, or from a file
+    test       Run scripts against fresh schemas and check their output against
+               `# expect:` comments or a .out file beside each script
+    rpc        Answer JSON-RPC 2.0 requests from stdin, one per line, for embedding ksr
 
 Examples:
+    login Jorge
+      $ Password:
+      $ Logged in as Jorge.
+
     project Sensel_Control_Pad 250000.00
       $ Added Sensel_Control_Pad project with target of $250,000.00
 
-    back Jorge Sensel_Control_Pad 123456789012 300
+    back Sensel_Control_Pad 123456789012 300
       $ Jorge backed project Sensel_Control_Pad for $300.00
 
     list Sensel_Control_Pad
@@ -50,8 +226,44 @@ Examples:
 
     backer Jorge
       $ Jorge backed project Sensel_Control_Pad for $300.00
+
+    comment Sensel_Control_Pad Can't wait to get mine!
+      $ Jorge commented on Sensel_Control_Pad (#1).
 ";
 
+/// Environment variable holding the API token, used when --token isn't given.
+const TOKEN_VAR: &'static str = "KSR_TOKEN";
+/// Environment variable holding the password for register and login, so that scripts can run unattended.
+const PASSWORD_VAR: &'static str = "KSR_PASSWORD";
+/// Password used by test scripts that register or log in, unless $KSR_PASSWORD is set.
+const TEST_PASSWORD: &'static str = "correct horse battery";
+
+thread_local! {
+    /// The token from the last `login` in this prompt or script, used when --token isn't given.
+    /// It stays in the process, rather than the environment, so that child processes never see it.
+    static SESSION_TOKEN: RefCell<Option<String>> = RefCell::new(None)
+}
+
+// Columns shown for each kind of result in tables, CSV and TSV.
+const PROJECT_COLUMNS: &'static [&'static str] = &["name", "goal", "creator_id", "date_created", "date_closed"];
+const PLEDGE_COLUMNS: &'static [&'static str] = &["user", "project", "card", "amount", "refunded", "pledged",
+                                                  "date_created"];
+const USER_COLUMNS: &'static [&'static str] = &["name", "role", "date_created"];
+const REFUND_COLUMNS: &'static [&'static str] = &["refund_id", "user", "kind", "amount", "reason", "reference",
+                                                  "date_created"];
+const COMMENT_COLUMNS: &'static [&'static str] = &["comment_id", "parent_id", "user_id", "body", "hidden",
+                                                   "flagged", "date_created"];
+const WEBHOOK_COLUMNS: &'static [&'static str] = &["webhook_id", "url", "project_id", "active", "date_created"];
+const DELIVERY_COLUMNS: &'static [&'static str] = &["delivery_id", "webhook_id", "event", "status", "attempts",
+                                                    "next_attempt", "date_delivered"];
+const MAIL_COLUMNS: &'static [&'static str] = &["mail_id", "recipient", "kind", "status", "attempts", "error"];
+const MUTE_COLUMNS: &'static [&'static str] = &["user", "kind", "enabled"];
+const AUDIT_COLUMNS: &'static [&'static str] = &["audit_id", "date_created", "actor", "action", "entity",
+                                                 "entity_id"];
+const REWARD_COLUMNS: &'static [&'static str] = &["reward_id", "title", "minimum", "date_created"];
+const BENCH_COLUMNS: &'static [&'static str] = &["operation", "count", "failed", "throughput", "p50_ms",
+                                                 "p95_ms", "p99_ms"];
+
 macro_rules! version {
     () => {
         format!("{}.{}.{}{}",
@@ -70,23 +282,132 @@ macro_rules! try_return {
     })
 }
 
+/// try! macro, but print the error description and exit with the code that describes it.
+macro_rules! try_exit {
+    ($expr:expr) => (match $expr {
+        std::result::Result::Ok(val) => val,
+        std::result::Result::Err(err) => { println!("ERROR: {}", err); process::exit(output::exit_code(&err)); },
+    })
+}
+
 #[derive(Debug, RustcDecodable)]
 struct Args {
     cmd_run: bool,
+    cmd_rpc: bool,
+    cmd_test: bool,
+    cmd_register: bool,
+    cmd_login: bool,
+    cmd_logout: bool,
+    cmd_whoami: bool,
     cmd_project: bool,
     cmd_back: bool,
     cmd_list: bool,
     cmd_backer: bool,
     cmd_listall: bool,
+    cmd_watch: bool,
+    cmd_serve: bool,
+    cmd_graphql: bool,
+    cmd_close: bool,
+    cmd_cancel: bool,
+    cmd_refund: bool,
+    cmd_chargeback: bool,
+    cmd_refunds: bool,
+    cmd_email: bool,
+    cmd_ship_to: bool,
+    cmd_mute: bool,
+    cmd_unmute: bool,
+    cmd_unsubscribe: bool,
+    cmd_mail: bool,
+    cmd_hook: bool,
+    cmd_unhook: bool,
+    cmd_hooks: bool,
+    cmd_deliver: bool,
+    cmd_deliveries: bool,
+    cmd_audit: bool,
+    cmd_replay: bool,
+    cmd_comment: bool,
+    cmd_reply: bool,
+    cmd_edit: bool,
+    cmd_delete: bool,
+    cmd_hide: bool,
+    cmd_unhide: bool,
+    cmd_flag: bool,
+    cmd_comments: bool,
+    cmd_restrict: bool,
+    cmd_unrestrict: bool,
+    cmd_role: bool,
+    cmd_dump: bool,
+    cmd_restore: bool,
+    cmd_import: bool,
+    cmd_projects: bool,
+    cmd_pledges: bool,
+    cmd_reward: bool,
+    cmd_rewards: bool,
+    cmd_export_backers: bool,
+    cmd_seed: bool,
+    cmd_bench: bool,
+    cmd_schema: bool,
+    cmd_config: bool,
+    cmd_check: bool,
     arg_file: Option<String>,
+    arg_script: Vec<String>,
     arg_user: Option<String>,
     arg_name: Option<String>,
     arg_card: Option<String>,
     arg_amount: Option<f64>,
+    arg_id: Option<i32>,
+    arg_schema: Option<String>,
+    arg_body: Vec<String>,
+    arg_address: Option<String>,
+    arg_shipping: Option<String>,
+    arg_title: Option<String>,
+    arg_kind: Option<String>,
+    arg_token: Option<String>,
+    arg_url: Option<String>,
+    arg_secret: Option<String>,
+    arg_role: Option<String>,
+    arg_query: Option<String>,
+    arg_snapshot: Option<String>,
+    arg_csv: Option<String>,
     flag_version: bool,
     flag_sync: bool,
     flag_build: bool,
     flag_config: String,
+    flag_bind: String,
+    flag_variables: Option<String>,
+    flag_operation: Option<String>,
+    flag_token: Option<String>,
+    flag_all: bool,
+    flag_admin: bool,
+    flag_kind: Option<String>,
+    flag_reason: Option<String>,
+    flag_reference: Option<String>,
+    flag_project: Option<String>,
+    flag_entity: Option<String>,
+    flag_entity_id: Option<String>,
+    flag_actor: Option<String>,
+    flag_since: Option<String>,
+    flag_until: Option<String>,
+    flag_format: String,
+    flag_dry_run: bool,
+    flag_atomic: bool,
+    flag_continue_on_error: bool,
+    flag_bless: bool,
+    flag_record: Option<String>,
+    flag_timestamps: bool,
+    flag_outcomes: bool,
+    flag_snapshot: bool,
+    flag_map: Option<String>,
+    flag_rejects: Option<String>,
+    flag_output: Option<String>,
+    flag_projects: usize,
+    flag_users: usize,
+    flag_pledges: usize,
+    flag_seed: u64,
+    flag_workers: usize,
+    flag_ops: usize,
+    flag_mix: String,
+    flag_skip_rejected: bool,
 }
 
 fn main() {
@@ -102,26 +423,47 @@ fn main() {
         return;
     }
 
+    // Check the configuration before connecting with it, so that its errors are reported in full.
+    if args.cmd_config {
+        if let Err(err) = cmd_config_check(&args) {
+            print_error(&args.flag_format, &err);
+            process::exit(output::exit_code(&err));
+        }
+        return;
+    }
+
     // Destructive flag -- double-check with the user.
     if args.flag_build {
         try_return!(ensure_build());
     }
 
     // Connect to the database.
-    let client = match Client::with_config(&args.flag_config, args.flag_build, true) {
+    let client = match Client::with_config(&args.flag_config) {
         Ok(client) => client,
         Err(ref e) => {
             println!("ERROR: {} \n\n\
                       Could not connect to the database. \n\
-                      Make sure that `{}` exists and is pointing to an existing database.",
+                      Make sure that `{}` exists and is pointing to an existing database, \n\
+                      or run `ksr config check` to see where each setting came from.",
                      e, &args.flag_config);
-            return;
+            process::exit(output::exit_code(e));
         }
     };
 
-    // Wipe the database and sync it with the configuration file, then generate the associated models.
+    // Wipe the database and rebuild it from the configuration file, then generate the associated models.
+    // Only a schema that hasn't been built yet may be built without logging in as an admin.
+    if args.flag_build {
+        let admin = match token(&args) {
+            Some(_) => Some(try_exit!(principal(&client, &args))),
+            None => None,
+        };
+        try_exit!(client.build(admin.as_ref(), true));
+    }
+
+    // Generate models from the existing tables.
     if args.flag_sync {
-        client.sync();
+        let admin = try_exit!(principal(&client, &args));
+        try_exit!(client.sync(&admin));
     }
 
     if args.flag_build || args.flag_sync {
@@ -129,12 +471,34 @@ fn main() {
     }
     
     // Handle main kickstarter commands.
-    if !args.cmd_run {
-        handle_args(&client, args);
+    if args.cmd_rpc {
+        cmd_rpc(&client, args);
+    } else if args.cmd_test {
+        if !cmd_test(&args) {
+            process::exit(output::EXIT_FAILURE);
+        }
+    } else if !args.cmd_run {
+        if let Err(err) = run_args(&client, args) {
+            process::exit(output::exit_code(&err));
+        }
     } else {
         match args.arg_file {
-            None => prompt(client, docopt),
-            Some(ref filename) => run_file(client, docopt, filename),
+            None => {
+                let recorder = match args.flag_record {
+                    Some(ref path) => {
+                        let recorder = repl::Recorder::open(path, args.flag_timestamps, args.flag_outcomes);
+                        Some(try_exit!(recorder.map_err(Error::from)))
+                    }
+                    None => None,
+                };
+                prompt(client, docopt, recorder);
+            }
+            Some(ref filename) => {
+                let code = run_file(&client, docopt, &args, filename);
+                if code != 0 {
+                    process::exit(code);
+                }
+            }
         }
     }
 }
@@ -158,45 +522,277 @@ fn ensure_build() -> Result<()> {
     }
 }
 
-// Run commands from stdin.
-fn prompt(client: Client, docopt: Docopt) {
-    let stdin = io::stdin();
-    print!("> ");
-    try_return!(io::stdout().flush());
+// Run commands from stdin, with line editing, history, and completion.
+// Commands are appended to the recorder's script, if there is one.
+fn prompt(client: Client, docopt: Docopt, mut recorder: Option<repl::Recorder>) {
+    let names = Rc::new(RefCell::new(repl::names(&client).unwrap_or(vec![])));
+    let mut editor = Editor::new();
+    editor.set_completer(Some(repl::Completer::new(USAGE, names.clone())));
+
+    let history = repl::history_path();
+    if let Some(ref path) = history {
+        let _ = editor.load_history(path);
+    }
+
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            // Ctrl-C abandons the line, and Ctrl-D ends the session.
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                println!("ERROR: {}", err);
+                break;
+            }
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+        editor.add_history_entry(&line);
+
+        let words = match repl::split(&line) {
+            Ok(words) => words,
+            Err(err) => {
+                println!("ERROR: {}\n", err);
+                continue;
+            }
+        };
 
-    for line in stdin.lock().lines() {
-        let args = read_args(docopt.clone(), &line.unwrap());
-        handle_args(&client, args);
+        if words.first().map(|w| &w[..]) == Some("help") {
+            print_help(words.get(1));
+        } else if !words.is_empty() {
+            match read_args(docopt.clone(), &words) {
+                Ok(args) => {
+                    let result = run_args(&client, args);
+                    if result.is_ok() {
+                        if let Ok(updated) = repl::names(&client) {
+                            *names.borrow_mut() = updated;
+                        }
+                    }
 
-        print!("\n> ");
-        try_return!(io::stdout().flush());
+                    if let Some(ref mut recorder) = recorder {
+                        let error = result.err().map(|e| e.to_string());
+                        if let Err(err) = recorder.record(&words, error.as_ref().map(|e| &e[..])) {
+                            println!("ERROR: Could not record the command: {}", err);
+                        }
+                    }
+                }
+                Err(message) => println!("{}", message),
+            }
+        }
+        println!("");
     }
+
+    if let Some(ref path) = history {
+        let _ = editor.save_history(path);
+    }
+}
+
+/// A line of a script that parsed and passed validation.
+struct ScriptLine {
+    number: usize,
+    text: String,
+    args: Args,
 }
 
-// Run a list of commands provided via file.
-// This will not run anything unless all lines are successfully parsed.
-fn run_file(client: Client, docopt: Docopt, filename: &str) {
+/// What happened to a line of a script.
+enum Outcome {
+    Succeeded,
+    Failed(String),
+    Skipped,
+}
+
+// Run a script of commands, one per line, with `#` starting a comment.
+// Every line is parsed and validated before any of them run. Returns the exit code.
+fn run_file(client: &Client, docopt: Docopt, options: &Args, filename: &str) -> i32 {
+    let text = match read_file_as_string(filename) {
+        Ok(text) => text,
+        Err(err) => {
+            println!("ERROR: {}", err);
+            return output::exit_code(&err);
+        }
+    };
+
+    let (lines, errors) = parse_script(docopt, &text);
+    if !errors.is_empty() {
+        for &(number, ref line, ref message) in &errors {
+            println!("{}:{}: {}\n    {}", filename, number, message, line);
+        }
+        println!("\nNothing was run: {} of {} lines have errors.", errors.len(), lines.len() + errors.len());
+        return output::EXIT_INVALID;
+    }
 
-    let cmds = try_return!(read_file_as_string(filename));
+    if options.flag_dry_run {
+        println!("{} lines parsed and validated. Nothing was run.", lines.len());
+        return 0;
+    }
 
-    // Split into a list of commands.
-    let mut run_list: Vec<(&str, Args)> = vec![];
-    let runs: Vec<&str> = cmds.split("\n")
-        .filter(|s| !s.is_empty())
-        .collect();
+    let numbered: Vec<(usize, String)> = lines.iter().map(|l| (l.number, l.text.clone())).collect();
+    let mut outcomes: Vec<Outcome> = lines.iter().map(|_| Outcome::Skipped).collect();
+    let mut code = 0;
 
-    // Parse each command.
-    for run in &runs {
-        let doc_args = read_args(docopt.clone(), run);
-        run_list.push((run, doc_args));
+    if options.flag_atomic {
+        let result = client.transaction(|| {
+            for (line, outcome) in lines.into_iter().zip(outcomes.iter_mut()) {
+                println!("> {}", line.text);
+                let result = run_args(client, line.args);
+                println!("");
+
+                if let Err(err) = result {
+                    *outcome = Outcome::Failed(err.to_string());
+                    return Err(err);
+                }
+                *outcome = Outcome::Succeeded;
+            }
+            Ok(())
+        });
+
+        if let Err(ref err) = result {
+            code = output::exit_code(err);
+        }
+        print_summary(&numbered, &outcomes);
+        if result.is_err() {
+            println!("The script was rolled back; none of its changes were kept.");
+        }
+        return code;
     }
 
-    // Run each command without checking for errors.
-    for (cmd, args) in run_list {
-        println!("> {}", cmd);
-        handle_args(&client, args);
+    for (line, outcome) in lines.into_iter().zip(outcomes.iter_mut()) {
+        println!("> {}", line.text);
+        let result = run_args(client, line.args);
         println!("");
+
+        match result {
+            Ok(()) => *outcome = Outcome::Succeeded,
+            Err(err) => {
+                *outcome = Outcome::Failed(err.to_string());
+                if code == 0 {
+                    code = output::exit_code(&err);
+                }
+                if !options.flag_continue_on_error {
+                    break;
+                }
+            }
+        }
+    }
+
+    print_summary(&numbered, &outcomes);
+    code
+}
+
+// Parses and validates every line of a script. Returns the lines that passed, along with
+// the line number, text and message of each that didn't.
+fn parse_script(docopt: Docopt, text: &str) -> (Vec<ScriptLine>, Vec<(usize, String, String)>) {
+    let mut lines = vec![];
+    let mut errors = vec![];
+
+    for (i, line) in text.lines().enumerate() {
+        let words = match repl::split(line) {
+            Ok(words) => words,
+            Err(err) => {
+                errors.push((i + 1, line.to_owned(), err.to_string()));
+                continue;
+            }
+        };
+
+        if words.is_empty() {
+            continue;
+        }
+
+        let checked = read_args(docopt.clone(), &words)
+            .and_then(|args| check_args(&words, &args).map(|_| args).map_err(|e| e.to_string()));
+
+        match checked {
+            Ok(args) => lines.push(ScriptLine { number: i + 1, text: line.trim().to_owned(), args: args }),
+            Err(message) => errors.push((i + 1, line.to_owned(), message.trim_left_matches("ERROR: ").to_owned())),
+        }
+    }
+
+    (lines, errors)
+}
+
+// Prints what happened to each line of a script, then the totals.
+fn print_summary(lines: &[(usize, String)], outcomes: &[Outcome]) {
+    let mut succeeded = 0;
+    let mut failed = 0;
+
+    println!("Summary:");
+    for (&(number, ref text), outcome) in lines.iter().zip(outcomes) {
+        match *outcome {
+            Outcome::Succeeded => {
+                succeeded += 1;
+                println!("    ok      {:>4}  {}", number, text);
+            }
+            Outcome::Failed(ref message) => {
+                failed += 1;
+                println!("    FAILED  {:>4}  {}\n                  {}", number, text, message);
+            }
+            Outcome::Skipped => println!("    skipped {:>4}  {}", number, text),
+        }
     }
+
+    println!("{} succeeded, {} failed, {} skipped.", succeeded, failed, outcomes.len() - succeeded - failed);
+}
+
+// Checks a script line's arguments before anything runs, so that typos can't leave a script half done.
+// Only what can be known without the database is checked.
+fn check_args(words: &[String], args: &Args) -> Result<()> {
+    // Building and syncing replace the schema underneath the rest of the script.
+    if !repl::scriptable(words) || args.flag_build || args.flag_sync {
+        return Err(Error::Config("This command can't be run from a script.".to_owned()));
+    }
+
+    try!(args.flag_format.parse::<Format>());
+
+    for name in args.arg_name.iter().chain(args.arg_user.iter()).chain(args.flag_project.iter()) {
+        try!(validate::length(name, 4, 20));
+        try!(validate::alphanumeric(name));
+    }
+    if let Some(ref card) = args.arg_card {
+        try!(validate::length(card, 1, 19));
+        try!(validate::luhn10(card));
+    }
+    if let Some(amount) = args.arg_amount {
+        try!(validate::currency(amount));
+    }
+    if let Some(ref address) = args.arg_address {
+        try!(validate::email(address));
+    }
+    if let Some(ref shipping) = args.arg_shipping {
+        try!(validate::not_blank(shipping));
+        try!(validate::single_line(shipping));
+        try!(validate::length(shipping, 1, 500));
+    }
+    if let Some(ref title) = args.arg_title {
+        try!(validate::not_blank(title));
+        try!(validate::single_line(title));
+        try!(validate::length(title, 1, 100));
+    }
+    if let Some(ref url) = args.arg_url {
+        try!(validate::url(url));
+    }
+    for kind in args.arg_kind.iter().chain(args.flag_kind.iter()) {
+        try!(kind.parse::<Kind>());
+    }
+    if let Some(ref role) = args.arg_role {
+        try!(role.parse::<Role>());
+    }
+    if let Some(ref reason) = args.flag_reason {
+        try!(reason.parse::<Reason>());
+    }
+    for t in args.flag_since.iter().chain(args.flag_until.iter()) {
+        try!(validate::timestamp(t));
+    }
+    if args.cmd_comment || args.cmd_reply || args.cmd_edit {
+        try!(validate::not_blank(&args.arg_body.join(" ")));
+    }
+    if let Some(ref query) = args.arg_query {
+        if query != "schema" {
+            try!(parser::parse(query));
+        }
+    }
+    Ok(())
 }
 
 fn read_file_as_string(filename: &str) -> Result<String> {
@@ -206,103 +802,1290 @@ fn read_file_as_string(filename: &str) -> Result<String> {
     Ok(s)
 }
 
-// Parses a single command without the `ksr` prefix.
-fn read_args(docopt: Docopt, line: &str) -> Args {
-    let formatted = format!("ksr {}", line);
-    let args = formatted.split(" ");
-    let doc = docopt.argv(args);
-    doc.decode().unwrap_or_else(|e| {
-        println!("Attempted to parse args: {}", line); 
-        e.exit()
+// Parses the words of a single command, without the `ksr` prefix.
+fn read_args(docopt: Docopt, words: &[String]) -> std::result::Result<Args, String> {
+    let argv = Some("ksr".to_owned()).into_iter().chain(words.iter().cloned());
+    docopt.argv(argv).decode().map_err(|e| parse_error(e, words))
+}
+
+// Prints the usage of a command, or of every command.
+fn print_help(command: Option<&String>) {
+    match command {
+        None => println!("{}", USAGE.trim()),
+        Some(command) => match repl::help(USAGE, command) {
+            Some(help) => print!("{}", help),
+            None => println!("{} isn't a command. Type `help` to list them.", command),
+        },
+    }
+}
+
+// Describes why a line couldn't be parsed, without printing the whole usage.
+fn parse_error(err: docopt::Error, words: &[String]) -> String {
+    match err {
+        docopt::Error::WithProgramUsage(inner, _) => parse_error(*inner, words),
+        docopt::Error::Help => USAGE.trim().to_owned(),
+        docopt::Error::Version(version) => version,
+        err => match words.first() {
+            Some(command) if repl::commands(USAGE).contains(command) => {
+                format!("ERROR: Invalid arguments for {}. Type `help {}` for its usage.", command, command)
+            }
+            Some(command) => format!("ERROR: {} isn't a command. Type `help` to list them.", command),
+            None => format!("ERROR: {}", err),
+        },
+    }
+}
+
+// Run a command, printing any error in the requested format.
+fn run_args(client: &Client, args: Args) -> Result<()> {
+    let format = args.flag_format.clone();
+    handle_args(client, args).map_err(|err| {
+        print_error(&format, &err);
+        err
     })
 }
 
 // Run the appropriate command based on arguments.
-fn handle_args(client: &Client, args: Args) {
-    if args.cmd_project {
-        cmd_project(&client, args);
+fn handle_args(client: &Client, args: Args) -> Result<()> {
+    // Reject unknown formats before anything is changed.
+    try!(args.flag_format.parse::<Format>());
+
+    if args.cmd_register {
+        cmd_register(&client, args)
+
+    } else if args.cmd_login {
+        cmd_login(&client, args)
+
+    } else if args.cmd_logout {
+        cmd_logout(&client, args)
+
+    } else if args.cmd_whoami {
+        cmd_whoami(&client, args)
+
+    } else if args.cmd_project {
+        cmd_project(&client, args)
 
     } else if args.cmd_back {
-        cmd_back(&client, args);
+        cmd_back(&client, args)
 
     } else if args.cmd_list {
-        cmd_list(&client, args);
+        cmd_list(&client, args)
 
     } else if args.cmd_backer {
-        cmd_backer(&client, args);
+        cmd_backer(&client, args)
 
     } else if args.cmd_listall {
-        cmd_listall(&client);
+        cmd_listall(&client, args)
+
+    } else if args.cmd_watch {
+        cmd_watch(&client, args)
+
+    } else if args.cmd_serve {
+        cmd_serve(args)
+
+    } else if args.cmd_graphql {
+        cmd_graphql(&client, args)
+
+    } else if args.cmd_close {
+        cmd_close(&client, args)
+
+    } else if args.cmd_cancel {
+        cmd_cancel(&client, args)
+
+    } else if args.cmd_refund || args.cmd_chargeback {
+        cmd_refund(&client, args)
+
+    } else if args.cmd_refunds {
+        cmd_refunds(&client, args)
+
+    } else if args.cmd_replay {
+        cmd_replay(&client, args)
+
+    } else if args.cmd_email {
+        cmd_email(&client, args)
+
+    } else if args.cmd_ship_to {
+        cmd_ship_to(&client, args)
+
+    } else if args.cmd_mute || args.cmd_unmute || args.cmd_unsubscribe {
+        cmd_mute(&client, args)
+
+    } else if args.cmd_mail {
+        cmd_mail(&client, args)
+
+    } else if args.cmd_hook || args.cmd_unhook {
+        cmd_hook(&client, args)
+
+    } else if args.cmd_hooks {
+        cmd_hooks(&client, args)
+
+    } else if args.cmd_deliver {
+        cmd_deliver(&client, args)
+
+    } else if args.cmd_deliveries {
+        cmd_deliveries(&client, args)
+
+    } else if args.cmd_audit {
+        cmd_audit(&client, args)
+
+    } else if args.cmd_comment || args.cmd_reply || args.cmd_edit {
+        cmd_comment(&client, args)
+
+    } else if args.cmd_delete || args.cmd_hide || args.cmd_unhide || args.cmd_flag {
+        cmd_moderate(&client, args)
+
+    } else if args.cmd_comments {
+        cmd_comments(&client, args)
+
+    } else if args.cmd_restrict || args.cmd_unrestrict {
+        cmd_restrict(&client, args)
+
+    } else if args.cmd_role {
+        cmd_role(&client, args)
+
+    } else if args.cmd_dump {
+        cmd_dump(&client, args)
+
+    } else if args.cmd_restore {
+        cmd_restore(&client, args)
+
+    } else if args.cmd_import {
+        cmd_import(&client, args)
+
+    } else if args.cmd_seed {
+        cmd_seed(&client, args)
+
+    } else if args.cmd_bench {
+        cmd_bench(&client, args)
+
+    } else if args.cmd_reward {
+        cmd_reward(&client, args)
+
+    } else if args.cmd_rewards {
+        cmd_rewards(&client, args)
+
+    } else if args.cmd_export_backers {
+        cmd_export_backers(&client, args)
+
+    } else if args.cmd_schema {
+        cmd_schema_check(&client, args)
+
+    } else if args.cmd_config {
+        cmd_config_check(&args)
+
+    } else {
+        Ok(())
+    }
+}
+
+/// Prints a command's result in the requested format. Text is printed by the command itself.
+fn emit<F: FnOnce()>(format: &str, json: &Json, table: Table, text: F) -> Result<()> {
+    match try!(format.parse::<Format>()) {
+        Format::Text => text(),
+        format => print!("{}", output::render(format, json, &table)),
+    }
+    Ok(())
+}
+
+/// Prints an error in the requested format. Errors go to stderr when stdout holds a table,
+/// so that they can't be mistaken for rows.
+fn print_error(format: &str, err: &Error) {
+    match format.parse::<Format>() {
+        Ok(Format::Json) => {
+            let mut json = BTreeMap::new();
+            json.insert("error".to_owned(), err.to_string().to_json());
+            json.insert("exit_code".to_owned(), output::exit_code(err).to_json());
+            println!("{}", Json::Object(json).pretty());
+        }
+        Ok(Format::Table) | Ok(Format::Csv) | Ok(Format::Tsv) => {
+            let _ = writeln!(io::stderr(), "ERROR: {}", err);
+        }
+        _ => println!("ERROR: {}", err),
+    }
+}
+
+/// Returns the API token from the --token flag, the session's last login or the environment, if any.
+fn token(args: &Args) -> Option<String> {
+    args.flag_token.clone()
+        .or_else(|| SESSION_TOKEN.with(|token| token.borrow().clone()))
+        .or_else(|| env::var(TOKEN_VAR).ok())
+}
+
+/// Authenticates the user running the command.
+fn principal(client: &Client, args: &Args) -> Result<Principal> {
+    match token(args) {
+        Some(ref token) => client.authenticate(token),
+        None => Err(From::from(validate::Error::NotAuthenticated)),
+    }
+}
+
+/// Reads a password from the environment, or prompts for one without echoing it.
+fn read_password() -> Result<String> {
+    if let Ok(password) = env::var(PASSWORD_VAR) {
+        return Ok(password);
+    }
+
+    print!("Password: ");
+    try!(io::stdout().flush());
+
+    // Echo can only be turned off at a terminal; a password piped in is read as it comes.
+    let password = match rpassword::read_password() {
+        Ok(password) => password,
+        Err(_) => {
+            let mut password = String::new();
+            try!(io::stdin().read_line(&mut password));
+            password
+        }
+    };
+    Ok(password.trim_right_matches(|c| c == '\n' || c == '\r').to_owned())
+}
+
+/// Answer JSON-RPC requests from stdin until the input ends.
+fn cmd_rpc(client: &Client, args: Args) {
+    let stdin   = io::stdin();
+    let stdout  = io::stdout();
+    let mut session = rpc::Session::new(client, &args.flag_config, token(&args));
+    try_return!(session.run(stdin.lock(), &mut stdout.lock()));
+}
+
+/// Run each test script against a fresh schema, and check or bless its expectations.
+/// Returns true if every script passed.
+fn cmd_test(args: &Args) -> bool {
+    let mut failed = 0;
+    for script in &args.arg_script {
+        match test_script(args, script) {
+            Ok(true) => println!("ok      {}", script),
+            Ok(false) => {
+                println!("FAILED  {}", script);
+                failed += 1;
+            }
+            Err(err) => {
+                println!("ERROR   {}: {}", script, err);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("\n{} passed, {} failed.", args.arg_script.len() - failed, failed);
+    failed == 0
+}
+
+/// Runs a script in a child `ksr run`, against a schema built for it and dropped afterwards.
+/// Returns whether its output met its expectations; blessed scripts always pass.
+fn test_script(args: &Args, script: &str) -> Result<bool> {
+    let text = try!(read_file_as_string(script));
+    let commands = golden::parse(&text, |line| repl::split(line).map(|w| !w.is_empty()).unwrap_or(false));
+    let inline = commands.iter().any(|c| !c.expected.is_empty());
+    let out_file = Path::new(script).with_extension("out");
+
+    // Scripts start logged out, on a schema of their own.
+    let client = try!(Ephemeral::with_config(&args.flag_config));
+
+    let child = Command::new(try!(env::current_exe()))
+        .arg("run").arg(script).arg("--continue-on-error")
+        .arg(format!("--config={}", args.flag_config))
+        .env(SCHEMA_VAR, &client.schema)
+        .env(PASSWORD_VAR, env::var(PASSWORD_VAR).unwrap_or(TEST_PASSWORD.to_owned()))
+        .env_remove(TOKEN_VAR)
+        .stdin(Stdio::null())
+        .output();
+
+    drop(client);
+    let stdout = String::from_utf8_lossy(&try!(child).stdout).into_owned();
+    let outputs = golden::outputs(&commands, &stdout);
+
+    if !commands.is_empty() && !stdout.lines().any(|l| l == "Summary:") {
+        print!("{}", stdout);
+        return Ok(false);
+    }
+
+    if args.flag_bless {
+        if inline {
+            let mut f = try!(File::create(script));
+            try!(f.write_all(golden::bless(&text, &commands, &outputs).as_bytes()));
+        } else {
+            let mut f = try!(File::create(&out_file));
+            try!(f.write_all(golden::transcript(&commands, &outputs).as_bytes()));
+        }
+        return Ok(true);
+    }
+
+    if inline {
+        let mismatches = golden::mismatches(&commands, &outputs);
+        for mismatch in &mismatches {
+            println!("{}:{}", script, mismatch);
+        }
+        return Ok(mismatches.is_empty());
+    }
+
+    if !out_file.exists() {
+        return Err(Error::Config(format!("{} has no expectations. Run with --bless to record its output in {}.",
+                                         script, out_file.display())));
+    }
+
+    let expected = try!(read_file_as_string(&out_file.to_string_lossy()));
+    match golden::diff(&expected, &golden::transcript(&commands, &outputs)) {
+        Some(diff) => {
+            println!("--- {}\n+++ output of {}\n{}", out_file.display(), script, diff);
+            Ok(false)
+        }
+        None => Ok(true),
     }
 }
 
-/// Create a new project with the desired amount.
-fn cmd_project(client: &Client, args: Args) {
+/// Create a new account, or the first admin of a freshly built schema.
+fn cmd_register(client: &Client, args: Args) -> Result<()> {
+    let user    = args.arg_user.unwrap();
+    let address = args.arg_address.unwrap();
+    let password = try!(read_password());
+    let account = if args.flag_admin {
+        try!(client.register_admin(&user, &address, &password))
+    } else {
+        try!(client.register(&user, &address, &password))
+    };
+
+    let json = api::user_json(&account);
+    emit(&args.flag_format, &json, Table::new(USER_COLUMNS, &json), || {
+        println!("Registered {} <{}>. Log in with `ksr login {}`.", user, address, user);
+    })
+}
+
+/// Log in and print a new API token.
+fn cmd_login(client: &Client, args: Args) -> Result<()> {
+    let user    = args.arg_user.unwrap();
+    let password = try!(read_password());
+    let (principal, token) = try!(client.login(&user, &password));
+
+    // Later commands in the same session act as this user.
+    SESSION_TOKEN.with(|session| *session.borrow_mut() = Some(token.clone()));
+
+    let mut json = BTreeMap::new();
+    json.insert("token".to_owned(), token.to_json());
+    json.insert("user".to_owned(), api::principal_json(&principal));
+    let json = Json::Object(json);
+
+    emit(&args.flag_format, &json, Table::new(&["token"], &json), || {
+        println!("Logged in as {}. Your API token is:\n\n    {}\n\n\
+                  Pass it with --token, or set {} to use it for every command.",
+                 principal.name(), token, TOKEN_VAR);
+    })
+}
+
+/// Revoke the current API token, or all of them.
+fn cmd_logout(client: &Client, args: Args) -> Result<()> {
+    let (revoked, user) = if args.flag_all {
+        let principal = try!(principal(client, &args));
+        (try!(client.logout_everywhere(&principal)).len(), principal.name().to_owned())
+    } else {
+        let token   = try!(token(&args).ok_or(validate::Error::NotAuthenticated));
+        try!(client.logout(&token));
+        (1, String::new())
+    };
+
+    SESSION_TOKEN.with(|session| *session.borrow_mut() = None);
+
+    let mut json = BTreeMap::new();
+    json.insert("revoked".to_owned(), revoked.to_json());
+    let json = Json::Object(json);
+
+    emit(&args.flag_format, &json, Table::new(&["revoked"], &json), || {
+        if args.flag_all {
+            println!("Revoked {} tokens for {}.", revoked, user);
+        } else {
+            println!("Logged out.");
+        }
+    })
+}
+
+/// Show who the API token belongs to.
+fn cmd_whoami(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let json = api::principal_json(&principal);
+    emit(&args.flag_format, &json, Table::new(&["user_id", "name", "role"], &json), || {
+        println!("Logged in as {} ({}).", principal.name(), principal.role());
+    })
+}
+
+/// Create a new project with the desired amount, run by the logged-in user.
+fn cmd_project(client: &Client, args: Args) -> Result<()> {
+    let creator = try!(principal(client, &args));
     let name    = args.arg_name.unwrap();
     let amount  = args.arg_amount.unwrap();
-    try_return!(client.create_project(&name, amount));
-    println!("Added project '{}' with a target goal of ${:.2}.", name, amount);
+    let project = try!(client.create_project(&creator, &name, amount));
+
+    let json = api::project_json(&project);
+    emit(&args.flag_format, &json, Table::new(PROJECT_COLUMNS, &json), || {
+        println!("Added project '{}' with a target goal of ${:.2}.", name, amount);
+    })
 }
 
-/// Back an existing project with a username, credit card, and contribution amount.
-fn cmd_back(client: &Client, args: Args) {
-    let user    = args.arg_user.unwrap();
+/// Back an existing project with a credit card and contribution amount.
+fn cmd_back(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
     let name    = args.arg_name.unwrap();
     let card    = args.arg_card.unwrap();
     let amount  = args.arg_amount.unwrap();
-    try_return!(client.back_project(&user, &name, &card, amount));
-    println!("{} backed project '{}' for ${:.2}.", user, name, amount);
+    let pledge  = try!(client.back_project(&principal, &name, &card, amount));
+
+    let json = api::pledge_json(&pledge, principal.name(), &name);
+    emit(&args.flag_format, &json, Table::new(PLEDGE_COLUMNS, &json), || {
+        println!("{} backed project '{}' for ${:.2}.", principal.name(), name, amount);
+    })
 }
 
 /// List all backers for an existing project.
-fn cmd_list(client: &Client, args: Args) {
+fn cmd_list(client: &Client, args: Args) -> Result<()> {
     let name    = args.arg_name.unwrap();
-    let (pledges, goal) = try_return!(client.list_backers(&name));
+    let (pledges, goal) = try!(client.list_backers(&name));
 
-    if pledges.is_empty() {
-        println!("{} doesn't have any backers yet. Maybe you'd like to help it get off the ground?", name);
-    } else {
-        let mut total = 0f64;
-        for pledge in &pledges {
-            let user = pledge.get_user();
-            println!("-- {} backed for ${:.2}", user.name, pledge.amount);
-            total += pledge.amount;
+    let json = output::backers_json(&name, &pledges, goal);
+    let table = Table::new(&["user", "card", "amount", "refunded", "pledged", "date_created"], &json["pledges"]);
+    emit(&args.flag_format, &json, table, || {
+        if pledges.is_empty() {
+            println!("{} doesn't have any backers yet. Maybe you'd like to help it get off the ground?", name);
+        } else {
+            let mut total = 0f64;
+            for pledge in &pledges {
+                let user = pledge.get_user();
+                if pledge.refunded > 0.0 {
+                    println!("-- {} backed for ${:.2} (${:.2} returned)", user.name, pledge.amount, pledge.refunded);
+                } else {
+                    println!("-- {} backed for ${:.2}", user.name, pledge.amount);
+                }
+                total += pledge.net_amount();
+            }
+
+            if total < goal {
+                println!("{} needs ${:.2} more dollars to be successful!", name, goal - total);
+            } else {
+                println!("{} is successfully funded!", name);
+            }
+        }
+    })
+}
+
+/// List all projects that have been backed by a user.
+fn cmd_backer(client: &Client, args: Args) -> Result<()> {
+    let user    = args.arg_user.unwrap();
+    let pledges = try!(client.list_backed_projects(&user));
+
+    let json = output::backed_json(&user, &pledges);
+    let table = Table::new(&["project", "card", "amount", "refunded", "pledged", "date_created"], &json["pledges"]);
+    emit(&args.flag_format, &json, table, || {
+        if pledges.is_empty() {
+            println!("{} hasn't backed any projects...yet. Get to it!", user);
+        } else {
+            let mut total = 0f64;
+            for pledge in &pledges {
+                let project = pledge.get_project();
+                println!("{} backed project '{}' for ${:.2}", user, project.name, pledge.net_amount());
+                total += pledge.net_amount();
+            }
+            println!("{} has given ${:.2} back to their community. Thanks {}!", user, total, user);
+        }
+    })
+}
+
+/// List all projects on Kickstarter.
+fn cmd_listall(client: &Client, args: Args) -> Result<()> {
+    let projects = try!(client.list_projects());
+
+    let json = Json::Array(projects.iter().map(api::project_json).collect());
+    emit(&args.flag_format, &json, Table::new(PROJECT_COLUMNS, &json), || {
+        if projects.is_empty() {
+            println!("There aren't any projects on Kickstarter right now. Check again in a little while!");
+        } else {
+            for project in &projects {
+                println!("Project '{}' is raising ${:.2}", project.name, project.goal);
+            }
+        }
+    })
+}
+
+/// Stream pledges towards a project, along with its running funding total.
+fn cmd_watch(client: &Client, args: Args) -> Result<()> {
+    let name    = args.arg_name.unwrap();
+
+    // Subscribe before reading the current total, so that no pledge slips in between.
+    let subscription = try!(client.subscribe());
+    let (pledges, goal) = try!(client.list_backers(&name));
+    let mut total = pledges.iter().fold(0f64, |total, p| total + p.net_amount());
+
+    println!("Watching '{}': ${:.2} of ${:.2} raised. Press Ctrl-C to stop.", name, total, goal);
+
+    for notice in subscription.notices() {
+        let notice = try!(notice);
+        if notice.project() != name {
+            continue;
         }
 
+        match notice {
+            Notice::PledgeCreated { ref user, amount, .. } => {
+                println!("+ {} backed for ${:.2}", user, amount);
+            }
+            Notice::PledgeRefunded { ref user, amount, .. } => {
+                println!("- ${:.2} was returned to {}", amount, user);
+            }
+            Notice::PledgeCancelled { ref user, amount, .. } => {
+                println!("- {} withdrew their ${:.2} pledge", user, amount);
+            }
+            _ => continue,
+        }
+
+        total += notice.funding_change();
         if total < goal {
-            println!("{} needs ${:.2} more dollars to be successful!", name, goal - total);
+            println!("  ${:.2} of ${:.2} raised, ${:.2} to go", total, goal, goal - total);
         } else {
-            println!("{} is successfully funded!", name);
+            println!("  ${:.2} of ${:.2} raised, successfully funded!", total, goal);
         }
     }
+    Ok(())
 }
 
-/// List all projects that have been backed by a user.
-fn cmd_backer(client: &Client, args: Args) {
-    let user    = args.arg_user.unwrap();
-    let pledges = try_return!(client.list_backed_projects(&user));
+/// Serve the REST API until interrupted.
+fn cmd_serve(args: Args) -> Result<()> {
+    let _listening = try!(api::serve(&args.flag_config, &args.flag_bind));
+    println!("Serving the API on http://{}. The routes are described at /openapi.json. \
+              Press Ctrl-C to stop.", args.flag_bind);
+
+    loop {
+        thread::park();
+    }
+}
+
+/// Run a GraphQL document, printing each response as JSON.
+/// Subscriptions print a response for every matching change until interrupted.
+fn cmd_graphql(client: &Client, args: Args) -> Result<()> {
+    let query   = args.arg_query.clone().unwrap();
+    if query == "schema" {
+        print!("{}", graphql::SCHEMA);
+        return Ok(());
+    }
+
+    let variables = match args.flag_variables {
+        Some(ref variables) => try!(Json::from_str(variables).map_err(json::DecoderError::ParseError)),
+        None => Json::Null,
+    };
+    let operation = args.flag_operation.as_ref().map(|o| &o[..]);
+
+    let document = try!(parser::parse(&query));
+    let subscription = document.operations.iter()
+        .filter(|o| operation.is_none() || o.name.as_ref().map(|n| &n[..]) == operation)
+        .any(|o| o.kind == OperationKind::Subscription);
+
+    if subscription {
+        let stream = try!(graphql::subscribe(client, &query, operation, &variables));
+        println!("Subscribed. Press Ctrl-C to stop.");
+        for response in stream {
+            println!("{}", try!(response).to_json());
+        }
+        return Ok(());
+    }
+
+    let principal = match token(&args) {
+        Some(_) => Some(try!(principal(client, &args))),
+        None => None,
+    };
+
+    let response = graphql::execute(client, principal.as_ref(), &query, operation, &variables);
+    println!("{}", response.to_json().pretty());
+    Ok(())
+}
+
+/// End a project's campaign.
+fn cmd_close(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let name    = args.arg_name.unwrap();
+    let (project, total) = try!(client.close_project(&principal, &name));
+
+    let json = output::closed_json(&project, total);
+    let table = Table::new(&["project", "goal", "pledged", "funded"], &output::funding_json(&name, total, project.goal));
+    emit(&args.flag_format, &json, table, || {
+        if total >= project.goal {
+            println!("{} closed, successfully funded with ${:.2} of ${:.2}!", name, total, project.goal);
+        } else {
+            println!("{} closed with ${:.2} of ${:.2}, short of its goal.", name, total, project.goal);
+        }
+    })
+}
+
+/// Withdraw a pledge from a project.
+fn cmd_cancel(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let name    = args.arg_name.unwrap();
+    let pledge  = try!(client.cancel_pledge(&principal, &name));
+
+    let json = api::pledge_json(&pledge, principal.name(), &name);
+    emit(&args.flag_format, &json, Table::new(PLEDGE_COLUMNS, &json), || {
+        println!("{} withdrew their ${:.2} pledge to project '{}'.", principal.name(), pledge.net_amount(), name);
+    })
+}
+
+/// Refund a pledge, or record a chargeback against one.
+fn cmd_refund(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let name    = args.arg_name.unwrap();
+    let amount  = args.arg_amount;
+
+    if args.cmd_refund {
+        let user    = args.arg_user.unwrap();
+        let reason  = try!(args.flag_reason.as_ref().map_or(Ok(Reason::Requested), |r| r.parse()));
+        let refund  = try!(client.refund_pledge(&principal, &user, &name, amount, reason));
+
+        let json = output::refund_json(&refund, &user);
+        emit(&args.flag_format, &json, Table::new(REFUND_COLUMNS, &json), || {
+            println!("Refunded ${:.2} to {} for project '{}' ({}).", refund.amount, user, name, reason.as_str());
+        })
+    } else {
+        let card    = args.arg_card.unwrap();
+        let reason  = try!(args.flag_reason.as_ref().map_or(Ok(Reason::Disputed), |r| r.parse()));
+        let reference = args.flag_reference.as_ref().map(|r| &r[..]);
+        let refund  = try!(client.record_chargeback(&principal, &name, &card, amount, reason, reference));
+
+        let json = output::refund_json(&refund, &refund.get_user().name);
+        emit(&args.flag_format, &json, Table::new(REFUND_COLUMNS, &json), || {
+            println!("Recorded a ${:.2} chargeback on card '{}' for project '{}' ({}).",
+                     refund.amount, card, name, reason.as_str());
+        })
+    }
+}
+
+/// List all refunds and chargebacks for a project.
+fn cmd_refunds(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let name    = args.arg_name.unwrap();
+    let refunds = try!(client.list_refunds(&principal, &name));
+
+    let json = Json::Array(refunds.iter().map(|r| output::refund_json(r, &r.get_user().name)).collect());
+    emit(&args.flag_format, &json, Table::new(REFUND_COLUMNS, &json), || {
+        if refunds.is_empty() {
+            println!("{} hasn't returned any pledges.", name);
+        } else {
+            for refund in &refunds {
+                println!("-- [{}] {} {} of ${:.2} ({}){}", refund.date_created, refund.get_user().name,
+                         refund.kind, refund.amount, refund.reason,
+                         refund.reference.as_ref().map_or(String::new(), |r| format!(", ref {}", r)));
+            }
+        }
+    })
+}
+
+/// Rebuild a fresh schema from the event log.
+fn cmd_replay(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let schema  = args.arg_schema.unwrap();
+    let until   = match args.flag_until {
+        Some(ref t) => Some(try!(validate::timestamp(t))),
+        None => None,
+    };
+
+    let count   = try!(client.replay(&principal, &schema, until));
+
+    let mut json = BTreeMap::new();
+    json.insert("schema".to_owned(), schema.to_json());
+    json.insert("events".to_owned(), count.to_json());
+    let json = Json::Object(json);
 
-    if pledges.is_empty() {
-        println!("{} hasn't backed any projects...yet. Get to it!", user);
+    emit(&args.flag_format, &json, Table::new(&["schema", "events"], &json), || {
+        println!("Replayed {} events into schema '{}'.", count, schema);
+    })
+}
+
+/// Set or clear a user's email address.
+fn cmd_email(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let address = args.arg_address.as_ref().map(|a| &a[..]);
+    try!(client.set_email(&principal, address));
+
+    let mut json = BTreeMap::new();
+    json.insert("user".to_owned(), principal.name().to_json());
+    json.insert("address".to_owned(), address.map(|a| a.to_owned()).to_json());
+    let json = Json::Object(json);
+
+    emit(&args.flag_format, &json, Table::new(&["user", "address"], &json), || {
+        match address {
+            Some(address) => println!("Mail for {} will be sent to {}.", principal.name(), address),
+            None => println!("{} won't receive any mail.", principal.name()),
+        }
+    })
+}
+
+/// Set or clear the address a user's rewards are shipped to.
+fn cmd_ship_to(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let address = args.arg_shipping.as_ref().map(|a| &a[..]);
+    try!(client.set_shipping_address(&principal, address));
+
+    let mut json = BTreeMap::new();
+    json.insert("user".to_owned(), principal.name().to_json());
+    json.insert("shipping_address".to_owned(), address.map(|a| a.to_owned()).to_json());
+    let json = Json::Object(json);
+
+    emit(&args.flag_format, &json, Table::new(&["user", "shipping_address"], &json), || {
+        match address {
+            Some(address) => println!("Rewards for {} will be shipped to {}.", principal.name(), address),
+            None => println!("{} has no shipping address.", principal.name()),
+        }
+    })
+}
+
+/// Turn a kind of mail on or off.
+fn cmd_mute(client: &Client, args: Args) -> Result<()> {
+    let mut json = BTreeMap::new();
+
+    if args.cmd_unsubscribe {
+        let token   = args.arg_token.unwrap();
+        let kind    = match args.flag_kind {
+            Some(ref kind) => Some(try!(kind.parse::<Kind>())),
+            None => None,
+        };
+
+        let user    = try!(client.unsubscribe(&token, kind));
+        json.insert("user".to_owned(), user.name.to_json());
+        json.insert("kind".to_owned(), kind.map(|k| k.as_str().to_owned()).to_json());
+        json.insert("enabled".to_owned(), false.to_json());
+        let json = Json::Object(json);
+
+        emit(&args.flag_format, &json, Table::new(MUTE_COLUMNS, &json), || {
+            println!("{} has been unsubscribed from {}.", user.name, kind.map_or("all mail", |k| k.as_str()));
+        })
     } else {
-        let mut total = 0f64;
-        for pledge in &pledges {
-            let project = pledge.get_project();
-            println!("{} backed project '{}' for ${:.2}", user, project.name, pledge.amount);
-            total += pledge.amount;
+        let principal = try!(principal(client, &args));
+        let kind    = try!(args.arg_kind.as_ref().unwrap().parse::<Kind>());
+        try!(client.set_mail_preference(&principal, kind, args.cmd_unmute));
+        json.insert("user".to_owned(), principal.name().to_json());
+        json.insert("kind".to_owned(), kind.as_str().to_json());
+        json.insert("enabled".to_owned(), args.cmd_unmute.to_json());
+        let json = Json::Object(json);
+
+        emit(&args.flag_format, &json, Table::new(MUTE_COLUMNS, &json), || {
+            println!("{} will {}receive {} mail.", principal.name(),
+                     if args.cmd_unmute { "" } else { "no longer " }, kind.as_str());
+        })
+    }
+}
+
+/// Send all queued mail.
+fn cmd_mail(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let mail = try!(client.send_mail(&principal));
+
+    let json = Json::Array(mail.iter().map(|m| m.to_json()).collect());
+    emit(&args.flag_format, &json, Table::new(MAIL_COLUMNS, &json), || {
+        let sent = mail.iter().filter(|m| m.status == "sent").count();
+        println!("Sent {} of {} messages.", sent, mail.len());
+
+        for m in mail.iter().filter(|m| m.status != "sent") {
+            println!("-- #{} to {} [{}]: {}", m.mail_id, m.recipient, m.status,
+                     m.error.as_ref().map_or("", |e| &e[..]));
         }
-        println!("{} has given ${:.2} back to their community. Thanks {}!", user, total, user);
+    })
+}
+
+/// Register or remove a webhook.
+fn cmd_hook(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    if args.cmd_hook {
+        let url     = args.arg_url.unwrap();
+        let secret  = args.arg_secret.unwrap();
+        let project = args.flag_project.as_ref().map(|p| &p[..]);
+        let webhook = try!(client.add_webhook(&principal, &url, &secret, project));
+
+        let json = webhook.redacted_json();
+        emit(&args.flag_format, &json, Table::new(WEBHOOK_COLUMNS, &json), || {
+            println!("Registered webhook #{} for {}.", webhook.webhook_id,
+                     project.map_or("every project".to_owned(), |p| format!("project '{}'", p)));
+        })
+    } else {
+        let id      = args.arg_id.unwrap();
+        let webhook = try!(client.remove_webhook(&principal, id));
+
+        let json = webhook.redacted_json();
+        emit(&args.flag_format, &json, Table::new(WEBHOOK_COLUMNS, &json), || {
+            println!("Removed webhook #{}.", id);
+        })
     }
 }
 
-/// List all projects on Kickstarter.
-fn cmd_listall(client: &Client) {
-    let projects = try_return!(client.list_projects());
-    if projects.is_empty() {
-        println!("There aren't any projects on Kickstarter right now. Check again in a little while!");
+/// List all webhooks.
+fn cmd_hooks(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let webhooks = try!(client.list_webhooks(&principal));
+
+    let json = Json::Array(webhooks.iter().map(|w| w.redacted_json()).collect());
+    emit(&args.flag_format, &json, Table::new(WEBHOOK_COLUMNS, &json), || {
+        if webhooks.is_empty() {
+            println!("No webhooks have been registered.");
+        } else {
+            for webhook in &webhooks {
+                let scope = webhook.project_id.map_or("every project".to_owned(), |id| format!("project #{}", id));
+                println!("#{} {} ({}){}", webhook.webhook_id, webhook.url, scope,
+                         if webhook.active { "" } else { " [inactive]" });
+            }
+        }
+    })
+}
+
+/// Send all webhook deliveries that are due.
+fn cmd_deliver(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let deliveries = try!(client.deliver_webhooks(&principal));
+
+    let json = Json::Array(deliveries.iter().map(output::delivery_json).collect());
+    emit(&args.flag_format, &json, Table::new(DELIVERY_COLUMNS, &json), || {
+        let delivered = deliveries.iter().filter(|d| d.status == "delivered").count();
+        let failed = deliveries.iter().filter(|d| d.status == "failed").count();
+        println!("Delivered {} of {} webhooks ({} will be retried, {} gave up).",
+                 delivered, deliveries.len(), deliveries.len() - delivered - failed, failed);
+    })
+}
+
+/// List all deliveries made to a webhook.
+fn cmd_deliveries(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let id      = args.arg_id.unwrap();
+    let deliveries = try!(client.list_deliveries(&principal, id));
+
+    let json = Json::Array(deliveries.iter().map(output::delivery_json).collect());
+    emit(&args.flag_format, &json, Table::new(DELIVERY_COLUMNS, &json), || {
+        if deliveries.is_empty() {
+            println!("Nothing has been sent to webhook #{} yet.", id);
+        } else {
+            for delivery in &deliveries {
+                println!("#{} {} [{}] after {} attempts", delivery.delivery_id, delivery.event,
+                         delivery.status, delivery.attempts);
+
+                for attempt in &delivery.webhook_attempt {
+                    let status = attempt.status_code.map_or("no response".to_owned(), |c| c.to_string());
+                    match attempt.error {
+                        Some(ref error) => println!("   [{}] {}: {}", attempt.date_created, status, error),
+                        None => println!("   [{}] {}", attempt.date_created, status),
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Search the audit log.
+fn cmd_audit(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let filter = Filter {
+        entity: args.flag_entity.clone(),
+        entity_id: args.flag_entity_id.clone(),
+        actor: args.flag_actor.clone(),
+        since: match args.flag_since {
+            Some(ref t) => Some(try!(validate::timestamp(t))),
+            None => None,
+        },
+        until: match args.flag_until {
+            Some(ref t) => Some(try!(validate::timestamp(t))),
+            None => None,
+        },
+    };
+
+    let entries = try!(client.audit_log(&principal, &filter));
+
+    let json = Json::Array(entries.iter().map(output::audit_json).collect());
+    emit(&args.flag_format, &json, Table::new(AUDIT_COLUMNS, &json), || {
+        if entries.is_empty() {
+            println!("No changes have been recorded that match your search.");
+        } else {
+            for entry in &entries {
+                println!("[{}] {} {} {} #{}", entry.date_created, entry.actor,
+                         entry.action, entry.entity, entry.entity_id);
+
+                if let Some(ref before) = entry.before_value {
+                    println!("   before: {}", before);
+                }
+                if let Some(ref after) = entry.after_value {
+                    println!("   after:  {}", after);
+                }
+            }
+        }
+    })
+}
+
+/// Post, reply to, or edit a comment.
+fn cmd_comment(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let user    = principal.name();
+    let body    = args.arg_body.join(" ");
+
+    let (comment, text) = if args.cmd_comment {
+        let name    = args.arg_name.unwrap();
+        let comment = try!(client.post_comment(&principal, &name, &body));
+        let text    = format!("{} commented on {} (#{}).", user, name, comment.comment_id);
+        (comment, text)
+    } else if args.cmd_reply {
+        let id      = args.arg_id.unwrap();
+        let comment = try!(client.reply_to_comment(&principal, id, &body));
+        let text    = format!("{} replied to #{} (#{}).", user, id, comment.comment_id);
+        (comment, text)
+    } else {
+        let id      = args.arg_id.unwrap();
+        let comment = try!(client.edit_comment(&principal, id, &body));
+        (comment, format!("{} edited #{}.", user, id))
+    };
+
+    let json = output::comment_json(&comment.to_json(), comment.visible_body());
+    emit(&args.flag_format, &json, Table::new(COMMENT_COLUMNS, &json), || println!("{}", text))
+}
+
+/// Delete, hide, reveal, or flag a comment.
+fn cmd_moderate(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let user    = principal.name();
+    let id      = args.arg_id.unwrap();
+
+    let (comment, text) = if args.cmd_delete {
+        (try!(client.delete_comment(&principal, id)), format!("{} deleted #{}.", user, id))
+    } else if args.cmd_flag {
+        (try!(client.flag_comment(&principal, id, true)), format!("{} flagged #{} for review.", user, id))
     } else {
-        for project in projects {
-            println!("Project '{}' is raising ${:.2}", project.name, project.goal);
+        let comment = try!(client.hide_comment(&principal, id, args.cmd_hide));
+        (comment, format!("{} {} #{}.", user, if args.cmd_hide { "hid" } else { "revealed" }, id))
+    };
+
+    let json = output::comment_json(&comment.to_json(), comment.visible_body());
+    emit(&args.flag_format, &json, Table::new(COMMENT_COLUMNS, &json), || println!("{}", text))
+}
+
+/// List all comment threads on a project.
+fn cmd_comments(client: &Client, args: Args) -> Result<()> {
+    let name    = args.arg_name.unwrap();
+    let threads = try!(client.list_comments(&name));
+
+    // Tables hold one row per comment, with replies after their parents.
+    let json = Json::Array(threads.iter().map(output::thread_json).collect());
+    let mut rows = vec![];
+    for thread in &threads {
+        thread_rows(thread, 0, &mut rows);
+    }
+    let table = Table::new(&["comment_id", "parent_id", "depth", "author", "backer", "flagged", "body",
+                             "date_created", "date_edited"], &Json::Array(rows));
+
+    emit(&args.flag_format, &json, table, || {
+        if threads.is_empty() {
+            println!("Nobody has commented on {} yet. Start the conversation!", name);
+        } else {
+            for thread in &threads {
+                print_thread(thread, 0);
+            }
+        }
+    })
+}
+
+/// Print a comment and its replies, indented by depth.
+fn print_thread(thread: &Thread, depth: usize) {
+    let comment = &thread.comment;
+    let indent  = "  ".repeat(depth);
+    let badge   = if thread.backer { " [backer]" } else { "" };
+    let flag    = if comment.flagged { " [flagged]" } else { "" };
+    let edited  = if comment.date_edited.is_some() { " (edited)" } else { "" };
+
+    println!("{}#{} {}{}{}: {}{}", indent, comment.comment_id, thread.author,
+             badge, flag, comment.visible_body(), edited);
+
+    for reply in &thread.replies {
+        print_thread(reply, depth + 1);
+    }
+}
+
+/// Collect a comment and its replies as table rows, noting the depth of each.
+fn thread_rows(thread: &Thread, depth: usize, rows: &mut Vec<Json>) {
+    let mut json = output::thread_json(thread);
+    if let Json::Object(ref mut map) = json {
+        map.remove("replies");
+        map.insert("depth".to_owned(), depth.to_json());
+    }
+    rows.push(json);
+
+    for reply in &thread.replies {
+        thread_rows(reply, depth + 1, rows);
+    }
+}
+
+/// Restrict or open up commenting on a project.
+fn cmd_restrict(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let name    = args.arg_name.unwrap();
+    let restrict = args.cmd_restrict;
+    let project = try!(client.restrict_comments(&principal, &name, restrict));
+
+    let json = api::project_json(&project);
+    emit(&args.flag_format, &json, Table::new(&["name", "comments_backers_only"], &json), || {
+        if restrict {
+            println!("Only backers can comment on {} now.", name);
+        } else {
+            println!("Anyone can comment on {} now.", name);
+        }
+    })
+}
+
+/// Change a user's role.
+fn cmd_role(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let user    = args.arg_user.unwrap();
+    let role    = try!(args.arg_role.as_ref().unwrap().parse::<Role>());
+    let account = try!(client.set_role(&principal, &user, role));
+
+    let json = api::user_json(&account);
+    emit(&args.flag_format, &json, Table::new(USER_COLUMNS, &json), || {
+        println!("{} is now a{} {}.", user, if role == Role::Admin { "n" } else { "" }, role);
+    })
+}
+
+/// Print a script or snapshot of the schema's data.
+fn cmd_dump(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    if args.flag_snapshot {
+        println!("{}", try!(client.dump(&principal)).pretty());
+    } else {
+        print!("{}", try!(client.dump_script(&principal)));
+    }
+    Ok(())
+}
+
+/// Load a snapshot into an empty schema.
+fn cmd_restore(client: &Client, args: Args) -> Result<()> {
+    let filename = args.arg_snapshot.unwrap();
+    let text    = try!(read_file_as_string(&filename));
+    let snapshot = try!(Json::from_str(&text).map_err(json::DecoderError::ParseError));
+    let count   = try!(client.restore(&snapshot));
+
+    let mut json = BTreeMap::new();
+    json.insert("schema".to_owned(), client.schema.to_json());
+    json.insert("rows".to_owned(), count.to_json());
+    let json = Json::Object(json);
+
+    emit(&args.flag_format, &json, Table::new(&["schema", "rows"], &json), || {
+        println!("Restored {} rows from {} into schema '{}'.", count, filename, client.schema);
+    })
+}
+
+/// Import projects or pledges from a CSV file, after checking every row.
+fn cmd_import(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let filename = args.arg_csv.unwrap();
+    let dry_run = args.flag_dry_run;
+    let kind    = if args.cmd_projects { import::Kind::Projects } else { import::Kind::Pledges };
+    let mapping = try!(Mapping::parse(kind, args.flag_map.as_ref().map_or("", |m| &m[..])));
+    let text    = try!(read_file_as_string(&filename));
+    let checked = try!(client.check_import(&principal, kind, &text, &mapping));
+
+    // Rejections go to stderr when stdout holds a result to parse.
+    let text_format = try!(args.flag_format.parse::<Format>()) == Format::Text;
+    for rejected in &checked.rejected {
+        if text_format {
+            println!("{}:{}: {}", filename, rejected.line, rejected.error);
+        } else {
+            let _ = writeln!(io::stderr(), "{}:{}: {}", filename, rejected.line, rejected.error);
+        }
+    }
+
+    if let Some(ref path) = args.flag_rejects {
+        let mut f = try!(File::create(path));
+        try!(f.write_all(import::rejects_csv(&checked).as_bytes()));
+    }
+
+    if !checked.rejected.is_empty() && !args.flag_skip_rejected {
+        return Err(From::from(validate::Error::RowsRejected(checked.rejected.len())));
+    }
+
+    let count = if dry_run { 0 } else { try!(client.import(&principal, &checked)) };
+
+    let mut json = BTreeMap::new();
+    json.insert("kind".to_owned(), kind.as_str().to_json());
+    json.insert("accepted".to_owned(), checked.accepted.len().to_json());
+    json.insert("rejected".to_owned(), checked.rejected.len().to_json());
+    json.insert("imported".to_owned(), count.to_json());
+    let json = Json::Object(json);
+
+    emit(&args.flag_format, &json, Table::new(&["kind", "accepted", "rejected", "imported"], &json), || {
+        if dry_run {
+            println!("{} of {} rows passed. Nothing was imported.",
+                     checked.accepted.len(), checked.accepted.len() + checked.rejected.len());
+        } else {
+            println!("Imported {} {} from {}, skipping {} rejected rows.",
+                     count, kind.as_str(), filename, checked.rejected.len());
+        }
+    })
+}
+
+/// Fill the database with generated users, projects and pledges.
+fn cmd_seed(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let options = seed::Options {
+        projects: args.flag_projects,
+        users: args.flag_users,
+        pledges: args.flag_pledges,
+        seed: args.flag_seed,
+    };
+    let dataset = try!(client.seed(&principal, &options));
+
+    let mut json = BTreeMap::new();
+    json.insert("users".to_owned(), dataset.users.len().to_json());
+    json.insert("projects".to_owned(), dataset.projects.len().to_json());
+    json.insert("pledges".to_owned(), dataset.pledges.len().to_json());
+    json.insert("seed".to_owned(), options.seed.to_json());
+    let json = Json::Object(json);
+
+    emit(&args.flag_format, &json, Table::new(&["users", "projects", "pledges", "seed"], &json), || {
+        println!("Seeded {} users, {} projects and {} pledges from seed {}.",
+                 dataset.users.len(), dataset.projects.len(), dataset.pledges.len(), options.seed);
+    })
+}
+
+/// Time concurrent operations against a freshly seeded schema.
+fn cmd_bench(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let schema = args.arg_schema.unwrap();
+    let seed = seed::Options {
+        projects: args.flag_projects,
+        users: args.flag_users,
+        pledges: 0,
+        seed: args.flag_seed,
+    };
+    let options = bench::Options {
+        workers: args.flag_workers,
+        operations: args.flag_ops,
+        mix: try!(bench::Mix::parse(&args.flag_mix)),
+        seed: args.flag_seed,
+    };
+
+    let report = try!(client.bench(&principal, &schema, &seed, &options));
+    let json = report.to_json();
+    let operations = json.find("operations").cloned().unwrap_or(Json::Null);
+
+    emit(&args.flag_format, &json, Table::new(BENCH_COLUMNS, &operations), || {
+        println!("{} operations from {} workers in {:.2}s against schema '{}' ({:.1} ops/s).",
+                 report.count(), report.workers, report.seconds(), schema, report.throughput(report.count()));
+        for summary in &report.operations {
+            println!("  {:<6} {:>6} ops {:>8.1} ops/s  p50 {:>7.2}ms  p95 {:>7.2}ms  p99 {:>7.2}ms  {} failed",
+                     summary.op.as_str(), summary.count, report.throughput(summary.count),
+                     summary.p50, summary.p95, summary.p99, summary.failed());
+            for (error, count) in &summary.errors {
+                println!("           {:>6} x {}", count, error);
+            }
+        }
+    })
+}
+
+/// Compare the database's tables with the generated models, failing if they differ.
+fn cmd_schema_check(client: &Client, args: Args) -> Result<()> {
+    let drift = try!(client.check_schema());
+
+    // Differences go to stderr when stdout holds a result to parse.
+    let text_format = try!(args.flag_format.parse::<Format>()) == Format::Text;
+    for difference in &drift {
+        if text_format {
+            println!("{}", difference);
+        } else {
+            let _ = writeln!(io::stderr(), "{}", difference);
+        }
+    }
+
+    if !drift.is_empty() {
+        return Err(From::from(validate::Error::SchemaDrift(drift.len())));
+    }
+
+    let tables = db::get_all_tables().len();
+    let mut json = BTreeMap::new();
+    json.insert("schema".to_owned(), client.schema.to_json());
+    json.insert("tables".to_owned(), tables.to_json());
+    let json = Json::Object(json);
+
+    emit(&args.flag_format, &json, Table::new(&["schema", "tables"], &json), || {
+        println!("Schema '{}' matches the generated models for all {} tables.", client.schema, tables);
+    })
+}
+
+/// Check the configuration and connect with it, showing where each setting came from.
+fn cmd_config_check(args: &Args) -> Result<()> {
+    let config = try!(Config::load(&args.flag_config));
+    try!(config.check());
+
+    let mail = match config.mail {
+        Some(ref mail) => match (&mail.maildir, &mail.host) {
+            (&Some(ref maildir), _) => format!("maildir {}, from {}", maildir, mail.from),
+            (_, &Some(ref host)) => format!("smtp {}:{}, from {}", host, mail.port, mail.from),
+            _ => "none".to_owned(),
+        },
+        None => "none".to_owned(),
+    };
+    let settings = vec![("uri", config.masked_uri()),
+                        ("schema", config.schema.clone()),
+                        ("sql_file", config.sql_file.clone()),
+                        ("password_file", config.password_file.clone().unwrap_or("none".to_owned())),
+                        ("mail", mail)];
+
+    let json = config.to_json();
+    emit(&args.flag_format, &json, Table::new(&["file", "profile", "uri", "schema", "sql_file"], &json), || {
+        match config.profile {
+            Some(ref profile) => println!("{} is valid with profile {}, and connects to the database.",
+                                          config.filename, profile),
+            None => println!("{} is valid, and connects to the database.", config.filename),
+        }
+        for &(key, ref value) in &settings {
+            let source = config.sources.get(key).map_or("", |s| &s[..]);
+            if source.is_empty() {
+                println!("  {:<14} {}", key, value);
+            } else {
+                println!("  {:<14} {}  ({})", key, value, source);
+            }
+        }
+    })
+}
+
+/// Offer a reward tier on a project.
+fn cmd_reward(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let name    = args.arg_name.unwrap();
+    let title   = args.arg_title.unwrap();
+    let minimum = args.arg_amount.unwrap();
+    let reward  = try!(client.add_reward(&principal, &name, &title, minimum));
+
+    let json = reward.to_json();
+    emit(&args.flag_format, &json, Table::new(REWARD_COLUMNS, &json), || {
+        println!("Project '{}' offers '{}' for pledges of ${:.2} or more.", name, reward.title, reward.minimum);
+    })
+}
+
+/// List a project's reward tiers.
+fn cmd_rewards(client: &Client, args: Args) -> Result<()> {
+    let name    = args.arg_name.unwrap();
+    let rewards = try!(client.list_rewards(&name));
+
+    let json = rewards.to_json();
+    emit(&args.flag_format, &json, Table::new(REWARD_COLUMNS, &json), || {
+        if rewards.is_empty() {
+            println!("{} doesn't offer any rewards.", name);
+        } else {
+            for reward in &rewards {
+                println!("-- ${:.2} or more: {}", reward.minimum, reward.title);
+            }
+        }
+    })
+}
+
+/// Export the backers of a project for fulfillment, as CSV on stdout or to a CSV or XLSX file.
+fn cmd_export_backers(client: &Client, args: Args) -> Result<()> {
+    let principal = try!(principal(client, &args));
+    let name    = args.arg_name.unwrap();
+    let backers = try!(client.export_backers(&principal, &name));
+
+    match args.flag_output {
+        Some(ref path) => {
+            let data = if path.to_lowercase().ends_with(".xlsx") {
+                fulfillment::xlsx(&name, &backers)
+            } else {
+                fulfillment::csv(&backers).into_bytes()
+            };
+            let mut f = try!(File::create(path));
+            try!(f.write_all(&data));
+            println!("Exported {} backers of {} to {}.", backers.len(), name, path);
         }
+        None => print!("{}", fulfillment::csv(&backers)),
     }
+    Ok(())
 }
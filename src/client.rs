@@ -1,24 +1,49 @@
 //! The high-level client library for interacting with Kickstarter.
 use {Error, Result};
-use models::{Pledge, Project, User};
+use account::{Principal, Role};
+use audit::Filter;
+use bench::{self, Report};
+use comment::Thread;
+use config::{self, Config};
+use drift::{self, Drift};
+use fulfillment::{self, Backer};
+use import::{self, Import, Mapping};
+use mail::{self, Kind};
+use models::{Audit, Comment, Event, Mail, MailPreference, Pledge, Project, Refund, Reward, Token, User,
+             Webhook, WebhookDelivery};
+use notify::Subscription;
+use refund::Reason;
+use seed::{self, Dataset, Options};
+use snapshot;
+use testing::Ephemeral;
 
-use codegenta::generator::{self, Config};
+use chrono::naive::datetime::NaiveDateTime;
+use codegenta::generator::{self, Config as GenConfig};
+use regex::Regex;
+use rustc_serialize::json::Json;
+use rustorm::dao::Value;
 use rustorm::database::Database;
 use rustorm::pool::{ManagedPool, Platform};
 
+use std::cell::{Cell, RefCell};
+use std::env;
 use std::fs::File;
 use std::io::Read;
-use toml;
 
-const ERR_PARSING_CONFIG: &'static str = "Failed to parse configuration file";
-const ERR_MISSING_URI:    &'static str = "Configuration has no database connection string 'uri'";
-const ERR_FAILED_BUILD:   &'static str = "Failed to run one or more build commands; skipping model generation.";
+const ERR_FAILED_BUILD:   &'static str = "Failed to run a build command; skipping model generation.";
+const ERR_REPLAY_SELF:    &'static str = "Events cannot be replayed into the schema they are read from.";
+const ERR_BENCH_SELF:     &'static str = "Benchmarks rebuild their schema, so they cannot run against this one.";
+const ERR_NO_MAIL:        &'static str = "Configuration has no [mail] section, so mail cannot be sent.";
+const ERR_SCHEMA_BUILT:   &'static str = "The schema has already been built; log in as an admin to rebuild it.";
 const SUCCESS_BUILD:      &'static str = "Successfully built the database!";
 const SUCCESS_GENERATION: &'static str = "Generated models into the db module.";
 
+lazy_static! {
+    static ref SCHEMA_NAME: Regex = Regex::new(r"\bkickstarter\b").unwrap();
+}
+
 // Default configurations.
-const DEFAULT_SCHEMA:     &'static str = "kickstarter";
-const DEFAULT_SQL_CONFIG: &'static str = "data/tables.sql";
+const DEFAULT_ACTOR:      &'static str = "system";
 
 /// Interfaces with a Kickstarter application running on a PostgreSQL database.
 pub struct Client {
@@ -26,8 +51,18 @@ pub struct Client {
     pub uri: String,
     /// Database schema name for kickstarter data.
     pub schema: String,
+    /// Name recorded in the audit log for changes made through this client by no one in particular.
+    actor: String,
+    /// Name of the user the client is acting for, recorded in the audit log in place of `actor`.
+    principal: RefCell<Option<String>>,
+    /// The .sql file used to build new schemas.
+    sql_file: String,
+    /// Settings for sending mail, if configured.
+    mail: Option<mail::Config>,
     /// Persistent database connection.
     db: Platform,
+    /// Number of transactions currently open on the connection.
+    depth: Cell<usize>,
 }
 
 impl Client {
@@ -39,50 +74,55 @@ impl Client {
         Ok(Client {
             uri: uri.to_owned(),
             schema: schema.to_owned(),
+            actor: env::var("USER").unwrap_or(DEFAULT_ACTOR.to_owned()),
+            principal: RefCell::new(None),
+            sql_file: config::DEFAULT_SQL_FILE.to_owned(),
+            mail: None,
             db: db,
+            depth: Cell::new(0),
         })
     }
 
-    /// Creates a new Kickstarter client using the provided .toml configuration filename.
-    /// If bootstrap is true, the client will wipe and recreate the database
-    /// using the sql_file specified in the configuration.
-    pub fn with_config(filename: &str, bootstrap: bool, sync: bool) -> Result<Client> {
-
-        // Open config file
-        let mut toml = try!(Client::read_file_as_string(filename));
-        let mut parser = toml::Parser::new(&mut toml);
-
-        let config = match parser.parse() {
-            Some(config) => config,
-            None => return Err(Error::Config(format!("{}: {:?}", ERR_PARSING_CONFIG, parser.errors))),
-        };
-
-        // Read config for schema
-        let schema = match config.get("schema") {
-            Some(ref s) => s.as_str().unwrap(),
-            None => DEFAULT_SCHEMA,
-        };
+    /// Returns the name recorded in the audit log for changes made through this client:
+    /// the user it is acting for, if any, or else its actor.
+    pub fn actor(&self) -> String {
+        self.principal.borrow().clone().unwrap_or(self.actor.clone())
+    }
 
-        // Retrieve and open database connection uri        
-        let client = match config.get("uri") {
-            Some(ref uri) => try!(Client::new(uri.as_str().unwrap(), schema)),
-            None => return Err(Error::Config(ERR_MISSING_URI.to_owned())),
-        };
+    /// Sets the name recorded in the audit log for changes made through this client while it
+    /// isn't acting for a user. Defaults to the current system user.
+    pub fn set_actor(&mut self, actor: &str) {
+        self.actor = actor.to_owned();
+    }
 
-        // Bootstrap database and generated models if desired
-        if bootstrap {
-            let sql_file = match config.get("sql_file") {
-                Some(ref s) => s.as_str().unwrap(),
-                None => DEFAULT_SQL_CONFIG,
-            };
+    /// Records later changes made through this client in the audit log under the principal's
+    /// name, or under the client's actor again if None. Logging in, authenticating a token and
+    /// every method that takes a principal act for that user, so servers answering many users
+    /// call this with None before each request.
+    pub fn act_as(&self, principal: Option<&Principal>) {
+        *self.principal.borrow_mut() = principal.map(|principal| principal.name().to_owned());
+    }
 
-            let cmds = try!(Client::read_file_as_string(sql_file));
-            try!(client.bootstrap(&cmds, schema, sync));
-        }
+    /// Creates a new Kickstarter client using the provided .toml configuration filename,
+    /// with the profile named by $KSR_PROFILE and any `KSR_*` overrides in the environment applied.
+    pub fn with_config(filename: &str) -> Result<Client> {
+        Client::from_config(&try!(Config::load(filename)))
+    }
 
+    /// Creates a new Kickstarter client from a configuration that has already been read.
+    pub fn from_config(config: &Config) -> Result<Client> {
+        let mut client = try!(Client::new(&config.uri, &config.schema));
+        client.sql_file = config.sql_file.clone();
+        client.mail = config.mail.clone();
         Ok(client)
     }
 
+    /// Creates a client on a new, uniquely named schema, built from the default .sql file and
+    /// dropped when the client is. Tests that each use one can run in parallel against one database.
+    pub fn ephemeral(uri: &str) -> Result<Ephemeral> {
+        Ephemeral::new(uri)
+    }
+
     /// Opens a file and returns the content as a String.
     fn read_file_as_string(filename: &str) -> Result<String> {
         let mut f = try!(File::open(filename));
@@ -91,26 +131,46 @@ impl Client {
         Ok(s)
     }
 
-    /// Rebuilds the database schema and models.
+    /// Wipes and recreates the database using the sql_file specified in the configuration,
+    /// and regenerates the models if sync is true. Only admins may rebuild a database;
+    /// a principal is only optional while the schema is missing or has no tables.
+    /// Returns the error of the first build command that fails, leaving the schema half built.
+    pub fn build(&self, principal: Option<&Principal>, sync: bool) -> Result<()> {
+        match principal {
+            Some(principal) => try!(self.require(principal, Role::Admin)),
+            None => if try!(self.is_built()) {
+                return Err(Error::PermissionDenied(ERR_SCHEMA_BUILT.to_owned()));
+            },
+        }
+
+        let cmds = try!(Client::read_file_as_string(&self.sql_file));
+        self.bootstrap(&cmds, &self.schema, sync)
+    }
+
+    /// Returns true if the schema exists and has any tables.
+    fn is_built(&self) -> Result<bool> {
+        let sql = "SELECT 1 FROM information_schema.tables WHERE table_schema = $1 LIMIT 1";
+        let tables = try!(self.db().execute_sql_with_return(sql, &vec![Value::String(self.schema.clone())]));
+        Ok(!tables.is_empty())
+    }
+
+    /// Rebuilds the database schema and models, stopping at the first command that fails.
+    /// Commands written for the default schema are rewritten to build the provided schema instead.
     fn bootstrap(&self, cmds: &str, schema: &str, sync: bool) -> Result<()> {
         try!(self.db().execute_sql(&format!("DROP SCHEMA IF EXISTS {} CASCADE", schema), &vec![]));
 
-        let mut failed = false;
+        let cmds = SCHEMA_NAME.replace_all(cmds, schema);
+
         for cmd in cmds.split("\n\n") {
-            let result = self.db().execute_sql(cmd, &vec![]);
-            if let Err(ref err) = result {
-                println!("{}", err);
-                failed = true;
+            if let Err(err) = self.db().execute_sql(cmd, &vec![]) {
+                println!("{}", ERR_FAILED_BUILD);
+                return Err(Error::Database(err));
             }
         }
 
-        if failed {
-            println!("{}", ERR_FAILED_BUILD);
-        } else {
-            println!("{}", SUCCESS_BUILD);
-            if sync {
-                self.sync();
-            }
+        println!("{}", SUCCESS_BUILD);
+        if sync {
+            self.generate_models();
         }
 
         Ok(())
@@ -121,6 +181,35 @@ impl Client {
         self.db.as_ref()
     }
     
+    /// Runs the closure inside a database transaction, committing if it succeeds
+    /// and rolling back if it returns an error. Nested calls use savepoints.
+    pub fn transaction<T, F>(&self, f: F) -> Result<T> where F: FnOnce() -> Result<T> {
+        let depth = self.depth.get();
+        let (begin, commit, rollback) = if depth == 0 {
+            ("BEGIN".to_owned(), "COMMIT".to_owned(), "ROLLBACK".to_owned())
+        } else {
+            (format!("SAVEPOINT ksr_{}", depth),
+             format!("RELEASE SAVEPOINT ksr_{}", depth),
+             format!("ROLLBACK TO SAVEPOINT ksr_{}", depth))
+        };
+
+        try!(self.db().execute_sql(&begin, &vec![]));
+        self.depth.set(depth + 1);
+        let result = f();
+        self.depth.set(depth);
+
+        match result {
+            Ok(val) => {
+                try!(self.db().execute_sql(&commit, &vec![]));
+                Ok(val)
+            }
+            Err(err) => {
+                let _ = self.db().execute_sql(&rollback, &vec![]);
+                Err(err)
+            }
+        }
+    }
+
     /// Returns the full table namespace.
     pub fn table(&self, table: &str) -> String {
         format!("{}.{}", self.schema, table)
@@ -132,9 +221,22 @@ impl Client {
         format!("{}.{} {}", self.schema, table, &table[..2])
     }
 
-    /// Syncs generated models in src/lib/gen with database tables.
-    pub fn sync(&self) {
-        let config = Config {
+    /// Syncs generated models in src/db with database tables. Requires the admin role.
+    pub fn sync(&self, principal: &Principal) -> Result<()> {
+        try!(self.require(principal, Role::Admin));
+        self.generate_models();
+        Ok(())
+    }
+
+    /// Compares the schema's tables with the generated models in src/db, without changing either.
+    /// Returns every difference found, so an empty list means the two agree.
+    pub fn check_schema(&self) -> Result<Vec<Drift>> {
+        drift::check(&self)
+    }
+
+    /// Generates models in src/db from the database tables.
+    fn generate_models(&self) {
+        let config = GenConfig {
             base_module: Some("db".to_owned()),
             include_table_references: true,
             use_condensed_name: true,
@@ -147,14 +249,87 @@ impl Client {
         println!("{}", SUCCESS_GENERATION);
     }
 
-    /// Creates a new Kickstarter project with the specified name and goal amount in US dollars.
-    pub fn create_project(&self, project_name: &str, amount: f64) -> Result<Project> {
-        Project::create(&self, project_name, amount)
+    /// Registers a new backer account with a unique name, email address and password.
+    pub fn register(&self, user: &str, email: &str, password: &str) -> Result<User> {
+        User::register(&self, user, email, password)
+    }
+
+    /// Registers the first admin of a schema that has none, such as one that was just built.
+    /// This is how a new installation is bootstrapped from the command line; the REST API and
+    /// JSON-RPC only ever register backers.
+    pub fn register_admin(&self, user: &str, email: &str, password: &str) -> Result<User> {
+        User::register_admin(&self, user, email, password)
+    }
+
+    /// Checks a user's password and issues a new API token.
+    /// Returns the authenticated principal and the token, which is only shown this once.
+    /// The client acts for the principal afterwards.
+    pub fn login(&self, user: &str, password: &str) -> Result<(Principal, String)> {
+        let (principal, token) = try!(Token::login(&self, user, password));
+        self.act_as(Some(&principal));
+        Ok((principal, token))
+    }
+
+    /// Returns the principal that an API token was issued to. The client acts for the
+    /// principal afterwards.
+    pub fn authenticate(&self, token: &str) -> Result<Principal> {
+        let principal = try!(Token::authenticate(&self, token));
+        self.act_as(Some(&principal));
+        Ok(principal)
+    }
+
+    /// Revokes an API token.
+    pub fn logout(&self, token: &str) -> Result<Token> {
+        Token::revoke(&self, token)
+    }
+
+    /// Revokes every API token issued to the principal.
+    pub fn logout_everywhere(&self, principal: &Principal) -> Result<Vec<Token>> {
+        self.act_as(Some(principal));
+        Token::revoke_all(&self, principal)
+    }
+
+    /// Returns the principal's API tokens that are still valid.
+    pub fn list_tokens(&self, principal: &Principal) -> Result<Vec<Token>> {
+        Token::list(&self, principal)
+    }
+
+    /// Creates a new Kickstarter project run by the principal, with the specified name
+    /// and goal amount in US dollars. Requires the creator role.
+    pub fn create_project(&self, creator: &Principal, project_name: &str, amount: f64) -> Result<Project> {
+        try!(creator.require(Role::Creator));
+        Project::create_by(&self, Some(creator.name()), project_name, amount)
     }
     
-    /// Backs an existing Kickstarter project with the specified user, credit card, and contribution amount.
-    pub fn back_project(&self, user: &str, project_name: &str, card: &str, amount: f64) -> Result<Pledge> {
-        Pledge::create(&self, user, project_name, card, amount)
+    /// Backs an existing Kickstarter project on behalf of the principal, with the specified credit card
+    /// and contribution amount.
+    pub fn back_project(&self, principal: &Principal, project_name: &str, card: &str, amount: f64) -> Result<Pledge> {
+        try!(self.require(principal, Role::Backer));
+        Pledge::create(&self, principal.name(), project_name, card, amount)
+    }
+
+    /// Ends a project's campaign and tells its backers whether it succeeded.
+    /// Returns the closed project and the total amount it raised.
+    /// Only the project's creator or an admin may close it.
+    pub fn close_project(&self, principal: &Principal, project_name: &str) -> Result<(Project, f64)> {
+        try!(self.require_creator(principal, project_name, Role::Admin));
+        Project::close(&self, project_name)
+    }
+
+    /// Withdraws the principal's pledge from a project.
+    pub fn cancel_pledge(&self, principal: &Principal, project_name: &str) -> Result<Pledge> {
+        try!(self.require(principal, Role::Backer));
+        Pledge::cancel(&self, principal.name(), project_name)
+    }
+
+    /// Returns a project by name.
+    pub fn get_project(&self, project_name: &str) -> Result<Project> {
+        Project::get(&self, project_name)
+    }
+
+    /// Returns a user by name.
+    pub fn get_user(&self, user: &str) -> Result<User> {
+        User::get(&self, user)
     }
 
     /// Returns a a list of all pledges (and users) towards a project,
@@ -172,4 +347,298 @@ impl Client {
     pub fn list_backed_projects(&self, user: &str) -> Result<Vec<Pledge>> {
         User::list_pledges(&self, user)
     }
+
+    /// Returns one page of projects, oldest first, and how many projects there are in all.
+    pub fn list_projects_page(&self, offset: usize, limit: usize) -> Result<(Vec<Project>, usize)> {
+        Project::list_page(&self, offset, limit)
+    }
+
+    /// Returns one page of the pledges towards a project, with their backers,
+    /// and how many pledges the project has in all.
+    pub fn list_backers_page(&self, project_name: &str, offset: usize, limit: usize) -> Result<(Vec<Pledge>, usize)> {
+        Project::list_pledges_page(&self, project_name, offset, limit)
+    }
+
+    /// Returns one page of the pledges made by a user, with their projects,
+    /// and how many pledges the user has made in all.
+    pub fn list_backed_projects_page(&self, user: &str, offset: usize, limit: usize) -> Result<(Vec<Pledge>, usize)> {
+        User::list_pledges_page(&self, user, offset, limit)
+    }
+
+    /// Refunds some or all of a user's pledge towards a project.
+    /// Only the project's creator or an admin may issue refunds.
+    pub fn refund_pledge(&self, principal: &Principal, user: &str, project_name: &str,
+                         amount: Option<f64>, reason: Reason) -> Result<Refund> {
+        try!(self.require_creator(principal, project_name, Role::Admin));
+        Refund::refund(&self, user, project_name, amount, reason)
+    }
+
+    /// Records a chargeback reported by a payment processor against a card used to back a project.
+    /// Requires the admin role.
+    pub fn record_chargeback(&self, principal: &Principal, project_name: &str, card: &str, amount: Option<f64>,
+                             reason: Reason, reference: Option<&str>) -> Result<Refund> {
+        try!(self.require(principal, Role::Admin));
+        Refund::chargeback(&self, project_name, card, amount, reason, reference)
+    }
+
+    /// Returns all refunds and chargebacks made against a project.
+    /// Only the project's creator or an admin may list them.
+    pub fn list_refunds(&self, principal: &Principal, project_name: &str) -> Result<Vec<Refund>> {
+        try!(self.require_creator(principal, project_name, Role::Admin));
+        Refund::list(&self, project_name)
+    }
+
+    /// Rebuilds users, projects, pledges and refunds in a fresh schema by replaying the event log.
+    /// If a time is provided, only events up to that time are replayed, reconstructing the state
+    /// of Kickstarter at that moment. The target schema is dropped and rebuilt from the .sql file.
+    /// Returns the number of events replayed. Requires the admin role.
+    pub fn replay(&self, principal: &Principal, target_schema: &str,
+                  until: Option<NaiveDateTime>) -> Result<usize> {
+        try!(self.require(principal, Role::Admin));
+        if target_schema == self.schema {
+            return Err(Error::Config(ERR_REPLAY_SELF.to_owned()));
+        }
+
+        let cmds = try!(Client::read_file_as_string(&self.sql_file));
+        let target = try!(Client::new(&self.uri, target_schema));
+        try!(target.bootstrap(&cmds, target_schema, false));
+
+        Event::replay(&self, &target, until)
+    }
+
+    /// Rebuilds the target schema, seeds it with users and projects, and runs a mix of creating,
+    /// backing and listing projects against it from concurrent workers. The target schema is
+    /// dropped and rebuilt from the .sql file. Requires the admin role.
+    pub fn bench(&self, principal: &Principal, target_schema: &str, seed: &seed::Options,
+                 options: &bench::Options) -> Result<Report> {
+        try!(self.require(principal, Role::Admin));
+        if target_schema == self.schema {
+            return Err(Error::Config(ERR_BENCH_SELF.to_owned()));
+        }
+
+        let dataset = try!(seed::generate(seed));
+        let cmds = try!(Client::read_file_as_string(&self.sql_file));
+        let target = try!(Client::new(&self.uri, target_schema));
+        try!(target.bootstrap(&cmds, target_schema, false));
+        try!(seed::load(&target, &dataset));
+
+        bench::run(&self.uri, target_schema, &dataset, options)
+    }
+
+    /// Returns a JSON snapshot of every row in the schema, with its IDs and timestamps.
+    /// Snapshots include card numbers and password hashes, so only admins may take them.
+    pub fn dump(&self, principal: &Principal) -> Result<Json> {
+        try!(self.require(principal, Role::Admin));
+        snapshot::dump(&self)
+    }
+
+    /// Returns a `.ksr` script that rebuilds the schema's users, projects and pledges in order.
+    /// Scripts include card numbers, so only admins may make them.
+    pub fn dump_script(&self, principal: &Principal) -> Result<String> {
+        try!(self.require(principal, Role::Admin));
+        snapshot::script(&self)
+    }
+
+    /// Loads a JSON snapshot into the schema, which must be built but empty, leaving out API tokens.
+    /// Returns the number of rows restored. Like building a schema without a principal, this is part
+    /// of bootstrapping one from the command line: an empty schema has no accounts to authenticate,
+    /// so the REST API and JSON-RPC never offer it.
+    pub fn restore(&self, snapshot: &Json) -> Result<usize> {
+        snapshot::restore(&self, snapshot)
+    }
+
+    /// Checks every row of a CSV file of projects or pledges, without importing anything.
+    /// Imports are made by admins.
+    pub fn check_import(&self, principal: &Principal, kind: import::Kind, csv: &str,
+                        mapping: &Mapping) -> Result<Import> {
+        try!(self.require(principal, Role::Admin));
+        import::check(&self, kind, csv, mapping)
+    }
+
+    /// Imports the accepted rows of a checked CSV file in a single transaction, creating any users
+    /// they name. Returns the number of rows imported. Requires the admin role.
+    pub fn import(&self, principal: &Principal, import: &Import) -> Result<usize> {
+        try!(self.require(principal, Role::Admin));
+        import::load(&self, import)
+    }
+
+    /// Generates users, projects and pledges from a seed and loads them in a single transaction,
+    /// returning what was generated. Requires the admin role.
+    pub fn seed(&self, principal: &Principal, options: &Options) -> Result<Dataset> {
+        try!(self.require(principal, Role::Admin));
+        let dataset = try!(seed::generate(options));
+        try!(seed::load(&self, &dataset));
+        Ok(dataset)
+    }
+
+    /// Offers a reward tier on a project, for backers whose pledge covers the minimum.
+    /// Available to the project's creator and to admins.
+    pub fn add_reward(&self, principal: &Principal, project_name: &str, title: &str, minimum: f64) -> Result<Reward> {
+        try!(self.require_creator(principal, project_name, Role::Admin));
+        Reward::create(&self, project_name, title, minimum)
+    }
+
+    /// Lists a project's reward tiers, cheapest first.
+    pub fn list_rewards(&self, project_name: &str) -> Result<Vec<Reward>> {
+        Reward::list(&self, project_name)
+    }
+
+    /// Lists the backers of a project who are still owed something, for fulfillment.
+    /// Available to the project's creator and to admins.
+    pub fn export_backers(&self, principal: &Principal, project_name: &str) -> Result<Vec<Backer>> {
+        try!(self.require_creator(principal, project_name, Role::Admin));
+        fulfillment::backers(&self, project_name)
+    }
+
+    /// Opens a dedicated connection that receives a notice for every change
+    /// to projects and pledges in this client's schema, as it is committed.
+    pub fn subscribe(&self) -> Result<Subscription> {
+        Subscription::listen(&self.uri, &self.schema)
+    }
+
+    /// Registers a webhook for a single project, or for every project if none is provided.
+    /// Webhooks are managed by admins.
+    pub fn add_webhook(&self, principal: &Principal, url: &str, secret: &str,
+                       project_name: Option<&str>) -> Result<Webhook> {
+        try!(self.require(principal, Role::Admin));
+        Webhook::register(&self, url, secret, project_name)
+    }
+
+    /// Removes a webhook, along with its pending deliveries and delivery log.
+    pub fn remove_webhook(&self, principal: &Principal, webhook_id: i32) -> Result<Webhook> {
+        try!(self.require(principal, Role::Admin));
+        Webhook::remove(&self, webhook_id)
+    }
+
+    /// Returns every registered webhook.
+    pub fn list_webhooks(&self, principal: &Principal) -> Result<Vec<Webhook>> {
+        try!(self.require(principal, Role::Admin));
+        Webhook::list(&self)
+    }
+
+    /// Sends every webhook delivery that is due. Returns the deliveries that were attempted.
+    pub fn deliver_webhooks(&self, principal: &Principal) -> Result<Vec<WebhookDelivery>> {
+        try!(self.require(principal, Role::Admin));
+        WebhookDelivery::deliver_pending(&self)
+    }
+
+    /// Returns every delivery made to a webhook, along with the log of attempts.
+    pub fn list_deliveries(&self, principal: &Principal, webhook_id: i32) -> Result<Vec<WebhookDelivery>> {
+        try!(self.require(principal, Role::Admin));
+        WebhookDelivery::list(&self, webhook_id)
+    }
+
+    /// Sets or clears the address that the principal's mail is sent to.
+    pub fn set_email(&self, principal: &Principal, email: Option<&str>) -> Result<User> {
+        try!(self.require(principal, Role::Backer));
+        User::set_email(&self, principal.name(), email)
+    }
+
+    /// Sets or clears the address that the principal's rewards are shipped to.
+    pub fn set_shipping_address(&self, principal: &Principal, address: Option<&str>) -> Result<User> {
+        try!(self.require(principal, Role::Backer));
+        User::set_shipping_address(&self, principal.name(), address)
+    }
+
+    /// Turns a kind of mail on or off for the principal.
+    pub fn set_mail_preference(&self, principal: &Principal, kind: Kind, enabled: bool) -> Result<MailPreference> {
+        try!(self.require(principal, Role::Backer));
+        MailPreference::set(&self, principal.name(), kind, enabled)
+    }
+
+    /// Turns off one kind of mail, or all of it, for the user with the unsubscribe token.
+    pub fn unsubscribe(&self, token: &str, kind: Option<Kind>) -> Result<User> {
+        MailPreference::unsubscribe(&self, token, kind)
+    }
+
+    /// Sends all queued mail through the configured transport. Returns the mail that was attempted.
+    /// Requires the admin role.
+    pub fn send_mail(&self, principal: &Principal) -> Result<Vec<Mail>> {
+        try!(self.require(principal, Role::Admin));
+        let config = match self.mail {
+            Some(ref config) => config,
+            None => return Err(Error::Config(ERR_NO_MAIL.to_owned())),
+        };
+
+        let mut transport = config.transport();
+        Mail::send_pending(&self, &mut *transport, &config.from)
+    }
+
+    /// Returns all audit log entries matching the filter, oldest first. Requires the admin role.
+    pub fn audit_log(&self, principal: &Principal, filter: &Filter) -> Result<Vec<Audit>> {
+        try!(self.require(principal, Role::Admin));
+        Audit::search(&self, filter)
+    }
+
+    /// Posts a comment on a project on behalf of the principal.
+    pub fn post_comment(&self, principal: &Principal, project_name: &str, body: &str) -> Result<Comment> {
+        try!(self.require(principal, Role::Backer));
+        Comment::create(&self, principal.name(), project_name, body)
+    }
+
+    /// Replies to an existing comment on behalf of the principal.
+    pub fn reply_to_comment(&self, principal: &Principal, comment_id: i32, body: &str) -> Result<Comment> {
+        try!(self.require(principal, Role::Backer));
+        Comment::reply(&self, principal.name(), comment_id, body)
+    }
+
+    /// Edits a comment on behalf of its author.
+    pub fn edit_comment(&self, principal: &Principal, comment_id: i32, body: &str) -> Result<Comment> {
+        try!(self.require(principal, Role::Backer));
+        Comment::edit(&self, principal.name(), comment_id, body)
+    }
+
+    /// Deletes a comment on behalf of its author.
+    pub fn delete_comment(&self, principal: &Principal, comment_id: i32) -> Result<Comment> {
+        try!(self.require(principal, Role::Backer));
+        Comment::delete(&self, principal.name(), comment_id)
+    }
+
+    /// Hides or reveals a comment on behalf of a moderator.
+    pub fn hide_comment(&self, moderator: &Principal, comment_id: i32, hidden: bool) -> Result<Comment> {
+        try!(moderator.require(Role::Moderator));
+        Comment::hide(&self, moderator.name(), comment_id, hidden)
+    }
+
+    /// Flags or unflags a comment on behalf of a moderator.
+    pub fn flag_comment(&self, moderator: &Principal, comment_id: i32, flagged: bool) -> Result<Comment> {
+        try!(moderator.require(Role::Moderator));
+        Comment::flag(&self, moderator.name(), comment_id, flagged)
+    }
+
+    /// Returns all comment threads on a project.
+    pub fn list_comments(&self, project_name: &str) -> Result<Vec<Thread>> {
+        Comment::list(&self, project_name)
+    }
+
+    /// Sets whether only backers of a project may comment on it.
+    /// Only the project's creator or a moderator may change this.
+    pub fn restrict_comments(&self, principal: &Principal, project_name: &str, backers_only: bool) -> Result<Project> {
+        try!(self.require_creator(principal, project_name, Role::Moderator));
+        Project::set_comments_backers_only(&self, project_name, backers_only)
+    }
+
+    /// Sets a user's role. Requires the admin role.
+    pub fn set_role(&self, principal: &Principal, user: &str, role: Role) -> Result<User> {
+        try!(self.require(principal, Role::Admin));
+        User::set_role(&self, user, role)
+    }
+
+    /// Ensures that the principal has at least the provided role, and acts for it.
+    fn require(&self, principal: &Principal, role: Role) -> Result<()> {
+        self.act_as(Some(principal));
+        principal.require(role)
+    }
+
+    /// Ensures that the principal created the project, or has at least the provided role,
+    /// and acts for it.
+    fn require_creator(&self, principal: &Principal, project_name: &str, role: Role) -> Result<()> {
+        self.act_as(Some(principal));
+        if principal.role() >= role {
+            return Ok(());
+        }
+
+        let project = try!(Project::get(&self, project_name));
+        principal.require_creator(&project, role)
+    }
 }
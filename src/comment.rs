@@ -0,0 +1,260 @@
+//! Module for interacting with project comments.
+pub use models::Comment;
+
+use {validate, Client, Result};
+use account::Role;
+use db::{column, table};
+use models::{Audit, Project, User};
+
+use rustc_serialize::json::ToJson;
+use rustorm::dao::{IsDao, Value};
+use rustorm::query::{Equality, Query};
+
+use std::convert::From;
+
+/// Body shown in place of comments that have been deleted by their author.
+const DELETED_BODY: &'static str = "[deleted]";
+/// Body shown in place of comments that have been hidden by a moderator.
+const HIDDEN_BODY:  &'static str = "[hidden by a moderator]";
+
+/// A comment along with its author, backer badge, and threaded replies.
+#[derive(Debug, Clone)]
+pub struct Thread {
+    /// The comment itself.
+    pub comment: Comment,
+    /// Name of the user that wrote the comment.
+    pub author: String,
+    /// True if the author has backed the project being discussed.
+    pub backer: bool,
+    /// Replies to this comment, oldest first.
+    pub replies: Vec<Thread>,
+}
+
+impl Comment {
+
+    /// Posts a new top-level comment on a project.
+    pub fn create(client: &Client, user: &str, project_name: &str, body: &str) -> Result<Comment> {
+        let project = try!(Project::get(client, project_name));
+        Comment::insert(client, user, &project, None, body)
+    }
+
+    /// Posts a reply to an existing comment, within the same project.
+    pub fn reply(client: &Client, user: &str, parent_id: i32, body: &str) -> Result<Comment> {
+        let parent = try!(Comment::get(client, parent_id));
+        if parent.deleted {
+            return Err(From::from(validate::Error::CommentDoesNotExist));
+        }
+
+        let project: Project = try!(Query::select_all()
+            .from_table(&client.table(table::project))
+            .filter(column::project_id, Equality::EQ, &parent.project_id)
+            .collect_one(client.db()));
+
+        Comment::insert(client, user, &project, Some(parent.comment_id), body)
+    }
+
+    /// Validates and inserts a comment, enforcing the project's comment policy.
+    fn insert(client: &Client, user: &str, project: &Project, parent_id: Option<i32>, body: &str) -> Result<Comment> {
+        try!(validate::length(user, 4, 20));
+        try!(validate::alphanumeric(user));
+        try!(Comment::validate_body(body));
+
+        if project.comments_backers_only && !try!(User::has_backed(client, user, project.project_id)) {
+            return Err(From::from(validate::Error::BackersOnly));
+        }
+
+        client.transaction(|| {
+            let uid = try!(User::upsert(client, user));
+
+            let mut query = Query::insert();
+            query.set(column::project_id, &project.project_id)
+                .set(column::user_id, &uid)
+                .set(column::body, &body);
+
+            if let Some(ref parent_id) = parent_id {
+                query.set(column::parent_id, parent_id);
+            }
+
+            let comment: Comment = try!(query
+                .into_table(&client.table(table::comment))
+                .return_all()
+                .collect_one(client.db()));
+
+            try!(Audit::record(client, "create", table::comment, &comment.comment_id.to_string(),
+                               None, Some(comment.to_json())));
+            Ok(comment)
+        })
+    }
+
+    /// Retrieves a single comment by ID.
+    pub fn get(client: &Client, comment_id: i32) -> Result<Comment> {
+        let mut comments: Vec<Comment> = try!(Query::select_all()
+            .from_table(&client.table(table::comment))
+            .filter(column::comment_id, Equality::EQ, &comment_id)
+            .collect(client.db()));
+
+        comments.pop().ok_or(From::from(validate::Error::CommentDoesNotExist))
+    }
+
+    /// Replaces the body of a comment. Only the author may edit their comments.
+    pub fn edit(client: &Client, user: &str, comment_id: i32, body: &str) -> Result<Comment> {
+        try!(Comment::validate_body(body));
+        try!(Comment::check_author(client, user, comment_id));
+
+        let sql = format!("UPDATE {} SET body = $1, date_edited = localtimestamp \
+                           WHERE comment_id = $2 RETURNING *",
+                          client.table(table::comment));
+
+        let params = vec![Value::String(body.to_owned()), Value::I32(comment_id)];
+        Comment::update(client, "edit", comment_id, &sql, params)
+    }
+
+    /// Deletes a comment. Replies are kept, so the comment is marked as deleted
+    /// rather than removed. Only the author may delete their comments.
+    pub fn delete(client: &Client, user: &str, comment_id: i32) -> Result<Comment> {
+        try!(Comment::check_author(client, user, comment_id));
+
+        let sql = format!("UPDATE {} SET deleted = true WHERE comment_id = $1 RETURNING *",
+                          client.table(table::comment));
+
+        Comment::update(client, "delete", comment_id, &sql, vec![Value::I32(comment_id)])
+    }
+
+    /// Hides or reveals a comment. Only moderators may hide comments.
+    pub fn hide(client: &Client, moderator: &str, comment_id: i32, hidden: bool) -> Result<Comment> {
+        try!(Comment::check_moderator(client, moderator));
+
+        let sql = format!("UPDATE {} SET hidden = $1 WHERE comment_id = $2 RETURNING *",
+                          client.table(table::comment));
+
+        let action = if hidden { "hide" } else { "unhide" };
+        let params = vec![Value::Bool(hidden), Value::I32(comment_id)];
+        Comment::update(client, action, comment_id, &sql, params)
+    }
+
+    /// Flags or unflags a comment for review. Only moderators may flag comments.
+    pub fn flag(client: &Client, moderator: &str, comment_id: i32, flagged: bool) -> Result<Comment> {
+        try!(Comment::check_moderator(client, moderator));
+
+        let sql = format!("UPDATE {} SET flagged = $1 WHERE comment_id = $2 RETURNING *",
+                          client.table(table::comment));
+
+        let action = if flagged { "flag" } else { "unflag" };
+        let params = vec![Value::Bool(flagged), Value::I32(comment_id)];
+        Comment::update(client, action, comment_id, &sql, params)
+    }
+
+    /// Runs an update statement that returns the modified comment, and records the change.
+    fn update(client: &Client, action: &str, comment_id: i32, sql: &str, params: Vec<Value>) -> Result<Comment> {
+        client.transaction(|| {
+            let before = try!(Comment::get(client, comment_id));
+            let mut daos = try!(client.db().execute_sql_with_return(sql, &params));
+            let after = match daos.pop() {
+                Some(dao) => Comment::from_dao(&dao),
+                None => return Err(From::from(validate::Error::CommentDoesNotExist)),
+            };
+
+            try!(Audit::record(client, action, table::comment, &comment_id.to_string(),
+                               Some(before.to_json()), Some(after.to_json())));
+            Ok(after)
+        })
+    }
+
+    /// Returns all comments on a project, threaded by reply.
+    /// Deleted and hidden comments are kept in place so that their replies remain visible.
+    pub fn list(client: &Client, project_name: &str) -> Result<Vec<Thread>> {
+        let pid = try!(Project::get_id(client, project_name));
+
+        let sql = format!("SELECT co.*, us.name AS author, (pl.user_id IS NOT NULL) AS backer \
+                           FROM {} \
+                           INNER JOIN {} ON us.user_id = co.user_id \
+                           LEFT JOIN {} ON pl.project_id = co.project_id AND pl.user_id = co.user_id \
+                           WHERE co.project_id = $1 \
+                           ORDER BY co.comment_id",
+                          client.table_abbr(table::comment),
+                          client.table_abbr(table::user),
+                          client.table_abbr(table::pledge));
+
+        let daos = try!(client.db().execute_sql_with_return(&sql, &vec![pid]));
+
+        let flat = daos.iter().map(|dao| {
+            Thread {
+                comment: Comment::from_dao(dao),
+                author: dao.get("author"),
+                backer: dao.get("backer"),
+                replies: vec![],
+            }
+        }).collect();
+
+        Ok(Thread::nest(flat))
+    }
+
+    /// Returns the body to display, accounting for deleted and hidden comments.
+    pub fn visible_body(&self) -> &str {
+        if self.deleted {
+            DELETED_BODY
+        } else if self.hidden {
+            HIDDEN_BODY
+        } else {
+            &self.body
+        }
+    }
+
+    /// Comment bodies must be between 1 and 2000 characters, non-blank, and printable.
+    pub fn validate_body(body: &str) -> Result<()> {
+        try!(validate::length(body, 1, 2000));
+        try!(validate::not_blank(body));
+        try!(validate::printable(body));
+        Ok(())
+    }
+
+    /// Ensures that the user wrote the comment and that it still exists.
+    fn check_author(client: &Client, user: &str, comment_id: i32) -> Result<()> {
+        let comment = try!(Comment::get(client, comment_id));
+        let author = try!(User::get(client, user));
+
+        if comment.deleted {
+            Err(From::from(validate::Error::CommentDoesNotExist))
+        } else if comment.user_id != author.user_id {
+            Err(From::from(validate::Error::NotCommentAuthor))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Ensures that the user is a moderator.
+    fn check_moderator(client: &Client, user: &str) -> Result<()> {
+        let moderator = try!(User::get(client, user));
+        if moderator.role() >= Role::Moderator {
+            Ok(())
+        } else {
+            Err(From::from(validate::Error::NotModerator))
+        }
+    }
+}
+
+impl Thread {
+
+    /// Nests a flat list of comments, ordered by ID, into threads.
+    fn nest(mut flat: Vec<Thread>) -> Vec<Thread> {
+
+        // Replies always have larger IDs than their parents, so walking backwards
+        // attaches every reply to its parent before the parent itself is moved.
+        let mut roots = vec![];
+        while let Some(mut thread) = flat.pop() {
+            thread.replies.reverse();
+
+            let parent = thread.comment.parent_id.and_then(|pid| {
+                flat.iter().position(|t| t.comment.comment_id == pid)
+            });
+
+            match parent {
+                Some(i) => flat[i].replies.push(thread),
+                None => roots.push(thread),
+            }
+        }
+
+        roots.reverse();
+        roots
+    }
+}
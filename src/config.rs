@@ -0,0 +1,386 @@
+//! Module for reading the .toml configuration that clients are created from.
+//!
+//! ```toml
+//! uri = "postgres://postgres@localhost:5432/kickstarter"
+//! schema = "kickstarter"             # optional
+//! sql_file = "data/tables.sql"       # optional
+//! password_file = "/run/secrets/pg"  # optional; the password is added to the uri
+//!
+//! [mail]                             # optional; see `mail::Config`
+//! from = "noreply@example.com"
+//! maildir = "data/mail"
+//!
+//! [profiles.test]                    # used when $KSR_PROFILE is test
+//! uri = "postgres://postgres@localhost:5432/ksr-test"
+//! ```
+//!
+//! A profile overrides the settings above it, and `KSR_URI`, `KSR_SCHEMA`, `KSR_SQL_FILE` and
+//! `KSR_PASSWORD_FILE` override both. Every key is checked, so a misspelled or mistyped setting
+//! is an error that names the file and the key, instead of being ignored.
+use {mail, Client, Error, Result};
+
+use regex::Regex;
+use rustc_serialize::json::{Json, ToJson};
+use toml::{self, Value};
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::File;
+use std::io::Read;
+
+/// Environment variable naming a schema to use instead of the configured one, such as a test's.
+pub const SCHEMA_VAR: &'static str = "KSR_SCHEMA";
+/// Environment variable holding a connection string to use instead of the configured one.
+pub const URI_VAR: &'static str = "KSR_URI";
+/// Environment variable naming a .sql file to use instead of the configured one.
+pub const SQL_FILE_VAR: &'static str = "KSR_SQL_FILE";
+/// Environment variable naming a file that holds the database password.
+pub const PASSWORD_FILE_VAR: &'static str = "KSR_PASSWORD_FILE";
+/// Environment variable naming the `[profiles.<name>]` section to apply.
+pub const PROFILE_VAR: &'static str = "KSR_PROFILE";
+
+/// The schema used when none is configured.
+pub const DEFAULT_SCHEMA: &'static str = "kickstarter";
+/// The .sql file used when none is configured.
+pub const DEFAULT_SQL_FILE: &'static str = "data/tables.sql";
+
+const ERR_MISSING_URI: &'static str = "no database connection string 'uri'. Set it in the file or with $KSR_URI.";
+const ERR_URI_SCHEME:  &'static str = "must be a postgres:// or postgresql:// connection string";
+const ERR_URI_USER:    &'static str = "names no user, so a password can't be added to it";
+const ERR_URI_HAS_PASSWORD: &'static str = "already has a password, so password_file can't add one";
+
+/// Settings that a profile or the environment can override, with the variables that override them.
+static SETTINGS: &'static [(&'static str, &'static str)] = &[("uri", URI_VAR), ("schema", SCHEMA_VAR),
+                                                             ("sql_file", SQL_FILE_VAR),
+                                                             ("password_file", PASSWORD_FILE_VAR)];
+
+/// Keys of the `[mail]` section, with the type each must have.
+static MAIL_KEYS: &'static [(&'static str, &'static str)] = &[("from", "string"), ("host", "string"),
+                                                              ("port", "integer"), ("maildir", "string")];
+
+lazy_static! {
+    static ref SCHEMA_NAME: Regex = Regex::new(r"^[A-Za-z_][A-Za-z0-9_]{0,62}$").unwrap();
+}
+
+/// A client's configuration, after applying its profile and the environment.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The file the configuration was read from.
+    pub filename: String,
+    /// The profile applied, if any.
+    pub profile: Option<String>,
+    /// PostgreSQL connection URI, including the password from `password_file`, if one was given.
+    pub uri: String,
+    /// Database schema name for kickstarter data.
+    pub schema: String,
+    /// The .sql file used to build new schemas.
+    pub sql_file: String,
+    /// The file the database password was read from, if any.
+    pub password_file: Option<String>,
+    /// Settings for sending mail, if configured.
+    pub mail: Option<mail::Config>,
+    /// Where each setting came from: the file, a profile, an environment variable, or the default.
+    pub sources: BTreeMap<String, String>,
+}
+
+/// The settings given at the top of a file, or in one of its profiles.
+#[derive(Default)]
+struct Layer {
+    settings: BTreeMap<String, String>,
+    mail: Option<Value>,
+}
+
+impl Config {
+
+    /// Reads a configuration file, applying the profile named by $KSR_PROFILE and
+    /// any `KSR_*` overrides in the environment.
+    pub fn load(filename: &str) -> Result<Config> {
+        let mut text = String::new();
+        let read = File::open(filename).and_then(|mut f| f.read_to_string(&mut text));
+        if let Err(err) = read {
+            return Err(Error::Config(format!("{}: {}", filename, err)));
+        }
+        Config::parse(filename, &text, &|var| env::var(var).ok())
+    }
+
+    /// Parses a configuration, looking up the profile and overrides with `env` instead of
+    /// reading the environment. The filename is only used to describe errors.
+    pub fn parse(filename: &str, text: &str, env: &Fn(&str) -> Option<String>) -> Result<Config> {
+        let err = |message: String| Error::Config(format!("{}: {}", filename, message));
+
+        let mut parser = toml::Parser::new(text);
+        let root = match parser.parse() {
+            Some(root) => root,
+            None => {
+                let errors: Vec<String> = parser.errors.iter().map(|e| {
+                    let (line, col) = parser.to_linecol(e.lo);
+                    format!("{}:{}:{}: {}", filename, line + 1, col + 1, e.desc)
+                }).collect();
+                return Err(Error::Config(errors.join("\n")));
+            }
+        };
+
+        let top = try!(layer(&root, "", true).map_err(&err));
+
+        // Every profile is checked, not just the one applied, so that mistakes show up early.
+        let mut profiles = BTreeMap::new();
+        match root.get("profiles") {
+            None => (),
+            Some(&Value::Table(ref table)) => {
+                for (name, value) in table {
+                    let path = format!("profiles.{}", name);
+                    let profile = match *value {
+                        Value::Table(ref profile) => try!(layer(profile, &path, false).map_err(&err)),
+                        ref value => return Err(err(mistyped(&path, "table", value))),
+                    };
+                    profiles.insert(name.clone(), profile);
+                }
+            }
+            Some(value) => return Err(err(mistyped("profiles", "table", value))),
+        }
+
+        let profile = non_empty(env(PROFILE_VAR));
+        let applied = match profile {
+            Some(ref name) => match profiles.get(name) {
+                Some(layer) => Some(layer),
+                None => return Err(err(format!("no [profiles.{}] section, as named by ${}.", name, PROFILE_VAR))),
+            },
+            None => None,
+        };
+
+        // Environment variables override the profile, which overrides the top of the file.
+        let mut sources = BTreeMap::new();
+        let mut settings = BTreeMap::new();
+        for &(key, var) in SETTINGS {
+            let from_profile = applied.and_then(|layer| layer.settings.get(key));
+            let (value, source) = match (non_empty(env(var)), from_profile, top.settings.get(key)) {
+                (Some(value), _, _) => (Some(value), format!("${}", var)),
+                (_, Some(value), _) => (Some(value.clone()), format!("[profiles.{}]", profile.as_ref().unwrap())),
+                (_, _, Some(value)) => (Some(value.clone()), filename.to_owned()),
+                _ => (None, "default".to_owned()),
+            };
+            sources.insert(key.to_owned(), source);
+            if let Some(value) = value {
+                settings.insert(key, value);
+            }
+        }
+
+        let mail = match (applied.and_then(|layer| layer.mail.as_ref()), top.mail.as_ref()) {
+            (Some(mail), _) => {
+                sources.insert("mail".to_owned(), format!("[profiles.{}]", profile.as_ref().unwrap()));
+                Some(try!(mail::Config::from_toml(mail).map_err(|e| err(format!("mail: {}", e)))))
+            }
+            (_, Some(mail)) => {
+                sources.insert("mail".to_owned(), filename.to_owned());
+                Some(try!(mail::Config::from_toml(mail).map_err(|e| err(format!("mail: {}", e)))))
+            }
+            _ => None,
+        };
+
+        let mut uri = match settings.get("uri") {
+            Some(uri) => uri.clone(),
+            None => return Err(err(ERR_MISSING_URI.to_owned())),
+        };
+        if uri.starts_with("postgresql://") {
+            // Both schemes are standard, but the connection pool only recognizes the shorter one.
+            uri = format!("postgres://{}", &uri["postgresql://".len()..]);
+        } else if !uri.starts_with("postgres://") {
+            return Err(err(format!("uri {} {}.", uri, ERR_URI_SCHEME)));
+        }
+
+        let schema = settings.get("schema").cloned().unwrap_or(DEFAULT_SCHEMA.to_owned());
+        if !SCHEMA_NAME.is_match(&schema) {
+            return Err(err(format!("schema {} must be at most 63 letters, digits or underscores, \
+                                    not starting with a digit.", schema)));
+        }
+
+        let password_file = settings.get("password_file").cloned();
+        if let Some(ref path) = password_file {
+            let password = try!(read_password(path).map_err(&err));
+            uri = try!(with_password(&uri, &password).map_err(&err));
+        }
+
+        Ok(Config {
+            filename: filename.to_owned(),
+            profile: profile,
+            uri: uri,
+            schema: schema,
+            sql_file: settings.get("sql_file").cloned().unwrap_or(DEFAULT_SQL_FILE.to_owned()),
+            password_file: password_file,
+            mail: mail,
+            sources: sources,
+        })
+    }
+
+    /// Returns the connection string with its password, if it has one, hidden.
+    pub fn masked_uri(&self) -> String {
+        match split_uri(&self.uri) {
+            Some((prefix, userinfo, rest)) if userinfo.contains(':') => {
+                let user = userinfo.splitn(2, ':').next().unwrap();
+                format!("{}{}:****@{}", prefix, user, rest)
+            }
+            _ => self.uri.clone(),
+        }
+    }
+
+    /// Checks that the .sql file can be read and that the database accepts a connection,
+    /// returning a client connected with the configuration.
+    pub fn check(&self) -> Result<Client> {
+        if let Err(err) = File::open(&self.sql_file) {
+            return Err(Error::Config(format!("{}: sql_file {}: {}", self.filename, self.sql_file, err)));
+        }
+        Client::from_config(self)
+    }
+}
+
+impl ToJson for Config {
+    fn to_json(&self) -> Json {
+        let mail = self.mail.as_ref().map(|mail| {
+            let mut json = BTreeMap::new();
+            json.insert("from".to_owned(), mail.from.to_json());
+            json.insert("host".to_owned(), mail.host.to_json());
+            json.insert("port".to_owned(), mail.port.to_json());
+            json.insert("maildir".to_owned(), mail.maildir.to_json());
+            Json::Object(json)
+        });
+
+        let mut json = BTreeMap::new();
+        json.insert("file".to_owned(), self.filename.to_json());
+        json.insert("profile".to_owned(), self.profile.to_json());
+        json.insert("uri".to_owned(), self.masked_uri().to_json());
+        json.insert("schema".to_owned(), self.schema.to_json());
+        json.insert("sql_file".to_owned(), self.sql_file.to_json());
+        json.insert("password_file".to_owned(), self.password_file.to_json());
+        json.insert("mail".to_owned(), mail.unwrap_or(Json::Null));
+        json.insert("sources".to_owned(), self.sources.to_json());
+        Json::Object(json)
+    }
+}
+
+/// Treats a variable that is set but empty as unset.
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.and_then(|s| if s.is_empty() { None } else { Some(s) })
+}
+
+/// Reads the settings at the top of a file or in a profile, rejecting unknown or mistyped keys.
+/// Only the top of the file may have profiles.
+fn layer(table: &toml::Table, path: &str, top: bool) -> ::std::result::Result<Layer, String> {
+    let mut layer = Layer::default();
+    for (key, value) in table {
+        let name = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+        if SETTINGS.iter().any(|&(setting, _)| setting == key) {
+            match *value {
+                Value::String(ref s) => { layer.settings.insert(key.clone(), s.clone()); }
+                ref value => return Err(mistyped(&name, "string", value)),
+            }
+        } else if key == "mail" {
+            try!(check_mail(&name, value));
+            layer.mail = Some(value.clone());
+        } else if key == "profiles" && top {
+            // Read by the caller.
+        } else {
+            let mut known: Vec<&str> = SETTINGS.iter().map(|&(setting, _)| setting).collect();
+            known.push("mail");
+            if top {
+                known.push("profiles");
+            }
+            return Err(unknown(&name, key, &known));
+        }
+    }
+    Ok(layer)
+}
+
+/// Checks the keys of a `[mail]` section and their types; `mail::Config` checks the rest.
+fn check_mail(path: &str, value: &Value) -> ::std::result::Result<(), String> {
+    let table = match *value {
+        Value::Table(ref table) => table,
+        ref value => return Err(mistyped(path, "table", value)),
+    };
+
+    for (key, value) in table {
+        let name = format!("{}.{}", path, key);
+        match MAIL_KEYS.iter().find(|&&(mail_key, _)| mail_key == key) {
+            Some(&(_, expected)) if type_name(value) != expected => return Err(mistyped(&name, expected, value)),
+            Some(_) => (),
+            None => return Err(unknown(&name, key, &MAIL_KEYS.iter().map(|&(k, _)| k).collect::<Vec<&str>>())),
+        }
+    }
+
+    if let Some(port) = table.get("port").and_then(|p| p.as_integer()) {
+        if port < 1 || port > 65535 {
+            return Err(format!("{}.port must be between 1 and 65535, not {}.", path, port));
+        }
+    }
+    Ok(())
+}
+
+/// Describes a key of the wrong type.
+fn mistyped(name: &str, expected: &str, value: &Value) -> String {
+    let article = |kind: &str| if kind == "integer" || kind == "array" { "an" } else { "a" };
+    let found = type_name(value);
+    format!("{} must be {} {}, not {} {}.", name, article(expected), expected, article(found), found)
+}
+
+/// Describes an unknown key, suggesting the known key that it most looks like a misspelling of.
+fn unknown(name: &str, key: &str, known: &[&str]) -> String {
+    let shared = |k: &&str| k.chars().zip(key.chars()).take_while(|&(a, b)| a == b).count();
+    let suggestion = known.iter().cloned().filter(|k| shared(k) >= 2).max_by_key(|k| shared(k));
+    match suggestion {
+        Some(k) => format!("unknown key {}. Did you mean {}?", name, k),
+        None => format!("unknown key {}. Use one of: {}.", name, known.join(", ")),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match *value {
+        Value::String(_)   => "string",
+        Value::Integer(_)  => "integer",
+        Value::Float(_)    => "float",
+        Value::Boolean(_)  => "boolean",
+        Value::Datetime(_) => "datetime",
+        Value::Array(_)    => "array",
+        Value::Table(_)    => "table",
+    }
+}
+
+/// Reads a password from the first line of a file, as written by `echo` or secret managers.
+fn read_password(path: &str) -> ::std::result::Result<String, String> {
+    let mut text = String::new();
+    if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut text)) {
+        return Err(format!("password_file {}: {}", path, e));
+    }
+
+    let password = text.lines().next().unwrap_or("");
+    if password.is_empty() {
+        return Err(format!("password_file {} is empty.", path));
+    }
+    Ok(password.to_owned())
+}
+
+/// Adds a password to a connection string that names a user, escaping it as the URI requires.
+fn with_password(uri: &str, password: &str) -> ::std::result::Result<String, String> {
+    match split_uri(uri) {
+        Some((_, userinfo, _)) if userinfo.contains(':') => Err(format!("uri {}.", ERR_URI_HAS_PASSWORD)),
+        Some((prefix, userinfo, rest)) if !userinfo.is_empty() => {
+            Ok(format!("{}{}:{}@{}", prefix, userinfo, percent_encode(password), rest))
+        }
+        _ => Err(format!("uri {} {}.", uri, ERR_URI_USER)),
+    }
+}
+
+/// Splits a connection string into its scheme, its user information and everything after them.
+fn split_uri(uri: &str) -> Option<(&str, &str, &str)> {
+    let start = match uri.find("://") {
+        Some(i) => i + 3,
+        None => return None,
+    };
+    let end = uri[start..].find(|c: char| c == '/' || c == '?').map_or(uri.len(), |i| start + i);
+    uri[start..end].rfind('@').map(|at| (&uri[..start], &uri[start..start + at], &uri[start + at + 1..]))
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes().map(|b| match b {
+        b'A'...b'Z' | b'a'...b'z' | b'0'...b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+        b => format!("%{:02X}", b),
+    }).collect()
+}
@@ -1,21 +1,191 @@
+#[allow(non_upper_case_globals)]
+pub const action: &'static str = "action";
+
+#[allow(non_upper_case_globals)]
+pub const active: &'static str = "active";
+
+#[allow(non_upper_case_globals)]
+pub const actor: &'static str = "actor";
+
+#[allow(non_upper_case_globals)]
+pub const after_value: &'static str = "after_value";
 
 #[allow(non_upper_case_globals)]
 pub const amount: &'static str = "amount";
 
+#[allow(non_upper_case_globals)]
+pub const attempt_id: &'static str = "attempt_id";
+
+#[allow(non_upper_case_globals)]
+pub const attempts: &'static str = "attempts";
+
+#[allow(non_upper_case_globals)]
+pub const audit_id: &'static str = "audit_id";
+
+#[allow(non_upper_case_globals)]
+pub const before_value: &'static str = "before_value";
+
+#[allow(non_upper_case_globals)]
+pub const body: &'static str = "body";
+
+#[allow(non_upper_case_globals)]
+pub const body_html: &'static str = "body_html";
+
+#[allow(non_upper_case_globals)]
+pub const body_text: &'static str = "body_text";
+
 #[allow(non_upper_case_globals)]
 pub const card: &'static str = "card";
 
+#[allow(non_upper_case_globals)]
+pub const comment_id: &'static str = "comment_id";
+
+#[allow(non_upper_case_globals)]
+pub const comments_backers_only: &'static str = "comments_backers_only";
+
+#[allow(non_upper_case_globals)]
+pub const creator_id: &'static str = "creator_id";
+
+#[allow(non_upper_case_globals)]
+pub const date_closed: &'static str = "date_closed";
+
 #[allow(non_upper_case_globals)]
 pub const date_created: &'static str = "date_created";
 
+#[allow(non_upper_case_globals)]
+pub const date_delivered: &'static str = "date_delivered";
+
+#[allow(non_upper_case_globals)]
+pub const date_edited: &'static str = "date_edited";
+
+#[allow(non_upper_case_globals)]
+pub const date_expires: &'static str = "date_expires";
+
+#[allow(non_upper_case_globals)]
+pub const date_revoked: &'static str = "date_revoked";
+
+#[allow(non_upper_case_globals)]
+pub const date_sent: &'static str = "date_sent";
+
+#[allow(non_upper_case_globals)]
+pub const date_used: &'static str = "date_used";
+
+#[allow(non_upper_case_globals)]
+pub const deleted: &'static str = "deleted";
+
+#[allow(non_upper_case_globals)]
+pub const delivery_id: &'static str = "delivery_id";
+
+#[allow(non_upper_case_globals)]
+pub const digest: &'static str = "digest";
+
+#[allow(non_upper_case_globals)]
+pub const email: &'static str = "email";
+
+#[allow(non_upper_case_globals)]
+pub const enabled: &'static str = "enabled";
+
+#[allow(non_upper_case_globals)]
+pub const entity: &'static str = "entity";
+
+#[allow(non_upper_case_globals)]
+pub const entity_id: &'static str = "entity_id";
+
+#[allow(non_upper_case_globals)]
+pub const error: &'static str = "error";
+
+#[allow(non_upper_case_globals)]
+pub const event: &'static str = "event";
+
+#[allow(non_upper_case_globals)]
+pub const event_id: &'static str = "event_id";
+
+#[allow(non_upper_case_globals)]
+pub const flagged: &'static str = "flagged";
+
 #[allow(non_upper_case_globals)]
 pub const goal: &'static str = "goal";
 
+#[allow(non_upper_case_globals)]
+pub const hidden: &'static str = "hidden";
+
+#[allow(non_upper_case_globals)]
+pub const kind: &'static str = "kind";
+
+#[allow(non_upper_case_globals)]
+pub const mail_id: &'static str = "mail_id";
+
+#[allow(non_upper_case_globals)]
+pub const minimum: &'static str = "minimum";
+
 #[allow(non_upper_case_globals)]
 pub const name: &'static str = "name";
 
+#[allow(non_upper_case_globals)]
+pub const next_attempt: &'static str = "next_attempt";
+
+#[allow(non_upper_case_globals)]
+pub const parent_id: &'static str = "parent_id";
+
+#[allow(non_upper_case_globals)]
+pub const password_hash: &'static str = "password_hash";
+
+#[allow(non_upper_case_globals)]
+pub const payload: &'static str = "payload";
+
 #[allow(non_upper_case_globals)]
 pub const project_id: &'static str = "project_id";
 
+#[allow(non_upper_case_globals)]
+pub const reason: &'static str = "reason";
+
+#[allow(non_upper_case_globals)]
+pub const recipient: &'static str = "recipient";
+
+#[allow(non_upper_case_globals)]
+pub const reference: &'static str = "reference";
+
+#[allow(non_upper_case_globals)]
+pub const refund_id: &'static str = "refund_id";
+
+#[allow(non_upper_case_globals)]
+pub const refunded: &'static str = "refunded";
+
+#[allow(non_upper_case_globals)]
+pub const reward_id: &'static str = "reward_id";
+
+#[allow(non_upper_case_globals)]
+pub const role: &'static str = "role";
+
+#[allow(non_upper_case_globals)]
+pub const secret: &'static str = "secret";
+
+#[allow(non_upper_case_globals)]
+pub const shipping_address: &'static str = "shipping_address";
+
+#[allow(non_upper_case_globals)]
+pub const status: &'static str = "status";
+
+#[allow(non_upper_case_globals)]
+pub const status_code: &'static str = "status_code";
+
+#[allow(non_upper_case_globals)]
+pub const subject: &'static str = "subject";
+
+#[allow(non_upper_case_globals)]
+pub const title: &'static str = "title";
+
+#[allow(non_upper_case_globals)]
+pub const token_id: &'static str = "token_id";
+
+#[allow(non_upper_case_globals)]
+pub const unsubscribe_token: &'static str = "unsubscribe_token";
+
+#[allow(non_upper_case_globals)]
+pub const url: &'static str = "url";
+
 #[allow(non_upper_case_globals)]
 pub const user_id: &'static str = "user_id";
+
+#[allow(non_upper_case_globals)]
+pub const webhook_id: &'static str = "webhook_id";
@@ -0,0 +1,209 @@
+//! WARNING: This file is generated, derived from table kickstarter.audit, DO NOT EDIT
+
+use chrono::naive::datetime::NaiveDateTime;
+use rustorm::dao::Dao;
+use rustorm::dao::IsDao;
+use db::schema;
+use db::table;
+use db::column;
+use rustorm::table::IsTable;
+use rustorm::table::Column;
+use rustorm::table::Table;
+use rustc_serialize::json::ToJson;
+use rustc_serialize::json::Json;
+
+
+
+#[derive(RustcDecodable, RustcEncodable)]
+#[derive(Debug, Clone)]
+pub struct Audit {
+    /// primary
+    /// default: nextval('kickstarter.audit_audit_id_seq'::regclass)
+    /// not nullable 
+    /// db data type: integer
+    pub audit_id: i32,
+    /// not nullable 
+    /// db data type: text
+    pub actor: String,
+    /// not nullable 
+    /// db data type: text
+    pub action: String,
+    /// not nullable 
+    /// db data type: text
+    pub entity: String,
+    /// not nullable 
+    /// db data type: text
+    pub entity_id: String,
+    /// db data type: text
+    pub before_value: Option<String>,
+    /// db data type: text
+    pub after_value: Option<String>,
+    /// default: ('now'::text)::timestamp without time zone
+    /// not nullable 
+    /// db data type: timestamp without time zone
+    pub date_created: NaiveDateTime,
+}
+
+
+
+impl IsDao for Audit {
+    fn from_dao(dao: &Dao) -> Self {
+        Audit {
+            audit_id: dao.get(column::audit_id),
+            actor: dao.get(column::actor),
+            action: dao.get(column::action),
+            entity: dao.get(column::entity),
+            entity_id: dao.get(column::entity_id),
+            before_value: dao.get_opt(column::before_value),
+            after_value: dao.get_opt(column::after_value),
+            date_created: dao.get(column::date_created),
+        }
+    }
+
+    fn to_dao(&self) -> Dao {
+        let mut dao = Dao::new();
+        dao.set(column::audit_id, &self.audit_id);
+        dao.set(column::actor, &self.actor);
+        dao.set(column::action, &self.action);
+        dao.set(column::entity, &self.entity);
+        dao.set(column::entity_id, &self.entity_id);
+        match self.before_value {
+            Some(ref _value) => dao.set(column::before_value, _value),
+            None => dao.set_null(column::before_value)
+        }
+        match self.after_value {
+            Some(ref _value) => dao.set(column::after_value, _value),
+            None => dao.set_null(column::after_value)
+        }
+        dao.set(column::date_created, &self.date_created);
+        dao
+    }
+}
+
+impl ToJson for Audit {
+
+    fn to_json(&self) -> Json {
+        self.to_dao().to_json()
+    }
+}
+
+impl IsTable for Audit {
+
+    fn table() -> Table {
+        Table {
+            schema: schema::kickstarter.to_owned(),
+            name: table::audit.to_owned(),
+            parent_table: None,
+            sub_table: vec![],
+            comment: None,
+            columns: vec![
+                Column {
+                    name: column::audit_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: true, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("nextval('kickstarter.audit_audit_id_seq'::regclass)".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::actor.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::action.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::entity.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::entity_id.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::before_value.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::after_value.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_created.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("('now'::text)::timestamp without time zone".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+            ],
+            is_view: false,
+        }
+    }
+}
+// Generated columns for easier development of dynamic queries without sacrificing wrong spelling of column names
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static audit_id: &'static str = "audit.audit_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static actor: &'static str = "audit.actor";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static action: &'static str = "audit.action";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static entity: &'static str = "audit.entity";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static entity_id: &'static str = "audit.entity_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static before_value: &'static str = "audit.before_value";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static after_value: &'static str = "audit.after_value";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_created: &'static str = "audit.date_created";
@@ -0,0 +1,273 @@
+//! WARNING: This file is generated, derived from table kickstarter.comment, DO NOT EDIT
+
+use chrono::naive::datetime::NaiveDateTime;
+use db::kickstarter::Project;
+use db::kickstarter::User;
+use rustorm::dao::Dao;
+use rustorm::dao::IsDao;
+use db::schema;
+use db::table;
+use db::column;
+use rustorm::table::IsTable;
+use rustorm::table::Column;
+use rustorm::table::Table;
+use rustorm::table::Foreign;
+use rustc_serialize::json::ToJson;
+use rustc_serialize::json::Json;
+
+
+
+#[derive(RustcDecodable, RustcEncodable)]
+#[derive(Debug, Clone)]
+pub struct Comment {
+    /// primary
+    /// default: nextval('kickstarter.comment_comment_id_seq'::regclass)
+    /// not nullable 
+    /// db data type: integer
+    pub comment_id: i32,
+    /// not nullable 
+    /// db data type: integer
+    pub project_id: i32,
+    /// not nullable 
+    /// db data type: integer
+    pub user_id: i32,
+    /// db data type: integer
+    pub parent_id: Option<i32>,
+    /// not nullable 
+    /// db data type: text
+    pub body: String,
+    /// default: false
+    /// not nullable 
+    /// db data type: boolean
+    pub hidden: bool,
+    /// default: false
+    /// not nullable 
+    /// db data type: boolean
+    pub flagged: bool,
+    /// default: false
+    /// not nullable 
+    /// db data type: boolean
+    pub deleted: bool,
+    /// default: ('now'::text)::timestamp without time zone
+    /// not nullable 
+    /// db data type: timestamp without time zone
+    pub date_created: NaiveDateTime,
+    /// db data type: timestamp without time zone
+    pub date_edited: Option<NaiveDateTime>,
+
+    /// has one
+    pub user: Option<User>,
+    /// has one
+    pub project: Option<Project>,
+}
+
+
+
+impl IsDao for Comment {
+    fn from_dao(dao: &Dao) -> Self {
+        Comment {
+            comment_id: dao.get(column::comment_id),
+            project_id: dao.get(column::project_id),
+            user_id: dao.get(column::user_id),
+            parent_id: dao.get_opt(column::parent_id),
+            body: dao.get(column::body),
+            hidden: dao.get(column::hidden),
+            flagged: dao.get(column::flagged),
+            deleted: dao.get(column::deleted),
+            date_created: dao.get(column::date_created),
+            date_edited: dao.get_opt(column::date_edited),
+            user: None,
+            project: None,
+        }
+    }
+
+    fn to_dao(&self) -> Dao {
+        let mut dao = Dao::new();
+        dao.set(column::comment_id, &self.comment_id);
+        dao.set(column::project_id, &self.project_id);
+        dao.set(column::user_id, &self.user_id);
+        match self.parent_id {
+            Some(ref _value) => dao.set(column::parent_id, _value),
+            None => dao.set_null(column::parent_id)
+        }
+        dao.set(column::body, &self.body);
+        dao.set(column::hidden, &self.hidden);
+        dao.set(column::flagged, &self.flagged);
+        dao.set(column::deleted, &self.deleted);
+        dao.set(column::date_created, &self.date_created);
+        match self.date_edited {
+            Some(ref _value) => dao.set(column::date_edited, _value),
+            None => dao.set_null(column::date_edited)
+        }
+        dao
+    }
+}
+
+impl ToJson for Comment {
+
+    fn to_json(&self) -> Json {
+        self.to_dao().to_json()
+    }
+}
+
+impl IsTable for Comment {
+
+    fn table() -> Table {
+        Table {
+            schema: schema::kickstarter.to_owned(),
+            name: table::comment.to_owned(),
+            parent_table: None,
+            sub_table: vec![],
+            comment: None,
+            columns: vec![
+                Column {
+                    name: column::comment_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: true, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("nextval('kickstarter.comment_comment_id_seq'::regclass)".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::project_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: Some(
+                        Foreign {
+                            schema: "kickstarter".to_owned(),
+                            table: "project".to_owned(),
+                            column: "project_id".to_owned(),
+                        }),
+                },
+                Column {
+                    name: column::user_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: Some(
+                        Foreign {
+                            schema: "kickstarter".to_owned(),
+                            table: "user".to_owned(),
+                            column: "user_id".to_owned(),
+                        }),
+                },
+                Column {
+                    name: column::parent_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: Some(
+                        Foreign {
+                            schema: "kickstarter".to_owned(),
+                            table: "comment".to_owned(),
+                            column: "comment_id".to_owned(),
+                        }),
+                },
+                Column {
+                    name: column::body.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::hidden.to_owned(),
+                    data_type: "bool".to_owned(),
+                    db_data_type: "boolean".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("false".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::flagged.to_owned(),
+                    data_type: "bool".to_owned(),
+                    db_data_type: "boolean".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("false".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::deleted.to_owned(),
+                    data_type: "bool".to_owned(),
+                    db_data_type: "boolean".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("false".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_created.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("('now'::text)::timestamp without time zone".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_edited.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+            ],
+            is_view: false,
+        }
+    }
+}
+// Generated columns for easier development of dynamic queries without sacrificing wrong spelling of column names
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static comment_id: &'static str = "comment.comment_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static project_id: &'static str = "comment.project_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static user_id: &'static str = "comment.user_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static parent_id: &'static str = "comment.parent_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static body: &'static str = "comment.body";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static hidden: &'static str = "comment.hidden";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static flagged: &'static str = "comment.flagged";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static deleted: &'static str = "comment.deleted";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_created: &'static str = "comment.date_created";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_edited: &'static str = "comment.date_edited";
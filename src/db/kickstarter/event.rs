@@ -0,0 +1,133 @@
+//! WARNING: This file is generated, derived from table kickstarter.event, DO NOT EDIT
+
+use chrono::naive::datetime::NaiveDateTime;
+use rustorm::dao::Dao;
+use rustorm::dao::IsDao;
+use db::schema;
+use db::table;
+use db::column;
+use rustorm::table::IsTable;
+use rustorm::table::Column;
+use rustorm::table::Table;
+use rustc_serialize::json::ToJson;
+use rustc_serialize::json::Json;
+
+
+
+#[derive(RustcDecodable, RustcEncodable)]
+#[derive(Debug, Clone)]
+pub struct Event {
+    /// primary
+    /// default: nextval('kickstarter.event_event_id_seq'::regclass)
+    /// not nullable 
+    /// db data type: integer
+    pub event_id: i32,
+    /// not nullable 
+    /// db data type: text
+    pub kind: String,
+    /// not nullable 
+    /// db data type: text
+    pub payload: String,
+    /// default: ('now'::text)::timestamp without time zone
+    /// not nullable 
+    /// db data type: timestamp without time zone
+    pub date_created: NaiveDateTime,
+}
+
+
+
+impl IsDao for Event {
+    fn from_dao(dao: &Dao) -> Self {
+        Event {
+            event_id: dao.get(column::event_id),
+            kind: dao.get(column::kind),
+            payload: dao.get(column::payload),
+            date_created: dao.get(column::date_created),
+        }
+    }
+
+    fn to_dao(&self) -> Dao {
+        let mut dao = Dao::new();
+        dao.set(column::event_id, &self.event_id);
+        dao.set(column::kind, &self.kind);
+        dao.set(column::payload, &self.payload);
+        dao.set(column::date_created, &self.date_created);
+        dao
+    }
+}
+
+impl ToJson for Event {
+
+    fn to_json(&self) -> Json {
+        self.to_dao().to_json()
+    }
+}
+
+impl IsTable for Event {
+
+    fn table() -> Table {
+        Table {
+            schema: schema::kickstarter.to_owned(),
+            name: table::event.to_owned(),
+            parent_table: None,
+            sub_table: vec![],
+            comment: None,
+            columns: vec![
+                Column {
+                    name: column::event_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: true, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("nextval('kickstarter.event_event_id_seq'::regclass)".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::kind.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::payload.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_created.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("('now'::text)::timestamp without time zone".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+            ],
+            is_view: false,
+        }
+    }
+}
+// Generated columns for easier development of dynamic queries without sacrificing wrong spelling of column names
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static event_id: &'static str = "event.event_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static kind: &'static str = "event.kind";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static payload: &'static str = "event.payload";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_created: &'static str = "event.date_created";
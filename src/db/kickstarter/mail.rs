@@ -0,0 +1,294 @@
+//! WARNING: This file is generated, derived from table kickstarter.mail, DO NOT EDIT
+
+use chrono::naive::datetime::NaiveDateTime;
+use db::kickstarter::User;
+use rustorm::dao::Dao;
+use rustorm::dao::IsDao;
+use db::schema;
+use db::table;
+use db::column;
+use rustorm::table::IsTable;
+use rustorm::table::Column;
+use rustorm::table::Table;
+use rustorm::table::Foreign;
+use rustc_serialize::json::ToJson;
+use rustc_serialize::json::Json;
+
+
+
+#[derive(RustcDecodable, RustcEncodable)]
+#[derive(Debug, Clone)]
+pub struct Mail {
+    /// primary
+    /// default: nextval('kickstarter.mail_mail_id_seq'::regclass)
+    /// not nullable 
+    /// db data type: integer
+    pub mail_id: i32,
+    /// not nullable 
+    /// db data type: integer
+    pub user_id: i32,
+    /// not nullable 
+    /// db data type: text
+    pub kind: String,
+    /// not nullable 
+    /// db data type: text
+    pub recipient: String,
+    /// not nullable 
+    /// db data type: text
+    pub subject: String,
+    /// not nullable 
+    /// db data type: text
+    pub body_text: String,
+    /// not nullable 
+    /// db data type: text
+    pub body_html: String,
+    /// default: 'pending'::text
+    /// not nullable 
+    /// db data type: text
+    pub status: String,
+    /// default: 0
+    /// not nullable 
+    /// db data type: integer
+    pub attempts: i32,
+    /// db data type: text
+    pub error: Option<String>,
+    /// default: ('now'::text)::timestamp without time zone
+    /// not nullable 
+    /// db data type: timestamp without time zone
+    pub date_created: NaiveDateTime,
+    /// db data type: timestamp without time zone
+    pub date_sent: Option<NaiveDateTime>,
+
+    /// has one
+    pub user: Option<User>,
+}
+
+
+
+impl IsDao for Mail {
+    fn from_dao(dao: &Dao) -> Self {
+        Mail {
+            mail_id: dao.get(column::mail_id),
+            user_id: dao.get(column::user_id),
+            kind: dao.get(column::kind),
+            recipient: dao.get(column::recipient),
+            subject: dao.get(column::subject),
+            body_text: dao.get(column::body_text),
+            body_html: dao.get(column::body_html),
+            status: dao.get(column::status),
+            attempts: dao.get(column::attempts),
+            error: dao.get_opt(column::error),
+            date_created: dao.get(column::date_created),
+            date_sent: dao.get_opt(column::date_sent),
+            user: None,
+        }
+    }
+
+    fn to_dao(&self) -> Dao {
+        let mut dao = Dao::new();
+        dao.set(column::mail_id, &self.mail_id);
+        dao.set(column::user_id, &self.user_id);
+        dao.set(column::kind, &self.kind);
+        dao.set(column::recipient, &self.recipient);
+        dao.set(column::subject, &self.subject);
+        dao.set(column::body_text, &self.body_text);
+        dao.set(column::body_html, &self.body_html);
+        dao.set(column::status, &self.status);
+        dao.set(column::attempts, &self.attempts);
+        match self.error {
+            Some(ref _value) => dao.set(column::error, _value),
+            None => dao.set_null(column::error)
+        }
+        dao.set(column::date_created, &self.date_created);
+        match self.date_sent {
+            Some(ref _value) => dao.set(column::date_sent, _value),
+            None => dao.set_null(column::date_sent)
+        }
+        dao
+    }
+}
+
+impl ToJson for Mail {
+
+    fn to_json(&self) -> Json {
+        self.to_dao().to_json()
+    }
+}
+
+impl IsTable for Mail {
+
+    fn table() -> Table {
+        Table {
+            schema: schema::kickstarter.to_owned(),
+            name: table::mail.to_owned(),
+            parent_table: None,
+            sub_table: vec![],
+            comment: None,
+            columns: vec![
+                Column {
+                    name: column::mail_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: true, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("nextval('kickstarter.mail_mail_id_seq'::regclass)".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::user_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: Some(
+                        Foreign {
+                            schema: "kickstarter".to_owned(),
+                            table: "user".to_owned(),
+                            column: "user_id".to_owned(),
+                        }),
+                },
+                Column {
+                    name: column::kind.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::recipient.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::subject.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::body_text.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::body_html.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::status.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("'pending'::text".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::attempts.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("0".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::error.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_created.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("('now'::text)::timestamp without time zone".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_sent.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+            ],
+            is_view: false,
+        }
+    }
+}
+// Generated columns for easier development of dynamic queries without sacrificing wrong spelling of column names
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static mail_id: &'static str = "mail.mail_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static user_id: &'static str = "mail.user_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static kind: &'static str = "mail.kind";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static recipient: &'static str = "mail.recipient";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static subject: &'static str = "mail.subject";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static body_text: &'static str = "mail.body_text";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static body_html: &'static str = "mail.body_html";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static status: &'static str = "mail.status";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static attempts: &'static str = "mail.attempts";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static error: &'static str = "mail.error";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_created: &'static str = "mail.date_created";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_sent: &'static str = "mail.date_sent";
@@ -0,0 +1,125 @@
+//! WARNING: This file is generated, derived from table kickstarter.mail_preference, DO NOT EDIT
+
+use db::kickstarter::User;
+use rustorm::dao::Dao;
+use rustorm::dao::IsDao;
+use db::schema;
+use db::table;
+use db::column;
+use rustorm::table::IsTable;
+use rustorm::table::Column;
+use rustorm::table::Table;
+use rustorm::table::Foreign;
+use rustc_serialize::json::ToJson;
+use rustc_serialize::json::Json;
+
+
+
+#[derive(RustcDecodable, RustcEncodable)]
+#[derive(Debug, Clone)]
+pub struct MailPreference {
+    /// primary
+    /// not nullable 
+    /// db data type: integer
+    pub user_id: i32,
+    /// primary
+    /// not nullable 
+    /// db data type: text
+    pub kind: String,
+    /// default: true
+    /// not nullable 
+    /// db data type: boolean
+    pub enabled: bool,
+
+    /// has one
+    pub user: Option<User>,
+}
+
+
+
+impl IsDao for MailPreference {
+    fn from_dao(dao: &Dao) -> Self {
+        MailPreference {
+            user_id: dao.get(column::user_id),
+            kind: dao.get(column::kind),
+            enabled: dao.get(column::enabled),
+            user: None,
+        }
+    }
+
+    fn to_dao(&self) -> Dao {
+        let mut dao = Dao::new();
+        dao.set(column::user_id, &self.user_id);
+        dao.set(column::kind, &self.kind);
+        dao.set(column::enabled, &self.enabled);
+        dao
+    }
+}
+
+impl ToJson for MailPreference {
+
+    fn to_json(&self) -> Json {
+        self.to_dao().to_json()
+    }
+}
+
+impl IsTable for MailPreference {
+
+    fn table() -> Table {
+        Table {
+            schema: schema::kickstarter.to_owned(),
+            name: table::mail_preference.to_owned(),
+            parent_table: None,
+            sub_table: vec![],
+            comment: None,
+            columns: vec![
+                Column {
+                    name: column::user_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: true, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: Some(
+                        Foreign {
+                            schema: "kickstarter".to_owned(),
+                            table: "user".to_owned(),
+                            column: "user_id".to_owned(),
+                        }),
+                },
+                Column {
+                    name: column::kind.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: true, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::enabled.to_owned(),
+                    data_type: "bool".to_owned(),
+                    db_data_type: "boolean".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("true".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+            ],
+            is_view: false,
+        }
+    }
+}
+// Generated columns for easier development of dynamic queries without sacrificing wrong spelling of column names
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static user_id: &'static str = "mail_preference.user_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static kind: &'static str = "mail_preference.kind";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static enabled: &'static str = "mail_preference.enabled";
@@ -1,6 +1,28 @@
+pub mod audit;
+pub mod comment;
+pub mod event;
+pub mod mail;
+pub mod mail_preference;
 pub mod pledge;
 pub mod project;
+pub mod refund;
+pub mod reward;
+pub mod token;
 pub mod user;
+pub mod webhook;
+pub mod webhook_attempt;
+pub mod webhook_delivery;
+pub use self::audit::Audit;
+pub use self::comment::Comment;
+pub use self::event::Event;
+pub use self::mail::Mail;
+pub use self::mail_preference::MailPreference;
 pub use self::pledge::Pledge;
 pub use self::project::Project;
+pub use self::refund::Refund;
+pub use self::reward::Reward;
+pub use self::token::Token;
 pub use self::user::User;
+pub use self::webhook::Webhook;
+pub use self::webhook_attempt::WebhookAttempt;
+pub use self::webhook_delivery::WebhookDelivery;
@@ -39,6 +39,10 @@ pub struct Pledge {
     /// not nullable 
     /// db data type: timestamp without time zone
     pub date_created: NaiveDateTime,
+    /// default: 0
+    /// not nullable 
+    /// db data type: double precision
+    pub refunded: f64,
 
     /// has one
     pub user: Option<User>,
@@ -56,6 +60,7 @@ impl IsDao for Pledge {
             amount: dao.get(column::amount),
             card: dao.get(column::card),
             date_created: dao.get(column::date_created),
+            refunded: dao.get(column::refunded),
             user: None,
             project: None,
         }
@@ -68,6 +73,7 @@ impl IsDao for Pledge {
         dao.set(column::amount, &self.amount);
         dao.set(column::card, &self.card);
         dao.set(column::date_created, &self.date_created);
+        dao.set(column::refunded, &self.refunded);
         dao
     }
 }
@@ -144,6 +150,15 @@ impl IsTable for Pledge {
                     comment: None,
                     foreign: None,
                 },
+                Column {
+                    name: column::refunded.to_owned(),
+                    data_type: "f64".to_owned(),
+                    db_data_type: "double precision".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("0".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
             ],
             is_view: false,
         }
@@ -170,3 +185,7 @@ pub static card: &'static str = "pledge.card";
 #[allow(non_upper_case_globals)]
 #[allow(dead_code)]
 pub static date_created: &'static str = "pledge.date_created";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static refunded: &'static str = "pledge.refunded";
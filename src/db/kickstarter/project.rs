@@ -1,7 +1,12 @@
 //! WARNING: This file is generated, derived from table kickstarter.project, DO NOT EDIT
 
 use chrono::naive::datetime::NaiveDateTime;
+use db::kickstarter::Comment;
 use db::kickstarter::Pledge;
+use db::kickstarter::Refund;
+use db::kickstarter::Reward;
+use db::kickstarter::User;
+use db::kickstarter::Webhook;
 use rustorm::dao::Dao;
 use rustorm::dao::IsDao;
 use db::schema;
@@ -10,6 +15,7 @@ use db::column;
 use rustorm::table::IsTable;
 use rustorm::table::Column;
 use rustorm::table::Table;
+use rustorm::table::Foreign;
 use rustc_serialize::json::ToJson;
 use rustc_serialize::json::Json;
 
@@ -34,9 +40,27 @@ pub struct Project {
     /// not nullable 
     /// db data type: double precision
     pub goal: f64,
+    /// default: false
+    /// not nullable 
+    /// db data type: boolean
+    pub comments_backers_only: bool,
+    /// db data type: integer
+    pub creator_id: Option<i32>,
+    /// db data type: timestamp without time zone
+    pub date_closed: Option<NaiveDateTime>,
 
     /// has many
     pub pledge: Vec<Pledge>,
+    /// has many
+    pub comment: Vec<Comment>,
+    /// has many
+    pub refund: Vec<Refund>,
+    /// has many
+    pub webhook: Vec<Webhook>,
+    /// has many
+    pub reward: Vec<Reward>,
+    /// has one
+    pub user: Option<User>,
 }
 
 
@@ -48,7 +72,15 @@ impl IsDao for Project {
             name: dao.get(column::name),
             goal: dao.get(column::goal),
             date_created: dao.get(column::date_created),
+            comments_backers_only: dao.get(column::comments_backers_only),
+            creator_id: dao.get_opt(column::creator_id),
+            date_closed: dao.get_opt(column::date_closed),
             pledge: vec![],
+            comment: vec![],
+            refund: vec![],
+            webhook: vec![],
+            reward: vec![],
+            user: None,
         }
     }
 
@@ -58,6 +90,15 @@ impl IsDao for Project {
         dao.set(column::name, &self.name);
         dao.set(column::goal, &self.goal);
         dao.set(column::date_created, &self.date_created);
+        dao.set(column::comments_backers_only, &self.comments_backers_only);
+        match self.creator_id {
+            Some(ref _value) => dao.set(column::creator_id, _value),
+            None => dao.set_null(column::creator_id)
+        }
+        match self.date_closed {
+            Some(ref _value) => dao.set(column::date_closed, _value),
+            None => dao.set_null(column::date_closed)
+        }
         dao
     }
 }
@@ -115,6 +156,38 @@ impl IsTable for Project {
                     comment: None,
                     foreign: None,
                 },
+                Column {
+                    name: column::comments_backers_only.to_owned(),
+                    data_type: "bool".to_owned(),
+                    db_data_type: "boolean".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("false".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::creator_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: Some(
+                        Foreign {
+                            schema: "kickstarter".to_owned(),
+                            table: "user".to_owned(),
+                            column: "user_id".to_owned(),
+                        }),
+                },
+                Column {
+                    name: column::date_closed.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
             ],
             is_view: false,
         }
@@ -137,3 +210,15 @@ pub static goal: &'static str = "project.goal";
 #[allow(non_upper_case_globals)]
 #[allow(dead_code)]
 pub static date_created: &'static str = "project.date_created";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static comments_backers_only: &'static str = "project.comments_backers_only";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static creator_id: &'static str = "project.creator_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_closed: &'static str = "project.date_closed";
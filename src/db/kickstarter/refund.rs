@@ -0,0 +1,227 @@
+//! WARNING: This file is generated, derived from table kickstarter.refund, DO NOT EDIT
+
+use chrono::naive::datetime::NaiveDateTime;
+use db::kickstarter::Project;
+use db::kickstarter::User;
+use rustorm::dao::Dao;
+use rustorm::dao::IsDao;
+use db::schema;
+use db::table;
+use db::column;
+use rustorm::table::IsTable;
+use rustorm::table::Column;
+use rustorm::table::Table;
+use rustorm::table::Foreign;
+use rustc_serialize::json::ToJson;
+use rustc_serialize::json::Json;
+
+
+
+#[derive(RustcDecodable, RustcEncodable)]
+#[derive(Debug, Clone)]
+pub struct Refund {
+    /// primary
+    /// default: nextval('kickstarter.refund_refund_id_seq'::regclass)
+    /// not nullable 
+    /// db data type: integer
+    pub refund_id: i32,
+    /// not nullable 
+    /// db data type: integer
+    pub user_id: i32,
+    /// not nullable 
+    /// db data type: integer
+    pub project_id: i32,
+    /// not nullable 
+    /// db data type: double precision
+    pub amount: f64,
+    /// not nullable 
+    /// db data type: text
+    pub kind: String,
+    /// not nullable 
+    /// db data type: text
+    pub reason: String,
+    /// db data type: text
+    pub reference: Option<String>,
+    /// default: ('now'::text)::timestamp without time zone
+    /// not nullable 
+    /// db data type: timestamp without time zone
+    pub date_created: NaiveDateTime,
+
+    /// has one
+    pub user: Option<User>,
+    /// has one
+    pub project: Option<Project>,
+}
+
+
+
+impl IsDao for Refund {
+    fn from_dao(dao: &Dao) -> Self {
+        Refund {
+            refund_id: dao.get(column::refund_id),
+            user_id: dao.get(column::user_id),
+            project_id: dao.get(column::project_id),
+            amount: dao.get(column::amount),
+            kind: dao.get(column::kind),
+            reason: dao.get(column::reason),
+            reference: dao.get_opt(column::reference),
+            date_created: dao.get(column::date_created),
+            user: None,
+            project: None,
+        }
+    }
+
+    fn to_dao(&self) -> Dao {
+        let mut dao = Dao::new();
+        dao.set(column::refund_id, &self.refund_id);
+        dao.set(column::user_id, &self.user_id);
+        dao.set(column::project_id, &self.project_id);
+        dao.set(column::amount, &self.amount);
+        dao.set(column::kind, &self.kind);
+        dao.set(column::reason, &self.reason);
+        match self.reference {
+            Some(ref _value) => dao.set(column::reference, _value),
+            None => dao.set_null(column::reference)
+        }
+        dao.set(column::date_created, &self.date_created);
+        dao
+    }
+}
+
+impl ToJson for Refund {
+
+    fn to_json(&self) -> Json {
+        self.to_dao().to_json()
+    }
+}
+
+impl IsTable for Refund {
+
+    fn table() -> Table {
+        Table {
+            schema: schema::kickstarter.to_owned(),
+            name: table::refund.to_owned(),
+            parent_table: None,
+            sub_table: vec![],
+            comment: None,
+            columns: vec![
+                Column {
+                    name: column::refund_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: true, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("nextval('kickstarter.refund_refund_id_seq'::regclass)".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::user_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: Some(
+                        Foreign {
+                            schema: "kickstarter".to_owned(),
+                            table: "user".to_owned(),
+                            column: "user_id".to_owned(),
+                        }),
+                },
+                Column {
+                    name: column::project_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: Some(
+                        Foreign {
+                            schema: "kickstarter".to_owned(),
+                            table: "project".to_owned(),
+                            column: "project_id".to_owned(),
+                        }),
+                },
+                Column {
+                    name: column::amount.to_owned(),
+                    data_type: "f64".to_owned(),
+                    db_data_type: "double precision".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::kind.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::reason.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::reference.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_created.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("('now'::text)::timestamp without time zone".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+            ],
+            is_view: false,
+        }
+    }
+}
+// Generated columns for easier development of dynamic queries without sacrificing wrong spelling of column names
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static refund_id: &'static str = "refund.refund_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static user_id: &'static str = "refund.user_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static project_id: &'static str = "refund.project_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static amount: &'static str = "refund.amount";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static kind: &'static str = "refund.kind";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static reason: &'static str = "refund.reason";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static reference: &'static str = "refund.reference";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_created: &'static str = "refund.date_created";
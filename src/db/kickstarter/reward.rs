@@ -0,0 +1,162 @@
+//! WARNING: This file is generated, derived from table kickstarter.reward, DO NOT EDIT
+
+use chrono::naive::datetime::NaiveDateTime;
+use db::kickstarter::Project;
+use rustorm::dao::Dao;
+use rustorm::dao::IsDao;
+use db::schema;
+use db::table;
+use db::column;
+use rustorm::table::IsTable;
+use rustorm::table::Column;
+use rustorm::table::Table;
+use rustorm::table::Foreign;
+use rustc_serialize::json::ToJson;
+use rustc_serialize::json::Json;
+
+
+
+#[derive(RustcDecodable, RustcEncodable)]
+#[derive(Debug, Clone)]
+pub struct Reward {
+    /// primary
+    /// default: nextval('kickstarter.reward_reward_id_seq'::regclass)
+    /// not nullable 
+    /// db data type: integer
+    pub reward_id: i32,
+    /// not nullable 
+    /// db data type: integer
+    pub project_id: i32,
+    /// not nullable 
+    /// db data type: text
+    pub title: String,
+    /// not nullable 
+    /// db data type: double precision
+    pub minimum: f64,
+    /// default: ('now'::text)::timestamp without time zone
+    /// not nullable 
+    /// db data type: timestamp without time zone
+    pub date_created: NaiveDateTime,
+
+    /// has one
+    pub project: Option<Project>,
+}
+
+
+
+impl IsDao for Reward {
+    fn from_dao(dao: &Dao) -> Self {
+        Reward {
+            reward_id: dao.get(column::reward_id),
+            project_id: dao.get(column::project_id),
+            title: dao.get(column::title),
+            minimum: dao.get(column::minimum),
+            date_created: dao.get(column::date_created),
+            project: None,
+        }
+    }
+
+    fn to_dao(&self) -> Dao {
+        let mut dao = Dao::new();
+        dao.set(column::reward_id, &self.reward_id);
+        dao.set(column::project_id, &self.project_id);
+        dao.set(column::title, &self.title);
+        dao.set(column::minimum, &self.minimum);
+        dao.set(column::date_created, &self.date_created);
+        dao
+    }
+}
+
+impl ToJson for Reward {
+
+    fn to_json(&self) -> Json {
+        self.to_dao().to_json()
+    }
+}
+
+impl IsTable for Reward {
+
+    fn table() -> Table {
+        Table {
+            schema: schema::kickstarter.to_owned(),
+            name: table::reward.to_owned(),
+            parent_table: None,
+            sub_table: vec![],
+            comment: None,
+            columns: vec![
+                Column {
+                    name: column::reward_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: true, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("nextval('kickstarter.reward_reward_id_seq'::regclass)".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::project_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: Some(
+                        Foreign {
+                            schema: "kickstarter".to_owned(),
+                            table: "project".to_owned(),
+                            column: "project_id".to_owned(),
+                        }),
+                },
+                Column {
+                    name: column::title.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::minimum.to_owned(),
+                    data_type: "f64".to_owned(),
+                    db_data_type: "double precision".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_created.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("('now'::text)::timestamp without time zone".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+            ],
+            is_view: false,
+        }
+    }
+}
+// Generated columns for easier development of dynamic queries without sacrificing wrong spelling of column names
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static reward_id: &'static str = "reward.reward_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static project_id: &'static str = "reward.project_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static title: &'static str = "reward.title";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static minimum: &'static str = "reward.minimum";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_created: &'static str = "reward.date_created";
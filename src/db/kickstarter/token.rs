@@ -0,0 +1,203 @@
+//! WARNING: This file is generated, derived from table kickstarter.token, DO NOT EDIT
+
+use chrono::naive::datetime::NaiveDateTime;
+use db::kickstarter::User;
+use rustorm::dao::Dao;
+use rustorm::dao::IsDao;
+use db::schema;
+use db::table;
+use db::column;
+use rustorm::table::IsTable;
+use rustorm::table::Column;
+use rustorm::table::Table;
+use rustorm::table::Foreign;
+use rustc_serialize::json::ToJson;
+use rustc_serialize::json::Json;
+
+
+
+#[derive(RustcDecodable, RustcEncodable)]
+#[derive(Debug, Clone)]
+pub struct Token {
+    /// primary
+    /// default: nextval('kickstarter.token_token_id_seq'::regclass)
+    /// not nullable 
+    /// db data type: integer
+    pub token_id: i32,
+    /// not nullable 
+    /// db data type: integer
+    pub user_id: i32,
+    /// unique
+    /// not nullable 
+    /// db data type: text
+    pub digest: String,
+    /// default: ('now'::text)::timestamp without time zone
+    /// not nullable 
+    /// db data type: timestamp without time zone
+    pub date_created: NaiveDateTime,
+    /// not nullable 
+    /// db data type: timestamp without time zone
+    pub date_expires: NaiveDateTime,
+    /// db data type: timestamp without time zone
+    pub date_used: Option<NaiveDateTime>,
+    /// db data type: timestamp without time zone
+    pub date_revoked: Option<NaiveDateTime>,
+
+    /// has one
+    pub user: Option<User>,
+}
+
+
+
+impl IsDao for Token {
+    fn from_dao(dao: &Dao) -> Self {
+        Token {
+            token_id: dao.get(column::token_id),
+            user_id: dao.get(column::user_id),
+            digest: dao.get(column::digest),
+            date_created: dao.get(column::date_created),
+            date_expires: dao.get(column::date_expires),
+            date_used: dao.get_opt(column::date_used),
+            date_revoked: dao.get_opt(column::date_revoked),
+            user: None,
+        }
+    }
+
+    fn to_dao(&self) -> Dao {
+        let mut dao = Dao::new();
+        dao.set(column::token_id, &self.token_id);
+        dao.set(column::user_id, &self.user_id);
+        dao.set(column::digest, &self.digest);
+        dao.set(column::date_created, &self.date_created);
+        dao.set(column::date_expires, &self.date_expires);
+        match self.date_used {
+            Some(ref _value) => dao.set(column::date_used, _value),
+            None => dao.set_null(column::date_used)
+        }
+        match self.date_revoked {
+            Some(ref _value) => dao.set(column::date_revoked, _value),
+            None => dao.set_null(column::date_revoked)
+        }
+        dao
+    }
+}
+
+impl ToJson for Token {
+
+    fn to_json(&self) -> Json {
+        self.to_dao().to_json()
+    }
+}
+
+impl IsTable for Token {
+
+    fn table() -> Table {
+        Table {
+            schema: schema::kickstarter.to_owned(),
+            name: table::token.to_owned(),
+            parent_table: None,
+            sub_table: vec![],
+            comment: None,
+            columns: vec![
+                Column {
+                    name: column::token_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: true, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("nextval('kickstarter.token_token_id_seq'::regclass)".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::user_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: Some(
+                        Foreign {
+                            schema: "kickstarter".to_owned(),
+                            table: "user".to_owned(),
+                            column: "user_id".to_owned(),
+                        }),
+                },
+                Column {
+                    name: column::digest.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: true, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_created.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("('now'::text)::timestamp without time zone".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_expires.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_used.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_revoked.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+            ],
+            is_view: false,
+        }
+    }
+}
+// Generated columns for easier development of dynamic queries without sacrificing wrong spelling of column names
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static token_id: &'static str = "token.token_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static user_id: &'static str = "token.user_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static digest: &'static str = "token.digest";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_created: &'static str = "token.date_created";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_expires: &'static str = "token.date_expires";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_used: &'static str = "token.date_used";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_revoked: &'static str = "token.date_revoked";
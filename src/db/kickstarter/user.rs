@@ -1,7 +1,13 @@
 //! WARNING: This file is generated, derived from table kickstarter.user, DO NOT EDIT
 
 use chrono::naive::datetime::NaiveDateTime;
+use db::kickstarter::Comment;
+use db::kickstarter::Mail;
+use db::kickstarter::MailPreference;
 use db::kickstarter::Pledge;
+use db::kickstarter::Project;
+use db::kickstarter::Refund;
+use db::kickstarter::Token;
 use rustorm::dao::Dao;
 use rustorm::dao::IsDao;
 use db::schema;
@@ -31,9 +37,37 @@ pub struct User {
     /// not nullable 
     /// db data type: timestamp without time zone
     pub date_created: NaiveDateTime,
+    /// default: 'backer'::text
+    /// not nullable 
+    /// db data type: text
+    pub role: String,
+    /// unique
+    /// db data type: text
+    pub email: Option<String>,
+    /// unique
+    /// default: encode(gen_random_bytes(16), 'hex'::text)
+    /// not nullable 
+    /// db data type: text
+    pub unsubscribe_token: String,
+    /// db data type: text
+    pub password_hash: Option<String>,
+    /// db data type: text
+    pub shipping_address: Option<String>,
 
     /// has many
     pub pledge: Vec<Pledge>,
+    /// has many
+    pub comment: Vec<Comment>,
+    /// has many
+    pub refund: Vec<Refund>,
+    /// has many
+    pub project: Vec<Project>,
+    /// has many
+    pub mail: Vec<Mail>,
+    /// has many
+    pub mail_preference: Vec<MailPreference>,
+    /// has many
+    pub token: Vec<Token>,
 }
 
 
@@ -44,7 +78,18 @@ impl IsDao for User {
             user_id: dao.get(column::user_id),
             name: dao.get(column::name),
             date_created: dao.get(column::date_created),
+            role: dao.get(column::role),
+            email: dao.get_opt(column::email),
+            unsubscribe_token: dao.get(column::unsubscribe_token),
+            password_hash: dao.get_opt(column::password_hash),
+            shipping_address: dao.get_opt(column::shipping_address),
             pledge: vec![],
+            comment: vec![],
+            refund: vec![],
+            project: vec![],
+            mail: vec![],
+            mail_preference: vec![],
+            token: vec![],
         }
     }
 
@@ -53,6 +98,20 @@ impl IsDao for User {
         dao.set(column::user_id, &self.user_id);
         dao.set(column::name, &self.name);
         dao.set(column::date_created, &self.date_created);
+        dao.set(column::role, &self.role);
+        match self.email {
+            Some(ref _value) => dao.set(column::email, _value),
+            None => dao.set_null(column::email)
+        }
+        dao.set(column::unsubscribe_token, &self.unsubscribe_token);
+        match self.password_hash {
+            Some(ref _value) => dao.set(column::password_hash, _value),
+            None => dao.set_null(column::password_hash)
+        }
+        match self.shipping_address {
+            Some(ref _value) => dao.set(column::shipping_address, _value),
+            None => dao.set_null(column::shipping_address)
+        }
         dao
     }
 }
@@ -101,6 +160,51 @@ impl IsTable for User {
                     comment: None,
                     foreign: None,
                 },
+                Column {
+                    name: column::role.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("'backer'::text".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::email.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: true, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::unsubscribe_token.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: true, not_null: true, is_inherited: false,
+                    default: Some("encode(gen_random_bytes(16), 'hex'::text)".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::password_hash.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::shipping_address.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
             ],
             is_view: false,
         }
@@ -119,3 +223,23 @@ pub static name: &'static str = "user.name";
 #[allow(non_upper_case_globals)]
 #[allow(dead_code)]
 pub static date_created: &'static str = "user.date_created";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static role: &'static str = "user.role";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static email: &'static str = "user.email";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static unsubscribe_token: &'static str = "user.unsubscribe_token";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static password_hash: &'static str = "user.password_hash";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static shipping_address: &'static str = "user.shipping_address";
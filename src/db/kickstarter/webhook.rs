@@ -0,0 +1,187 @@
+//! WARNING: This file is generated, derived from table kickstarter.webhook, DO NOT EDIT
+
+use chrono::naive::datetime::NaiveDateTime;
+use db::kickstarter::Project;
+use db::kickstarter::WebhookDelivery;
+use rustorm::dao::Dao;
+use rustorm::dao::IsDao;
+use db::schema;
+use db::table;
+use db::column;
+use rustorm::table::IsTable;
+use rustorm::table::Column;
+use rustorm::table::Table;
+use rustorm::table::Foreign;
+use rustc_serialize::json::ToJson;
+use rustc_serialize::json::Json;
+
+
+
+#[derive(RustcDecodable, RustcEncodable)]
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    /// primary
+    /// default: nextval('kickstarter.webhook_webhook_id_seq'::regclass)
+    /// not nullable 
+    /// db data type: integer
+    pub webhook_id: i32,
+    /// db data type: integer
+    pub project_id: Option<i32>,
+    /// not nullable 
+    /// db data type: text
+    pub url: String,
+    /// not nullable 
+    /// db data type: text
+    pub secret: String,
+    /// default: true
+    /// not nullable 
+    /// db data type: boolean
+    pub active: bool,
+    /// default: ('now'::text)::timestamp without time zone
+    /// not nullable 
+    /// db data type: timestamp without time zone
+    pub date_created: NaiveDateTime,
+
+    /// has one
+    pub project: Option<Project>,
+    /// has many
+    pub webhook_delivery: Vec<WebhookDelivery>,
+}
+
+
+
+impl IsDao for Webhook {
+    fn from_dao(dao: &Dao) -> Self {
+        Webhook {
+            webhook_id: dao.get(column::webhook_id),
+            project_id: dao.get_opt(column::project_id),
+            url: dao.get(column::url),
+            secret: dao.get(column::secret),
+            active: dao.get(column::active),
+            date_created: dao.get(column::date_created),
+            project: None,
+            webhook_delivery: vec![],
+        }
+    }
+
+    fn to_dao(&self) -> Dao {
+        let mut dao = Dao::new();
+        dao.set(column::webhook_id, &self.webhook_id);
+        match self.project_id {
+            Some(ref _value) => dao.set(column::project_id, _value),
+            None => dao.set_null(column::project_id)
+        }
+        dao.set(column::url, &self.url);
+        dao.set(column::secret, &self.secret);
+        dao.set(column::active, &self.active);
+        dao.set(column::date_created, &self.date_created);
+        dao
+    }
+}
+
+impl ToJson for Webhook {
+
+    fn to_json(&self) -> Json {
+        self.to_dao().to_json()
+    }
+}
+
+impl IsTable for Webhook {
+
+    fn table() -> Table {
+        Table {
+            schema: schema::kickstarter.to_owned(),
+            name: table::webhook.to_owned(),
+            parent_table: None,
+            sub_table: vec![],
+            comment: None,
+            columns: vec![
+                Column {
+                    name: column::webhook_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: true, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("nextval('kickstarter.webhook_webhook_id_seq'::regclass)".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::project_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: Some(
+                        Foreign {
+                            schema: "kickstarter".to_owned(),
+                            table: "project".to_owned(),
+                            column: "project_id".to_owned(),
+                        }),
+                },
+                Column {
+                    name: column::url.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::secret.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::active.to_owned(),
+                    data_type: "bool".to_owned(),
+                    db_data_type: "boolean".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("true".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_created.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("('now'::text)::timestamp without time zone".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+            ],
+            is_view: false,
+        }
+    }
+}
+// Generated columns for easier development of dynamic queries without sacrificing wrong spelling of column names
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static webhook_id: &'static str = "webhook.webhook_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static project_id: &'static str = "webhook.project_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static url: &'static str = "webhook.url";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static secret: &'static str = "webhook.secret";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static active: &'static str = "webhook.active";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_created: &'static str = "webhook.date_created";
@@ -0,0 +1,166 @@
+//! WARNING: This file is generated, derived from table kickstarter.webhook_attempt, DO NOT EDIT
+
+use chrono::naive::datetime::NaiveDateTime;
+use db::kickstarter::WebhookDelivery;
+use rustorm::dao::Dao;
+use rustorm::dao::IsDao;
+use db::schema;
+use db::table;
+use db::column;
+use rustorm::table::IsTable;
+use rustorm::table::Column;
+use rustorm::table::Table;
+use rustorm::table::Foreign;
+use rustc_serialize::json::ToJson;
+use rustc_serialize::json::Json;
+
+
+
+#[derive(RustcDecodable, RustcEncodable)]
+#[derive(Debug, Clone)]
+pub struct WebhookAttempt {
+    /// primary
+    /// default: nextval('kickstarter.webhook_attempt_attempt_id_seq'::regclass)
+    /// not nullable 
+    /// db data type: integer
+    pub attempt_id: i32,
+    /// not nullable 
+    /// db data type: integer
+    pub delivery_id: i32,
+    /// db data type: integer
+    pub status_code: Option<i32>,
+    /// db data type: text
+    pub error: Option<String>,
+    /// default: ('now'::text)::timestamp without time zone
+    /// not nullable 
+    /// db data type: timestamp without time zone
+    pub date_created: NaiveDateTime,
+
+    /// has one
+    pub webhook_delivery: Option<WebhookDelivery>,
+}
+
+
+
+impl IsDao for WebhookAttempt {
+    fn from_dao(dao: &Dao) -> Self {
+        WebhookAttempt {
+            attempt_id: dao.get(column::attempt_id),
+            delivery_id: dao.get(column::delivery_id),
+            status_code: dao.get_opt(column::status_code),
+            error: dao.get_opt(column::error),
+            date_created: dao.get(column::date_created),
+            webhook_delivery: None,
+        }
+    }
+
+    fn to_dao(&self) -> Dao {
+        let mut dao = Dao::new();
+        dao.set(column::attempt_id, &self.attempt_id);
+        dao.set(column::delivery_id, &self.delivery_id);
+        match self.status_code {
+            Some(ref _value) => dao.set(column::status_code, _value),
+            None => dao.set_null(column::status_code)
+        }
+        match self.error {
+            Some(ref _value) => dao.set(column::error, _value),
+            None => dao.set_null(column::error)
+        }
+        dao.set(column::date_created, &self.date_created);
+        dao
+    }
+}
+
+impl ToJson for WebhookAttempt {
+
+    fn to_json(&self) -> Json {
+        self.to_dao().to_json()
+    }
+}
+
+impl IsTable for WebhookAttempt {
+
+    fn table() -> Table {
+        Table {
+            schema: schema::kickstarter.to_owned(),
+            name: table::webhook_attempt.to_owned(),
+            parent_table: None,
+            sub_table: vec![],
+            comment: None,
+            columns: vec![
+                Column {
+                    name: column::attempt_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: true, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("nextval('kickstarter.webhook_attempt_attempt_id_seq'::regclass)".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::delivery_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: Some(
+                        Foreign {
+                            schema: "kickstarter".to_owned(),
+                            table: "webhook_delivery".to_owned(),
+                            column: "delivery_id".to_owned(),
+                        }),
+                },
+                Column {
+                    name: column::status_code.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::error.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_created.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("('now'::text)::timestamp without time zone".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+            ],
+            is_view: false,
+        }
+    }
+}
+// Generated columns for easier development of dynamic queries without sacrificing wrong spelling of column names
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static attempt_id: &'static str = "webhook_attempt.attempt_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static delivery_id: &'static str = "webhook_attempt.delivery_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static status_code: &'static str = "webhook_attempt.status_code";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static error: &'static str = "webhook_attempt.error";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_created: &'static str = "webhook_attempt.date_created";
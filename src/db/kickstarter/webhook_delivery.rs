@@ -0,0 +1,243 @@
+//! WARNING: This file is generated, derived from table kickstarter.webhook_delivery, DO NOT EDIT
+
+use chrono::naive::datetime::NaiveDateTime;
+use db::kickstarter::Webhook;
+use db::kickstarter::WebhookAttempt;
+use rustorm::dao::Dao;
+use rustorm::dao::IsDao;
+use db::schema;
+use db::table;
+use db::column;
+use rustorm::table::IsTable;
+use rustorm::table::Column;
+use rustorm::table::Table;
+use rustorm::table::Foreign;
+use rustc_serialize::json::ToJson;
+use rustc_serialize::json::Json;
+
+
+
+#[derive(RustcDecodable, RustcEncodable)]
+#[derive(Debug, Clone)]
+pub struct WebhookDelivery {
+    /// primary
+    /// default: nextval('kickstarter.webhook_delivery_delivery_id_seq'::regclass)
+    /// not nullable 
+    /// db data type: integer
+    pub delivery_id: i32,
+    /// not nullable 
+    /// db data type: integer
+    pub webhook_id: i32,
+    /// not nullable 
+    /// db data type: text
+    pub event: String,
+    /// not nullable 
+    /// db data type: text
+    pub payload: String,
+    /// default: 'pending'::text
+    /// not nullable 
+    /// db data type: text
+    pub status: String,
+    /// default: 0
+    /// not nullable 
+    /// db data type: integer
+    pub attempts: i32,
+    /// default: ('now'::text)::timestamp without time zone
+    /// not nullable 
+    /// db data type: timestamp without time zone
+    pub next_attempt: NaiveDateTime,
+    /// default: ('now'::text)::timestamp without time zone
+    /// not nullable 
+    /// db data type: timestamp without time zone
+    pub date_created: NaiveDateTime,
+    /// db data type: timestamp without time zone
+    pub date_delivered: Option<NaiveDateTime>,
+
+    /// has one
+    pub webhook: Option<Webhook>,
+    /// has many
+    pub webhook_attempt: Vec<WebhookAttempt>,
+}
+
+
+
+impl IsDao for WebhookDelivery {
+    fn from_dao(dao: &Dao) -> Self {
+        WebhookDelivery {
+            delivery_id: dao.get(column::delivery_id),
+            webhook_id: dao.get(column::webhook_id),
+            event: dao.get(column::event),
+            payload: dao.get(column::payload),
+            status: dao.get(column::status),
+            attempts: dao.get(column::attempts),
+            next_attempt: dao.get(column::next_attempt),
+            date_created: dao.get(column::date_created),
+            date_delivered: dao.get_opt(column::date_delivered),
+            webhook: None,
+            webhook_attempt: vec![],
+        }
+    }
+
+    fn to_dao(&self) -> Dao {
+        let mut dao = Dao::new();
+        dao.set(column::delivery_id, &self.delivery_id);
+        dao.set(column::webhook_id, &self.webhook_id);
+        dao.set(column::event, &self.event);
+        dao.set(column::payload, &self.payload);
+        dao.set(column::status, &self.status);
+        dao.set(column::attempts, &self.attempts);
+        dao.set(column::next_attempt, &self.next_attempt);
+        dao.set(column::date_created, &self.date_created);
+        match self.date_delivered {
+            Some(ref _value) => dao.set(column::date_delivered, _value),
+            None => dao.set_null(column::date_delivered)
+        }
+        dao
+    }
+}
+
+impl ToJson for WebhookDelivery {
+
+    fn to_json(&self) -> Json {
+        self.to_dao().to_json()
+    }
+}
+
+impl IsTable for WebhookDelivery {
+
+    fn table() -> Table {
+        Table {
+            schema: schema::kickstarter.to_owned(),
+            name: table::webhook_delivery.to_owned(),
+            parent_table: None,
+            sub_table: vec![],
+            comment: None,
+            columns: vec![
+                Column {
+                    name: column::delivery_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: true, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("nextval('kickstarter.webhook_delivery_delivery_id_seq'::regclass)".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::webhook_id.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: Some(
+                        Foreign {
+                            schema: "kickstarter".to_owned(),
+                            table: "webhook".to_owned(),
+                            column: "webhook_id".to_owned(),
+                        }),
+                },
+                Column {
+                    name: column::event.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::payload.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::status.to_owned(),
+                    data_type: "String".to_owned(),
+                    db_data_type: "text".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("'pending'::text".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::attempts.to_owned(),
+                    data_type: "i32".to_owned(),
+                    db_data_type: "integer".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("0".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::next_attempt.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("('now'::text)::timestamp without time zone".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_created.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: true, is_inherited: false,
+                    default: Some("('now'::text)::timestamp without time zone".to_owned()),
+                    comment: None,
+                    foreign: None,
+                },
+                Column {
+                    name: column::date_delivered.to_owned(),
+                    data_type: "NaiveDateTime".to_owned(),
+                    db_data_type: "timestamp without time zone".to_owned(),
+                    is_primary: false, is_unique: false, not_null: false, is_inherited: false,
+                    default: None,
+                    comment: None,
+                    foreign: None,
+                },
+            ],
+            is_view: false,
+        }
+    }
+}
+// Generated columns for easier development of dynamic queries without sacrificing wrong spelling of column names
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static delivery_id: &'static str = "webhook_delivery.delivery_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static webhook_id: &'static str = "webhook_delivery.webhook_id";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static event: &'static str = "webhook_delivery.event";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static payload: &'static str = "webhook_delivery.payload";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static status: &'static str = "webhook_delivery.status";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static attempts: &'static str = "webhook_delivery.attempts";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static next_attempt: &'static str = "webhook_delivery.next_attempt";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_created: &'static str = "webhook_delivery.date_created";
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub static date_delivered: &'static str = "webhook_delivery.date_delivered";
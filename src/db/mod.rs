@@ -6,15 +6,37 @@ pub mod column;
 
 use rustorm::table::Table;
 use rustorm::table::IsTable;
+use db::kickstarter::Audit;
+use db::kickstarter::Comment;
+use db::kickstarter::Event;
+use db::kickstarter::Mail;
+use db::kickstarter::MailPreference;
 use db::kickstarter::Pledge;
 use db::kickstarter::Project;
+use db::kickstarter::Refund;
+use db::kickstarter::Reward;
+use db::kickstarter::Token;
 use db::kickstarter::User;
+use db::kickstarter::Webhook;
+use db::kickstarter::WebhookAttempt;
+use db::kickstarter::WebhookDelivery;
 
 
 pub fn get_all_tables() -> Vec<Table> {
     vec![
+        Audit::table(),
+        Comment::table(),
+        Event::table(),
+        Mail::table(),
+        MailPreference::table(),
         Pledge::table(),
         Project::table(),
+        Refund::table(),
+        Reward::table(),
+        Token::table(),
         User::table(),
+        Webhook::table(),
+        WebhookAttempt::table(),
+        WebhookDelivery::table(),
     ]
-}
\ No newline at end of file
+}
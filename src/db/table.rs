@@ -1,3 +1,17 @@
+#[allow(non_upper_case_globals)]
+pub const audit: &'static str = "audit";
+
+#[allow(non_upper_case_globals)]
+pub const comment: &'static str = "comment";
+
+#[allow(non_upper_case_globals)]
+pub const event: &'static str = "event";
+
+#[allow(non_upper_case_globals)]
+pub const mail: &'static str = "mail";
+
+#[allow(non_upper_case_globals)]
+pub const mail_preference: &'static str = "mail_preference";
 
 #[allow(non_upper_case_globals)]
 pub const pledge: &'static str = "pledge";
@@ -5,5 +19,23 @@ pub const pledge: &'static str = "pledge";
 #[allow(non_upper_case_globals)]
 pub const project: &'static str = "project";
 
+#[allow(non_upper_case_globals)]
+pub const refund: &'static str = "refund";
+
+#[allow(non_upper_case_globals)]
+pub const reward: &'static str = "reward";
+
+#[allow(non_upper_case_globals)]
+pub const token: &'static str = "token";
+
 #[allow(non_upper_case_globals)]
 pub const user: &'static str = "user";
+
+#[allow(non_upper_case_globals)]
+pub const webhook: &'static str = "webhook";
+
+#[allow(non_upper_case_globals)]
+pub const webhook_attempt: &'static str = "webhook_attempt";
+
+#[allow(non_upper_case_globals)]
+pub const webhook_delivery: &'static str = "webhook_delivery";
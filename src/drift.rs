@@ -0,0 +1,228 @@
+//! Module for finding where the database has drifted from the generated models, for `ksr schema check`.
+//!
+//! The models in `db::kickstarter` describe each table as it was when they were last generated.
+//! A check reads the same description of each table from the Postgres catalog and compares the
+//! two: tables or columns that only one side has, and columns whose type, nullability or foreign
+//! key differ. Constraints other than foreign keys, defaults and indexes aren't compared, since
+//! the models don't describe them fully. Foreign keys are compared by the table and column they
+//! reference, so that a schema built under another name still matches.
+use {Client, Result};
+use db;
+
+use rustc_serialize::json::{Json, ToJson};
+use rustorm::dao::Value;
+use rustorm::table::{Column, Foreign, Table};
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// How a table or column differs between the models and the database.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// The models have a table that the database doesn't.
+    MissingTable,
+    /// The database has a table that the models don't.
+    ExtraTable,
+    /// The models have a column that the database doesn't.
+    MissingColumn,
+    /// The database has a column that the models don't.
+    ExtraColumn,
+    Type,
+    Nullability,
+    ForeignKey,
+}
+
+impl Kind {
+    /// Returns the name of the difference, as reported.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Kind::MissingTable  => "missing_table",
+            Kind::ExtraTable    => "extra_table",
+            Kind::MissingColumn => "missing_column",
+            Kind::ExtraColumn   => "extra_column",
+            Kind::Type          => "type",
+            Kind::Nullability   => "nullability",
+            Kind::ForeignKey    => "foreign_key",
+        }
+    }
+}
+
+/// A difference between a model and the table in the database.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Drift {
+    pub kind: Kind,
+    pub table: String,
+    /// The column that differs, unless the whole table does.
+    pub column: Option<String>,
+    /// What the models describe, if anything.
+    pub expected: Option<String>,
+    /// What the database has, if anything.
+    pub found: Option<String>,
+}
+
+impl Drift {
+    fn new(kind: Kind, table: &str, column: Option<&str>, expected: Option<String>, found: Option<String>) -> Drift {
+        Drift {
+            kind: kind,
+            table: table.to_owned(),
+            column: column.map(|c| c.to_owned()),
+            expected: expected,
+            found: found,
+        }
+    }
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.column {
+            Some(ref column) => format!("{}.{}", self.table, column),
+            None => self.table.clone(),
+        };
+        let or_none = |value: &Option<String>| value.clone().unwrap_or("none".to_owned());
+
+        match self.kind {
+            Kind::MissingTable  => write!(fmt, "Table {} is in the models but not the database.", name),
+            Kind::ExtraTable    => write!(fmt, "Table {} is in the database but not the models.", name),
+            Kind::MissingColumn => write!(fmt, "Column {} is in the models but not the database.", name),
+            Kind::ExtraColumn   => write!(fmt, "Column {} is in the database but not the models.", name),
+            Kind::Type          => write!(fmt, "Column {} is {} in the models but {} in the database.",
+                                          name, or_none(&self.expected), or_none(&self.found)),
+            Kind::Nullability   => write!(fmt, "Column {} is {} in the models but {} in the database.",
+                                          name, or_none(&self.expected), or_none(&self.found)),
+            Kind::ForeignKey    => write!(fmt, "Column {} references {} in the models but {} in the database.",
+                                          name, or_none(&self.expected), or_none(&self.found)),
+        }
+    }
+}
+
+impl ToJson for Drift {
+    fn to_json(&self) -> Json {
+        let mut json = BTreeMap::new();
+        json.insert("kind".to_owned(), self.kind.as_str().to_json());
+        json.insert("table".to_owned(), self.table.to_json());
+        json.insert("column".to_owned(), self.column.to_json());
+        json.insert("expected".to_owned(), self.expected.to_json());
+        json.insert("found".to_owned(), self.found.to_json());
+        json.insert("message".to_owned(), self.to_string().to_json());
+        Json::Object(json)
+    }
+}
+
+/// Compares the client's schema with the generated models.
+pub fn check(client: &Client) -> Result<Vec<Drift>> {
+    Ok(compare(&db::get_all_tables(), &try!(tables(client))))
+}
+
+/// Returns every table in the client's schema, described as the models describe them.
+pub fn tables(client: &Client) -> Result<Vec<Table>> {
+    let sql = "SELECT c.relname::text AS table_name, \
+                      a.attname::text AS column_name, \
+                      format_type(a.atttypid, a.atttypmod) AS data_type, \
+                      a.attnotnull AS not_null, \
+                      (SELECT r.relname::text || '.' || ra.attname::text FROM pg_constraint f \
+                       INNER JOIN pg_class r ON r.oid = f.confrelid \
+                       INNER JOIN pg_attribute ra ON ra.attrelid = f.confrelid AND ra.attnum = f.confkey[1] \
+                       WHERE f.conrelid = c.oid AND f.contype = 'f' AND f.conkey = ARRAY[a.attnum] \
+                       ORDER BY f.conname LIMIT 1) AS foreign_key \
+               FROM pg_class c \
+               INNER JOIN pg_namespace n ON n.oid = c.relnamespace \
+               INNER JOIN pg_attribute a ON a.attrelid = c.oid AND a.attnum > 0 AND NOT a.attisdropped \
+               WHERE n.nspname = $1 AND c.relkind = 'r' \
+               ORDER BY c.relname, a.attnum";
+
+    let daos = try!(client.db().execute_sql_with_return(sql, &vec![Value::String(client.schema.clone())]));
+
+    let mut tables: Vec<Table> = vec![];
+    for dao in &daos {
+        let name: String = dao.get("table_name");
+        if tables.last().map_or(true, |t| t.name != name) {
+            tables.push(Table {
+                schema: client.schema.clone(),
+                name: name,
+                parent_table: None,
+                sub_table: vec![],
+                comment: None,
+                columns: vec![],
+                is_view: false,
+            });
+        }
+
+        let foreign = dao.get_opt::<String>("foreign_key").map(|reference| {
+            let mut parts = reference.splitn(2, '.');
+            Foreign {
+                schema: client.schema.clone(),
+                table: parts.next().unwrap_or("").to_owned(),
+                column: parts.next().unwrap_or("").to_owned(),
+            }
+        });
+
+        tables.last_mut().unwrap().columns.push(Column {
+            name: dao.get("column_name"),
+            data_type: String::new(),
+            db_data_type: dao.get("data_type"),
+            is_primary: false, is_unique: false, not_null: dao.get("not_null"), is_inherited: false,
+            default: None,
+            comment: None,
+            foreign: foreign,
+        });
+    }
+    Ok(tables)
+}
+
+/// Compares the tables that the models expect with the tables found in a database, in the
+/// order that the models list them, followed by tables that only the database has.
+pub fn compare(expected: &[Table], found: &[Table]) -> Vec<Drift> {
+    let mut drift = vec![];
+    for model in expected {
+        let table = match found.iter().find(|t| t.name == model.name) {
+            Some(table) => table,
+            None => {
+                drift.push(Drift::new(Kind::MissingTable, &model.name, None, None, None));
+                continue;
+            }
+        };
+
+        for column in &model.columns {
+            let live = match table.columns.iter().find(|c| c.name == column.name) {
+                Some(live) => live,
+                None => {
+                    drift.push(Drift::new(Kind::MissingColumn, &model.name, Some(&column.name),
+                                          Some(column.db_data_type.clone()), None));
+                    continue;
+                }
+            };
+
+            if column.db_data_type != live.db_data_type {
+                drift.push(Drift::new(Kind::Type, &model.name, Some(&column.name),
+                                      Some(column.db_data_type.clone()), Some(live.db_data_type.clone())));
+            }
+            if column.not_null != live.not_null {
+                drift.push(Drift::new(Kind::Nullability, &model.name, Some(&column.name),
+                                      Some(nullability(column)), Some(nullability(live))));
+            }
+            if reference(column) != reference(live) {
+                drift.push(Drift::new(Kind::ForeignKey, &model.name, Some(&column.name),
+                                      reference(column), reference(live)));
+            }
+        }
+
+        for live in table.columns.iter().filter(|live| !model.columns.iter().any(|c| c.name == live.name)) {
+            drift.push(Drift::new(Kind::ExtraColumn, &model.name, Some(&live.name),
+                                  None, Some(live.db_data_type.clone())));
+        }
+    }
+
+    for table in found.iter().filter(|table| !expected.iter().any(|t| t.name == table.name)) {
+        drift.push(Drift::new(Kind::ExtraTable, &table.name, None, None, None));
+    }
+    drift
+}
+
+fn nullability(column: &Column) -> String {
+    (if column.not_null { "not null" } else { "nullable" }).to_owned()
+}
+
+/// Returns the table and column that a column references, ignoring the schema.
+fn reference(column: &Column) -> Option<String> {
+    column.foreign.as_ref().map(|f| format!("{}.{}", f.table, f.column))
+}
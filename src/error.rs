@@ -1,6 +1,8 @@
 //! Kickstarter errors
 use validate;
+use postgres::error as pg;
 use rustorm::database;
+use rustc_serialize::json;
 use std::{error, fmt, io, result};
 
 /// A type for results generated by Kickstarter-related functions, where the Err type is kickstarter::Error.
@@ -17,6 +19,10 @@ pub enum Error {
     Database(database::DbError),
     /// An I/O operation could not be completed.
     IO(io::Error),
+    /// Stored JSON could not be decoded.
+    Decode(json::DecoderError),
+    /// The principal's role does not allow the operation.
+    PermissionDenied(String),
 }
 
 impl From<validate::Error> for Error {
@@ -31,19 +37,39 @@ impl From<database::DbError> for Error {
     }
 }
 
+impl From<pg::Error> for Error {
+    fn from(err: pg::Error) -> Error {
+        Error::Database(database::DbError::new(&err.to_string()))
+    }
+}
+
+impl From<pg::ConnectError> for Error {
+    fn from(err: pg::ConnectError) -> Error {
+        Error::Database(database::DbError::new(&err.to_string()))
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Error {
         Error::IO(err)
     }
 }
 
+impl From<json::DecoderError> for Error {
+    fn from(err: json::DecoderError) -> Error {
+        Error::Decode(err)
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Error::InvalidData(ref inner) => inner.fmt(fmt),
             Error::Database(ref inner) => inner.fmt(fmt),
             Error::IO(ref inner) => inner.fmt(fmt),
+            Error::Decode(ref inner) => inner.fmt(fmt),
             Error::Config(ref inner) => inner.fmt(fmt),
+            Error::PermissionDenied(ref inner) => inner.fmt(fmt),
         }
     }
 }
@@ -54,7 +80,9 @@ impl error::Error for Error {
             Error::InvalidData(ref inner) => inner.description(),
             Error::Database(ref inner) => inner.description(),
             Error::IO(ref inner) => inner.description(),
+            Error::Decode(ref inner) => inner.description(),
             Error::Config(ref inner) => inner,
+            Error::PermissionDenied(ref inner) => inner,
         }
     }
 
@@ -63,7 +91,9 @@ impl error::Error for Error {
             Error::InvalidData(ref inner) => Some(inner),
             Error::Database(ref inner) => Some(inner),
             Error::IO(ref inner) => Some(inner),
+            Error::Decode(ref inner) => Some(inner),
             Error::Config(_) => None,
+            Error::PermissionDenied(_) => None,
         }
     }
 }
@@ -0,0 +1,357 @@
+//! Module for recording domain events and replaying them into projections.
+//!
+//! Every change to users, projects, rewards, pledges and refunds is recorded as a domain event
+//! in the same transaction as the change itself. Those tables are projections of the
+//! event log, and can be rebuilt in a fresh schema by replaying it, either in full
+//! or up to a point in time.
+pub use models::Event;
+
+use {Client, Result};
+use db::{column, table};
+
+use chrono::naive::datetime::NaiveDateTime;
+use rustc_serialize::json::{self, DecoderError};
+use rustorm::dao::{IsDao, Value};
+use rustorm::query::Query;
+
+/// A project was created.
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct ProjectCreated {
+    pub project_id: i32,
+    pub name: String,
+    pub goal: f64,
+    pub creator_id: Option<i32>,
+}
+
+/// A project's campaign ended.
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct ProjectClosed {
+    pub project_id: i32,
+}
+
+/// A project offered a reward tier.
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct RewardAdded {
+    pub reward_id: i32,
+    pub project_id: i32,
+    pub title: String,
+    pub minimum: f64,
+}
+
+/// A user was created. Users created implicitly, such as by their first pledge, have no password.
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct UserCreated {
+    pub user_id: i32,
+    pub name: String,
+    /// The bcrypt hash of the password, so that replayed accounts can still log in.
+    pub password_hash: Option<String>,
+}
+
+/// A user pledged towards a project.
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct PledgeMade {
+    pub user_id: i32,
+    pub project_id: i32,
+    pub card: String,
+    pub amount: f64,
+}
+
+/// Money was returned from a pledge, through a refund or chargeback.
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct PledgeRefunded {
+    pub refund_id: i32,
+    pub user_id: i32,
+    pub project_id: i32,
+    pub amount: f64,
+    pub kind: String,
+    pub reason: String,
+    pub reference: Option<String>,
+}
+
+/// A user withdrew their pledge from a project.
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct PledgeCancelled {
+    pub user_id: i32,
+    pub project_id: i32,
+}
+
+/// A project started or stopped restricting comments to its backers.
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct CommentsRestricted {
+    pub project_id: i32,
+    pub backers_only: bool,
+}
+
+/// A user set or cleared their email address.
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct EmailChanged {
+    pub user_id: i32,
+    pub email: Option<String>,
+}
+
+/// A user set or cleared the address that rewards are shipped to.
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct ShippingAddressChanged {
+    pub user_id: i32,
+    pub shipping_address: Option<String>,
+}
+
+/// A user's role changed.
+#[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct RoleChanged {
+    pub user_id: i32,
+    pub role: String,
+}
+
+/// Something that happened on Kickstarter.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DomainEvent {
+    ProjectCreated(ProjectCreated),
+    ProjectClosed(ProjectClosed),
+    RewardAdded(RewardAdded),
+    UserCreated(UserCreated),
+    EmailChanged(EmailChanged),
+    ShippingAddressChanged(ShippingAddressChanged),
+    PledgeMade(PledgeMade),
+    PledgeRefunded(PledgeRefunded),
+    PledgeCancelled(PledgeCancelled),
+    CommentsRestricted(CommentsRestricted),
+    RoleChanged(RoleChanged),
+}
+
+impl DomainEvent {
+
+    /// Returns the name the event is stored under.
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            DomainEvent::ProjectCreated(_)     => "ProjectCreated",
+            DomainEvent::ProjectClosed(_)      => "ProjectClosed",
+            DomainEvent::RewardAdded(_)        => "RewardAdded",
+            DomainEvent::UserCreated(_)        => "UserCreated",
+            DomainEvent::EmailChanged(_)       => "EmailChanged",
+            DomainEvent::ShippingAddressChanged(_) => "ShippingAddressChanged",
+            DomainEvent::PledgeMade(_)         => "PledgeMade",
+            DomainEvent::PledgeRefunded(_)     => "PledgeRefunded",
+            DomainEvent::PledgeCancelled(_)    => "PledgeCancelled",
+            DomainEvent::CommentsRestricted(_) => "CommentsRestricted",
+            DomainEvent::RoleChanged(_)        => "RoleChanged",
+        }
+    }
+
+    /// Encodes the event's fields as JSON.
+    fn payload(&self) -> String {
+        // Encoding plain structs into a String cannot fail.
+        match *self {
+            DomainEvent::ProjectCreated(ref e)     => json::encode(e),
+            DomainEvent::ProjectClosed(ref e)      => json::encode(e),
+            DomainEvent::RewardAdded(ref e)        => json::encode(e),
+            DomainEvent::UserCreated(ref e)        => json::encode(e),
+            DomainEvent::EmailChanged(ref e)       => json::encode(e),
+            DomainEvent::ShippingAddressChanged(ref e) => json::encode(e),
+            DomainEvent::PledgeMade(ref e)         => json::encode(e),
+            DomainEvent::PledgeRefunded(ref e)     => json::encode(e),
+            DomainEvent::PledgeCancelled(ref e)    => json::encode(e),
+            DomainEvent::CommentsRestricted(ref e) => json::encode(e),
+            DomainEvent::RoleChanged(ref e)        => json::encode(e),
+        }.unwrap()
+    }
+
+    /// Decodes an event from its stored kind and JSON payload.
+    pub fn decode(kind: &str, payload: &str) -> Result<DomainEvent> {
+        let event = match kind {
+            "ProjectCreated"     => DomainEvent::ProjectCreated(try!(json::decode(payload))),
+            "ProjectClosed"      => DomainEvent::ProjectClosed(try!(json::decode(payload))),
+            "RewardAdded"        => DomainEvent::RewardAdded(try!(json::decode(payload))),
+            "UserCreated"        => DomainEvent::UserCreated(try!(json::decode(payload))),
+            "EmailChanged"       => DomainEvent::EmailChanged(try!(json::decode(payload))),
+            "ShippingAddressChanged" => DomainEvent::ShippingAddressChanged(try!(json::decode(payload))),
+            "PledgeMade"         => DomainEvent::PledgeMade(try!(json::decode(payload))),
+            "PledgeRefunded"     => DomainEvent::PledgeRefunded(try!(json::decode(payload))),
+            "PledgeCancelled"    => DomainEvent::PledgeCancelled(try!(json::decode(payload))),
+            "CommentsRestricted" => DomainEvent::CommentsRestricted(try!(json::decode(payload))),
+            "RoleChanged"        => DomainEvent::RoleChanged(try!(json::decode(payload))),
+            _ => return Err(From::from(DecoderError::UnknownVariantError(kind.to_owned()))),
+        };
+
+        Ok(event)
+    }
+
+    /// Applies the event to the client's projection tables, as if it happened at the provided time.
+    fn apply(&self, client: &Client, date: &NaiveDateTime) -> Result<()> {
+        let date = Value::NaiveDateTime(date.clone());
+
+        match *self {
+            DomainEvent::ProjectCreated(ref e) => {
+                let creator_id = e.creator_id.map_or(Value::Null, Value::I32);
+                let sql = format!("INSERT INTO {} (project_id, name, goal, creator_id, date_created) \
+                                   VALUES ($1, $2, $3, $4, $5)", client.table(table::project));
+                try!(client.db().execute_sql(&sql, &vec![Value::I32(e.project_id), Value::String(e.name.clone()),
+                                                          Value::F64(e.goal), creator_id, date]));
+            }
+
+            DomainEvent::ProjectClosed(ref e) => {
+                let sql = format!("UPDATE {} SET date_closed = $1 WHERE project_id = $2", client.table(table::project));
+                try!(client.db().execute_sql(&sql, &vec![date, Value::I32(e.project_id)]));
+            }
+
+            DomainEvent::RewardAdded(ref e) => {
+                let sql = format!("INSERT INTO {} (reward_id, project_id, title, minimum, date_created) \
+                                   VALUES ($1, $2, $3, $4, $5)", client.table(table::reward));
+                try!(client.db().execute_sql(&sql, &vec![Value::I32(e.reward_id), Value::I32(e.project_id),
+                                                          Value::String(e.title.clone()), Value::F64(e.minimum), date]));
+            }
+
+            DomainEvent::UserCreated(ref e) => {
+                let password_hash = e.password_hash.clone().map_or(Value::Null, Value::String);
+                let sql = format!("INSERT INTO {} (user_id, name, password_hash, date_created) \
+                                   VALUES ($1, $2, $3, $4)", client.table(table::user));
+                try!(client.db().execute_sql(&sql, &vec![Value::I32(e.user_id), Value::String(e.name.clone()),
+                                                          password_hash, date]));
+            }
+
+            DomainEvent::EmailChanged(ref e) => {
+                let email = e.email.clone().map_or(Value::Null, Value::String);
+                let sql = format!("UPDATE {} SET email = $1 WHERE user_id = $2", client.table(table::user));
+                try!(client.db().execute_sql(&sql, &vec![email, Value::I32(e.user_id)]));
+            }
+
+            DomainEvent::ShippingAddressChanged(ref e) => {
+                let address = e.shipping_address.clone().map_or(Value::Null, Value::String);
+                let sql = format!("UPDATE {} SET shipping_address = $1 WHERE user_id = $2", client.table(table::user));
+                try!(client.db().execute_sql(&sql, &vec![address, Value::I32(e.user_id)]));
+            }
+
+            DomainEvent::PledgeMade(ref e) => {
+                let sql = format!("INSERT INTO {} (user_id, project_id, card, amount, date_created) \
+                                   VALUES ($1, $2, $3, $4, $5)", client.table(table::pledge));
+                try!(client.db().execute_sql(&sql, &vec![Value::I32(e.user_id), Value::I32(e.project_id),
+                                                          Value::String(e.card.clone()), Value::F64(e.amount), date]));
+            }
+
+            DomainEvent::PledgeRefunded(ref e) => {
+                // Rounded and capped as `Refund::record` does, so that replays reach the same totals.
+                let sql = format!("UPDATE {} \
+                                   SET refunded = LEAST(amount, round((refunded + $1)::numeric, 2)::float8) \
+                                   WHERE user_id = $2 AND project_id = $3", client.table(table::pledge));
+                try!(client.db().execute_sql(&sql, &vec![Value::F64(e.amount), Value::I32(e.user_id),
+                                                          Value::I32(e.project_id)]));
+
+                let reference = match e.reference {
+                    Some(ref reference) => Value::String(reference.clone()),
+                    None => Value::Null,
+                };
+
+                let sql = format!("INSERT INTO {} (refund_id, user_id, project_id, amount, kind, reason, \
+                                   reference, date_created) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+                                  client.table(table::refund));
+                try!(client.db().execute_sql(&sql, &vec![Value::I32(e.refund_id), Value::I32(e.user_id),
+                                                          Value::I32(e.project_id), Value::F64(e.amount),
+                                                          Value::String(e.kind.clone()), Value::String(e.reason.clone()),
+                                                          reference, date]));
+            }
+
+            DomainEvent::PledgeCancelled(ref e) => {
+                let sql = format!("DELETE FROM {} WHERE user_id = $1 AND project_id = $2", client.table(table::pledge));
+                try!(client.db().execute_sql(&sql, &vec![Value::I32(e.user_id), Value::I32(e.project_id)]));
+            }
+
+            DomainEvent::CommentsRestricted(ref e) => {
+                let sql = format!("UPDATE {} SET comments_backers_only = $1 WHERE project_id = $2",
+                                  client.table(table::project));
+                try!(client.db().execute_sql(&sql, &vec![Value::Bool(e.backers_only), Value::I32(e.project_id)]));
+            }
+
+            DomainEvent::RoleChanged(ref e) => {
+                let sql = format!("UPDATE {} SET role = $1 WHERE user_id = $2", client.table(table::user));
+                try!(client.db().execute_sql(&sql, &vec![Value::String(e.role.clone()), Value::I32(e.user_id)]));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Event {
+
+    /// Appends a domain event to the log.
+    /// Callers should run this in the same transaction as the change being described.
+    pub fn record(client: &Client, event: DomainEvent) -> Result<Event> {
+        let event = try!(Query::insert()
+            .set(column::kind, &event.kind())
+            .set(column::payload, &event.payload())
+            .into_table(&client.table(table::event))
+            .return_all()
+            .collect_one(client.db()));
+
+        Ok(event)
+    }
+
+    /// Decodes the stored domain event.
+    pub fn domain_event(&self) -> Result<DomainEvent> {
+        DomainEvent::decode(&self.kind, &self.payload)
+    }
+
+    /// Returns the event log in order, optionally stopping at a point in time.
+    pub fn list(client: &Client, until: Option<NaiveDateTime>) -> Result<Vec<Event>> {
+        let daos = match until {
+            Some(until) => {
+                let sql = format!("SELECT * FROM {} WHERE date_created <= $1 ORDER BY event_id",
+                                  client.table(table::event));
+                try!(client.db().execute_sql_with_return(&sql, &vec![Value::NaiveDateTime(until)]))
+            }
+            None => {
+                let sql = format!("SELECT * FROM {} ORDER BY event_id", client.table(table::event));
+                try!(client.db().execute_sql_with_return(&sql, &vec![]))
+            }
+        };
+
+        Ok(daos.iter().map(Event::from_dao).collect())
+    }
+
+    /// Replays the source's event log into the target's freshly built schema,
+    /// optionally stopping at a point in time. Returns the number of events replayed.
+    pub fn replay(source: &Client, target: &Client, until: Option<NaiveDateTime>) -> Result<usize> {
+        let events = try!(Event::list(source, until));
+
+        target.transaction(|| {
+            for event in &events {
+                let domain_event = try!(event.domain_event());
+                try!(domain_event.apply(target, &event.date_created));
+                try!(event.copy_to(target));
+            }
+
+            try!(Event::reset_sequences(target));
+            Ok(events.len())
+        })
+    }
+
+    /// Copies the event, with its original ID and time, into the target's event log.
+    fn copy_to(&self, target: &Client) -> Result<()> {
+        let sql = format!("INSERT INTO {} (event_id, kind, payload, date_created) VALUES ($1, $2, $3, $4)",
+                          target.table(table::event));
+
+        try!(target.db().execute_sql(&sql, &vec![Value::I32(self.event_id),
+                                                  Value::String(self.kind.clone()),
+                                                  Value::String(self.payload.clone()),
+                                                  Value::NaiveDateTime(self.date_created.clone())]));
+        Ok(())
+    }
+
+    /// Moves each serial sequence past the largest replayed ID, so that new rows don't collide.
+    fn reset_sequences(target: &Client) -> Result<()> {
+        let serials = [(table::project, column::project_id),
+                       (table::user, column::user_id),
+                       (table::refund, column::refund_id),
+                       (table::reward, column::reward_id),
+                       (table::event, column::event_id)];
+
+        for &(table, column) in &serials {
+            let sql = format!("SELECT setval(pg_get_serial_sequence('{table}', '{column}'), \
+                               COALESCE(MAX({column}), 0) + 1, false) FROM {table}",
+                              table = target.table(table), column = column);
+            try!(target.db().execute_sql_with_return(&sql, &vec![]));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+//! Module for exporting a project's backers for fulfillment, for `ksr export-backers`.
+//!
+//! Each backer still owed something appears once, with how to reach them, where to ship
+//! their reward, which reward tier they earned and what they pledged, as CSV or as an .xlsx
+//! spreadsheet. Card numbers are masked to their last four digits, which is enough to match
+//! a backer's receipt. Pledges that were refunded in full are left out, since there is
+//! nothing left to fulfil.
+use {Client, Result};
+use models::{Pledge, Project, Reward};
+use output;
+use xlsx::{self, Cell};
+
+use chrono::naive::datetime::NaiveDateTime;
+use rustc_serialize::json::{Json, ToJson};
+
+use std::collections::BTreeMap;
+
+/// The columns of an export, in order.
+pub const COLUMNS: &'static [&'static str] = &["backer", "email", "shipping_address", "reward", "amount",
+                                               "pledged", "refunded", "date_pledged", "card"];
+
+/// A backer to fulfil a pledge for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Backer {
+    pub name: String,
+    pub email: Option<String>,
+    pub shipping_address: Option<String>,
+    /// The title of the richest reward tier that the remaining amount covers, if any.
+    pub reward: Option<String>,
+    /// What remains of the pledge after refunds and chargebacks.
+    pub amount: f64,
+    /// What was originally pledged.
+    pub pledged: f64,
+    pub refunded: f64,
+    pub date_pledged: NaiveDateTime,
+    /// The card number, masked to its last four digits.
+    pub card: String,
+}
+
+impl Backer {
+    fn from_pledge(pledge: &Pledge, rewards: &[Reward]) -> Backer {
+        let user = pledge.get_user();
+        Backer {
+            name: user.name.clone(),
+            email: user.email.clone(),
+            shipping_address: user.shipping_address.clone(),
+            reward: Reward::tier(rewards, pledge.net_amount()).map(|reward| reward.title.clone()),
+            amount: pledge.net_amount(),
+            pledged: pledge.amount,
+            refunded: pledge.refunded,
+            date_pledged: pledge.date_created,
+            card: pledge.masked_card(),
+        }
+    }
+
+    /// Returns the backer's fields as text, in the order of `COLUMNS`.
+    fn fields(&self) -> Vec<String> {
+        vec![self.name.clone(),
+             self.email.clone().unwrap_or(String::new()),
+             self.shipping_address.clone().unwrap_or(String::new()),
+             self.reward.clone().unwrap_or(String::new()),
+             format!("{:.2}", self.amount),
+             format!("{:.2}", self.pledged),
+             format!("{:.2}", self.refunded),
+             self.date_pledged.format("%Y-%m-%d %H:%M:%S").to_string(),
+             self.card.clone()]
+    }
+}
+
+impl ToJson for Backer {
+    fn to_json(&self) -> Json {
+        let mut json = BTreeMap::new();
+        json.insert("backer".to_owned(), self.name.to_json());
+        json.insert("email".to_owned(), self.email.to_json());
+        json.insert("shipping_address".to_owned(), self.shipping_address.to_json());
+        json.insert("reward".to_owned(), self.reward.to_json());
+        json.insert("amount".to_owned(), self.amount.to_json());
+        json.insert("pledged".to_owned(), self.pledged.to_json());
+        json.insert("refunded".to_owned(), self.refunded.to_json());
+        json.insert("date_pledged".to_owned(), self.date_pledged.to_string().to_json());
+        json.insert("card".to_owned(), self.card.to_json());
+        Json::Object(json)
+    }
+}
+
+/// Returns the backers of a project who are still owed something, in the order they pledged.
+pub fn backers(client: &Client, project_name: &str) -> Result<Vec<Backer>> {
+    let (mut pledges, _) = try!(Project::list_pledges(client, project_name));
+    pledges.sort_by(|a, b| a.date_created.cmp(&b.date_created));
+    let rewards = try!(Reward::list(client, project_name));
+
+    Ok(pledges.iter()
+        .filter(|pledge| pledge.net_amount() > 0f64)
+        .map(|pledge| Backer::from_pledge(pledge, &rewards))
+        .collect())
+}
+
+/// Returns the backers as CSV, with a header.
+pub fn csv(backers: &[Backer]) -> String {
+    let mut csv = format!("{}\n", COLUMNS.join(","));
+    for backer in backers {
+        let fields: Vec<String> = backer.fields().iter().map(|f| output::csv_quote(f)).collect();
+        csv.push_str(&format!("{}\n", fields.join(",")));
+    }
+    csv
+}
+
+/// Returns the backers as an .xlsx workbook, with a header, in a sheet named after the project.
+/// Amounts are stored as numbers so that they can be summed.
+pub fn xlsx(project_name: &str, backers: &[Backer]) -> Vec<u8> {
+    let mut rows = vec![COLUMNS.iter().map(|c| Cell::Text((*c).to_owned())).collect()];
+    for backer in backers {
+        rows.push(vec![Cell::Text(backer.name.clone()),
+                       backer.email.clone().map_or(Cell::Empty, Cell::Text),
+                       backer.shipping_address.clone().map_or(Cell::Empty, Cell::Text),
+                       backer.reward.clone().map_or(Cell::Empty, Cell::Text),
+                       Cell::Number(backer.amount),
+                       Cell::Number(backer.pledged),
+                       Cell::Number(backer.refunded),
+                       Cell::Text(backer.date_pledged.format("%Y-%m-%d %H:%M:%S").to_string()),
+                       Cell::Text(backer.card.clone())]);
+    }
+    xlsx::workbook(project_name, &rows)
+}
@@ -0,0 +1,173 @@
+//! Module for checking the output of `.ksr` scripts against expectations, for `ksr test`.
+//!
+//! Expectations are written inline, as `# expect:` comments after a command, or stored in a
+//! `.out` file beside the script. Inline expectations must each appear in the command's output,
+//! in order, so that `# expect: Attack_Owl needs` matches a longer line. A `.out` file holds the
+//! whole transcript, each command after `> ` followed by its output, and must match exactly.
+use std::cmp;
+
+/// The comment that introduces an inline expectation.
+pub const EXPECT: &'static str = "# expect:";
+
+/// A command in a script, with the expectations written after it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Command {
+    /// The line number of the command, counting from 1.
+    pub line: usize,
+    /// The command as `ksr run` echoes it.
+    pub text: String,
+    /// The text that must appear in the command's output, in order.
+    pub expected: Vec<String>,
+}
+
+/// Returns the commands in a script. `is_command` tells commands apart from blank and comment lines.
+pub fn parse<F>(script: &str, is_command: F) -> Vec<Command> where F: Fn(&str) -> bool {
+    let mut commands: Vec<Command> = vec![];
+    for (i, line) in script.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with(EXPECT) {
+            if let Some(command) = commands.last_mut() {
+                command.expected.push(trimmed[EXPECT.len()..].trim().to_owned());
+            }
+        } else if is_command(line) {
+            commands.push(Command {
+                line: i + 1,
+                text: trimmed.to_owned(),
+                expected: vec![],
+            });
+        }
+    }
+    commands
+}
+
+/// Splits the output of `ksr run` into the output of each command, using the `> ` line
+/// echoed before each one. Output after the summary is left out.
+pub fn outputs(commands: &[Command], stdout: &str) -> Vec<Vec<String>> {
+    let mut outputs: Vec<Vec<String>> = commands.iter().map(|_| vec![]).collect();
+    let mut next = 0;
+    let mut current = None;
+
+    for line in stdout.lines() {
+        if next < commands.len() && line == format!("> {}", commands[next].text) {
+            current = Some(next);
+            next += 1;
+        } else if current.is_some() && line == "Summary:" {
+            break;
+        } else if let Some(i) = current {
+            outputs[i].push(line.to_owned());
+        }
+    }
+
+    // `ksr run` leaves a blank line after each command.
+    for output in &mut outputs {
+        while output.last().map_or(false, |l| l.is_empty()) {
+            output.pop();
+        }
+    }
+    outputs
+}
+
+/// Returns the transcript stored in `.out` files.
+pub fn transcript(commands: &[Command], outputs: &[Vec<String>]) -> String {
+    let mut transcript = String::new();
+    for (command, output) in commands.iter().zip(outputs) {
+        transcript.push_str(&format!("> {}\n", command.text));
+        for line in output {
+            transcript.push_str(line);
+            transcript.push('\n');
+        }
+        transcript.push('\n');
+    }
+    transcript
+}
+
+/// Returns a description of every command whose output doesn't meet its inline expectations.
+pub fn mismatches(commands: &[Command], outputs: &[Vec<String>]) -> Vec<String> {
+    let mut mismatches = vec![];
+    for (command, output) in commands.iter().zip(outputs) {
+        let mut pos = 0;
+        let missing = command.expected.iter().find(|expected| {
+            match output[pos..].iter().position(|line| line.contains(&expected[..])) {
+                Some(i) => { pos += i + 1; false }
+                None => true,
+            }
+        });
+
+        if let Some(missing) = missing {
+            let mut message = format!("line {}: > {}\n- {}\n", command.line, command.text, missing);
+            for line in output {
+                message.push_str(&format!("+ {}\n", line));
+            }
+            mismatches.push(message);
+        }
+    }
+    mismatches
+}
+
+/// Rewrites a script's inline expectations to match the output of each command.
+pub fn bless(script: &str, commands: &[Command], outputs: &[Vec<String>]) -> String {
+    let mut blessed = String::new();
+    let mut next = 0;
+
+    for (i, line) in script.lines().enumerate() {
+        if line.trim().starts_with(EXPECT) {
+            continue;
+        }
+
+        blessed.push_str(line);
+        blessed.push('\n');
+
+        if next < commands.len() && commands[next].line == i + 1 {
+            for output in outputs[next].iter().filter(|l| !l.trim().is_empty()) {
+                blessed.push_str(&format!("{} {}\n", EXPECT, output));
+            }
+            next += 1;
+        }
+    }
+    blessed
+}
+
+/// Returns a line-by-line diff from the expected text to the actual text, with removed lines
+/// marked `-` and added lines marked `+`. Returns None if they are the same.
+pub fn diff(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+
+    let a: Vec<&str> = expected.lines().collect();
+    let b: Vec<&str> = actual.lines().collect();
+
+    // Lengths of the longest common subsequences of every pair of suffixes.
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                cmp::max(lcs[i + 1][j], lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            diff.push_str(&format!("  {}\n", a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push_str(&format!("- {}\n", a[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+ {}\n", b[j]));
+            j += 1;
+        }
+    }
+
+    // The texts differ only in trailing newlines.
+    if diff.lines().all(|l| l.starts_with("  ")) {
+        diff.push_str("(the files differ in their final newlines)\n");
+    }
+    Some(diff)
+}
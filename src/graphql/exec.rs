@@ -0,0 +1,627 @@
+//! Resolves parsed operations against the database.
+//!
+//! Fields are resolved for a whole batch of objects at once. Listing projects with their
+//! pledges and backers loads every project's pledges with one query, and every backer with
+//! one more, rather than issuing a query per project.
+use {api, validate, Client, Result};
+use account::Principal;
+use db::table;
+use models::{Pledge, Project, User};
+use notify::Notice;
+use super::parser::{Directive, Document, Field, Fragment, Operation, OperationKind, Selection, Value};
+
+use rustc_serialize::json::{Json, ToJson};
+use rustorm::dao::{Dao, IsDao, Value as DbValue};
+
+use std::cell::{Cell, RefCell};
+use std::cmp;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// How deeply fragments may spread into each other.
+const MAX_FRAGMENT_DEPTH: usize = 16;
+
+/// A batch of objects of the same type, resolved together.
+pub enum Nodes {
+    Projects(Vec<Project>),
+    Pledges(Vec<Pledge>),
+    Users(Vec<User>),
+    Events(Vec<Notice>),
+}
+
+impl Nodes {
+
+    /// Returns the name of the objects' type in the schema.
+    fn type_name(&self) -> &'static str {
+        match *self {
+            Nodes::Projects(_) => "Project",
+            Nodes::Pledges(_)  => "Pledge",
+            Nodes::Users(_)    => "User",
+            Nodes::Events(_)   => "PledgeEvent",
+        }
+    }
+
+    fn len(&self) -> usize {
+        match *self {
+            Nodes::Projects(ref v) => v.len(),
+            Nodes::Pledges(ref v)  => v.len(),
+            Nodes::Users(ref v)    => v.len(),
+            Nodes::Events(ref v)   => v.len(),
+        }
+    }
+}
+
+/// The state shared while executing one operation.
+pub struct Context<'a> {
+    client: &'a Client,
+    principal: Option<&'a Principal>,
+    fragments: &'a HashMap<String, Fragment>,
+    variables: BTreeMap<String, Json>,
+    /// Number of database queries made to load fields.
+    pub queries: Cell<usize>,
+    totals: RefCell<HashMap<i32, (f64, i64)>>,
+}
+
+impl<'a> Context<'a> {
+
+    /// Prepares to execute an operation from the document, checking the variables it declares.
+    pub fn new(client: &'a Client, principal: Option<&'a Principal>, document: &'a Document,
+               operation: &Operation, variables: &Json) -> Result<Context<'a>> {
+
+        let provided = match *variables {
+            Json::Object(ref map) => map.clone(),
+            Json::Null => BTreeMap::new(),
+            _ => return invalid("Variables must be a JSON object.".to_owned()),
+        };
+
+        let mut values = BTreeMap::new();
+        for definition in &operation.variables {
+            let value = match provided.get(&definition.name) {
+                Some(value) if *value != Json::Null => value.clone(),
+                _ => match definition.default {
+                    Some(ref default) => try!(to_json(default, &BTreeMap::new())),
+                    None => Json::Null,
+                },
+            };
+
+            if value == Json::Null && definition.type_name.ends_with('!') {
+                return invalid(format!("Variable \"${}\" of type \"{}\" is required.",
+                                       definition.name, definition.type_name));
+            }
+            values.insert(definition.name.clone(), value);
+        }
+
+        Ok(Context {
+            client: client,
+            principal: principal,
+            fragments: &document.fragments,
+            variables: values,
+            queries: Cell::new(0),
+            totals: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// Flattens fragments in a selection on the type, leaving the fields that apply to it.
+    /// Fields requested more than once under the same key have their selections merged.
+    pub fn collect(&self, type_name: &str, selection: &[Selection]) -> Result<Vec<Field>> {
+        let mut fields = vec![];
+        try!(self.collect_into(type_name, selection, &mut fields, 0));
+        Ok(fields)
+    }
+
+    fn collect_into(&self, type_name: &str, selection: &[Selection], fields: &mut Vec<Field>,
+                    depth: usize) -> Result<()> {
+        if depth > MAX_FRAGMENT_DEPTH {
+            return invalid("Fragments are nested too deeply; do any spread into themselves?".to_owned());
+        }
+
+        for item in selection {
+            match *item {
+                Selection::Field(ref field) => {
+                    if !try!(self.included(&field.directives)) {
+                        continue;
+                    }
+
+                    match fields.iter().position(|f| f.key() == field.key()) {
+                        Some(i) => fields[i].selection.extend(field.selection.iter().cloned()),
+                        None => fields.push(field.clone()),
+                    }
+                }
+                Selection::FragmentSpread(ref name, ref directives) => {
+                    let fragment = match self.fragments.get(name) {
+                        Some(fragment) => fragment,
+                        None => return invalid(format!("Unknown fragment \"{}\".", name)),
+                    };
+
+                    if fragment.type_condition == type_name && try!(self.included(directives)) {
+                        try!(self.collect_into(type_name, &fragment.selection, fields, depth + 1));
+                    }
+                }
+                Selection::InlineFragment(ref condition, ref directives, ref selection) => {
+                    let applies = condition.as_ref().map_or(true, |c| c == type_name);
+                    if applies && try!(self.included(directives)) {
+                        try!(self.collect_into(type_name, selection, fields, depth + 1));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies the `@skip` and `@include` directives.
+    fn included(&self, directives: &[Directive]) -> Result<bool> {
+        for directive in directives {
+            let condition = match try!(self.argument(&directive.arguments, "if")) {
+                Json::Boolean(condition) => condition,
+                _ => return invalid(format!("Directive \"@{}\" needs a Boolean \"if\" argument.", directive.name)),
+            };
+
+            match &directive.name[..] {
+                "skip" if condition     => return Ok(false),
+                "include" if !condition => return Ok(false),
+                "skip" | "include"      => (),
+                other => return invalid(format!("Unknown directive \"@{}\".", other)),
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Resolves the selection on every object in the batch.
+    pub fn complete(&self, nodes: &Nodes, selection: &[Selection]) -> Result<Vec<Json>> {
+        let fields = try!(self.collect(nodes.type_name(), selection));
+        if nodes.len() == 0 {
+            return Ok(vec![]);
+        }
+
+        let mut objects = vec![BTreeMap::new(); nodes.len()];
+        for field in &fields {
+            let values = try!(match *nodes {
+                Nodes::Projects(ref projects) => self.resolve_projects(projects, field),
+                Nodes::Pledges(ref pledges)   => self.resolve_pledges(pledges, field),
+                Nodes::Users(ref users)       => self.resolve_users(users, field),
+                Nodes::Events(ref notices)    => self.resolve_events(notices, field),
+            });
+
+            for (object, value) in objects.iter_mut().zip(values) {
+                object.insert(field.key().to_owned(), value);
+            }
+        }
+
+        Ok(objects.into_iter().map(Json::Object).collect())
+    }
+
+    /// Resolves a field of the query or mutation type.
+    pub fn resolve_root(&self, kind: OperationKind, field: &Field) -> Result<Json> {
+        // Each mutation can change funding, so totals loaded before it are out of date.
+        if kind == OperationKind::Mutation {
+            self.totals.borrow_mut().clear();
+        }
+
+        match (kind, &field.name[..]) {
+            (OperationKind::Query, "__typename")    => Ok("Query".to_json()),
+            (OperationKind::Mutation, "__typename") => Ok("Mutation".to_json()),
+
+            (OperationKind::Query, "projects") => {
+                let offset = try!(self.int(field, "offset")).unwrap_or(0);
+                let limit = try!(self.int(field, "limit")).unwrap_or(api::DEFAULT_LIMIT as i64);
+                if offset < 0 || limit < 0 {
+                    return invalid("Offsets and limits cannot be negative.".to_owned());
+                }
+
+                let sql = format!("SELECT * FROM {} ORDER BY project_id LIMIT {} OFFSET {}",
+                                  self.client.table(table::project),
+                                  cmp::min(limit, api::MAX_LIMIT as i64), offset);
+                let projects = try!(self.query(&sql, vec![]));
+                self.list(field, Nodes::Projects(projects))
+            }
+
+            (OperationKind::Query, "project") => {
+                let name = try!(self.required_string(field, "name"));
+                let projects = try!(self.load(table::project, "name", vec![DbValue::String(name)]));
+                self.first(field, Nodes::Projects(projects))
+            }
+
+            (OperationKind::Query, "user") => {
+                let name = try!(self.required_string(field, "name"));
+                let users = try!(self.load(table::user, "name", vec![DbValue::String(name)]));
+                self.first(field, Nodes::Users(users))
+            }
+
+            (OperationKind::Mutation, "createProject") => {
+                let principal = try!(self.principal());
+                let name = try!(self.required_string(field, "name"));
+                let goal = try!(self.required_float(field, "goal"));
+                let project = try!(self.client.create_project(principal, &name, goal));
+                self.first(field, Nodes::Projects(vec![project]))
+            }
+
+            (OperationKind::Mutation, "backProject") => {
+                let principal = try!(self.principal());
+                let project = try!(self.required_string(field, "project"));
+                let card = try!(self.required_string(field, "card"));
+                let amount = try!(self.required_float(field, "amount"));
+                let pledge = try!(self.client.back_project(principal, &project, &card, amount));
+                self.first(field, Nodes::Pledges(vec![pledge]))
+            }
+
+            (OperationKind::Mutation, "cancelPledge") => {
+                let principal = try!(self.principal());
+                let project = try!(self.required_string(field, "project"));
+                let pledge = try!(self.client.cancel_pledge(principal, &project));
+                self.first(field, Nodes::Pledges(vec![pledge]))
+            }
+
+            (OperationKind::Query, _)        => unknown(field, "Query"),
+            (OperationKind::Mutation, _)     => unknown(field, "Mutation"),
+            (OperationKind::Subscription, _) => unknown(field, "Subscription"),
+        }
+    }
+
+    /// Returns the value of the project argument to a subscription field.
+    pub fn subscription_project(&self, field: &Field) -> Result<Option<String>> {
+        if field.name != "pledges" {
+            return unknown(field, "Subscription");
+        }
+        try!(self.object(field));
+        self.string(field, "project")
+    }
+
+    fn resolve_projects(&self, projects: &[Project], field: &Field) -> Result<Vec<Json>> {
+        match &field.name[..] {
+            "__typename"          => self.scalar(field, projects, |_| "Project".to_json()),
+            "id"                  => self.scalar(field, projects, |p| p.project_id.to_json()),
+            "name"                => self.scalar(field, projects, |p| p.name.to_json()),
+            "goal"                => self.scalar(field, projects, |p| p.goal.to_json()),
+            "dateCreated"         => self.scalar(field, projects, |p| p.date_created.to_string().to_json()),
+            "dateClosed"          => self.scalar(field, projects, |p| p.date_closed.map(|d| d.to_string()).to_json()),
+            "commentsBackersOnly" => self.scalar(field, projects, |p| p.comments_backers_only.to_json()),
+
+            "pledged" | "backers" => {
+                let project_ids: Vec<i32> = projects.iter().map(|p| p.project_id).collect();
+                let totals = try!(self.load_totals(&project_ids));
+                let backers = field.name == "backers";
+                self.scalar(field, projects, |p| {
+                    let (pledged, count) = totals.get(&p.project_id).cloned().unwrap_or((0f64, 0i64));
+                    if backers { count.to_json() } else { pledged.to_json() }
+                })
+            }
+
+            "creator" => {
+                try!(self.object(field));
+                let users: Vec<User> = try!(self.load(table::user, "user_id",
+                                                      ids(projects.iter().filter_map(|p| p.creator_id))));
+                let by_id = users.into_iter().map(|u| (u.user_id, u)).collect();
+                self.one(field, projects.iter().map(|p| p.creator_id).collect(), by_id, Nodes::Users)
+            }
+
+            "pledges" => {
+                try!(self.object(field));
+                let pledges = try!(self.load(table::pledge, "project_id", ids(projects.iter().map(|p| p.project_id))));
+                let keys: Vec<i32> = projects.iter().map(|p| p.project_id).collect();
+                self.many(field, group(&keys, pledges, |p: &Pledge| p.project_id), Nodes::Pledges)
+            }
+
+            _ => unknown(field, "Project"),
+        }
+    }
+
+    fn resolve_pledges(&self, pledges: &[Pledge], field: &Field) -> Result<Vec<Json>> {
+        match &field.name[..] {
+            "__typename"  => self.scalar(field, pledges, |_| "Pledge".to_json()),
+            "amount"      => self.scalar(field, pledges, |p| p.amount.to_json()),
+            "refunded"    => self.scalar(field, pledges, |p| p.refunded.to_json()),
+            "pledged"     => self.scalar(field, pledges, |p| p.net_amount().to_json()),
+            "card"        => self.scalar(field, pledges, |p| p.masked_card().to_json()),
+            "dateCreated" => self.scalar(field, pledges, |p| p.date_created.to_string().to_json()),
+
+            "user" => {
+                try!(self.object(field));
+                let users: Vec<User> = try!(self.load(table::user, "user_id", ids(pledges.iter().map(|p| p.user_id))));
+                let by_id = users.into_iter().map(|u| (u.user_id, u)).collect();
+                self.one(field, pledges.iter().map(|p| Some(p.user_id)).collect(), by_id, Nodes::Users)
+            }
+
+            "project" => {
+                try!(self.object(field));
+                let projects: Vec<Project> = try!(self.load(table::project, "project_id",
+                                                            ids(pledges.iter().map(|p| p.project_id))));
+                let by_id = projects.into_iter().map(|p| (p.project_id, p)).collect();
+                self.one(field, pledges.iter().map(|p| Some(p.project_id)).collect(), by_id, Nodes::Projects)
+            }
+
+            _ => unknown(field, "Pledge"),
+        }
+    }
+
+    fn resolve_users(&self, users: &[User], field: &Field) -> Result<Vec<Json>> {
+        match &field.name[..] {
+            "__typename"  => self.scalar(field, users, |_| "User".to_json()),
+            "id"          => self.scalar(field, users, |u| u.user_id.to_json()),
+            "name"        => self.scalar(field, users, |u| u.name.to_json()),
+            "role"        => self.scalar(field, users, |u| u.role().as_str().to_uppercase().to_json()),
+            "dateCreated" => self.scalar(field, users, |u| u.date_created.to_string().to_json()),
+
+            "pledges" => {
+                try!(self.object(field));
+                let pledges = try!(self.load(table::pledge, "user_id", ids(users.iter().map(|u| u.user_id))));
+                let keys: Vec<i32> = users.iter().map(|u| u.user_id).collect();
+                self.many(field, group(&keys, pledges, |p: &Pledge| p.user_id), Nodes::Pledges)
+            }
+
+            "projects" => {
+                try!(self.object(field));
+                let projects = try!(self.load(table::project, "creator_id", ids(users.iter().map(|u| u.user_id))));
+                let keys: Vec<i32> = users.iter().map(|u| u.user_id).collect();
+                self.many(field, group(&keys, projects, |p: &Project| p.creator_id.unwrap_or(0)), Nodes::Projects)
+            }
+
+            _ => unknown(field, "User"),
+        }
+    }
+
+    fn resolve_events(&self, notices: &[Notice], field: &Field) -> Result<Vec<Json>> {
+        match &field.name[..] {
+            "__typename" => self.scalar(field, notices, |_| "PledgeEvent".to_json()),
+            "amount"     => self.scalar(field, notices, |n| n.funding_change().abs().to_json()),
+            "kind" => self.scalar(field, notices, |n| {
+                let kind = match *n {
+                    Notice::PledgeCreated { .. }   => "CREATED",
+                    Notice::PledgeRefunded { .. }  => "REFUNDED",
+                    Notice::PledgeCancelled { .. } => "CANCELLED",
+                    _ => "UPDATED",
+                };
+                kind.to_json()
+            }),
+
+            "project" => {
+                try!(self.object(field));
+                let projects: Vec<Project> = try!(self.load(table::project, "name",
+                                                            names(notices.iter().map(|n| n.project()))));
+                let by_name = projects.into_iter().map(|p| (p.name.clone(), p)).collect();
+                self.one(field, notices.iter().map(|n| Some(n.project().to_owned())).collect(), by_name,
+                         Nodes::Projects)
+            }
+
+            "user" => {
+                try!(self.object(field));
+                let users: Vec<User> = try!(self.load(table::user, "name", names(notices.iter().filter_map(|n| n.user()))));
+                let by_name = users.into_iter().map(|u| (u.name.clone(), u)).collect();
+                self.one(field, notices.iter().map(|n| n.user().map(|u| u.to_owned())).collect(), by_name,
+                         Nodes::Users)
+            }
+
+            _ => unknown(field, "PledgeEvent"),
+        }
+    }
+
+    /// Resolves a scalar field on each item.
+    fn scalar<T, F>(&self, field: &Field, items: &[T], f: F) -> Result<Vec<Json>> where F: Fn(&T) -> Json {
+        if !field.selection.is_empty() {
+            return invalid(format!("Field \"{}\" is a scalar and cannot have a selection.", field.name));
+        }
+        Ok(items.iter().map(f).collect())
+    }
+
+    /// Checks that an object field selects some of its subfields.
+    fn object(&self, field: &Field) -> Result<()> {
+        if field.selection.is_empty() {
+            invalid(format!("Field \"{}\" must have a selection of subfields.", field.name))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Resolves a field that refers to at most one object per item. The keys identify each
+    /// item's object among those loaded, and the objects are completed as a single batch.
+    fn one<K, T>(&self, field: &Field, keys: Vec<Option<K>>, loaded: HashMap<K, T>,
+                 wrap: fn(Vec<T>) -> Nodes) -> Result<Vec<Json>> where K: Hash + Eq, T: Clone {
+        let mut items = vec![];
+        let index: Vec<Option<usize>> = keys.iter().map(|key| {
+            key.as_ref().and_then(|k| loaded.get(k)).map(|item| {
+                items.push(item.clone());
+                items.len() - 1
+            })
+        }).collect();
+
+        let completed = try!(self.complete(&wrap(items), &field.selection));
+        Ok(index.into_iter().map(|i| i.map_or(Json::Null, |i| completed[i].clone())).collect())
+    }
+
+    /// Resolves a field that refers to a list of objects per item. The groups are flattened
+    /// so that the objects are completed as a single batch, then split back into lists.
+    fn many<T>(&self, field: &Field, groups: Vec<Vec<T>>, wrap: fn(Vec<T>) -> Nodes) -> Result<Vec<Json>> {
+        let sizes: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+        let items = groups.into_iter().flat_map(|g| g.into_iter()).collect();
+
+        let mut completed = try!(self.complete(&wrap(items), &field.selection)).into_iter();
+        Ok(sizes.into_iter().map(|n| Json::Array(completed.by_ref().take(n).collect())).collect())
+    }
+
+    /// Resolves a field that returns a list of objects.
+    fn list(&self, field: &Field, nodes: Nodes) -> Result<Json> {
+        try!(self.object(field));
+        Ok(Json::Array(try!(self.complete(&nodes, &field.selection))))
+    }
+
+    /// Resolves a field that returns the first of the objects, or null if there are none.
+    fn first(&self, field: &Field, nodes: Nodes) -> Result<Json> {
+        try!(self.object(field));
+        Ok(try!(self.complete(&nodes, &field.selection)).into_iter().next().unwrap_or(Json::Null))
+    }
+
+    /// Returns the principal the operation acts on behalf of.
+    fn principal(&self) -> Result<&'a Principal> {
+        self.principal.ok_or(From::from(validate::Error::NotAuthenticated))
+    }
+
+    /// Runs a query, counting it against the operation.
+    fn execute(&self, sql: &str, params: Vec<DbValue>) -> Result<Vec<Dao>> {
+        self.queries.set(self.queries.get() + 1);
+        Ok(try!(self.client.db().execute_sql_with_return(sql, &params)))
+    }
+
+    fn query<T: IsDao>(&self, sql: &str, params: Vec<DbValue>) -> Result<Vec<T>> {
+        let daos = try!(self.execute(sql, params));
+        Ok(daos.iter().map(|dao| T::from_dao(dao)).collect())
+    }
+
+    /// Loads every row of the table whose column matches one of the keys, with one query.
+    fn load<T: IsDao>(&self, table: &str, column: &str, keys: Vec<DbValue>) -> Result<Vec<T>> {
+        if keys.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let sql = format!("SELECT * FROM {} WHERE {} IN ({}) ORDER BY date_created",
+                          self.client.table(table), column, placeholders(keys.len()));
+        self.query(&sql, keys)
+    }
+
+    /// Loads the total pledged to each of the projects, and how many backers they have.
+    /// Totals are kept for the rest of the operation, so `pledged` and `backers` share one query.
+    fn load_totals(&self, project_ids: &[i32]) -> Result<HashMap<i32, (f64, i64)>> {
+        let missing = ids(project_ids.iter().cloned().filter(|id| !self.totals.borrow().contains_key(id)));
+        if !missing.is_empty() {
+            let sql = format!("SELECT project_id, COALESCE(SUM(amount - refunded), 0) AS pledged, \
+                               COUNT(*) AS backers FROM {} WHERE project_id IN ({}) GROUP BY project_id",
+                              self.client.table(table::pledge), placeholders(missing.len()));
+
+            let daos = try!(self.execute(&sql, missing));
+            let mut totals = self.totals.borrow_mut();
+            for id in project_ids {
+                totals.entry(*id).or_insert((0f64, 0i64));
+            }
+            for dao in &daos {
+                let project_id: i32 = dao.get("project_id");
+                let pledged: f64 = dao.get("pledged");
+                let backers: i64 = dao.get("backers");
+                totals.insert(project_id, (pledged, backers));
+            }
+        }
+
+        Ok(self.totals.borrow().clone())
+    }
+
+    fn argument(&self, arguments: &[(String, Value)], name: &str) -> Result<Json> {
+        match arguments.iter().find(|&&(ref n, _)| n == name) {
+            Some(&(_, ref value)) => to_json(value, &self.variables),
+            None => Ok(Json::Null),
+        }
+    }
+
+    fn string(&self, field: &Field, name: &str) -> Result<Option<String>> {
+        match try!(self.argument(&field.arguments, name)) {
+            Json::String(s) => Ok(Some(s)),
+            Json::Null => Ok(None),
+            other => invalid(format!("Argument \"{}\" of \"{}\" must be a String, not {}.", name, field.name, other)),
+        }
+    }
+
+    fn int(&self, field: &Field, name: &str) -> Result<Option<i64>> {
+        match try!(self.argument(&field.arguments, name)) {
+            Json::I64(i) => Ok(Some(i)),
+            Json::U64(u) => Ok(Some(u as i64)),
+            Json::Null => Ok(None),
+            other => invalid(format!("Argument \"{}\" of \"{}\" must be an Int, not {}.", name, field.name, other)),
+        }
+    }
+
+    fn float(&self, field: &Field, name: &str) -> Result<Option<f64>> {
+        match try!(self.argument(&field.arguments, name)) {
+            Json::F64(f) => Ok(Some(f)),
+            Json::I64(i) => Ok(Some(i as f64)),
+            Json::U64(u) => Ok(Some(u as f64)),
+            Json::Null => Ok(None),
+            other => invalid(format!("Argument \"{}\" of \"{}\" must be a Float, not {}.", name, field.name, other)),
+        }
+    }
+
+    fn required_string(&self, field: &Field, name: &str) -> Result<String> {
+        match try!(self.string(field, name)) {
+            Some(value) => Ok(value),
+            None => missing(field, name),
+        }
+    }
+
+    fn required_float(&self, field: &Field, name: &str) -> Result<f64> {
+        match try!(self.float(field, name)) {
+            Some(value) => Ok(value),
+            None => missing(field, name),
+        }
+    }
+}
+
+/// Converts an input value to JSON, substituting variables.
+fn to_json(value: &Value, variables: &BTreeMap<String, Json>) -> Result<Json> {
+    let json = match *value {
+        Value::Variable(ref name) => match variables.get(name) {
+            Some(value) => value.clone(),
+            None => return invalid(format!("Variable \"${}\" is not defined.", name)),
+        },
+        Value::Int(i) => Json::I64(i),
+        Value::Float(f) => Json::F64(f),
+        Value::String(ref s) | Value::Enum(ref s) => Json::String(s.clone()),
+        Value::Boolean(b) => Json::Boolean(b),
+        Value::Null => Json::Null,
+        Value::List(ref list) => {
+            let mut items = vec![];
+            for item in list {
+                items.push(try!(to_json(item, variables)));
+            }
+            Json::Array(items)
+        }
+        Value::Object(ref fields) => {
+            let mut map = BTreeMap::new();
+            for &(ref key, ref value) in fields {
+                map.insert(key.clone(), try!(to_json(value, variables)));
+            }
+            Json::Object(map)
+        }
+    };
+
+    Ok(json)
+}
+
+/// Splits the items into groups, one for each key, in the order of the keys.
+fn group<T, F>(keys: &[i32], items: Vec<T>, key: F) -> Vec<Vec<T>> where T: Clone, F: Fn(&T) -> i32 {
+    let mut groups: HashMap<i32, Vec<T>> = HashMap::new();
+    for item in items {
+        groups.entry(key(&item)).or_insert(vec![]).push(item);
+    }
+
+    keys.iter().map(|k| groups.get(k).cloned().unwrap_or(vec![])).collect()
+}
+
+/// Returns the distinct ids as query parameters.
+fn ids<I: Iterator<Item = i32>>(ids: I) -> Vec<DbValue> {
+    let mut ids: Vec<i32> = ids.collect();
+    ids.sort();
+    ids.dedup();
+    ids.into_iter().map(DbValue::I32).collect()
+}
+
+/// Returns the distinct names as query parameters.
+fn names<'b, I: Iterator<Item = &'b str>>(names: I) -> Vec<DbValue> {
+    let mut names: Vec<&str> = names.collect();
+    names.sort();
+    names.dedup();
+    names.into_iter().map(|name| DbValue::String(name.to_owned())).collect()
+}
+
+/// Returns the parameter placeholders `$1, $2, ...`.
+fn placeholders(n: usize) -> String {
+    (1..n + 1).map(|i| format!("${}", i)).collect::<Vec<String>>().join(", ")
+}
+
+fn invalid<T>(message: String) -> Result<T> {
+    Err(From::from(validate::Error::InvalidQuery(message)))
+}
+
+fn unknown<T>(field: &Field, type_name: &str) -> Result<T> {
+    invalid(format!("Cannot query field \"{}\" on type \"{}\".", field.name, type_name))
+}
+
+fn missing<T>(field: &Field, name: &str) -> Result<T> {
+    invalid(format!("Field \"{}\" needs the \"{}\" argument.", field.name, name))
+}
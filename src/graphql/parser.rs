@@ -0,0 +1,519 @@
+//! Parses GraphQL documents into operations and fragments.
+//!
+//! Every part of the executable grammar is supported except for type system definitions,
+//! which only the schema itself needs.
+use validate::{Error, Result};
+
+use std::collections::HashMap;
+
+/// A parsed GraphQL document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Document {
+    pub operations: Vec<Operation>,
+    pub fragments: HashMap<String, Fragment>,
+}
+
+/// The kind of operation being requested.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OperationKind {
+    Query,
+    Mutation,
+    Subscription,
+}
+
+/// A query, mutation or subscription.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Operation {
+    pub kind: OperationKind,
+    pub name: Option<String>,
+    pub variables: Vec<VariableDefinition>,
+    pub selection: Vec<Selection>,
+}
+
+/// A variable declared by an operation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariableDefinition {
+    pub name: String,
+    /// The declared type, such as `[String!]!`.
+    pub type_name: String,
+    pub default: Option<Value>,
+}
+
+/// A named set of fields that can be spread into selections.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fragment {
+    pub type_condition: String,
+    pub selection: Vec<Selection>,
+}
+
+/// An entry in a selection set.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Selection {
+    Field(Field),
+    FragmentSpread(String, Vec<Directive>),
+    InlineFragment(Option<String>, Vec<Directive>, Vec<Selection>),
+}
+
+/// A field to resolve, with its arguments and sub-selection.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    pub alias: Option<String>,
+    pub name: String,
+    pub arguments: Vec<(String, Value)>,
+    pub directives: Vec<Directive>,
+    pub selection: Vec<Selection>,
+}
+
+/// A directive such as `@include(if: $flag)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Directive {
+    pub name: String,
+    pub arguments: Vec<(String, Value)>,
+}
+
+/// An input value written in a document.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Variable(String),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+    Null,
+    Enum(String),
+    List(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Field {
+    /// Returns the key the field's value is returned under.
+    pub fn key(&self) -> &str {
+        self.alias.as_ref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Punct(char),
+    Spread,
+    Name(String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+/// Parses a GraphQL document.
+pub fn parse(source: &str) -> Result<Document> {
+    let tokens = try!(tokenize(source));
+    let mut parser = Parser { tokens: tokens, pos: 0 };
+
+    let mut document = Document { operations: vec![], fragments: HashMap::new() };
+    while !parser.done() {
+        if parser.peek_name("fragment") {
+            let (name, fragment) = try!(parser.fragment());
+            if document.fragments.insert(name.clone(), fragment).is_some() {
+                return Err(invalid(format!("There can be only one fragment named \"{}\".", name)));
+            }
+        } else {
+            document.operations.push(try!(parser.operation()));
+        }
+    }
+
+    if document.operations.is_empty() {
+        return Err(invalid("The document has no operations.".to_owned()));
+    }
+
+    Ok(document)
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidQuery(message)
+}
+
+/// Splits the source into tokens, skipping whitespace, commas and comments.
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() || c == ',' || c == '\u{feff}' {
+            i += 1;
+        } else if c == '#' {
+            while i < chars.len() && chars[i] != '\n' && chars[i] != '\r' {
+                i += 1;
+            }
+        } else if "!$()=:@[]{}|".contains(c) {
+            tokens.push(Token::Punct(c));
+            i += 1;
+        } else if c == '.' {
+            if chars[i..].starts_with(&['.', '.', '.']) {
+                tokens.push(Token::Spread);
+                i += 3;
+            } else {
+                return Err(invalid("Unexpected \".\"; did you mean \"...\"?".to_owned()));
+            }
+        } else if c == '_' || c.is_ascii_alphabetic() {
+            let start = i;
+            while i < chars.len() && (chars[i] == '_' || chars[i].is_ascii_alphanumeric()) {
+                i += 1;
+            }
+            tokens.push(Token::Name(chars[start..i].iter().cloned().collect()));
+        } else if c == '-' || c.is_ascii_digit() {
+            let start = i;
+            let mut float = false;
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || ".eE+-".contains(chars[i])) {
+                float = float || ".eE".contains(chars[i]);
+                i += 1;
+            }
+
+            let text: String = chars[start..i].iter().cloned().collect();
+            let token = if float {
+                text.parse().map(Token::Float).ok()
+            } else {
+                text.parse().map(Token::Int).ok()
+            };
+            tokens.push(try!(token.ok_or(invalid(format!("{} is not a number.", text)))));
+        } else if c == '"' {
+            let (s, end) = try!(string(&chars, i));
+            tokens.push(Token::Str(s));
+            i = end;
+        } else {
+            return Err(invalid(format!("Unexpected character \"{}\".", c)));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Reads a string literal starting at the opening quote. Returns the string and the index after it.
+fn string(chars: &[char], start: usize) -> Result<(String, usize)> {
+    // Block strings keep their contents as written.
+    if chars[start..].starts_with(&['"', '"', '"']) {
+        let mut i = start + 3;
+        let mut s = String::new();
+        while i < chars.len() {
+            if chars[i..].starts_with(&['"', '"', '"']) {
+                return Ok((s.trim().to_owned(), i + 3));
+            }
+            s.push(chars[i]);
+            i += 1;
+        }
+        return Err(invalid("Unterminated block string.".to_owned()));
+    }
+
+    let mut i = start + 1;
+    let mut s = String::new();
+    while i < chars.len() {
+        match chars[i] {
+            '"' => return Ok((s, i + 1)),
+            '\n' | '\r' => break,
+            '\\' if i + 1 < chars.len() => {
+                i += 1;
+                match chars[i] {
+                    'n' => s.push('\n'),
+                    't' => s.push('\t'),
+                    'r' => s.push('\r'),
+                    'b' => s.push('\u{8}'),
+                    'f' => s.push('\u{c}'),
+                    'u' if i + 4 < chars.len() => {
+                        let hex: String = chars[i + 1..i + 5].iter().cloned().collect();
+                        let ch = u32::from_str_radix(&hex, 16).ok().and_then(::std::char::from_u32);
+                        s.push(try!(ch.ok_or(invalid(format!("\\u{} is not a character.", hex)))));
+                        i += 4;
+                    }
+                    c => s.push(c),
+                }
+            }
+            c => s.push(c),
+        }
+        i += 1;
+    }
+
+    Err(invalid("Unterminated string.".to_owned()))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+
+    fn done(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_punct(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Punct(c))
+    }
+
+    fn peek_name(&self, name: &str) -> bool {
+        match self.peek() {
+            Some(&Token::Name(ref n)) => n == name,
+            _ => false,
+        }
+    }
+
+    fn unexpected(&self) -> Error {
+        match self.peek() {
+            Some(token) => invalid(format!("Unexpected {}.", describe(token))),
+            None => invalid("Unexpected end of document.".to_owned()),
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.peek_punct(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            match self.peek() {
+                Some(token) => Err(invalid(format!("Expected \"{}\", found {}.", c, describe(token)))),
+                None => Err(invalid(format!("Expected \"{}\", found the end of the document.", c))),
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let name = match self.peek() {
+            Some(&Token::Name(ref name)) => name.clone(),
+            _ => return Err(self.unexpected()),
+        };
+        self.pos += 1;
+        Ok(name)
+    }
+
+    fn operation(&mut self) -> Result<Operation> {
+        // A bare selection set is shorthand for an anonymous query.
+        if self.peek_punct('{') {
+            return Ok(Operation {
+                kind: OperationKind::Query,
+                name: None,
+                variables: vec![],
+                selection: try!(self.selection_set()),
+            });
+        }
+
+        let kind = match &try!(self.name())[..] {
+            "query" => OperationKind::Query,
+            "mutation" => OperationKind::Mutation,
+            "subscription" => OperationKind::Subscription,
+            other => return Err(invalid(format!("Unknown operation \"{}\".", other))),
+        };
+
+        let name = if let Some(&Token::Name(_)) = self.peek() {
+            Some(try!(self.name()))
+        } else {
+            None
+        };
+
+        let mut variables = vec![];
+        if self.peek_punct('(') {
+            self.pos += 1;
+            while !self.peek_punct(')') {
+                try!(self.expect('$'));
+                let name = try!(self.name());
+                try!(self.expect(':'));
+                let type_name = try!(self.type_name());
+                let default = if self.peek_punct('=') {
+                    self.pos += 1;
+                    Some(try!(self.value(true)))
+                } else {
+                    None
+                };
+                variables.push(VariableDefinition { name: name, type_name: type_name, default: default });
+            }
+            try!(self.expect(')'));
+        }
+
+        let _ = try!(self.directives(false));
+        Ok(Operation { kind: kind, name: name, variables: variables, selection: try!(self.selection_set()) })
+    }
+
+    fn fragment(&mut self) -> Result<(String, Fragment)> {
+        let _ = try!(self.name());
+        let name = try!(self.name());
+        if name == "on" {
+            return Err(invalid("Fragments cannot be named \"on\".".to_owned()));
+        }
+
+        if !self.peek_name("on") {
+            return Err(self.unexpected());
+        }
+        self.pos += 1;
+
+        let type_condition = try!(self.name());
+        let _ = try!(self.directives(false));
+        let selection = try!(self.selection_set());
+        Ok((name, Fragment { type_condition: type_condition, selection: selection }))
+    }
+
+    fn type_name(&mut self) -> Result<String> {
+        let mut type_name = if self.peek_punct('[') {
+            self.pos += 1;
+            let inner = try!(self.type_name());
+            try!(self.expect(']'));
+            format!("[{}]", inner)
+        } else {
+            try!(self.name())
+        };
+
+        if self.peek_punct('!') {
+            self.pos += 1;
+            type_name.push('!');
+        }
+
+        Ok(type_name)
+    }
+
+    fn selection_set(&mut self) -> Result<Vec<Selection>> {
+        try!(self.expect('{'));
+        let mut selection = vec![];
+        while !self.peek_punct('}') {
+            if self.done() {
+                return Err(self.unexpected());
+            }
+            selection.push(try!(self.selection()));
+        }
+        try!(self.expect('}'));
+
+        if selection.is_empty() {
+            Err(invalid("Selection sets cannot be empty.".to_owned()))
+        } else {
+            Ok(selection)
+        }
+    }
+
+    fn selection(&mut self) -> Result<Selection> {
+        if self.peek() == Some(&Token::Spread) {
+            self.pos += 1;
+
+            if self.peek_name("on") {
+                self.pos += 1;
+                let type_condition = try!(self.name());
+                let directives = try!(self.directives(false));
+                let selection = try!(self.selection_set());
+                return Ok(Selection::InlineFragment(Some(type_condition), directives, selection));
+            }
+
+            if let Some(&Token::Name(_)) = self.peek() {
+                let name = try!(self.name());
+                let directives = try!(self.directives(false));
+                return Ok(Selection::FragmentSpread(name, directives));
+            }
+
+            let directives = try!(self.directives(false));
+            let selection = try!(self.selection_set());
+            return Ok(Selection::InlineFragment(None, directives, selection));
+        }
+
+        let mut name = try!(self.name());
+        let mut alias = None;
+        if self.peek_punct(':') {
+            self.pos += 1;
+            alias = Some(name);
+            name = try!(self.name());
+        }
+
+        let arguments = try!(self.arguments(false));
+        let directives = try!(self.directives(false));
+        let selection = if self.peek_punct('{') { try!(self.selection_set()) } else { vec![] };
+
+        Ok(Selection::Field(Field {
+            alias: alias,
+            name: name,
+            arguments: arguments,
+            directives: directives,
+            selection: selection,
+        }))
+    }
+
+    fn arguments(&mut self, constant: bool) -> Result<Vec<(String, Value)>> {
+        let mut arguments = vec![];
+        if self.peek_punct('(') {
+            self.pos += 1;
+            while !self.peek_punct(')') {
+                let name = try!(self.name());
+                try!(self.expect(':'));
+                arguments.push((name, try!(self.value(constant))));
+            }
+            try!(self.expect(')'));
+        }
+        Ok(arguments)
+    }
+
+    fn directives(&mut self, constant: bool) -> Result<Vec<Directive>> {
+        let mut directives = vec![];
+        while self.peek_punct('@') {
+            self.pos += 1;
+            let name = try!(self.name());
+            let arguments = try!(self.arguments(constant));
+            directives.push(Directive { name: name, arguments: arguments });
+        }
+        Ok(directives)
+    }
+
+    /// Parses a value. Constant values, such as variable defaults, cannot contain variables.
+    fn value(&mut self, constant: bool) -> Result<Value> {
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return Err(self.unexpected()),
+        };
+
+        let value = match token {
+            Token::Punct('$') if !constant => {
+                self.pos += 1;
+                return Ok(Value::Variable(try!(self.name())));
+            }
+            Token::Punct('[') => {
+                self.pos += 1;
+                let mut list = vec![];
+                while !self.peek_punct(']') {
+                    if self.done() {
+                        return Err(self.unexpected());
+                    }
+                    list.push(try!(self.value(constant)));
+                }
+                Value::List(list)
+            }
+            Token::Punct('{') => {
+                self.pos += 1;
+                let mut fields = vec![];
+                while !self.peek_punct('}') {
+                    let name = try!(self.name());
+                    try!(self.expect(':'));
+                    fields.push((name, try!(self.value(constant))));
+                }
+                Value::Object(fields)
+            }
+            Token::Int(i) => Value::Int(i),
+            Token::Float(f) => Value::Float(f),
+            Token::Str(s) => Value::String(s),
+            Token::Name(ref name) if name == "true" => Value::Boolean(true),
+            Token::Name(ref name) if name == "false" => Value::Boolean(false),
+            Token::Name(ref name) if name == "null" => Value::Null,
+            Token::Name(name) => Value::Enum(name),
+            _ => return Err(self.unexpected()),
+        };
+
+        self.pos += 1;
+        Ok(value)
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match *token {
+        Token::Punct(c) => format!("\"{}\"", c),
+        Token::Spread => "\"...\"".to_owned(),
+        Token::Name(ref name) => format!("\"{}\"", name),
+        Token::Int(i) => i.to_string(),
+        Token::Float(f) => f.to_string(),
+        Token::Str(ref s) => format!("{:?}", s),
+    }
+}
@@ -0,0 +1,435 @@
+//! Module for importing projects and pledges in bulk from CSV files, for `ksr import`.
+//!
+//! Every row is checked before anything is written, against the same rules as `ksr project`
+//! and `ksr back` and against what is already in the database. Rejected rows are reported with
+//! their line and reason, and can be written to a CSV file of their own to be fixed and imported
+//! again. Accepted rows are loaded with PostgreSQL's COPY into a temporary table on a dedicated
+//! connection, then moved into the schema in a single transaction, along with the audit entries
+//! and domain events that the commands they stand in for would record. Imports migrate existing
+//! campaigns, so they don't queue receipts, new backer mail or webhook deliveries.
+use {validate, Client, Result};
+use db::table;
+use output;
+
+use postgres::{Connection, SslMode, Transaction};
+
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+/// What a CSV file holds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    /// One project per row, with columns `name`, `goal` and optionally `creator`.
+    Projects,
+    /// One pledge per row, with columns `user`, `project`, `card` and `amount`.
+    Pledges,
+}
+
+impl Kind {
+    /// Returns the fields read from each row, and whether each must have a column.
+    pub fn fields(&self) -> &'static [(&'static str, bool)] {
+        match *self {
+            Kind::Projects => &[("name", true), ("goal", true), ("creator", false)],
+            Kind::Pledges  => &[("user", true), ("project", true), ("card", true), ("amount", true)],
+        }
+    }
+
+    /// Returns the plural name of the rows, as used on the command line.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Kind::Projects => "projects",
+            Kind::Pledges  => "pledges",
+        }
+    }
+}
+
+/// Names the CSV column that holds each field, where it differs from the field's name.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mapping {
+    columns: HashMap<String, String>,
+}
+
+impl Mapping {
+
+    /// Parses mappings written as `field=column` pairs separated by commas, such as `goal=Target,name=Title`.
+    pub fn parse(kind: Kind, s: &str) -> validate::Result<Mapping> {
+        let mut columns = HashMap::new();
+        for pair in s.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let mut parts = pair.splitn(2, '=');
+            let field = parts.next().unwrap().trim();
+            let column = match parts.next() {
+                Some(column) if !column.trim().is_empty() => column.trim(),
+                _ => return Err(validate::Error::InvalidMapping(pair.to_owned())),
+            };
+
+            if !kind.fields().iter().any(|&(f, _)| f == field) {
+                return Err(validate::Error::InvalidMapping(pair.to_owned()));
+            }
+            columns.insert(field.to_owned(), column.to_owned());
+        }
+        Ok(Mapping { columns: columns })
+    }
+
+    /// Returns the name of the column that holds a field.
+    pub fn column<'a>(&'a self, field: &'a str) -> &'a str {
+        self.columns.get(field).map_or(field, |column| &column[..])
+    }
+}
+
+/// A row that can't be imported.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rejected {
+    /// The line the row starts on, counting the header as line 1.
+    pub line: usize,
+    /// The row as it was read.
+    pub record: Vec<String>,
+    /// Why the row was rejected.
+    pub error: String,
+}
+
+/// A CSV file whose rows have been checked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Import {
+    pub kind: Kind,
+    /// The columns named in the file's header.
+    pub header: Vec<String>,
+    /// The line of each accepted row, and its fields in the order `Kind::fields` lists them.
+    pub accepted: Vec<(usize, Vec<String>)>,
+    pub rejected: Vec<Rejected>,
+}
+
+/// What the database already holds that rows could collide with.
+#[derive(Default)]
+struct Existing {
+    /// Projects by name, and whether each has closed.
+    projects: HashMap<String, bool>,
+    /// Each project's backers, and the cards used to back it.
+    backers: HashSet<(String, String)>,
+    cards: HashSet<(String, String)>,
+}
+
+/// Reads the records of a CSV file, along with the line each starts on. Fields may be quoted,
+/// with `""` standing for a quote, to hold commas and line breaks. Blank lines are skipped.
+pub fn parse_csv(text: &str) -> validate::Result<Vec<(usize, Vec<String>)>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            '\n' if quoted => {
+                line += 1;
+                field.push(c);
+            }
+            ',' if !quoted => record.push(mem::replace(&mut field, String::new())),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => (),
+            '\n' => {
+                record.push(mem::replace(&mut field, String::new()));
+                records.push((start, mem::replace(&mut record, vec![])));
+                line += 1;
+                start = line;
+            }
+            c => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(validate::Error::UnclosedQuote);
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push((start, record));
+    }
+
+    Ok(records.into_iter().filter(|&(_, ref record)| record.len() > 1 || !record[0].trim().is_empty()).collect())
+}
+
+/// Checks every row of a CSV file, without writing anything.
+/// Fails if the file can't be read or lacks a required column; problems with rows are rejections.
+pub fn check(client: &Client, kind: Kind, text: &str, mapping: &Mapping) -> Result<Import> {
+    let mut records = try!(parse_csv(text)).into_iter();
+    let header = records.next().map_or(vec![], |(_, header)| header);
+
+    let mut indexes = vec![];
+    for &(field, required) in kind.fields() {
+        let column = mapping.column(field);
+        match header.iter().position(|h| h.trim().to_lowercase() == column.to_lowercase()) {
+            Some(i) => indexes.push(Some(i)),
+            None if required => return Err(From::from(validate::Error::MissingColumn(column.to_owned()))),
+            None => indexes.push(None),
+        }
+    }
+
+    let existing = try!(Existing::load(client, kind));
+    let mut seen = HashMap::new();
+    let mut import = Import {
+        kind: kind,
+        header: header,
+        accepted: vec![],
+        rejected: vec![],
+    };
+
+    for (line, record) in records {
+        let checked = if record.len() != import.header.len() {
+            Err(format!("Expected {} columns, but found {}.", import.header.len(), record.len()))
+        } else {
+            let fields: Vec<String> = indexes.iter()
+                .map(|i| i.map_or(String::new(), |i| record[i].trim().to_owned()))
+                .collect();
+            check_row(kind, fields, line, &existing, &mut seen)
+        };
+
+        match checked {
+            Ok(fields) => import.accepted.push((line, fields)),
+            Err(error) => import.rejected.push(Rejected {
+                line: line,
+                record: record,
+                error: error,
+            }),
+        }
+    }
+
+    Ok(import)
+}
+
+/// Validates a row's fields, and returns them normalized for loading.
+/// `seen` holds the line of each name, backer and card claimed by earlier rows.
+fn check_row(kind: Kind, mut fields: Vec<String>, line: usize, existing: &Existing,
+             seen: &mut HashMap<Vec<String>, usize>) -> ::std::result::Result<Vec<String>, String> {
+
+    let mut claims = vec![];
+    match kind {
+        Kind::Projects => {
+            try!(name(&fields[0]));
+            fields[1] = try!(amount(&fields[1]));
+            if !fields[2].is_empty() {
+                try!(name(&fields[2]));
+            }
+
+            if existing.projects.contains_key(&fields[0]) {
+                return Err(format!("Project '{}' already exists!", fields[0]));
+            }
+            claims.push((vec!["project".to_owned(), fields[0].clone()],
+                         format!("Project '{}'", fields[0])));
+        }
+        Kind::Pledges => {
+            let (user, project, card) = (fields[0].clone(), fields[1].clone(), fields[2].clone());
+            try!(name(&user));
+            try!(name(&project));
+            try!(validate::length(&card, 1, 19).map_err(|e| e.to_string()));
+            try!(validate::luhn10(&card).map_err(|e| e.to_string()));
+            fields[3] = try!(amount(&fields[3]));
+
+            match existing.projects.get(&project) {
+                None => return Err(validate::Error::ProjectDoesNotExist.to_string()),
+                Some(&true) => return Err(validate::Error::ProjectClosed.to_string()),
+                Some(&false) => (),
+            }
+            if existing.backers.contains(&(project.clone(), user.clone())) {
+                return Err(format!("User '{}' has already backed project '{}'.", user, project));
+            }
+            if existing.cards.contains(&(project.clone(), card.clone())) {
+                return Err(format!("Credit card '{}' has already been used to back project '{}'.", card, project));
+            }
+
+            claims.push((vec!["backer".to_owned(), project.clone(), user.clone()],
+                         format!("{}'s pledge to '{}'", user, project)));
+            claims.push((vec!["card".to_owned(), project.clone(), card.clone()],
+                         format!("Credit card '{}' for '{}'", card, project)));
+        }
+    }
+
+    for &(ref key, ref what) in &claims {
+        if let Some(earlier) = seen.get(key) {
+            return Err(format!("{} is already on line {}.", what, earlier));
+        }
+    }
+    for (key, _) in claims {
+        seen.insert(key, line);
+    }
+
+    Ok(fields)
+}
+
+/// Validates a project or user name.
+fn name(s: &str) -> ::std::result::Result<(), String> {
+    try!(validate::length(s, 4, 20).map_err(|e| e.to_string()));
+    validate::alphanumeric(s).map_err(|e| e.to_string())
+}
+
+/// Validates an amount of money, and returns it rounded to cents.
+fn amount(s: &str) -> ::std::result::Result<String, String> {
+    let amount = try!(s.parse::<f64>().map_err(|_| validate::Error::NotNumeric(s.to_owned()).to_string()));
+    let amount = try!(validate::currency(amount).map_err(|e| e.to_string()));
+    Ok(format!("{:.2}", amount))
+}
+
+impl Existing {
+
+    /// Reads the projects, and for pledges each project's backers and cards.
+    fn load(client: &Client, kind: Kind) -> Result<Existing> {
+        let mut existing = Existing::default();
+
+        let sql = format!("SELECT name, date_closed IS NOT NULL AS closed FROM {}", client.table(table::project));
+        for dao in try!(client.db().execute_sql_with_return(&sql, &vec![])) {
+            existing.projects.insert(dao.get("name"), dao.get("closed"));
+        }
+
+        if kind == Kind::Pledges {
+            let sql = format!("SELECT pr.name AS project, us.name AS backer, pl.card FROM {} \
+                               INNER JOIN {} ON pr.project_id = pl.project_id \
+                               INNER JOIN {} ON us.user_id = pl.user_id",
+                              client.table_abbr(table::pledge), client.table_abbr(table::project),
+                              client.table_abbr(table::user));
+
+            for dao in try!(client.db().execute_sql_with_return(&sql, &vec![])) {
+                let project: String = dao.get("project");
+                existing.backers.insert((project.clone(), dao.get("backer")));
+                existing.cards.insert((project, dao.get("card")));
+            }
+        }
+
+        Ok(existing)
+    }
+}
+
+/// Loads the accepted rows in a single transaction, creating any users they name.
+/// Returns the number of rows imported. Rejected rows are left out.
+pub fn load(client: &Client, import: &Import) -> Result<usize> {
+    if import.accepted.is_empty() {
+        return Ok(0);
+    }
+
+    let conn = try!(Connection::connect(&client.uri[..], &SslMode::None));
+    let trans = try!(conn.transaction());
+    let count = try!(load_in(client, &trans, import));
+    try!(trans.commit());
+    Ok(count)
+}
+
+/// Loads the accepted rows within a transaction on a dedicated connection, which the caller
+/// commits, so that several imports can be made at once.
+pub fn load_in(client: &Client, trans: &Transaction, import: &Import) -> Result<usize> {
+    let (columns, user_column) = match import.kind {
+        Kind::Projects => ("line integer, name text, goal double precision, creator text", "creator"),
+        Kind::Pledges  => ("line integer, backer text, project text, card text, amount double precision", "backer"),
+    };
+    try!(trans.batch_execute(&format!("DROP TABLE IF EXISTS ksr_import; \
+                                       CREATE TEMPORARY TABLE ksr_import ({}) ON COMMIT DROP", columns)));
+
+    // Empty fields are loaded as nulls.
+    let mut data = String::new();
+    for &(line, ref fields) in &import.accepted {
+        let fields: Vec<String> = fields.iter().map(|f| output::csv_quote(f)).collect();
+        data.push_str(&format!("{},{}\n", line, fields.join(",")));
+    }
+
+    {
+        let copy = try!(trans.prepare("COPY ksr_import FROM STDIN WITH (FORMAT csv)"));
+        try!(copy.copy_in(&[], &mut data.as_bytes()));
+    }
+
+    try!(create_users(client, trans, &format!("SELECT DISTINCT {0} FROM ksr_import WHERE {0} IS NOT NULL", user_column)));
+    let actor = client.actor();
+
+    let sql = match import.kind {
+        Kind::Projects => format!(
+            "WITH created AS ( \
+                 INSERT INTO {project} (name, goal, creator_id) \
+                 SELECT im.name, im.goal, us.user_id FROM ksr_import im \
+                 LEFT JOIN {user} us ON us.name = im.creator \
+                 ORDER BY im.line \
+                 RETURNING *), \
+             audited AS ( \
+                 INSERT INTO {audit} (actor, action, entity, entity_id, after_value) \
+                 SELECT $1, 'create', 'project', project_id::text, row_to_json(created)::text FROM created) \
+             INSERT INTO {event} (kind, payload) \
+             SELECT 'ProjectCreated', json_build_object('project_id', project_id, 'name', name, 'goal', goal, \
+                                                        'creator_id', creator_id)::text \
+             FROM created ORDER BY project_id",
+            project = client.table(table::project), user = client.table(table::user),
+            audit = client.table(table::audit), event = client.table(table::event)),
+
+        // Projects that closed since the file was checked are left out, which the count catches.
+        Kind::Pledges => format!(
+            "WITH created AS ( \
+                 INSERT INTO {pledge} (user_id, project_id, card, amount) \
+                 SELECT us.user_id, pr.project_id, im.card, im.amount FROM ksr_import im \
+                 INNER JOIN {user} us ON us.name = im.backer \
+                 INNER JOIN {project} pr ON pr.name = im.project AND pr.date_closed IS NULL \
+                 ORDER BY im.line \
+                 RETURNING *), \
+             audited AS ( \
+                 INSERT INTO {audit} (actor, action, entity, entity_id, after_value) \
+                 SELECT $1, 'create', 'pledge', user_id || ':' || project_id, row_to_json(created)::text \
+                 FROM created) \
+             INSERT INTO {event} (kind, payload) \
+             SELECT 'PledgeMade', json_build_object('user_id', user_id, 'project_id', project_id, \
+                                                    'card', card, 'amount', amount)::text \
+             FROM created",
+            pledge = client.table(table::pledge), user = client.table(table::user),
+            project = client.table(table::project), audit = client.table(table::audit),
+            event = client.table(table::event)),
+    };
+
+    let count = try!(trans.execute(&sql, &[&actor])) as usize;
+    if count != import.accepted.len() {
+        return Err(From::from(validate::Error::ConcurrentChange));
+    }
+
+    Ok(count)
+}
+
+/// Creates the users named by a query that don't exist yet, within a transaction. They are
+/// created and audited as `User::upsert` would create them.
+pub fn create_users(client: &Client, trans: &Transaction, names: &str) -> Result<()> {
+    let sql = format!("WITH created AS ( \
+                           INSERT INTO {user} (name) \
+                           SELECT name FROM ({names}) AS names (name) \
+                           WHERE name NOT IN (SELECT name FROM {user}) \
+                           RETURNING user_id, name, date_created, role, email), \
+                       audited AS ( \
+                           INSERT INTO {audit} (actor, action, entity, entity_id, after_value) \
+                           SELECT $1, 'create', 'user', user_id::text, \
+                                  json_build_object('user_id', user_id, 'name', name, \
+                                                    'date_created', date_created, 'role', role, \
+                                                    'email', email)::text \
+                           FROM created) \
+                       INSERT INTO {event} (kind, payload) \
+                       SELECT 'UserCreated', json_build_object('user_id', user_id, 'name', name)::text \
+                       FROM created ORDER BY user_id",
+                      user = client.table(table::user), audit = client.table(table::audit),
+                      event = client.table(table::event), names = names);
+    let actor = client.actor();
+    try!(trans.execute(&sql, &[&actor]));
+    Ok(())
+}
+
+/// Returns the rejected rows as CSV, under the file's header with an `error` column added,
+/// so that they can be fixed and imported again.
+pub fn rejects_csv(import: &Import) -> String {
+    let mut csv = String::new();
+    let mut header: Vec<String> = import.header.iter().map(|h| output::csv_quote(h)).collect();
+    header.push("error".to_owned());
+    csv.push_str(&format!("{}\n", header.join(",")));
+
+    for rejected in &import.rejected {
+        let mut fields: Vec<String> = rejected.record.iter().map(|f| output::csv_quote(f)).collect();
+        fields.push(output::csv_quote(&rejected.error));
+        csv.push_str(&format!("{}\n", fields.join(",")));
+    }
+    csv
+}
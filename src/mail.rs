@@ -0,0 +1,494 @@
+//! Module for emailing users about their pledges and projects.
+//!
+//! Messages are rendered from the templates in `data/templates` into a plain text and an
+//! HTML body, and queued in the same transaction as the change that prompted them. Queued
+//! mail is sent later through a `Transport`: SMTP for real delivery, or a maildir for
+//! local testing. Users can turn off each kind of mail, either by name or with the
+//! unsubscribe token included at the bottom of every message.
+pub use models::{Mail, MailPreference};
+
+use {validate, Client, Error, Result};
+use db::{column, table};
+use models::{Audit, Pledge, Project, User};
+
+use chrono::Local;
+use rustc_serialize::json::ToJson;
+use rustorm::dao::{IsDao, Value};
+use rustorm::query::Query;
+use toml;
+
+use std::convert::From;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Mail is abandoned after this many failed attempts to send it.
+pub const MAX_ATTEMPTS: i32 = 5;
+
+const ERR_MAIL_CONFIG: &'static str = "The [mail] configuration needs a 'from' address, and either \
+                                       an smtp 'host' or a 'maildir' path";
+
+// Default configurations.
+const DEFAULT_SMTP_PORT: u16 = 25;
+
+/// The kinds of mail that Kickstarter sends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// Sent to a backer when they back a project.
+    Receipt,
+    /// Sent to a project's creator when someone backs it.
+    NewBacker,
+    /// Sent to every backer when a campaign closes having reached its goal.
+    CampaignSucceeded,
+    /// Sent to every backer when a campaign closes short of its goal.
+    CampaignFailed,
+}
+
+impl Kind {
+    /// Returns every kind of mail.
+    pub fn all() -> Vec<Kind> {
+        vec![Kind::Receipt, Kind::NewBacker, Kind::CampaignSucceeded, Kind::CampaignFailed]
+    }
+
+    /// Returns the code stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Kind::Receipt           => "receipt",
+            Kind::NewBacker         => "new_backer",
+            Kind::CampaignSucceeded => "campaign_succeeded",
+            Kind::CampaignFailed    => "campaign_failed",
+        }
+    }
+
+    /// Returns the plain text and HTML templates. The first line of the
+    /// plain text template holds the subject.
+    fn templates(&self) -> (&'static str, &'static str) {
+        match *self {
+            Kind::Receipt           => (include_str!("../data/templates/receipt.txt"),
+                                        include_str!("../data/templates/receipt.html")),
+            Kind::NewBacker         => (include_str!("../data/templates/new_backer.txt"),
+                                        include_str!("../data/templates/new_backer.html")),
+            Kind::CampaignSucceeded => (include_str!("../data/templates/campaign_succeeded.txt"),
+                                        include_str!("../data/templates/campaign_succeeded.html")),
+            Kind::CampaignFailed    => (include_str!("../data/templates/campaign_failed.txt"),
+                                        include_str!("../data/templates/campaign_failed.html")),
+        }
+    }
+}
+
+impl FromStr for Kind {
+    type Err = validate::Error;
+
+    fn from_str(s: &str) -> validate::Result<Kind> {
+        match s {
+            "receipt"            => Ok(Kind::Receipt),
+            "new_backer"         => Ok(Kind::NewBacker),
+            "campaign_succeeded" => Ok(Kind::CampaignSucceeded),
+            "campaign_failed"    => Ok(Kind::CampaignFailed),
+            _                    => Err(validate::Error::InvalidMailKind(s.to_owned())),
+        }
+    }
+}
+
+/// Replaces each `{{name}}` in the template with its value, escaping values for HTML if desired.
+pub fn render(template: &str, vars: &[(&str, String)], html: bool) -> String {
+    let mut rendered = template.to_owned();
+    for &(name, ref value) in vars {
+        let value = if html { escape_html(value) } else { value.clone() };
+        rendered = rendered.replace(&format!("{{{{{}}}}}", name), &value);
+    }
+    rendered
+}
+
+/// Escapes the characters that are significant in HTML.
+fn escape_html(s: &str) -> String {
+    s.replace("&", "&amp;")
+        .replace("<", "&lt;")
+        .replace(">", "&gt;")
+        .replace("\"", "&quot;")
+        .replace("'", "&#39;")
+}
+
+impl Mail {
+
+    /// Renders and queues a message to the user, unless they have no email address
+    /// or have turned this kind of mail off. The user's name and unsubscribe token are
+    /// always available to templates. Callers should run this in the same transaction
+    /// as the change that prompted it.
+    pub fn enqueue(client: &Client, user: &User, kind: Kind, vars: &[(&str, String)]) -> Result<Option<Mail>> {
+        let recipient = match user.email {
+            Some(ref email) => email,
+            None => return Ok(None),
+        };
+
+        if !try!(MailPreference::enabled(client, user.user_id, kind)) {
+            return Ok(None);
+        }
+
+        let mut vars = vars.to_vec();
+        vars.push(("user", user.name.clone()));
+        vars.push(("token", user.unsubscribe_token.clone()));
+
+        let (text, html) = kind.templates();
+        let text = render(text, &vars, false);
+        let html = render(html, &vars, true);
+
+        let (subject, body) = match text.find("\n\n") {
+            Some(i) => (text[..i].trim_left_matches("Subject:").trim(), text[i..].trim()),
+            None => ("", text.trim()),
+        };
+
+        let mail: Mail = try!(Query::insert()
+            .set(column::user_id, &user.user_id)
+            .set(column::kind, &kind.as_str())
+            .set(column::recipient, recipient)
+            .set(column::subject, &subject)
+            .set(column::body_text, &body)
+            .set(column::body_html, &html)
+            .into_table(&client.table(table::mail))
+            .return_all()
+            .collect_one(client.db()));
+
+        Ok(Some(mail))
+    }
+
+    /// Queues a receipt for the backer, and a notice for the project's creator.
+    pub fn enqueue_pledge(client: &Client, pledge: &Pledge, backer: &User, project: &Project) -> Result<()> {
+        let amount = format!("{:.2}", pledge.amount);
+        let goal = format!("{:.2}", project.goal);
+
+        try!(Mail::enqueue(client, backer, Kind::Receipt, &[("project", project.name.clone()),
+                                                              ("amount", amount.clone()),
+                                                              ("goal", goal.clone())]));
+
+        let creator_id = match project.creator_id {
+            Some(creator_id) if creator_id != backer.user_id => creator_id,
+            _ => return Ok(()),
+        };
+
+        let sql = format!("SELECT us.*, (SELECT SUM(amount - refunded) FROM {} WHERE project_id = $2) AS total \
+                           FROM {} WHERE us.user_id = $1",
+                          client.table(table::pledge),
+                          client.table_abbr(table::user));
+
+        let mut daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::I32(creator_id),
+                                                                              Value::I32(project.project_id)]));
+        if let Some(dao) = daos.pop() {
+            let creator = User::from_dao(&dao);
+            let total: f64 = dao.get("total");
+            try!(Mail::enqueue(client, &creator, Kind::NewBacker, &[("project", project.name.clone()),
+                                                                     ("backer", backer.name.clone()),
+                                                                     ("amount", amount),
+                                                                     ("goal", goal),
+                                                                     ("total", format!("{:.2}", total))]));
+        }
+
+        Ok(())
+    }
+
+    /// Tells every backer of a closed project whether its campaign succeeded.
+    pub fn enqueue_outcome(client: &Client, project: &Project, total: f64) -> Result<()> {
+        let kind = if total >= project.goal { Kind::CampaignSucceeded } else { Kind::CampaignFailed };
+
+        let sql = format!("SELECT us.*, pl.amount - pl.refunded AS pledged FROM {} \
+                           INNER JOIN {} ON us.user_id = pl.user_id \
+                           WHERE pl.project_id = $1 ORDER BY us.user_id",
+                          client.table_abbr(table::pledge),
+                          client.table_abbr(table::user));
+
+        let daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::I32(project.project_id)]));
+        for dao in &daos {
+            let backer = User::from_dao(dao);
+            let pledged: f64 = dao.get("pledged");
+            try!(Mail::enqueue(client, &backer, kind, &[("project", project.name.clone()),
+                                                         ("amount", format!("{:.2}", pledged)),
+                                                         ("goal", format!("{:.2}", project.goal)),
+                                                         ("total", format!("{:.2}", total))]));
+        }
+
+        Ok(())
+    }
+
+    /// Sends all queued mail through the transport, oldest first. Mail that fails to send
+    /// is retried on the next call, until `MAX_ATTEMPTS` is reached.
+    /// Returns the mail that was attempted, as updated.
+    pub fn send_pending(client: &Client, transport: &mut Transport, from: &str) -> Result<Vec<Mail>> {
+        let sql = format!("SELECT * FROM {} WHERE status = 'pending' ORDER BY mail_id", client.table(table::mail));
+        let daos = try!(client.db().execute_sql_with_return(&sql, &vec![]));
+
+        let mut attempted = vec![];
+        for mail in daos.iter().map(Mail::from_dao) {
+            let attempts = mail.attempts + 1;
+            let (sql, params) = match transport.send(from, &mail) {
+                Ok(()) => {
+                    (format!("UPDATE {} SET status = 'sent', attempts = $1, error = NULL, \
+                              date_sent = localtimestamp WHERE mail_id = $2 RETURNING *",
+                             client.table(table::mail)),
+                     vec![Value::I32(attempts), Value::I32(mail.mail_id)])
+                }
+                Err(err) => {
+                    let status = if attempts >= MAX_ATTEMPTS { "failed" } else { "pending" };
+                    (format!("UPDATE {} SET status = $1, attempts = $2, error = $3 \
+                              WHERE mail_id = $4 RETURNING *", client.table(table::mail)),
+                     vec![Value::String(status.to_owned()), Value::I32(attempts),
+                          Value::String(err.to_string()), Value::I32(mail.mail_id)])
+                }
+            };
+
+            let mut daos = try!(client.db().execute_sql_with_return(&sql, &params));
+            if let Some(dao) = daos.pop() {
+                attempted.push(Mail::from_dao(&dao));
+            }
+        }
+
+        Ok(attempted)
+    }
+
+    /// Formats the mail as a MIME message with plain text and HTML alternatives.
+    pub fn to_message(&self, from: &str) -> String {
+        let boundary = format!("ksr-mail-{}", self.mail_id);
+        let date = Local::now().format("%a, %d %b %Y %H:%M:%S %z");
+
+        let message = format!("From: {from}\n\
+                               To: {to}\n\
+                               Subject: {subject}\n\
+                               Date: {date}\n\
+                               MIME-Version: 1.0\n\
+                               Content-Type: multipart/alternative; boundary=\"{boundary}\"\n\
+                               \n\
+                               --{boundary}\n\
+                               Content-Type: text/plain; charset=utf-8\n\
+                               \n\
+                               {text}\n\
+                               --{boundary}\n\
+                               Content-Type: text/html; charset=utf-8\n\
+                               \n\
+                               {html}\n\
+                               --{boundary}--\n",
+                              from = from, to = self.recipient, subject = self.subject, date = date,
+                              boundary = boundary, text = self.body_text, html = self.body_html.trim());
+
+        // Mail uses CRLF line endings.
+        message.lines().collect::<Vec<_>>().join("\r\n") + "\r\n"
+    }
+}
+
+impl MailPreference {
+
+    /// Turns a kind of mail on or off for a user.
+    pub fn set(client: &Client, user: &str, kind: Kind, enabled: bool) -> Result<MailPreference> {
+        let user = try!(User::get(client, user));
+        client.transaction(|| MailPreference::update(client, &user, kind, enabled))
+    }
+
+    /// Turns off one kind of mail, or every kind if none is provided, for the user with the
+    /// unsubscribe token. Returns the user.
+    pub fn unsubscribe(client: &Client, token: &str, kind: Option<Kind>) -> Result<User> {
+        let sql = format!("SELECT * FROM {} WHERE unsubscribe_token = $1", client.table(table::user));
+        let mut daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::String(token.to_owned())]));
+
+        let user = match daos.pop() {
+            Some(dao) => User::from_dao(&dao),
+            None => return Err(From::from(validate::Error::InvalidToken)),
+        };
+
+        let kinds = kind.map_or(Kind::all(), |kind| vec![kind]);
+
+        client.transaction(|| {
+            for kind in kinds {
+                try!(MailPreference::update(client, &user, kind, false));
+            }
+            Ok(user.clone())
+        })
+    }
+
+    /// Returns true unless the user has turned this kind of mail off.
+    pub fn enabled(client: &Client, user_id: i32, kind: Kind) -> Result<bool> {
+        let sql = format!("SELECT enabled FROM {} WHERE user_id = $1 AND kind = $2",
+                          client.table(table::mail_preference));
+
+        let daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::I32(user_id),
+                                                                         Value::String(kind.as_str().to_owned())]));
+        Ok(daos.iter().all(|dao| dao.get::<bool>(column::enabled)))
+    }
+
+    /// Updates or inserts the preference, and records the change.
+    fn update(client: &Client, user: &User, kind: Kind, enabled: bool) -> Result<MailPreference> {
+        let params = vec![Value::Bool(enabled), Value::I32(user.user_id), Value::String(kind.as_str().to_owned())];
+
+        let sql = format!("UPDATE {} SET enabled = $1 WHERE user_id = $2 AND kind = $3 RETURNING *",
+                          client.table(table::mail_preference));
+        let mut daos = try!(client.db().execute_sql_with_return(&sql, &params));
+
+        if daos.is_empty() {
+            let sql = format!("INSERT INTO {} (enabled, user_id, kind) VALUES ($1, $2, $3) RETURNING *",
+                              client.table(table::mail_preference));
+            daos = try!(client.db().execute_sql_with_return(&sql, &params));
+        }
+
+        let preference = MailPreference::from_dao(&daos.pop().unwrap());
+        try!(Audit::record(client, "set_preference", table::mail_preference,
+                           &format!("{}:{}", user.user_id, kind.as_str()), None, Some(preference.to_json())));
+        Ok(preference)
+    }
+}
+
+/// Delivers rendered mail.
+pub trait Transport {
+    /// Sends the mail from the provided address.
+    fn send(&mut self, from: &str, mail: &Mail) -> Result<()>;
+}
+
+/// Sends mail to an SMTP relay. Authentication and TLS are not supported,
+/// so this is meant for a relay running on the same host or network.
+pub struct Smtp {
+    host: String,
+    port: u16,
+}
+
+/// Writes each message into a maildir, for local testing and inspection.
+pub struct Maildir {
+    path: PathBuf,
+}
+
+impl Smtp {
+    /// Creates a transport that connects to the relay at host:port.
+    pub fn new(host: &str, port: u16) -> Smtp {
+        Smtp { host: host.to_owned(), port: port }
+    }
+
+    /// Reads a reply and checks that its code is the one expected.
+    /// Multi-line replies are joined into one.
+    fn expect(reader: &mut BufRead, code: &str) -> Result<()> {
+        let mut reply = String::new();
+        loop {
+            let mut line = String::new();
+            if try!(reader.read_line(&mut line)) == 0 {
+                return Err(Smtp::error("The SMTP server closed the connection"));
+            }
+
+            reply.push_str(line.trim_right());
+            if line.len() < 4 || &line[3..4] != "-" {
+                break;
+            }
+            reply.push(' ');
+        }
+
+        if reply.starts_with(code) {
+            Ok(())
+        } else {
+            Err(Smtp::error(&format!("Unexpected SMTP reply: {}", reply)))
+        }
+    }
+
+    /// Sends a command and checks the reply.
+    fn command(stream: &mut Write, reader: &mut BufRead, command: &str, code: &str) -> Result<()> {
+        try!(write!(stream, "{}\r\n", command));
+        Smtp::expect(reader, code)
+    }
+
+    fn error(message: &str) -> Error {
+        Error::IO(io::Error::new(io::ErrorKind::Other, message))
+    }
+}
+
+impl Transport for Smtp {
+    fn send(&mut self, from: &str, mail: &Mail) -> Result<()> {
+        let mut stream = try!(TcpStream::connect((&self.host[..], self.port)));
+        let mut reader = BufReader::new(try!(stream.try_clone()));
+
+        try!(Smtp::expect(&mut reader, "220"));
+        try!(Smtp::command(&mut stream, &mut reader, "HELO localhost", "250"));
+        try!(Smtp::command(&mut stream, &mut reader, &format!("MAIL FROM:<{}>", from), "250"));
+        try!(Smtp::command(&mut stream, &mut reader, &format!("RCPT TO:<{}>", mail.recipient), "25"));
+        try!(Smtp::command(&mut stream, &mut reader, "DATA", "354"));
+
+        // Lines starting with a dot are escaped by doubling it.
+        let message = mail.to_message(from).replace("\r\n.", "\r\n..");
+        try!(stream.write_all(message.as_bytes()));
+        try!(Smtp::command(&mut stream, &mut reader, ".", "250"));
+
+        let _ = Smtp::command(&mut stream, &mut reader, "QUIT", "221");
+        Ok(())
+    }
+}
+
+impl Maildir {
+    /// Creates a transport that writes into the maildir at path, creating it if necessary.
+    pub fn new(path: &str) -> Maildir {
+        Maildir { path: PathBuf::from(path) }
+    }
+}
+
+impl Transport for Maildir {
+    fn send(&mut self, from: &str, mail: &Mail) -> Result<()> {
+        for dir in &["tmp", "new", "cur"] {
+            try!(fs::create_dir_all(self.path.join(dir)));
+        }
+
+        // Write into tmp first and move into new, so that readers never see partial messages.
+        let name = format!("{}.M{}.ksr", Local::now().timestamp(), mail.mail_id);
+        let tmp = self.path.join("tmp").join(&name);
+
+        let mut f = try!(File::create(&tmp));
+        try!(f.write_all(mail.to_message(from).as_bytes()));
+        try!(fs::rename(&tmp, self.path.join("new").join(&name)));
+        Ok(())
+    }
+}
+
+/// Mail settings, read from the `[mail]` section of the configuration:
+///
+/// ```toml
+/// [mail]
+/// from = "noreply@example.com"
+/// host = "localhost"      # send through an SMTP relay...
+/// port = 25
+/// maildir = "data/mail"   # ...or write into a maildir instead.
+/// ```
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The address that mail is sent from.
+    pub from: String,
+    /// SMTP relay host, if mail is sent through SMTP.
+    pub host: Option<String>,
+    /// SMTP relay port.
+    pub port: u16,
+    /// Maildir path, if mail is written locally instead.
+    pub maildir: Option<String>,
+}
+
+impl Config {
+    /// Reads the `[mail]` section of the configuration.
+    pub fn from_toml(value: &toml::Value) -> Result<Config> {
+        let get = |key: &str| value.lookup(key).and_then(|v| v.as_str()).map(|s| s.to_owned());
+
+        let config = Config {
+            from: match get("from") {
+                Some(from) => from,
+                None => return Err(Error::Config(ERR_MAIL_CONFIG.to_owned())),
+            },
+            host: get("host"),
+            port: value.lookup("port").and_then(|v| v.as_integer()).map_or(DEFAULT_SMTP_PORT, |p| p as u16),
+            maildir: get("maildir"),
+        };
+
+        if config.host.is_none() && config.maildir.is_none() {
+            return Err(Error::Config(ERR_MAIL_CONFIG.to_owned()));
+        }
+
+        try!(validate::email(&config.from));
+        Ok(config)
+    }
+
+    /// Returns the configured transport. A maildir takes precedence over SMTP.
+    pub fn transport(&self) -> Box<Transport> {
+        match (&self.maildir, &self.host) {
+            (&Some(ref path), _) => Box::new(Maildir::new(path)),
+            (_, &Some(ref host)) => Box::new(Smtp::new(host, self.port)),
+            _ => unreachable!(),
+        }
+    }
+}
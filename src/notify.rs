@@ -0,0 +1,139 @@
+//! Module for subscribing to live changes through PostgreSQL LISTEN/NOTIFY.
+//!
+//! Triggers on the project and pledge tables notify a channel named after the schema
+//! whenever a row changes. Subscribers hold their own connection to the database and
+//! receive each change as a typed notice once its transaction commits.
+use Result;
+
+use postgres::{Connection, SslMode};
+use postgres::notification::Notification;
+use rustc_serialize::json;
+
+use std::time::Duration;
+
+/// A change made to Kickstarter, as announced by the database.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Notice {
+    /// A project was created.
+    ProjectCreated { project: String, goal: f64 },
+    /// A project's settings were changed.
+    ProjectUpdated { project: String },
+    /// A user backed a project.
+    PledgeCreated { project: String, user: String, amount: f64 },
+    /// Money was returned from a pledge, through a refund or chargeback.
+    PledgeRefunded { project: String, user: String, amount: f64 },
+    /// A pledge was withdrawn. The amount is what remained of the pledge.
+    PledgeCancelled { project: String, user: String, amount: f64 },
+}
+
+/// The JSON payload sent by the notification triggers.
+#[derive(RustcDecodable)]
+struct Payload {
+    event: String,
+    project: Option<String>,
+    user: Option<String>,
+    amount: Option<f64>,
+    goal: Option<f64>,
+}
+
+impl Notice {
+
+    /// Returns the name of the project that changed.
+    pub fn project(&self) -> &str {
+        match *self {
+            Notice::ProjectCreated { ref project, .. }  |
+            Notice::ProjectUpdated { ref project }      |
+            Notice::PledgeCreated { ref project, .. }   |
+            Notice::PledgeRefunded { ref project, .. }  |
+            Notice::PledgeCancelled { ref project, .. } => project,
+        }
+    }
+
+    /// Returns the name of the user whose pledge changed, if the change was to a pledge.
+    pub fn user(&self) -> Option<&str> {
+        match *self {
+            Notice::PledgeCreated { ref user, .. }   |
+            Notice::PledgeRefunded { ref user, .. }  |
+            Notice::PledgeCancelled { ref user, .. } => Some(user),
+            _ => None,
+        }
+    }
+
+    /// Returns how much the change moved the project's funding total.
+    pub fn funding_change(&self) -> f64 {
+        match *self {
+            Notice::PledgeCreated { amount, .. }   => amount,
+            Notice::PledgeRefunded { amount, .. }  |
+            Notice::PledgeCancelled { amount, .. } => -amount,
+            _ => 0f64,
+        }
+    }
+
+    /// Decodes a notice from a trigger's JSON payload.
+    pub fn decode(payload: &str) -> Result<Notice> {
+        let p: Payload = try!(json::decode(payload));
+        let project = p.project.unwrap_or(String::new());
+        let user = p.user.unwrap_or(String::new());
+        let amount = p.amount.unwrap_or(0f64);
+
+        let notice = match &p.event[..] {
+            "ProjectCreated"  => Notice::ProjectCreated { project: project, goal: p.goal.unwrap_or(0f64) },
+            "ProjectUpdated"  => Notice::ProjectUpdated { project: project },
+            "PledgeCreated"   => Notice::PledgeCreated { project: project, user: user, amount: amount },
+            "PledgeRefunded"  => Notice::PledgeRefunded { project: project, user: user, amount: amount },
+            "PledgeCancelled" => Notice::PledgeCancelled { project: project, user: user, amount: amount },
+            _ => return Err(From::from(json::DecoderError::UnknownVariantError(p.event))),
+        };
+
+        Ok(notice)
+    }
+}
+
+/// A dedicated connection listening for changes to a schema.
+pub struct Subscription {
+    conn: Connection,
+}
+
+/// A blocking iterator over the notices received by a subscription.
+pub struct Notices<'a> {
+    subscription: &'a Subscription,
+}
+
+impl Subscription {
+
+    /// Opens a new connection to the database and listens for changes to the schema.
+    pub fn listen(uri: &str, schema: &str) -> Result<Subscription> {
+        let conn = try!(Connection::connect(uri, &SslMode::None));
+        try!(conn.batch_execute(&format!("LISTEN \"{}\"", schema.replace("\"", "\"\""))));
+        Ok(Subscription { conn: conn })
+    }
+
+    /// Returns an iterator that blocks until each notice arrives.
+    pub fn notices(&self) -> Notices {
+        Notices { subscription: self }
+    }
+
+    /// Waits up to the provided duration for the next notice.
+    /// Returns None if nothing arrived in time.
+    pub fn wait(&self, timeout: Duration) -> Option<Result<Notice>> {
+        let notifications = self.conn.notifications();
+        let next = notifications.timeout_iter(timeout).next();
+        next.map(Subscription::decode)
+    }
+
+    /// Converts a raw notification into a notice.
+    fn decode(notification: ::postgres::Result<Notification>) -> Result<Notice> {
+        let notification = try!(notification);
+        Notice::decode(&notification.payload)
+    }
+}
+
+impl<'a> Iterator for Notices<'a> {
+    type Item = Result<Notice>;
+
+    fn next(&mut self) -> Option<Result<Notice>> {
+        let notifications = self.subscription.conn.notifications();
+        let next = notifications.blocking_iter().next();
+        next.map(Subscription::decode)
+    }
+}
@@ -0,0 +1,317 @@
+//! Module for presenting command results as tables, JSON, CSV or TSV.
+//!
+//! Results are built as JSON, in the shapes described by `data/output.schema.json`, and
+//! flattened into a `Table` of chosen columns for the other formats. Tables align their
+//! columns and show amounts with thousands separators; CSV and TSV leave amounts as plain
+//! numbers so that they can be loaded elsewhere.
+//!
+//! Failures map to distinct exit codes, so that scripts can tell a typo from a missing
+//! project or a database outage without reading the message.
+use {api, validate, Error};
+use comment::Thread;
+use models::{Audit, Pledge, Project, Refund, WebhookDelivery};
+
+use postgres::error::SqlState;
+use rustc_serialize::json::{Json, ToJson};
+
+use std::cmp;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Exit code for failures not covered below, such as I/O and configuration errors.
+pub const EXIT_FAILURE:   i32 = 1;
+/// Exit code for invalid input.
+pub const EXIT_INVALID:   i32 = 3;
+/// Exit code for a missing project, user, pledge, comment or webhook.
+pub const EXIT_NOT_FOUND: i32 = 4;
+/// Exit code for a conflict with existing data, such as a taken name or a reused card.
+pub const EXIT_CONFLICT:  i32 = 5;
+/// Exit code for a missing login, or a role that doesn't allow the command.
+pub const EXIT_DENIED:    i32 = 6;
+/// Exit code for a database failure.
+pub const EXIT_DATABASE:  i32 = 7;
+/// Exit code for data that changed while the command ran, which changed nothing and can be retried.
+pub const EXIT_RETRY:     i32 = 8;
+
+/// The columns that hold amounts of money, which tables show as currency.
+const MONEY_COLUMNS: &'static [&'static str] = &["amount", "goal", "minimum", "pledged", "refunded"];
+
+/// A way of presenting results.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// The sentences printed by the command line.
+    Text,
+    /// Aligned columns with a header.
+    Table,
+    /// A JSON document, pretty-printed.
+    Json,
+    /// Comma-separated values with a header row.
+    Csv,
+    /// Tab-separated values with a header row.
+    Tsv,
+}
+
+/// Rows of values under named columns.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Json>>,
+}
+
+impl FromStr for Format {
+    type Err = validate::Error;
+
+    fn from_str(s: &str) -> validate::Result<Format> {
+        match s {
+            "text"  => Ok(Format::Text),
+            "table" => Ok(Format::Table),
+            "json"  => Ok(Format::Json),
+            "csv"   => Ok(Format::Csv),
+            "tsv"   => Ok(Format::Tsv),
+            _       => Err(validate::Error::InvalidFormat(s.to_owned())),
+        }
+    }
+}
+
+impl Table {
+
+    /// Builds a table from a JSON object, or an array of them, taking the named columns of each.
+    /// Missing members are left empty.
+    pub fn new(columns: &[&str], json: &Json) -> Table {
+        let items: Vec<&Json> = match *json {
+            Json::Array(ref items) => items.iter().collect(),
+            ref item => vec![item],
+        };
+
+        Table {
+            columns: columns.iter().map(|c| (*c).to_owned()).collect(),
+            rows: items.iter()
+                .map(|item| columns.iter().map(|c| item.find(c).cloned().unwrap_or(Json::Null)).collect())
+                .collect(),
+        }
+    }
+
+    /// Renders the table in a format other than JSON or text, ending with a newline.
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Csv => self.delimited(",", csv_field),
+            Format::Tsv => self.delimited("\t", tsv_field),
+            _ => self.aligned(),
+        }
+    }
+
+    /// Renders the table with aligned columns. Numbers are aligned to the right.
+    fn aligned(&self) -> String {
+        let cells: Vec<Vec<String>> = self.rows.iter()
+            .map(|row| row.iter().zip(&self.columns).map(|(value, column)| table_cell(column, value)).collect())
+            .collect();
+
+        let widths: Vec<usize> = self.columns.iter().enumerate()
+            .map(|(i, column)| cells.iter().fold(column.len(), |width, row| cmp::max(width, row[i].chars().count())))
+            .collect();
+
+        let mut out = String::new();
+        let header: Vec<String> = self.columns.iter().zip(&widths)
+            .map(|(column, &width)| format!("{:<1$}", column.to_uppercase(), width))
+            .collect();
+        out.push_str(header.join("  ").trim_right());
+        out.push('\n');
+
+        for (row, values) in cells.iter().zip(&self.rows) {
+            let line: Vec<String> = row.iter().zip(values).zip(&widths)
+                .map(|((cell, value), &width)| if value.is_number() {
+                    format!("{:>1$}", cell, width)
+                } else {
+                    format!("{:<1$}", cell, width)
+                })
+                .collect();
+            out.push_str(line.join("  ").trim_right());
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Renders the table as delimited text with a header row.
+    fn delimited(&self, delimiter: &str, field: fn(&Json) -> String) -> String {
+        let mut out = self.columns.join(delimiter);
+        out.push('\n');
+        for row in &self.rows {
+            let fields: Vec<String> = row.iter().map(field).collect();
+            out.push_str(&fields.join(delimiter));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Renders a result in a format. Text isn't rendered here, since it is written by each command.
+pub fn render(format: Format, json: &Json, table: &Table) -> String {
+    match format {
+        Format::Json | Format::Text => format!("{}\n", json.pretty()),
+        _ => table.render(format),
+    }
+}
+
+/// Formats an amount of dollars with thousands separators, such as `$250,000.00`.
+pub fn currency(amount: f64) -> String {
+    let fixed = format!("{:.2}", amount.abs());
+    let (whole, cents) = fixed.split_at(fixed.len() - 3);
+
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    format!("{}${}{}", if amount < 0f64 { "-" } else { "" }, grouped, cents)
+}
+
+/// Returns the exit code that describes an error.
+pub fn exit_code(err: &Error) -> i32 {
+    match *err {
+        Error::Database(ref inner) => match inner.code {
+            Some(SqlState::UniqueViolation) => EXIT_CONFLICT,
+            _ => EXIT_DATABASE,
+        },
+        Error::Config(_) | Error::IO(_) => EXIT_FAILURE,
+        Error::InvalidData(validate::Error::ConcurrentChange) => EXIT_RETRY,
+        _ => match api::status(err).to_u16() {
+            400 => EXIT_INVALID,
+            401 | 403 => EXIT_DENIED,
+            404 => EXIT_NOT_FOUND,
+            409 => EXIT_CONFLICT,
+            _ => EXIT_FAILURE,
+        },
+    }
+}
+
+/// Returns the pledges towards a project, along with its funding progress.
+pub fn backers_json(project: &str, pledges: &[Pledge], goal: f64) -> Json {
+    let pledged = pledges.iter().fold(0f64, |total, p| total + p.net_amount());
+    let mut json = funding_json(project, pledged, goal);
+    if let Json::Object(ref mut map) = json {
+        let items = pledges.iter().map(|p| api::pledge_json(p, &p.get_user().name, project)).collect();
+        map.insert("pledges".to_owned(), Json::Array(items));
+    }
+    json
+}
+
+/// Returns the pledges that a user has made, along with their total.
+pub fn backed_json(user: &str, pledges: &[Pledge]) -> Json {
+    let pledged = pledges.iter().fold(0f64, |total, p| total + p.net_amount());
+    let items = pledges.iter().map(|p| api::pledge_json(p, user, &p.get_project().name)).collect();
+
+    let mut json = BTreeMap::new();
+    json.insert("user".to_owned(), user.to_json());
+    json.insert("pledged".to_owned(), pledged.to_json());
+    json.insert("pledges".to_owned(), Json::Array(items));
+    Json::Object(json)
+}
+
+/// Returns a project's funding progress.
+pub fn funding_json(project: &str, pledged: f64, goal: f64) -> Json {
+    let mut json = BTreeMap::new();
+    json.insert("project".to_owned(), project.to_json());
+    json.insert("pledged".to_owned(), pledged.to_json());
+    json.insert("goal".to_owned(), goal.to_json());
+    json.insert("funded".to_owned(), (pledged >= goal).to_json());
+    Json::Object(json)
+}
+
+/// Returns a closed project, along with what it raised.
+pub fn closed_json(project: &Project, pledged: f64) -> Json {
+    let mut json = BTreeMap::new();
+    json.insert("project".to_owned(), api::project_json(project));
+    json.insert("pledged".to_owned(), pledged.to_json());
+    json.insert("funded".to_owned(), (pledged >= project.goal).to_json());
+    Json::Object(json)
+}
+
+/// Returns a refund, along with the name of the backer it was returned to.
+pub fn refund_json(refund: &Refund, user: &str) -> Json {
+    let mut json = refund.to_json();
+    if let Json::Object(ref mut map) = json {
+        map.insert("user".to_owned(), user.to_json());
+    }
+    json
+}
+
+/// Returns a webhook delivery, along with its log of attempts.
+pub fn delivery_json(delivery: &WebhookDelivery) -> Json {
+    let mut json = delivery.to_json();
+    if let Json::Object(ref mut map) = json {
+        let attempts = delivery.webhook_attempt.iter().map(|a| a.to_json()).collect();
+        map.insert("webhook_attempt".to_owned(), Json::Array(attempts));
+    }
+    json
+}
+
+/// Returns an audit log entry, with the recorded values parsed.
+pub fn audit_json(audit: &Audit) -> Json {
+    let mut json = audit.to_json();
+    if let Json::Object(ref mut map) = json {
+        map.insert("before_value".to_owned(), audit.before().unwrap_or(Json::Null));
+        map.insert("after_value".to_owned(), audit.after().unwrap_or(Json::Null));
+    }
+    json
+}
+
+/// Returns a comment, showing only the body that may be displayed.
+pub fn comment_json(comment: &Json, body: &str) -> Json {
+    let mut json = comment.clone();
+    if let Json::Object(ref mut map) = json {
+        map.insert("body".to_owned(), body.to_json());
+    }
+    json
+}
+
+/// Returns a comment thread, with its replies nested.
+pub fn thread_json(thread: &Thread) -> Json {
+    let mut json = comment_json(&thread.comment.to_json(), thread.comment.visible_body());
+    if let Json::Object(ref mut map) = json {
+        map.insert("author".to_owned(), thread.author.to_json());
+        map.insert("backer".to_owned(), thread.backer.to_json());
+        map.insert("replies".to_owned(), Json::Array(thread.replies.iter().map(thread_json).collect()));
+    }
+    json
+}
+
+/// Shows a value in an aligned table. Amounts of money are shown as currency, while other
+/// numbers, such as throughputs and latencies, are shown plainly.
+fn table_cell(column: &str, value: &Json) -> String {
+    match *value {
+        Json::F64(amount) if MONEY_COLUMNS.contains(&column) => currency(amount),
+        _ => plain(value),
+    }
+}
+
+/// Shows a value in a CSV field.
+fn csv_field(value: &Json) -> String {
+    csv_quote(&plain(value))
+}
+
+/// Quotes a CSV field if it holds a delimiter, quote or line break.
+pub fn csv_quote(field: &str) -> String {
+    if field.contains(|c: char| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace("\"", "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Shows a value in a TSV field. Tabs and line breaks can't be escaped, so become spaces.
+fn tsv_field(value: &Json) -> String {
+    plain(value).replace(|c: char| c == '\t' || c == '\n' || c == '\r', " ")
+}
+
+/// Shows a value without decoration. Amounts keep two decimal places, and nulls are empty.
+fn plain(value: &Json) -> String {
+    match *value {
+        Json::Null => String::new(),
+        Json::String(ref s) => s.clone(),
+        Json::F64(amount) => format!("{:.2}", amount),
+        ref other => other.to_string(),
+    }
+}
@@ -3,9 +3,13 @@ pub use models::Pledge;
 
 use {validate, Client, Result};
 use db::{column, table};
-use models::{Project, User};
+use event::{self, DomainEvent};
+use models::{Audit, Event, Mail, Project, User, Webhook};
+use webhook;
 
 use postgres::error::SqlState;
+use rustc_serialize::json::ToJson;
+use rustorm::dao::{IsDao, Value};
 use rustorm::database::DbError;
 use rustorm::query::Query;
 
@@ -23,6 +27,17 @@ impl Pledge {
         self.project.as_ref().unwrap()
     }
 
+    /// Returns the pledged amount, less any refunds and chargebacks.
+    pub fn net_amount(&self) -> f64 {
+        self.amount - self.refunded
+    }
+
+    /// Returns the credit card number with all but the last four digits masked.
+    pub fn masked_card(&self) -> String {
+        let visible = self.card.len().saturating_sub(4);
+        format!("{}{}", "*".repeat(visible), &self.card[visible..])
+    }
+
     /// Creates a new pledge for an existing project.
     pub fn create(client: &Client, user: &str, project_name: &str, card: &str, amount: f64) -> Result<Pledge> {
 
@@ -31,23 +46,69 @@ impl Pledge {
         // Validate and truncate currency amount.
         let amount = try!(validate::currency(amount));
 
-        let uid = try!(User::upsert(client, user));
-        let pid = try!(Project::get_id(client, project_name));
+        client.transaction(|| {
+            let uid = try!(User::upsert(client, user));
+            let project = try!(Project::get(client, project_name));
+            if project.date_closed.is_some() {
+                return Err(From::from(validate::Error::ProjectClosed));
+            }
 
-        // Add a new pledge.
-        let mut res = Query::insert()
-            .set(column::user_id, &uid)
-            .set(column::project_id, &pid)
-            .set(column::card, &card)
-            .set(column::amount, &amount)
-            .into_table(&client.table(table::pledge))
-            .return_all()
-            .collect_one(client.db());
+            let pid = project.project_id;
+
+            // Add a new pledge.
+            let mut res = Query::insert()
+                .set(column::user_id, &uid)
+                .set(column::project_id, &pid)
+                .set(column::card, &card)
+                .set(column::amount, &amount)
+                .into_table(&client.table(table::pledge))
+                .return_all()
+                .collect_one(client.db());
+
+            Pledge::check_valid_errors(&mut res, user, project_name, card);
+
+            let pledge = try!(res);
+            try!(Audit::record(client, "create", table::pledge, &pledge.key(), None, Some(pledge.to_json())));
+            try!(Event::record(client, DomainEvent::PledgeMade(event::PledgeMade {
+                user_id: pledge.user_id,
+                project_id: pledge.project_id,
+                card: pledge.card.clone(),
+                amount: pledge.amount,
+            })));
+            try!(Webhook::enqueue_pledge(client, webhook::PLEDGE_CREATED, &pledge, pledge.amount));
+            try!(Webhook::enqueue_if_funded(client, &pledge));
+            try!(Mail::enqueue_pledge(client, &pledge, &try!(User::get(client, user)), &project));
+            Ok(pledge)
+        })
+    }
 
-        Pledge::check_valid_errors(&mut res, user, project_name, card);
+    /// Withdraws a user's pledge from a project. Returns the cancelled pledge.
+    pub fn cancel(client: &Client, user: &str, project_name: &str) -> Result<Pledge> {
+        let sql = format!("DELETE FROM {} WHERE project_id = $1 AND user_id = \
+                           (SELECT user_id FROM {} WHERE name = $2) RETURNING *",
+                          client.table(table::pledge), client.table(table::user));
+
+        client.transaction(|| {
+            let pid = try!(Project::get_id(client, project_name));
+            let mut daos = try!(client.db().execute_sql_with_return(&sql, &vec![pid, Value::String(user.to_owned())]));
+            let pledge = match daos.pop() {
+                Some(dao) => Pledge::from_dao(&dao),
+                None => return Err(From::from(validate::Error::PledgeDoesNotExist)),
+            };
+
+            try!(Audit::record(client, "cancel", table::pledge, &pledge.key(), Some(pledge.to_json()), None));
+            try!(Event::record(client, DomainEvent::PledgeCancelled(event::PledgeCancelled {
+                user_id: pledge.user_id,
+                project_id: pledge.project_id,
+            })));
+            try!(Webhook::enqueue_pledge(client, webhook::PLEDGE_CANCELLED, &pledge, pledge.net_amount()));
+            Ok(pledge)
+        })
+    }
 
-        let pledge = try!(res);
-        Ok(pledge)
+    /// Returns the pledge's composite key as `user_id:project_id`, as recorded in the audit log.
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.user_id, self.project_id)
     }
 
     /// Checks pledge creation results for acceptable errors, and reformats the message.
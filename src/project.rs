@@ -3,10 +3,13 @@ pub use models::Project;
 
 use {validate, Client, Result};
 use db::{column, table};
-use models::{Pledge, User};
+use event::{self, DomainEvent};
+use models::{Audit, Event, Mail, Pledge, User, Webhook};
+use webhook;
 
 use postgres::error::SqlState;
-use rustorm::dao::{FromValue, Value};
+use rustc_serialize::json::ToJson;
+use rustorm::dao::{FromValue, IsDao, Value};
 use rustorm::database::DbError;
 use rustorm::query::{Equality, Query};
 
@@ -18,6 +21,12 @@ impl Project {
     /// Creates a new Kickstarter project with the provided goal amount in dollars.
     /// Returns the created project on success.
     pub fn create(client: &Client, project_name: &str, amount: f64) -> Result<Project> {
+        Project::create_by(client, None, project_name, amount)
+    }
+
+    /// Creates a new Kickstarter project on behalf of its creator, who is told about new backers.
+    /// Returns the created project on success.
+    pub fn create_by(client: &Client, creator: Option<&str>, project_name: &str, amount: f64) -> Result<Project> {
 
         // Names must be alphanumeric and between 4 & 20 characters.
         try!(validate::length(project_name, 4, 20));
@@ -26,19 +35,46 @@ impl Project {
         // Validate and truncate the currency amount.
         let amount = try!(validate::currency(amount));
 
-        // Attempt to insert project into the table...
-        let mut result = Query::insert()
-            .set(column::name, &project_name)
-            .set(column::goal, &amount)
-            .into_table(&client.table(table::project))
-            .return_all()
-            .collect_one(client.db());
+        if let Some(creator) = creator {
+            try!(validate::length(creator, 4, 20));
+            try!(validate::alphanumeric(creator));
+        }
+
+        client.transaction(|| {
+            let creator_id = match creator {
+                Some(creator) => Some(try!(User::upsert(client, creator))),
+                None => None,
+            };
 
-        // and catch uniqueness violations to return a custom error.
-        Project::check_valid_errors(&mut result, project_name);
+            // Attempt to insert project into the table...
+            let mut query = Query::insert();
+            query.set(column::name, &project_name)
+                .set(column::goal, &amount);
 
-        let project = try!(result);
-        Ok(project)
+            if let Some(ref creator_id) = creator_id {
+                query.set(column::creator_id, creator_id);
+            }
+
+            let mut result = query
+                .into_table(&client.table(table::project))
+                .return_all()
+                .collect_one(client.db());
+
+            // and catch uniqueness violations to return a custom error.
+            Project::check_valid_errors(&mut result, project_name);
+
+            let project = try!(result);
+            try!(Audit::record(client, "create", table::project, &project.project_id.to_string(),
+                               None, Some(project.to_json())));
+            try!(Event::record(client, DomainEvent::ProjectCreated(event::ProjectCreated {
+                project_id: project.project_id,
+                name: project.name.clone(),
+                goal: project.goal,
+                creator_id: project.creator_id,
+            })));
+            try!(Webhook::enqueue_project(client, webhook::PROJECT_CREATED, &project));
+            Ok(project)
+        })
     }
 
     /// Checks project creation results for acceptable errors, and reformats the message.
@@ -73,6 +109,75 @@ impl Project {
         }
     }
 
+    /// Retrieve a project by name.
+    pub fn get(client: &Client, project_name: &str) -> Result<Project> {
+        let mut projects: Vec<Project> = try!(Query::select_all()
+            .from_table(&client.table(table::project))
+            .filter(column::name, Equality::EQ, &project_name)
+            .collect(client.db()));
+
+        projects.pop().ok_or(From::from(validate::Error::ProjectDoesNotExist))
+    }
+
+    /// Sets whether only backers of the project may post comments on it.
+    pub fn set_comments_backers_only(client: &Client, project_name: &str, backers_only: bool) -> Result<Project> {
+        let sql = format!("UPDATE {} SET comments_backers_only = $1 WHERE name = $2 RETURNING *",
+                          client.table(table::project));
+
+        let params = vec![Value::Bool(backers_only), Value::String(project_name.to_owned())];
+
+        client.transaction(|| {
+            let before = try!(Project::get(client, project_name));
+            let mut daos = try!(client.db().execute_sql_with_return(&sql, &params));
+            let project = match daos.pop() {
+                Some(dao) => Project::from_dao(&dao),
+                None => return Err(From::from(validate::Error::ProjectDoesNotExist)),
+            };
+
+            try!(Audit::record(client, "restrict_comments", table::project, &project.project_id.to_string(),
+                               Some(before.to_json()), Some(project.to_json())));
+            try!(Event::record(client, DomainEvent::CommentsRestricted(event::CommentsRestricted {
+                project_id: project.project_id,
+                backers_only: project.comments_backers_only,
+            })));
+            Ok(project)
+        })
+    }
+
+    /// Ends a project's campaign, so that it no longer accepts pledges, and tells its backers
+    /// whether it succeeded. Returns the closed project and the total amount it raised.
+    pub fn close(client: &Client, project_name: &str) -> Result<(Project, f64)> {
+        let sql = format!("UPDATE {} SET date_closed = localtimestamp \
+                           WHERE name = $1 AND date_closed IS NULL RETURNING *",
+                          client.table(table::project));
+
+        let total_sql = format!("SELECT COALESCE(SUM(amount - refunded), 0) AS total FROM {} WHERE project_id = $1",
+                                client.table(table::pledge));
+
+        client.transaction(|| {
+            let before = try!(Project::get(client, project_name));
+            let mut daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::String(project_name.to_owned())]));
+            let project = match daos.pop() {
+                Some(dao) => Project::from_dao(&dao),
+                None => return Err(From::from(validate::Error::ProjectClosed)),
+            };
+
+            let mut daos = try!(client.db().execute_sql_with_return(&total_sql, &vec![Value::I32(project.project_id)]));
+            let total: f64 = daos.pop().map_or(0f64, |dao| dao.get("total"));
+
+            try!(Audit::record(client, "close", table::project, &project.project_id.to_string(),
+                               Some(before.to_json()), Some(project.to_json())));
+            try!(Event::record(client, DomainEvent::ProjectClosed(event::ProjectClosed {
+                project_id: project.project_id,
+            })));
+
+            let outcome = if total >= project.goal { webhook::PROJECT_SUCCEEDED } else { webhook::PROJECT_FAILED };
+            try!(Webhook::enqueue_project(client, outcome, &project));
+            try!(Mail::enqueue_outcome(client, &project, total));
+            Ok((project, total))
+        })
+    }
+
     /// Returns a list of all projects on Kickstarter.
     pub fn list_all(client: &Client) -> Result<Vec<Project>> {
         let results: Vec<Project> = try!(Query::select_all()
@@ -82,6 +187,50 @@ impl Project {
         Ok(results)
     }
 
+    /// Returns one page of projects, oldest first, along with how many projects there are in all.
+    pub fn list_page(client: &Client, offset: usize, limit: usize) -> Result<(Vec<Project>, usize)> {
+        let count_sql = format!("SELECT COUNT(*) AS total FROM {}", client.table(table::project));
+        let page_sql = format!("SELECT * FROM {} ORDER BY project_id LIMIT {} OFFSET {}",
+                               client.table(table::project), limit, offset);
+
+        let mut daos = try!(client.db().execute_sql_with_return(&count_sql, &vec![]));
+        let total: i64 = daos.pop().map_or(0, |dao| dao.get("total"));
+        let daos = try!(client.db().execute_sql_with_return(&page_sql, &vec![]));
+        Ok((daos.iter().map(Project::from_dao).collect(), total as usize))
+    }
+
+    /// Returns one page of the pledges towards a project, in the order they were made, along with
+    /// how many pledges it has in all. Each pledge comes with its backer.
+    pub fn list_pledges_page(client: &Client, project_name: &str, offset: usize,
+                             limit: usize) -> Result<(Vec<Pledge>, usize)> {
+        let project = try!(Project::get(client, project_name));
+        let pid = vec![Value::I32(project.project_id)];
+
+        let count_sql = format!("SELECT COUNT(*) AS total FROM {} WHERE project_id = $1",
+                                client.table(table::pledge));
+        let page_sql = format!("SELECT * FROM {} WHERE project_id = $1 \
+                                ORDER BY date_created, user_id LIMIT {} OFFSET {}",
+                               client.table(table::pledge), limit, offset);
+
+        let mut daos = try!(client.db().execute_sql_with_return(&count_sql, &pid));
+        let total: i64 = daos.pop().map_or(0, |dao| dao.get("total"));
+        let daos = try!(client.db().execute_sql_with_return(&page_sql, &pid));
+        let mut pledges: Vec<Pledge> = daos.iter().map(Pledge::from_dao).collect();
+
+        let ids: Vec<String> = pledges.iter().map(|p| p.user_id.to_string()).collect();
+        if !ids.is_empty() {
+            let users_sql = format!("SELECT * FROM {} WHERE user_id IN ({})", client.table(table::user), ids.join(", "));
+            let users: Vec<User> = try!(client.db().execute_sql_with_return(&users_sql, &vec![]))
+                .iter().map(User::from_dao).collect();
+            for pledge in &mut pledges {
+                pledge.user = users.iter().find(|u| u.user_id == pledge.user_id).cloned();
+                pledge.project = Some(project.clone());
+            }
+        }
+
+        Ok((pledges, total as usize))
+    }
+
     /// Retrieves a list of all pledges for a given project. Returns a list of 
     /// all pledges with user information, as well as the overall project goal amount.
     pub fn list_pledges(client: &Client, project_name: &str) -> Result<(Vec<Pledge>, f64)> {
@@ -0,0 +1,228 @@
+//! Module for returning money from pledges, through refunds or chargebacks.
+pub use models::Refund;
+
+use {validate, Client, Result};
+use db::{column, table};
+use event::{self, DomainEvent};
+use models::{Audit, Event, Pledge, Project, User, Webhook};
+use webhook;
+
+use rustc_serialize::json::ToJson;
+use rustorm::dao::{IsDao, Value};
+use rustorm::query::Query;
+
+use std::convert::From;
+use std::str::FromStr;
+
+/// How money was returned to a backer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// Returned at the request of the backer or the platform.
+    Refund,
+    /// Reversed by the payment processor after a dispute.
+    Chargeback,
+}
+
+/// Why money was returned to a backer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reason {
+    /// The backer asked for their money back.
+    Requested,
+    /// The pledge was made more than once.
+    Duplicate,
+    /// The pledge was made with a stolen or fraudulent card.
+    Fraudulent,
+    /// The project was cancelled by its creator.
+    Cancelled,
+    /// The cardholder disputed the charge with their bank.
+    Disputed,
+    /// Anything else.
+    Other,
+}
+
+impl Kind {
+    /// Returns the code stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Kind::Refund     => "refund",
+            Kind::Chargeback => "chargeback",
+        }
+    }
+}
+
+impl Reason {
+    /// Returns the code stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Reason::Requested  => "requested",
+            Reason::Duplicate  => "duplicate",
+            Reason::Fraudulent => "fraudulent",
+            Reason::Cancelled  => "cancelled",
+            Reason::Disputed   => "disputed",
+            Reason::Other      => "other",
+        }
+    }
+}
+
+impl FromStr for Reason {
+    type Err = validate::Error;
+
+    fn from_str(s: &str) -> validate::Result<Reason> {
+        match s {
+            "requested"  => Ok(Reason::Requested),
+            "duplicate"  => Ok(Reason::Duplicate),
+            "fraudulent" => Ok(Reason::Fraudulent),
+            "cancelled"  => Ok(Reason::Cancelled),
+            "disputed"   => Ok(Reason::Disputed),
+            "other"      => Ok(Reason::Other),
+            _            => Err(validate::Error::InvalidReason(s.to_owned())),
+        }
+    }
+}
+
+impl Refund {
+
+    /// Refunds a user's pledge towards a project. If no amount is provided,
+    /// everything that remains of the pledge is refunded.
+    pub fn refund(client: &Client, user: &str, project_name: &str,
+                  amount: Option<f64>, reason: Reason) -> Result<Refund> {
+
+        let filter = "pr.name = $1 AND us.name = $2";
+        let params = vec![Value::String(project_name.to_owned()), Value::String(user.to_owned())];
+
+        client.transaction(|| {
+            let (pledge, user) = try!(Refund::lock_pledge(client, filter, params));
+            let mut refund = try!(Refund::record(client, &pledge, Kind::Refund, amount, reason, None));
+            refund.user = Some(user);
+            Ok(refund)
+        })
+    }
+
+    /// Records a chargeback reported by a payment processor against the card used
+    /// to back a project. If no amount is provided, the entire remaining pledge is charged back.
+    pub fn chargeback(client: &Client, project_name: &str, card: &str, amount: Option<f64>,
+                      reason: Reason, reference: Option<&str>) -> Result<Refund> {
+
+        let filter = "pr.name = $1 AND pl.card = $2";
+        let params = vec![Value::String(project_name.to_owned()), Value::String(card.to_owned())];
+
+        client.transaction(|| {
+            let (pledge, user) = try!(Refund::lock_pledge(client, filter, params));
+            let mut refund = try!(Refund::record(client, &pledge, Kind::Chargeback, amount, reason, reference));
+            refund.user = Some(user);
+            Ok(refund)
+        })
+    }
+
+    /// Retrieves and locks a pledge for the rest of the transaction, along with its backer.
+    fn lock_pledge(client: &Client, filter: &str, params: Vec<Value>) -> Result<(Pledge, User)> {
+        let sql = format!("SELECT us.*, pl.* FROM {} \
+                           INNER JOIN {} ON pr.project_id = pl.project_id \
+                           INNER JOIN {} ON us.user_id = pl.user_id \
+                           WHERE {} FOR UPDATE OF pl",
+                          client.table_abbr(table::pledge),
+                          client.table_abbr(table::project),
+                          client.table_abbr(table::user),
+                          filter);
+
+        let mut daos = try!(client.db().execute_sql_with_return(&sql, &params));
+        match daos.pop() {
+            Some(dao) => Ok((Pledge::from_dao(&dao), User::from_dao(&dao))),
+            None => Err(From::from(validate::Error::PledgeDoesNotExist)),
+        }
+    }
+
+    /// Deducts the amount from the pledge and inserts the matching record.
+    fn record(client: &Client, pledge: &Pledge, kind: Kind, amount: Option<f64>,
+              reason: Reason, reference: Option<&str>) -> Result<Refund> {
+
+        let remaining = pledge.net_amount();
+        if remaining < 0.01 {
+            return Err(From::from(validate::Error::ExceedsPledge));
+        }
+
+        // Round the remainder the same way as pledges, so that full refunds match exactly.
+        let remaining = try!(validate::currency(remaining));
+        let amount = match amount {
+            Some(amount) => try!(validate::currency(amount)),
+            None => remaining,
+        };
+
+        if amount > remaining {
+            return Err(From::from(validate::Error::ExceedsPledge));
+        }
+
+        // Amounts are stored as doubles, so the new total is rounded to cents and capped at the pledge;
+        // otherwise 0.10 + 0.20 would exceed a 0.30 pledge and fail pledge_refunded_chk.
+        let sql = format!("UPDATE {} SET refunded = LEAST(amount, round((refunded + $1)::numeric, 2)::float8) \
+                           WHERE user_id = $2 AND project_id = $3 RETURNING *",
+                          client.table(table::pledge));
+
+        let mut daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::F64(amount),
+                                                                              Value::I32(pledge.user_id),
+                                                                              Value::I32(pledge.project_id)]));
+
+        let after = match daos.pop() {
+            Some(dao) => Pledge::from_dao(&dao),
+            None => return Err(From::from(validate::Error::PledgeDoesNotExist)),
+        };
+
+        try!(Audit::record(client, kind.as_str(), table::pledge, &pledge.key(),
+                           Some(pledge.to_json()), Some(after.to_json())));
+        try!(Webhook::enqueue_pledge(client, webhook::PLEDGE_REFUNDED, &after, amount));
+
+        let mut query = Query::insert();
+        query.set(column::user_id, &pledge.user_id)
+            .set(column::project_id, &pledge.project_id)
+            .set(column::amount, &amount)
+            .set(column::kind, &kind.as_str())
+            .set(column::reason, &reason.as_str());
+
+        if let Some(ref reference) = reference {
+            query.set(column::reference, reference);
+        }
+
+        let refund: Refund = try!(query
+            .into_table(&client.table(table::refund))
+            .return_all()
+            .collect_one(client.db()));
+
+        try!(Event::record(client, DomainEvent::PledgeRefunded(event::PledgeRefunded {
+            refund_id: refund.refund_id,
+            user_id: refund.user_id,
+            project_id: refund.project_id,
+            amount: refund.amount,
+            kind: refund.kind.clone(),
+            reason: refund.reason.clone(),
+            reference: refund.reference.clone(),
+        })));
+
+        Ok(refund)
+    }
+
+    /// Returns every refund and chargeback made against a project, oldest first,
+    /// along with the user whose pledge was affected.
+    pub fn list(client: &Client, project_name: &str) -> Result<Vec<Refund>> {
+        let pid = try!(Project::get_id(client, project_name));
+
+        let sql = format!("SELECT us.*, re.* FROM {} \
+                           INNER JOIN {} ON us.user_id = re.user_id \
+                           WHERE re.project_id = $1 \
+                           ORDER BY re.refund_id",
+                          client.table_abbr(table::refund),
+                          client.table_abbr(table::user));
+
+        let daos = try!(client.db().execute_sql_with_return(&sql, &vec![pid]));
+
+        Ok(daos.iter().map(|dao| {
+            let mut refund = Refund::from_dao(dao);
+            refund.user = Some(User::from_dao(dao));
+            refund
+        }).collect())
+    }
+
+    /// Returns a reference to the user whose pledge was refunded.
+    pub fn get_user(&self) -> &User {
+        self.user.as_ref().unwrap()
+    }
+}
@@ -0,0 +1,306 @@
+//! Module for reading `ksr run` commands, at the prompt or from a script.
+//!
+//! Lines are split into words the way a shell would: whitespace separates words, single
+//! quotes keep everything literally, double quotes allow `\"` and `\\` escapes, and a
+//! backslash outside quotes escapes the next character. A `#` at the start of a word
+//! begins a comment that runs to the end of the line. Command names, options and help
+//! are read from the docopt usage text, so that they can't drift from the parser.
+use {validate, Client, Result};
+use models::{Project, User};
+
+use chrono::Local;
+use rustyline;
+use rustyline::completion;
+
+use std::cell::RefCell;
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::mem;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// Environment variable naming the history file, used instead of `~/.ksr_history`.
+pub const HISTORY_VAR: &'static str = "KSR_HISTORY";
+
+/// Completes command names, options, and the names of existing projects and users.
+pub struct Completer {
+    commands: Vec<String>,
+    options: Vec<String>,
+    names: Rc<RefCell<Vec<String>>>,
+}
+
+impl Completer {
+
+    /// Creates a completer for the commands and options in a docopt usage text.
+    /// The names are shared, so that they can be refreshed as projects and users are created.
+    pub fn new(usage: &str, names: Rc<RefCell<Vec<String>>>) -> Completer {
+        let mut commands = commands(usage);
+        commands.push("help".to_owned());
+        commands.sort();
+
+        Completer {
+            commands: commands,
+            options: options(usage),
+            names: names,
+        }
+    }
+}
+
+impl completion::Completer for Completer {
+    fn complete(&self, line: &str, pos: usize) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+        let before: Vec<&str> = line[..start].split_whitespace().collect();
+
+        let names = self.names.borrow();
+        let candidates = match before.first() {
+            None => &self.commands,
+            Some(&"help") if before.len() == 1 => &self.commands,
+            _ if word.starts_with("-") => &self.options,
+            _ => &*names,
+        };
+
+        Ok((start, candidates.iter().filter(|c| c.starts_with(word)).cloned().collect()))
+    }
+}
+
+/// Appends the commands of a session to a script that `ksr run` can replay.
+pub struct Recorder {
+    file: File,
+    timestamps: bool,
+    outcomes: bool,
+}
+
+impl Recorder {
+
+    /// Opens a script to append to, creating it if needed. With `timestamps`, each command
+    /// is preceded by a comment saying when it ran. With `outcomes`, failed commands are
+    /// recorded too, commented out along with their error, so that replays skip them.
+    pub fn open(path: &str, timestamps: bool, outcomes: bool) -> io::Result<Recorder> {
+        let file = try!(OpenOptions::new().create(true).append(true).open(path));
+        Ok(Recorder {
+            file: file,
+            timestamps: timestamps,
+            outcomes: outcomes,
+        })
+    }
+
+    /// Records a command, given as words, and whether it succeeded.
+    /// API tokens are left out, since they wouldn't be valid when the script is replayed.
+    pub fn record(&mut self, words: &[String], error: Option<&str>) -> io::Result<()> {
+        if error.is_some() && !self.outcomes {
+            return Ok(());
+        }
+
+        let mut out = String::new();
+        if self.timestamps {
+            out.push_str(&format!("# {}\n", Local::now().format("%Y-%m-%d %H:%M:%S")));
+        }
+
+        let mut skip = false;
+        let line: Vec<String> = words.iter()
+            .filter(|word| {
+                let token = skip || word.starts_with("--token=");
+                skip = *word == "--token";
+                !token && !skip
+            })
+            .map(|word| quote(word))
+            .collect();
+
+        match error {
+            None => out.push_str(&format!("{}\n", line.join(" "))),
+            Some(error) => out.push_str(&format!("# failed: {}\n#   {}\n", line.join(" "), error.replace('\n', " "))),
+        }
+
+        try!(self.file.write_all(out.as_bytes()));
+        self.file.flush()
+    }
+}
+
+/// Quotes a word so that `split` reads it back unchanged.
+pub fn quote(word: &str) -> String {
+    let plain = !word.is_empty() && !word.starts_with('#') &&
+        word.chars().all(|c| !c.is_whitespace() && c != '\'' && c != '"' && c != '\\');
+
+    if plain {
+        word.to_owned()
+    } else {
+        format!("'{}'", word.replace("'", "'\\''"))
+    }
+}
+
+/// Splits a line into words, following shell quoting rules and dropping any comment.
+pub fn split(line: &str) -> validate::Result<Vec<String>> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(validate::Error::UnclosedQuote),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) if c == '"' || c == '\\' => word.push(c),
+                            Some(c) => { word.push('\\'); word.push(c); }
+                            None => return Err(validate::Error::UnclosedQuote),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(validate::Error::UnclosedQuote),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(c) = chars.next() {
+                    word.push(c);
+                }
+            }
+            '#' if !in_word => break,
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(mem::replace(&mut word, String::new()));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Commands that can't be run from a script. Most use connections of their own, which `--atomic`
+/// couldn't roll back, and the rest run until they're stopped.
+pub const UNSCRIPTABLE: &'static [&'static str] = &["run", "rpc", "watch", "serve", "import", "seed",
+                                                    "bench", "replay", "restore"];
+
+/// Returns true if the command that a line of words starts with can be run from a script.
+pub fn scriptable(words: &[String]) -> bool {
+    words.first().map_or(true, |command| !UNSCRIPTABLE.contains(&&command[..]))
+}
+
+/// Returns the names of the commands in a docopt usage text, in order.
+pub fn commands(usage: &str) -> Vec<String> {
+    let mut commands: Vec<String> = vec![];
+    for line in section(usage, "Usage:") {
+        if let Some(command) = line.split_whitespace().nth(1) {
+            if command.starts_with(char::is_alphabetic) && !commands.iter().any(|c| c == command) {
+                commands.push(command.to_owned());
+            }
+        }
+    }
+    commands
+}
+
+/// Returns the long options described in a docopt usage text, such as `--token=`.
+pub fn options(usage: &str) -> Vec<String> {
+    let mut options = vec![];
+    for line in section(usage, "Options:") {
+        for word in line.split_whitespace().take_while(|w| w.starts_with("-")) {
+            if word.starts_with("--") {
+                options.push(word.find('=').map_or(word.to_owned(), |i| word[..i + 1].to_owned()));
+            }
+        }
+    }
+    options
+}
+
+/// Returns the usage, description and options of a command, without the `ksr` prefix,
+/// or None if the usage text doesn't mention it.
+pub fn help(usage: &str, command: &str) -> Option<String> {
+    let lines: Vec<&str> = section(usage, "Usage:").into_iter()
+        .filter(|line| line.split_whitespace().nth(1) == Some(command))
+        .collect();
+
+    if lines.is_empty() {
+        return None;
+    }
+
+    let mut help = String::from("Usage:\n");
+    for line in &lines {
+        help.push_str(&format!("    {}\n", line.trim().trim_left_matches("ksr").trim()));
+    }
+
+    for line in section(usage, "Commands:") {
+        if line.split_whitespace().next() == Some(command) {
+            help.push_str(&format!("\n{}\n", line.trim()[command.len()..].trim()));
+        }
+    }
+
+    // Options that the command accepts, along with the lines that continue their descriptions.
+    let flags: Vec<&str> = lines.iter()
+        .flat_map(|line| line.split_whitespace())
+        .map(|word| word.trim_matches(|c: char| c == '[' || c == ']' || c == '(' || c == ')' || c == '|'))
+        .filter(|word| word.starts_with("--"))
+        .map(|word| word.split('=').next().unwrap())
+        .collect();
+
+    let mut described = false;
+    let mut accepted = false;
+    for line in section(usage, "Options:") {
+        let trimmed = line.trim();
+        if trimmed.starts_with("-") {
+            accepted = trimmed.split_whitespace()
+                .take_while(|w| w.starts_with("-"))
+                .any(|w| flags.contains(&w.split('=').next().unwrap()));
+        }
+
+        if accepted {
+            if !described {
+                help.push_str("\nOptions:\n");
+                described = true;
+            }
+            help.push_str(&format!("    {}\n", trimmed));
+        }
+    }
+
+    Some(help)
+}
+
+/// Returns the names of every project and user, for completion.
+pub fn names(client: &Client) -> Result<Vec<String>> {
+    let mut names: Vec<String> = try!(Project::list_all(client)).into_iter().map(|p| p.name).collect();
+    names.extend(try!(User::list_all(client)).into_iter().map(|u| u.name));
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+/// Returns the path of the history file, from `KSR_HISTORY` or the home directory.
+pub fn history_path() -> Option<PathBuf> {
+    match env::var(HISTORY_VAR) {
+        Ok(path) => Some(PathBuf::from(path)),
+        Err(_) => env::home_dir().map(|home| home.join(".ksr_history")),
+    }
+}
+
+/// Returns the lines of a section of a docopt usage text, after its heading.
+fn section<'a>(usage: &'a str, heading: &str) -> Vec<&'a str> {
+    usage.lines()
+        .skip_while(|line| line.trim() != heading)
+        .skip(1)
+        .take_while(|line| line.starts_with(char::is_whitespace) || line.trim().is_empty())
+        .filter(|line| !line.trim().is_empty())
+        .collect()
+}
@@ -0,0 +1,74 @@
+//! Module for a project's reward tiers.
+//!
+//! A tier has a title and the least a backer must pledge to receive it. Backers don't choose
+//! a tier: each is owed the richest one that what remains of their pledge covers, so a partial
+//! refund can move a backer down a tier, and a pledge below every minimum earns none.
+pub use models::Reward;
+
+use {validate, Client, Result};
+use db::{column, table};
+use event::{self, DomainEvent};
+use models::{Audit, Event, Project};
+
+use rustc_serialize::json::ToJson;
+use rustorm::dao::IsDao;
+use rustorm::query::Query;
+
+use std::convert::From;
+
+impl Reward {
+
+    /// Offers a new reward tier on a project that is still open.
+    pub fn create(client: &Client, project_name: &str, title: &str, minimum: f64) -> Result<Reward> {
+        try!(validate::not_blank(title));
+        try!(validate::single_line(title));
+        try!(validate::length(title, 1, 100));
+        let minimum = try!(validate::currency(minimum));
+
+        client.transaction(|| {
+            let project = try!(Project::get(client, project_name));
+            if project.date_closed.is_some() {
+                return Err(From::from(validate::Error::ProjectClosed));
+            }
+            if try!(Reward::list(client, project_name)).iter().any(|reward| reward.title == title) {
+                return Err(From::from(validate::Error::RewardExists));
+            }
+
+            let reward: Reward = try!(Query::insert()
+                .set(column::project_id, &project.project_id)
+                .set(column::title, &title)
+                .set(column::minimum, &minimum)
+                .into_table(&client.table(table::reward))
+                .return_all()
+                .collect_one(client.db()));
+
+            try!(Audit::record(client, "create", table::reward, &reward.reward_id.to_string(),
+                               None, Some(reward.to_json())));
+            try!(Event::record(client, DomainEvent::RewardAdded(event::RewardAdded {
+                reward_id: reward.reward_id,
+                project_id: reward.project_id,
+                title: reward.title.clone(),
+                minimum: reward.minimum,
+            })));
+            Ok(reward)
+        })
+    }
+
+    /// Returns a project's reward tiers, cheapest first.
+    pub fn list(client: &Client, project_name: &str) -> Result<Vec<Reward>> {
+        let pid = try!(Project::get_id(client, project_name));
+
+        let sql = format!("SELECT * FROM {} WHERE project_id = $1 ORDER BY minimum, reward_id",
+                          client.table(table::reward));
+
+        let daos = try!(client.db().execute_sql_with_return(&sql, &vec![pid]));
+        Ok(daos.iter().map(Reward::from_dao).collect())
+    }
+
+    /// Returns the richest of the tiers, listed cheapest first, that an amount covers.
+    pub fn tier(rewards: &[Reward], amount: f64) -> Option<&Reward> {
+        // Amounts left after refunds can be a hair under the cents they stand for.
+        let amount = (amount * 100f64).round() / 100f64;
+        rewards.iter().filter(|reward| reward.minimum <= amount).last()
+    }
+}
@@ -0,0 +1,781 @@
+//! Module for driving Kickstarter with JSON-RPC 2.0 requests, one per line.
+//!
+//! Every `ksr` command is a method that takes its arguments by name, and answers with
+//! structured JSON instead of the lines printed by the command line:
+//!
+//! ```text
+//! --> {"jsonrpc": "2.0", "id": 1, "method": "back", "params": {"name": "Sensel_Control_Pad", "card": "4298708533045499", "amount": 300}}
+//! <-- {"id":1,"jsonrpc":"2.0","result":{"amount":300.0,"card":"************5499",...}}
+//! ```
+//!
+//! Methods that act on a user's behalf take a `token` parameter, falling back to the token
+//! returned by the session's last `login`. Passwords for `register` and `login` are passed
+//! as a `password` parameter rather than prompted for.
+//!
+//! Malformed requests are answered with the standard JSON-RPC error codes. Errors raised by
+//! the library are answered with the HTTP status code that the REST API would have used,
+//! such as 404 for a missing project or 403 for a missing role. Requests without an `id`
+//! are notifications, and are never answered.
+//!
+//! `watch`, and `graphql` subscriptions, answer as soon as they start listening, then send
+//! each change as a notification (`notice` or `graphql`) carrying the request's `id`.
+//! They hold the session until the output is closed.
+//!
+//! Methods that read or write files on the command line take or return their contents instead:
+//! `import` takes the CSV as a `csv` parameter and lists its rejected rows in the result, `dump`
+//! returns the script or snapshot, and `export-backers` returns the backers as JSON. `schema check`
+//! answers with the differences it found rather than failing.
+//!
+//! A few commands are only available from the command line:
+//!
+//! * `run` and `test` read scripts of command lines, parse them with the command line's own
+//!   argument parser and compare printed output; an embedding program sends requests instead.
+//! * `restore` only loads into a freshly built schema, which has no accounts to authenticate
+//!   anyone with, so it belongs to the same command line bootstrap as `--build`.
+//! * `rpc` is the session itself.
+use {api, bench, db, graphql, import, output, seed, validate, Client, Error, Result};
+use account::{Principal, Role};
+use audit::Filter;
+use config::Config;
+use graphql::parser::{self, OperationKind};
+use import::Mapping;
+use mail::Kind;
+use notify::Notice;
+use output::{audit_json, comment_json, delivery_json, funding_json, refund_json, thread_json};
+use refund::Reason;
+
+use hyper::server::Listening;
+use rustc_serialize::Decodable;
+use rustc_serialize::json::{self, Json, ToJson};
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+/// Invalid JSON was received.
+pub const PARSE_ERROR:      i64 = -32700;
+/// The JSON sent is not a valid request object.
+pub const INVALID_REQUEST:  i64 = -32600;
+/// The method does not exist.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// The method's parameters are missing or have the wrong types.
+pub const INVALID_PARAMS:   i64 = -32602;
+
+/// Every method that can be called, in the order of `ksr --help`.
+pub const METHODS: &'static [&'static str] = &[
+    "register", "login", "logout", "whoami", "project", "back", "list", "backer", "listall",
+    "watch", "serve", "graphql", "close", "cancel", "refund", "chargeback", "refunds", "replay",
+    "email", "ship-to", "mute", "unmute", "unsubscribe", "mail", "hook", "unhook", "hooks", "deliver",
+    "deliveries", "audit", "comment", "reply", "edit", "delete", "hide", "unhide", "flag",
+    "comments", "restrict", "unrestrict", "role", "dump", "import", "seed", "bench", "reward",
+    "rewards", "export-backers", "schema check", "config check",
+];
+
+/// A connection to an embedding program, which remembers who logged in.
+pub struct Session<'a> {
+    client: &'a Client,
+    /// The configuration file, used to open clients for `serve` and checked by `config check`.
+    config: String,
+    token: Option<String>,
+    /// Servers started by `serve`. Dropping the session waits on them, so they keep serving
+    /// after the input ends.
+    servers: Vec<Listening>,
+}
+
+/// Parameters naming a user and their password.
+#[derive(RustcDecodable)]
+struct Credentials { user: String, password: String }
+
+/// Parameters of `register`.
+#[derive(RustcDecodable)]
+struct Registration { user: String, address: String, password: String }
+
+/// Parameters of `logout`.
+#[derive(RustcDecodable)]
+struct Logout { all: Option<bool> }
+
+/// Parameters naming a project.
+#[derive(RustcDecodable)]
+struct Named { name: String }
+
+/// Parameters naming a user.
+#[derive(RustcDecodable)]
+struct Backer { user: String }
+
+/// Parameters of `project`.
+#[derive(RustcDecodable)]
+struct NewProject { name: String, amount: f64 }
+
+/// Parameters of `back`.
+#[derive(RustcDecodable)]
+struct NewPledge { name: String, card: String, amount: f64 }
+
+/// Parameters of `serve`.
+#[derive(RustcDecodable)]
+struct Serve { bind: Option<String> }
+
+/// Parameters of `graphql`.
+#[derive(RustcDecodable)]
+struct Query { query: String, variables: Option<Json>, operation: Option<String> }
+
+/// Parameters of `refund`.
+#[derive(RustcDecodable)]
+struct NewRefund { user: String, name: String, amount: Option<f64>, reason: Option<String> }
+
+/// Parameters of `chargeback`.
+#[derive(RustcDecodable)]
+struct NewChargeback { name: String, card: String, amount: Option<f64>, reason: Option<String>,
+                       reference: Option<String> }
+
+/// Parameters of `replay`.
+#[derive(RustcDecodable)]
+struct Replay { schema: String, until: Option<String> }
+
+/// Parameters of `email`.
+#[derive(RustcDecodable)]
+struct Email { address: Option<String> }
+
+/// Parameters of `ship-to`.
+#[derive(RustcDecodable)]
+struct ShipTo { shipping_address: Option<String> }
+
+/// Parameters of `reward`.
+#[derive(RustcDecodable)]
+struct NewReward { name: String, title: String, amount: f64 }
+
+/// Parameters of `mute` and `unmute`.
+#[derive(RustcDecodable)]
+struct Mute { kind: String }
+
+/// Parameters of `unsubscribe`.
+#[derive(RustcDecodable)]
+struct Unsubscribe { token: String, kind: Option<String> }
+
+/// Parameters of `hook`.
+#[derive(RustcDecodable)]
+struct Hook { url: String, secret: String, project: Option<String> }
+
+/// Parameters naming a webhook or comment.
+#[derive(RustcDecodable)]
+struct Id { id: i32 }
+
+/// Parameters of `audit`.
+#[derive(RustcDecodable)]
+struct Search { entity: Option<String>, entity_id: Option<String>, actor: Option<String>,
+                since: Option<String>, until: Option<String> }
+
+/// Parameters of `comment`.
+#[derive(RustcDecodable)]
+struct NewComment { name: String, body: String }
+
+/// Parameters of `reply` and `edit`.
+#[derive(RustcDecodable)]
+struct CommentBody { id: i32, body: String }
+
+/// Parameters of `role`.
+#[derive(RustcDecodable)]
+struct SetRole { user: String, role: String }
+
+/// Parameters of `dump`.
+#[derive(RustcDecodable)]
+struct Dump { snapshot: Option<bool> }
+
+/// Parameters of `import`.
+#[derive(RustcDecodable)]
+struct NewImport { kind: String, csv: String, map: Option<String>, skip_rejected: Option<bool>,
+                   dry_run: Option<bool> }
+
+/// Parameters of `seed`.
+#[derive(RustcDecodable)]
+struct Seed { projects: Option<usize>, users: Option<usize>, pledges: Option<usize>, seed: Option<u64> }
+
+/// Parameters of `bench`.
+#[derive(RustcDecodable)]
+struct Bench { schema: String, workers: Option<usize>, ops: Option<usize>, mix: Option<String>,
+               projects: Option<usize>, users: Option<usize>, seed: Option<u64> }
+
+impl<'a> Session<'a> {
+
+    /// Creates a session. The token, if any, is used until someone logs in.
+    pub fn new(client: &'a Client, config: &str, token: Option<String>) -> Session<'a> {
+        Session { client: client, config: config.to_owned(), token: token, servers: vec![] }
+    }
+
+    /// Answers each line of the input until it ends, writing one JSON message per line.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, output: &mut W) -> io::Result<()> {
+        for line in input.lines() {
+            let line = try!(line);
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            try!(self.handle(&line, &mut |message: Json| {
+                try!(writeln!(output, "{}", message));
+                output.flush()
+            }));
+        }
+        Ok(())
+    }
+
+    /// Answers a single line, which holds a request or a batch of requests.
+    /// Responses and notifications are passed to `out` as they are ready.
+    pub fn handle(&mut self, line: &str, out: &mut FnMut(Json) -> io::Result<()>) -> io::Result<()> {
+        let json = match Json::from_str(line) {
+            Ok(json) => json,
+            Err(err) => return out(error(&Json::Null, PARSE_ERROR, &err.to_string())),
+        };
+
+        match json {
+            Json::Array(ref requests) if requests.is_empty() => {
+                out(error(&Json::Null, INVALID_REQUEST, "The batch is empty."))
+            }
+
+            // Batches are answered with an array of every response that isn't to a notification.
+            Json::Array(requests) => {
+                let mut responses = vec![];
+                for request in requests {
+                    try!(self.request(request, &mut |message: Json| {
+                        if message.find("id").is_some() {
+                            responses.push(message);
+                            Ok(())
+                        } else {
+                            out(message)
+                        }
+                    }));
+                }
+
+                if responses.is_empty() { Ok(()) } else { out(Json::Array(responses)) }
+            }
+
+            request => self.request(request, out),
+        }
+    }
+
+    /// Answers a single request object.
+    fn request(&mut self, request: Json, out: &mut FnMut(Json) -> io::Result<()>) -> io::Result<()> {
+        let id = request.find("id").cloned();
+        let method = request.find("method").and_then(|m| m.as_string()).map(|m| m.to_owned());
+        let params = request.find("params").cloned().unwrap_or(Json::Object(BTreeMap::new()));
+
+        let method = match method {
+            Some(ref method) if request.find("jsonrpc") == Some(&Json::String("2.0".to_owned())) => method,
+            _ => return out(error(&id.unwrap_or(Json::Null), INVALID_REQUEST,
+                                  "Requests need a \"jsonrpc\": \"2.0\" member and a method name.")),
+        };
+
+        if !params.is_object() {
+            return respond(id, Err((INVALID_PARAMS, "Parameters must be passed by name.".to_owned())), out);
+        }
+
+        if !METHODS.contains(&&method[..]) {
+            return respond(id, Err((METHOD_NOT_FOUND, format!("There is no method named \"{}\".", method))), out);
+        }
+
+        let result = self.call(method, &params, id.as_ref(), out).map_err(|err| (code(&err), err.to_string()));
+        match result {
+            Ok(None) => Ok(()),
+            Ok(Some(result)) => respond(id, Ok(result), out),
+            Err(err) => respond(id, Err(err), out),
+        }
+    }
+
+    /// Calls a method. Returns None if the method already sent its response.
+    fn call(&mut self, method: &str, params: &Json, id: Option<&Json>,
+            out: &mut FnMut(Json) -> io::Result<()>) -> Result<Option<Json>> {
+
+        let client = self.client;
+        client.act_as(None);
+        let result = match method {
+            "register" => {
+                let p: Registration = try!(decode(params));
+                api::user_json(&try!(client.register(&p.user, &p.address, &p.password)))
+            }
+
+            "login" => {
+                let p: Credentials = try!(decode(params));
+                let (principal, token) = try!(client.login(&p.user, &p.password));
+
+                // Later requests in the same session act as this user.
+                self.token = Some(token.clone());
+                object(vec![("token", token.to_json()), ("user", api::principal_json(&principal))])
+            }
+
+            "logout" => {
+                let p: Logout = try!(decode(params));
+                let token = try!(self.token(params).ok_or(validate::Error::NotAuthenticated));
+                let revoked = if p.all.unwrap_or(false) {
+                    let principal = try!(client.authenticate(&token));
+                    try!(client.logout_everywhere(&principal)).len()
+                } else {
+                    try!(client.logout(&token));
+                    1
+                };
+
+                if self.token.as_ref() == Some(&token) || p.all.unwrap_or(false) {
+                    self.token = None;
+                }
+                object(vec![("revoked", revoked.to_json())])
+            }
+
+            "whoami" => {
+                api::principal_json(&try!(self.principal(params)))
+            }
+
+            "project" => {
+                let principal = try!(self.principal(params));
+                let p: NewProject = try!(decode(params));
+                api::project_json(&try!(client.create_project(&principal, &p.name, p.amount)))
+            }
+
+            "back" => {
+                let principal = try!(self.principal(params));
+                let p: NewPledge = try!(decode(params));
+                let pledge = try!(client.back_project(&principal, &p.name, &p.card, p.amount));
+                api::pledge_json(&pledge, principal.name(), &p.name)
+            }
+
+            "list" => {
+                let p: Named = try!(decode(params));
+                let (pledges, goal) = try!(client.list_backers(&p.name));
+                output::backers_json(&p.name, &pledges, goal)
+            }
+
+            "backer" => {
+                let p: Backer = try!(decode(params));
+                let pledges = try!(client.list_backed_projects(&p.user));
+                output::backed_json(&p.user, &pledges)
+            }
+
+            "listall" => {
+                Json::Array(try!(client.list_projects()).iter().map(api::project_json).collect())
+            }
+
+            "watch" => {
+                let p: Named = try!(decode(params));
+
+                // Subscribe before reading the current total, so that no pledge slips in between.
+                let subscription = try!(client.subscribe());
+                let (pledges, goal) = try!(client.list_backers(&p.name));
+                let mut total = pledges.iter().fold(0f64, |total, p| total + p.net_amount());
+
+                try!(respond(id.cloned(), Ok(funding_json(&p.name, total, goal)), out));
+                for notice in subscription.notices() {
+                    let notice = try!(notice);
+                    if notice.project() != p.name || notice.user().is_none() {
+                        continue;
+                    }
+
+                    total += notice.funding_change();
+                    let mut change = funding_json(&p.name, total, goal);
+                    if let Json::Object(ref mut map) = change {
+                        map.insert("event".to_owned(), event(&notice).to_json());
+                        map.insert("user".to_owned(), notice.user().map(|u| u.to_owned()).to_json());
+                        map.insert("amount".to_owned(), notice.funding_change().abs().to_json());
+                    }
+                    try!(out(notification("notice", id, change)));
+                }
+                return Ok(None);
+            }
+
+            "serve" => {
+                let p: Serve = try!(decode(params));
+                let bind = p.bind.unwrap_or("127.0.0.1:3000".to_owned());
+                self.servers.push(try!(api::serve_schema(&self.config, &self.client.schema, &bind)));
+                object(vec![("bind", bind.to_json()), ("openapi", format!("http://{}/openapi.json", bind).to_json())])
+            }
+
+            "graphql" => {
+                let p: Query = try!(decode(params));
+                let variables = p.variables.unwrap_or(Json::Null);
+                let operation = p.operation.as_ref().map(|o| &o[..]);
+
+                let document = try!(parser::parse(&p.query));
+                let subscription = document.operations.iter()
+                    .filter(|o| operation.is_none() || o.name.as_ref().map(|n| &n[..]) == operation)
+                    .any(|o| o.kind == OperationKind::Subscription);
+
+                if subscription {
+                    let stream = try!(graphql::subscribe(client, &p.query, operation, &variables));
+                    try!(respond(id.cloned(), Ok(object(vec![("subscribed", true.to_json())])), out));
+                    for response in stream {
+                        try!(out(notification("graphql", id, try!(response).to_json())));
+                    }
+                    return Ok(None);
+                }
+
+                let principal = match self.token(params) {
+                    Some(ref token) => Some(try!(client.authenticate(token))),
+                    None => None,
+                };
+                graphql::execute(client, principal.as_ref(), &p.query, operation, &variables).to_json()
+            }
+
+            "close" => {
+                let principal = try!(self.principal(params));
+                let p: Named = try!(decode(params));
+                let (project, total) = try!(client.close_project(&principal, &p.name));
+                output::closed_json(&project, total)
+            }
+
+            "cancel" => {
+                let principal = try!(self.principal(params));
+                let p: Named = try!(decode(params));
+                let pledge = try!(client.cancel_pledge(&principal, &p.name));
+                api::pledge_json(&pledge, principal.name(), &p.name)
+            }
+
+            "refund" => {
+                let principal = try!(self.principal(params));
+                let p: NewRefund = try!(decode(params));
+                let reason = try!(p.reason.as_ref().map_or(Ok(Reason::Requested), |r| r.parse()));
+                let refund = try!(client.refund_pledge(&principal, &p.user, &p.name, p.amount, reason));
+                refund_json(&refund, &p.user)
+            }
+
+            "chargeback" => {
+                let principal = try!(self.principal(params));
+                let p: NewChargeback = try!(decode(params));
+                let reason = try!(p.reason.as_ref().map_or(Ok(Reason::Disputed), |r| r.parse()));
+                let reference = p.reference.as_ref().map(|r| &r[..]);
+                let refund = try!(client.record_chargeback(&principal, &p.name, &p.card, p.amount, reason, reference));
+                refund_json(&refund, &refund.get_user().name)
+            }
+
+            "refunds" => {
+                let principal = try!(self.principal(params));
+                let p: Named = try!(decode(params));
+                let refunds = try!(client.list_refunds(&principal, &p.name));
+                Json::Array(refunds.iter().map(|r| refund_json(r, &r.get_user().name)).collect())
+            }
+
+            "replay" => {
+                let principal = try!(self.principal(params));
+                let p: Replay = try!(decode(params));
+                let until = match p.until {
+                    Some(ref t) => Some(try!(validate::timestamp(t))),
+                    None => None,
+                };
+
+                let count = try!(client.replay(&principal, &p.schema, until));
+                object(vec![("schema", p.schema.to_json()), ("events", count.to_json())])
+            }
+
+            "email" => {
+                let principal = try!(self.principal(params));
+                let p: Email = try!(decode(params));
+                let user = try!(client.set_email(&principal, p.address.as_ref().map(|a| &a[..])));
+                object(vec![("user", user.name.to_json()), ("address", user.email.to_json())])
+            }
+
+            "ship-to" => {
+                let principal = try!(self.principal(params));
+                let p: ShipTo = try!(decode(params));
+                let address = p.shipping_address.as_ref().map(|a| &a[..]);
+                let user = try!(client.set_shipping_address(&principal, address));
+                object(vec![("user", user.name.to_json()), ("shipping_address", user.shipping_address.to_json())])
+            }
+
+            "mute" | "unmute" => {
+                let principal = try!(self.principal(params));
+                let p: Mute = try!(decode(params));
+                let kind = try!(p.kind.parse::<Kind>());
+                let preference = try!(client.set_mail_preference(&principal, kind, method == "unmute"));
+                object(vec![("user", principal.name().to_json()), ("kind", preference.kind.to_json()),
+                            ("enabled", preference.enabled.to_json())])
+            }
+
+            "unsubscribe" => {
+                let p: Unsubscribe = try!(decode(params));
+                let kind = match p.kind {
+                    Some(ref kind) => Some(try!(kind.parse::<Kind>())),
+                    None => None,
+                };
+
+                let user = try!(client.unsubscribe(&p.token, kind));
+                object(vec![("user", user.name.to_json()), ("kind", kind.map(|k| k.as_str().to_owned()).to_json())])
+            }
+
+            "mail" => {
+                let principal = try!(self.principal(params));
+                Json::Array(try!(client.send_mail(&principal)).iter().map(|m| m.to_json()).collect())
+            }
+
+            "hook" => {
+                let principal = try!(self.principal(params));
+                let p: Hook = try!(decode(params));
+                let project = p.project.as_ref().map(|p| &p[..]);
+                try!(client.add_webhook(&principal, &p.url, &p.secret, project)).redacted_json()
+            }
+
+            "unhook" => {
+                let principal = try!(self.principal(params));
+                let p: Id = try!(decode(params));
+                try!(client.remove_webhook(&principal, p.id)).redacted_json()
+            }
+
+            "hooks" => {
+                let principal = try!(self.principal(params));
+                let webhooks = try!(client.list_webhooks(&principal));
+                Json::Array(webhooks.iter().map(|w| w.redacted_json()).collect())
+            }
+
+            "deliver" => {
+                let principal = try!(self.principal(params));
+                Json::Array(try!(client.deliver_webhooks(&principal)).iter().map(delivery_json).collect())
+            }
+
+            "deliveries" => {
+                let principal = try!(self.principal(params));
+                let p: Id = try!(decode(params));
+                Json::Array(try!(client.list_deliveries(&principal, p.id)).iter().map(delivery_json).collect())
+            }
+
+            "audit" => {
+                let principal = try!(self.principal(params));
+                let p: Search = try!(decode(params));
+                let filter = Filter {
+                    entity: p.entity,
+                    entity_id: p.entity_id,
+                    actor: p.actor,
+                    since: match p.since {
+                        Some(ref t) => Some(try!(validate::timestamp(t))),
+                        None => None,
+                    },
+                    until: match p.until {
+                        Some(ref t) => Some(try!(validate::timestamp(t))),
+                        None => None,
+                    },
+                };
+
+                Json::Array(try!(client.audit_log(&principal, &filter)).iter().map(audit_json).collect())
+            }
+
+            "comment" => {
+                let principal = try!(self.principal(params));
+                let p: NewComment = try!(decode(params));
+                let comment = try!(client.post_comment(&principal, &p.name, &p.body));
+                comment_json(&comment.to_json(), comment.visible_body())
+            }
+
+            "reply" | "edit" => {
+                let principal = try!(self.principal(params));
+                let p: CommentBody = try!(decode(params));
+                let comment = if method == "reply" {
+                    try!(client.reply_to_comment(&principal, p.id, &p.body))
+                } else {
+                    try!(client.edit_comment(&principal, p.id, &p.body))
+                };
+                comment_json(&comment.to_json(), comment.visible_body())
+            }
+
+            "delete" | "hide" | "unhide" | "flag" => {
+                let principal = try!(self.principal(params));
+                let p: Id = try!(decode(params));
+                let comment = match method {
+                    "delete" => try!(client.delete_comment(&principal, p.id)),
+                    "flag"   => try!(client.flag_comment(&principal, p.id, true)),
+                    _        => try!(client.hide_comment(&principal, p.id, method == "hide")),
+                };
+                comment_json(&comment.to_json(), comment.visible_body())
+            }
+
+            "comments" => {
+                let p: Named = try!(decode(params));
+                Json::Array(try!(client.list_comments(&p.name)).iter().map(thread_json).collect())
+            }
+
+            "restrict" | "unrestrict" => {
+                let principal = try!(self.principal(params));
+                let p: Named = try!(decode(params));
+                api::project_json(&try!(client.restrict_comments(&principal, &p.name, method == "restrict")))
+            }
+
+            "role" => {
+                let principal = try!(self.principal(params));
+                let p: SetRole = try!(decode(params));
+                let role = try!(p.role.parse::<Role>());
+                api::user_json(&try!(client.set_role(&principal, &p.user, role)))
+            }
+
+            "dump" => {
+                let principal = try!(self.principal(params));
+                let p: Dump = try!(decode(params));
+                if p.snapshot.unwrap_or(false) {
+                    try!(client.dump(&principal))
+                } else {
+                    object(vec![("script", try!(client.dump_script(&principal)).to_json())])
+                }
+            }
+
+            "import" => {
+                let principal = try!(self.principal(params));
+                let p: NewImport = try!(decode(params));
+                let kind = match &p.kind[..] {
+                    "projects" => import::Kind::Projects,
+                    "pledges"  => import::Kind::Pledges,
+                    _ => return Err(From::from(json::DecoderError::UnknownVariantError(p.kind.clone()))),
+                };
+                let mapping = try!(Mapping::parse(kind, p.map.as_ref().map_or("", |m| &m[..])));
+                let checked = try!(client.check_import(&principal, kind, &p.csv, &mapping));
+
+                // Nothing is imported while rows are rejected, unless they are to be skipped.
+                let skip = checked.rejected.is_empty() || p.skip_rejected.unwrap_or(false);
+                let count = if skip && !p.dry_run.unwrap_or(false) {
+                    try!(client.import(&principal, &checked))
+                } else {
+                    0
+                };
+
+                let rejected = checked.rejected.iter()
+                    .map(|r| object(vec![("line", r.line.to_json()), ("error", r.error.to_json())]))
+                    .collect();
+                object(vec![("kind", kind.as_str().to_json()), ("accepted", checked.accepted.len().to_json()),
+                            ("rejected", Json::Array(rejected)), ("imported", count.to_json())])
+            }
+
+            "seed" => {
+                let principal = try!(self.principal(params));
+                let p: Seed = try!(decode(params));
+                let defaults = seed::Options::default();
+                let options = seed::Options {
+                    projects: p.projects.unwrap_or(defaults.projects),
+                    users: p.users.unwrap_or(defaults.users),
+                    pledges: p.pledges.unwrap_or(defaults.pledges),
+                    seed: p.seed.unwrap_or(defaults.seed),
+                };
+
+                let dataset = try!(client.seed(&principal, &options));
+                object(vec![("users", dataset.users.len().to_json()), ("projects", dataset.projects.len().to_json()),
+                            ("pledges", dataset.pledges.len().to_json()), ("seed", options.seed.to_json())])
+            }
+
+            "bench" => {
+                let principal = try!(self.principal(params));
+                let p: Bench = try!(decode(params));
+                let (seeded, run) = (seed::Options::default(), bench::Options::default());
+                let seed = p.seed.unwrap_or(run.seed);
+                let dataset = seed::Options {
+                    projects: p.projects.unwrap_or(seeded.projects),
+                    users: p.users.unwrap_or(seeded.users),
+                    pledges: 0,
+                    seed: seed,
+                };
+                let options = bench::Options {
+                    workers: p.workers.unwrap_or(run.workers),
+                    operations: p.ops.unwrap_or(run.operations),
+                    mix: match p.mix {
+                        Some(ref mix) => try!(bench::Mix::parse(mix)),
+                        None => run.mix,
+                    },
+                    seed: seed,
+                };
+
+                try!(client.bench(&principal, &p.schema, &dataset, &options)).to_json()
+            }
+
+            "reward" => {
+                let principal = try!(self.principal(params));
+                let p: NewReward = try!(decode(params));
+                try!(client.add_reward(&principal, &p.name, &p.title, p.amount)).to_json()
+            }
+
+            "rewards" => {
+                let p: Named = try!(decode(params));
+                Json::Array(try!(client.list_rewards(&p.name)).iter().map(|r| r.to_json()).collect())
+            }
+
+            "export-backers" => {
+                let principal = try!(self.principal(params));
+                let p: Named = try!(decode(params));
+                Json::Array(try!(client.export_backers(&principal, &p.name)).iter().map(|b| b.to_json()).collect())
+            }
+
+            "schema check" => {
+                let drift = try!(client.check_schema());
+                object(vec![("schema", client.schema.to_json()), ("tables", db::get_all_tables().len().to_json()),
+                            ("differences", Json::Array(drift.iter().map(|d| d.to_json()).collect()))])
+            }
+
+            "config check" => {
+                let config = try!(Config::load(&self.config));
+                try!(config.check());
+                config.to_json()
+            }
+
+            _ => unreachable!(),
+        };
+
+        Ok(Some(result))
+    }
+
+    /// Returns the token passed with the request, or the one from the session's last login.
+    fn token(&self, params: &Json) -> Option<String> {
+        params.find("token").and_then(|t| t.as_string()).map(|t| t.to_owned()).or(self.token.clone())
+    }
+
+    /// Authenticates the user making the request.
+    fn principal(&self, params: &Json) -> Result<Principal> {
+        match self.token(params) {
+            Some(ref token) => self.client.authenticate(token),
+            None => Err(From::from(validate::Error::NotAuthenticated)),
+        }
+    }
+}
+
+/// Returns the error code that describes an error raised by a method.
+pub fn code(err: &Error) -> i64 {
+    match *err {
+        Error::Decode(_) => INVALID_PARAMS,
+        _ => api::status(err).to_u16() as i64,
+    }
+}
+
+/// Decodes a method's named parameters.
+fn decode<T: Decodable>(params: &Json) -> Result<T> {
+    let mut decoder = json::Decoder::new(params.clone());
+    Ok(try!(T::decode(&mut decoder)))
+}
+
+/// Sends the response to a request, unless the request was a notification.
+fn respond(id: Option<Json>, result: ::std::result::Result<Json, (i64, String)>,
+           out: &mut FnMut(Json) -> io::Result<()>) -> io::Result<()> {
+    let id = match id {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+
+    match result {
+        Ok(result) => out(object(vec![("jsonrpc", "2.0".to_json()), ("id", id), ("result", result)])),
+        Err((code, message)) => out(error(&id, code, &message)),
+    }
+}
+
+/// Returns an error response.
+fn error(id: &Json, code: i64, message: &str) -> Json {
+    let error = object(vec![("code", code.to_json()), ("message", message.to_json())]);
+    object(vec![("jsonrpc", "2.0".to_json()), ("id", id.clone()), ("error", error)])
+}
+
+/// Returns a notification sent on behalf of the request with the ID.
+fn notification(method: &str, id: Option<&Json>, params: Json) -> Json {
+    let mut params = params;
+    if let Json::Object(ref mut map) = params {
+        map.insert("id".to_owned(), id.cloned().unwrap_or(Json::Null));
+    }
+    object(vec![("jsonrpc", "2.0".to_json()), ("method", method.to_json()), ("params", params)])
+}
+
+/// Builds a JSON object from its members.
+fn object(members: Vec<(&str, Json)>) -> Json {
+    Json::Object(members.into_iter().map(|(k, v)| (k.to_owned(), v)).collect())
+}
+
+/// Returns the name of the trigger event that announced a notice.
+fn event(notice: &Notice) -> &'static str {
+    match *notice {
+        Notice::ProjectCreated { .. }  => "ProjectCreated",
+        Notice::ProjectUpdated { .. }  => "ProjectUpdated",
+        Notice::PledgeCreated { .. }   => "PledgeCreated",
+        Notice::PledgeRefunded { .. }  => "PledgeRefunded",
+        Notice::PledgeCancelled { .. } => "PledgeCancelled",
+    }
+}
@@ -0,0 +1,219 @@
+//! Module for generating synthetic users, projects and pledges, for `ksr seed`.
+//!
+//! The same options always generate the same data, so that load tests and demos can be repeated.
+//! Names are drawn from short word lists, so that they stay within the 20 characters that
+//! names are allowed even when numbered. Goals follow a log-normal distribution around $10,000,
+//! and pledges favour a few popular projects and the amounts that reward tiers are usually set at.
+//! Each user has one card, of a brand chosen in proportion to how common it is, whose number
+//! passes the Luhn-10 test. Seeded data is loaded the way `ksr import` loads rows, in a single
+//! transaction, and doesn't queue mail or webhook deliveries.
+use {validate, Client, Result};
+use import::{self, Import, Kind};
+use output;
+use validate::Brand;
+
+use postgres::{Connection, SslMode};
+use rand::{Rng, SeedableRng, XorShiftRng};
+use rand::distributions::{IndependentSample, LogNormal};
+
+use std::collections::HashSet;
+
+static FIRST_NAMES: &'static [&'static str] = &[
+    "Amalia", "Ben", "Carmen", "Dev", "Elena", "Farid", "Grace", "Hiro", "Ines", "Jorge", "Kofi", "Lena",
+    "Mateo", "Nadia", "Omar", "Priya", "Quinn", "Rosa", "Sam", "Tariq", "Uma", "Viktor", "Wren", "Yusuf",
+];
+
+static LAST_NAMES: &'static [&'static str] = &[
+    "Abara", "Bianchi", "Chen", "Dubois", "Eriksen", "Fischer", "Garcia", "Haddad", "Ito", "Jensen",
+    "Kowalski", "Larsen", "Moreau", "Novak", "Okafor", "Patel", "Quispe", "Rossi", "Silva", "Tanaka",
+];
+
+static ADJECTIVES: &'static [&'static str] = &[
+    "Attack", "Brave", "Cosmic", "Daring", "Endless", "Feral", "Golden", "Hidden", "Jolly", "Lunar",
+    "Modular", "Neon", "Nimble", "Open", "Pocket", "Quiet", "Retro", "Solar", "Tiny", "Wild",
+];
+
+static NOUNS: &'static [&'static str] = &[
+    "Owl", "Banana", "Bakery", "Camera", "Comic", "Console", "Drone", "Film", "Garden", "Guitar",
+    "Journal", "Lamp", "Novel", "Podcast", "Puzzle", "Robot", "Synth", "Tent", "Watch", "Zine",
+];
+
+/// How common each card brand is, in the order of `Brand::all`.
+static BRAND_WEIGHTS: &'static [f64] = &[50f64, 30f64, 12f64, 8f64];
+
+/// Pledge amounts that reward tiers are commonly set at, and how often each is chosen.
+static TIERS: &'static [(f64, f64)] = &[(1f64, 4f64), (5f64, 6f64), (10f64, 12f64), (25f64, 20f64),
+                                        (35f64, 8f64), (50f64, 16f64), (75f64, 8f64), (100f64, 12f64),
+                                        (150f64, 4f64), (250f64, 5f64), (500f64, 3f64), (1000f64, 2f64)];
+
+/// How much to generate, and the seed that decides what is generated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Options {
+    pub projects: usize,
+    pub users: usize,
+    pub pledges: usize,
+    pub seed: u64,
+}
+
+impl Default for Options {
+    /// The amounts that `ksr seed` generates unless told otherwise.
+    fn default() -> Options {
+        Options { projects: 10, users: 100, pledges: 500, seed: 1 }
+    }
+}
+
+/// Generated users, projects and pledges.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dataset {
+    /// Each user's name and card.
+    pub users: Vec<(String, String)>,
+    /// Each project's name, goal and creator, if it has one.
+    pub projects: Vec<(String, f64, Option<String>)>,
+    /// Each pledge's user, project, card and amount.
+    pub pledges: Vec<(String, String, String, f64)>,
+}
+
+/// Generates the data described by the options. At most one pledge is made by each user
+/// to each project.
+pub fn generate(options: &Options) -> validate::Result<Dataset> {
+    let max_pledges = options.users * options.projects;
+    if options.pledges > max_pledges {
+        return Err(validate::Error::TooManyPledges(max_pledges));
+    }
+
+    // The fixed words keep the seed from ever being all zeroes, which XorShift can't start from.
+    let mut rng = XorShiftRng::from_seed([options.seed as u32 ^ 0x193a6754, (options.seed >> 32) as u32 ^ 0xa8a7d469,
+                                          0x97830e05, 0x113ba7bb]);
+
+    let mut names = HashSet::new();
+    let mut cards = HashSet::new();
+    let mut users = vec![];
+    for _ in 0..options.users {
+        let name = unique_name(&mut rng, FIRST_NAMES, LAST_NAMES, &mut names);
+        let brand = Brand::all()[weighted(&mut rng, BRAND_WEIGHTS)];
+        let mut card = validate::luhn10_card(&mut rng, brand);
+        while !cards.insert(card.clone()) {
+            card = validate::luhn10_card(&mut rng, brand);
+        }
+        users.push((name, card));
+    }
+
+    // Most projects are run by a small share of the users.
+    let creators = (options.users + 9) / 10;
+    let goals = LogNormal::new(10000f64.ln(), 1.1);
+    let mut projects = vec![];
+    for _ in 0..options.projects {
+        let name = unique_name(&mut rng, ADJECTIVES, NOUNS, &mut names);
+        let goal = round_goal(goals.ind_sample(&mut rng).max(100f64).min(5000000f64));
+        let creator = if creators > 0 && !rng.gen_weighted_bool(5) {
+            Some(users[rng.gen_range(0, creators)].0.clone())
+        } else {
+            None
+        };
+        projects.push((name, goal, creator));
+    }
+
+    // A few projects draw most of the backers.
+    let popularity = LogNormal::new(0f64, 1f64);
+    let weights: Vec<f64> = projects.iter().map(|_| popularity.ind_sample(&mut rng)).collect();
+    let tier_weights: Vec<f64> = TIERS.iter().map(|&(_, weight)| weight).collect();
+
+    let mut backed = HashSet::new();
+    let mut pledges = vec![];
+    while pledges.len() < options.pledges {
+        let mut project = weighted(&mut rng, &weights);
+        let mut user = rng.gen_range(0, options.users);
+
+        // Once random picks keep landing on pairs already taken, take the next free pair in turn,
+        // so that asking for nearly every pair still finishes.
+        let mut tries = 0;
+        while backed.contains(&(user, project)) {
+            tries += 1;
+            if tries > 100 {
+                let next = user * options.projects + project + 1;
+                user = (next / options.projects) % options.users;
+                project = next % options.projects;
+            } else {
+                project = weighted(&mut rng, &weights);
+                user = rng.gen_range(0, options.users);
+            }
+        }
+        backed.insert((user, project));
+
+        let amount = TIERS[weighted(&mut rng, &tier_weights)].0;
+        pledges.push((users[user].0.clone(), projects[project].0.clone(), users[user].1.clone(), amount));
+    }
+
+    Ok(Dataset { users: users, projects: projects, pledges: pledges })
+}
+
+/// Loads generated data in a single transaction, creating its users, then its projects and
+/// pledges as `ksr import` would.
+pub fn load(client: &Client, dataset: &Dataset) -> Result<()> {
+    let conn = try!(Connection::connect(&client.uri[..], &SslMode::None));
+    let trans = try!(conn.transaction());
+
+    try!(trans.batch_execute("CREATE TEMPORARY TABLE ksr_seed (name text) ON COMMIT DROP"));
+    let data: String = dataset.users.iter().map(|&(ref name, _)| format!("{}\n", output::csv_quote(name))).collect();
+    {
+        let copy = try!(trans.prepare("COPY ksr_seed FROM STDIN WITH (FORMAT csv)"));
+        try!(copy.copy_in(&[], &mut data.as_bytes()));
+    }
+
+    try!(import::create_users(client, &trans, "SELECT name FROM ksr_seed"));
+
+    let projects = dataset.projects.iter().map(|&(ref name, goal, ref creator)| {
+        vec![name.clone(), format!("{:.2}", goal), creator.clone().unwrap_or(String::new())]
+    });
+    let pledges = dataset.pledges.iter().map(|&(ref user, ref project, ref card, amount)| {
+        vec![user.clone(), project.clone(), card.clone(), format!("{:.2}", amount)]
+    });
+
+    try!(import::load_in(client, &trans, &rows(Kind::Projects, projects.collect())));
+    try!(import::load_in(client, &trans, &rows(Kind::Pledges, pledges.collect())));
+
+    try!(trans.commit());
+    Ok(())
+}
+
+/// Returns generated rows as an import that accepted all of them.
+fn rows(kind: Kind, rows: Vec<Vec<String>>) -> Import {
+    Import {
+        kind: kind,
+        header: kind.fields().iter().map(|&(field, _)| field.to_owned()).collect(),
+        accepted: rows.into_iter().enumerate().map(|(i, row)| (i + 2, row)).collect(),
+        rejected: vec![],
+    }
+}
+
+/// Returns a name made of two words, not yet taken, numbered if every pairing has been.
+fn unique_name<R: Rng>(rng: &mut R, first: &[&str], second: &[&str], taken: &mut HashSet<String>) -> String {
+    let base = format!("{}_{}", rng.choose(first).unwrap(), rng.choose(second).unwrap());
+    let mut name = base.clone();
+    let mut n = 1;
+    while taken.contains(&name) {
+        n += 1;
+        name = format!("{}{}", base, n);
+    }
+    taken.insert(name.clone());
+    name
+}
+
+/// Rounds a goal to two significant figures, as goals are usually set.
+fn round_goal(goal: f64) -> f64 {
+    let scale = 10f64.powi(goal.log10().floor() as i32 - 1);
+    (goal / scale).round() * scale
+}
+
+/// Returns an index chosen at random, in proportion to its weight.
+fn weighted<R: Rng>(rng: &mut R, weights: &[f64]) -> usize {
+    let total: f64 = weights.iter().sum();
+    let mut point = rng.gen::<f64>() * total;
+    for (i, weight) in weights.iter().enumerate() {
+        if point < *weight {
+            return i;
+        }
+        point -= *weight;
+    }
+    weights.len() - 1
+}
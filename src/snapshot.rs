@@ -0,0 +1,335 @@
+//! Module for dumping Kickstarter data, as a JSON snapshot or a `.ksr` script, and restoring snapshots.
+//!
+//! A snapshot holds every row of every table in the schema, with its original IDs and timestamps,
+//! and can only be restored into an empty schema built from the same .sql file. A script holds
+//! the commands that rebuild users, projects, rewards, pledges, refunds and closings in the order they
+//! happened. Replaying a script through `ksr run` assigns new IDs and timestamps, but passes every
+//! change through validation, so it also works against schemas built from a newer .sql file.
+use {validate, Client, Error, Result};
+use account::Role;
+use db::table;
+use repl;
+
+use chrono::Local;
+use chrono::naive::datetime::NaiveDateTime;
+use rustc_serialize::json::{DecoderError, Json, ToJson};
+use rustorm::dao::Value;
+
+use std::collections::BTreeMap;
+
+/// The version of the snapshot format, recorded in each snapshot.
+pub const VERSION: u64 = 1;
+
+const ERR_NOT_SNAPSHOT:   &'static str = "The file is not a snapshot made by `ksr dump --snapshot`.";
+const ERR_VERSION:        &'static str = "The snapshot was made by a version of `ksr dump --snapshot` that this one can't read.";
+const ERR_UNKNOWN_TABLE:  &'static str = "The snapshot has rows for a table that the schema doesn't have";
+
+/// A table in the schema, with the columns needed to dump and restore it.
+struct Table {
+    name: String,
+    /// Primary key columns, which order the dumped rows.
+    keys: Vec<String>,
+    /// Columns filled from a sequence, which must be moved past the restored IDs.
+    serials: Vec<String>,
+}
+
+/// Returns every row of every table in the schema, along with when and where it was dumped.
+pub fn dump(client: &Client) -> Result<Json> {
+    let mut tables = BTreeMap::new();
+    for table in try!(list_tables(client)) {
+        let order: Vec<String> = table.keys.iter().map(|key| format!("t.{}", key)).collect();
+        let sql = format!("SELECT COALESCE(json_agg(t{}), '[]')::text AS data FROM {} t",
+                          if order.is_empty() { String::new() } else { format!(" ORDER BY {}", order.join(", ")) },
+                          client.table(&table.name));
+
+        let mut daos = try!(client.db().execute_sql_with_return(&sql, &vec![]));
+        let data: String = daos.pop().map_or("[]".to_owned(), |dao| dao.get("data"));
+        let rows = try!(Json::from_str(&data).map_err(DecoderError::ParseError));
+        tables.insert(table.name, rows);
+    }
+
+    let mut snapshot = BTreeMap::new();
+    snapshot.insert("schema".to_owned(), client.schema.to_json());
+    snapshot.insert("version".to_owned(), VERSION.to_json());
+    snapshot.insert("date_created".to_owned(), Local::now().naive_local().to_string().to_json());
+    snapshot.insert("tables".to_owned(), Json::Object(tables));
+    Ok(Json::Object(snapshot))
+}
+
+/// Loads a snapshot into the client's schema, keeping its IDs and timestamps, in one transaction.
+/// Every table must be empty, and stays locked until the restore commits, so nothing else can
+/// register or restore in between. API tokens are left out, so everyone logs in again.
+/// Returns the number of rows restored.
+pub fn restore(client: &Client, snapshot: &Json) -> Result<usize> {
+    let rows = match snapshot.find("tables").and_then(|tables| tables.as_object()) {
+        Some(rows) => rows,
+        None => return Err(Error::Config(ERR_NOT_SNAPSHOT.to_owned())),
+    };
+    if snapshot.find("version").and_then(|version| version.as_u64()) != Some(VERSION) {
+        return Err(Error::Config(ERR_VERSION.to_owned()));
+    }
+
+    let tables = try!(list_tables(client));
+    if let Some(name) = rows.keys().find(|name| !tables.iter().any(|t| &t.name == *name)) {
+        return Err(Error::Config(format!("{}: {}", ERR_UNKNOWN_TABLE, name)));
+    }
+
+    client.transaction(|| {
+        let names: Vec<String> = tables.iter().map(|table| client.table(&table.name)).collect();
+        let lock = format!("LOCK TABLE {} IN SHARE ROW EXCLUSIVE MODE", names.join(", "));
+        try!(client.db().execute_sql(&lock, &vec![]));
+
+        for table in &tables {
+            let sql = format!("SELECT 1 AS found FROM {} LIMIT 1", client.table(&table.name));
+            if !try!(client.db().execute_sql_with_return(&sql, &vec![])).is_empty() {
+                return Err(From::from(validate::Error::SchemaNotEmpty));
+            }
+        }
+
+        // Tables are loaded in the order they were created, so that rows come after those they reference.
+        let mut count = 0;
+        for table in tables.iter().filter(|table| table.name != table::token) {
+            let data = match rows.get(&table.name) {
+                Some(&Json::Array(ref data)) if !data.is_empty() => data,
+                _ => continue,
+            };
+
+            let sql = format!("INSERT INTO {table} SELECT * FROM json_populate_recordset(NULL::{table}, $1::json)",
+                              table = client.table(&table.name));
+            try!(client.db().execute_sql(&sql, &vec![Value::String(Json::Array(data.clone()).to_string())]));
+            count += data.len();
+
+            for column in &table.serials {
+                let sql = format!("SELECT setval(pg_get_serial_sequence('{table}', '{column}'), \
+                                   COALESCE(MAX({column}), 0) + 1, false) FROM {table}",
+                                  table = client.table(&table.name), column = column);
+                try!(client.db().execute_sql_with_return(&sql, &vec![]));
+            }
+        }
+
+        Ok(count)
+    })
+}
+
+/// Returns a `.ksr` script that rebuilds the schema's users, projects and pledges through `ksr run`.
+///
+/// Accounts are registered first, with admins ahead of everyone else so that the first of them
+/// is registered as the new schema's admin, and given their roles. Projects, reward tiers, pledges, refunds,
+/// chargebacks and closings follow in the order they happened, each made by logging in as whoever
+/// made it originally, or as the admin for refunds, closings and the tiers of projects without a
+/// creator. Shipping addresses are set afterwards. Every account gets the password in `$KSR_PASSWORD`
+/// when the script runs.
+pub fn script(client: &Client) -> Result<String> {
+    let users_sql = format!("SELECT name, email, role, shipping_address FROM {} \
+                             ORDER BY role = 'admin' DESC, user_id",
+                            client.table(table::user));
+    let users = try!(client.db().execute_sql_with_return(&users_sql, &vec![]));
+
+    let projects_sql = format!("SELECT pr.name, pr.goal, pr.date_created, pr.date_closed, us.name AS creator \
+                                FROM {} LEFT JOIN {} ON us.user_id = pr.creator_id \
+                                ORDER BY pr.project_id",
+                               client.table_abbr(table::project), client.table_abbr(table::user));
+    let projects = try!(client.db().execute_sql_with_return(&projects_sql, &vec![]));
+
+    let rewards_sql = format!("SELECT pr.name AS project, us.name AS creator, re.title, re.minimum, re.date_created \
+                               FROM {} INNER JOIN {} ON pr.project_id = re.project_id \
+                               LEFT JOIN {} ON us.user_id = pr.creator_id \
+                               ORDER BY re.reward_id",
+                              client.table_abbr(table::reward), client.table_abbr(table::project),
+                              client.table_abbr(table::user));
+    let rewards = try!(client.db().execute_sql_with_return(&rewards_sql, &vec![]));
+
+    let pledges_sql = format!("SELECT pr.name AS project, us.name AS backer, pl.card, pl.amount, pl.date_created \
+                               FROM {} INNER JOIN {} ON pr.project_id = pl.project_id \
+                               INNER JOIN {} ON us.user_id = pl.user_id \
+                               ORDER BY pl.date_created",
+                              client.table_abbr(table::pledge), client.table_abbr(table::project),
+                              client.table_abbr(table::user));
+    let pledges = try!(client.db().execute_sql_with_return(&pledges_sql, &vec![]));
+
+    let refunds_sql = format!("SELECT pr.name AS project, us.name AS backer, pl.card, re.kind, re.amount, \
+                               re.reason, re.reference, re.date_created \
+                               FROM {} INNER JOIN {} ON pr.project_id = re.project_id \
+                               INNER JOIN {} ON us.user_id = re.user_id \
+                               LEFT JOIN {} ON pl.project_id = re.project_id AND pl.user_id = re.user_id \
+                               ORDER BY re.refund_id",
+                              client.table_abbr(table::refund), client.table_abbr(table::project),
+                              client.table_abbr(table::user), client.table_abbr(table::pledge));
+    let refunds = try!(client.db().execute_sql_with_return(&refunds_sql, &vec![]));
+
+    let mut script = format!("# Rebuilds the projects and pledges of schema '{}', as of {}.\n\
+                              # Run it against a freshly built schema with `ksr run`; every account \
+                              gets the password in $KSR_PASSWORD.\n",
+                             client.schema, Local::now().format("%Y-%m-%d %H:%M:%S"));
+
+    let admin: Option<String> = users.first().map(|dao| dao.get("name"));
+    let admin = match admin {
+        Some(admin) => admin,
+        None => return Ok(script),
+    };
+
+    // Creators need their role before they can create projects, even if they have since lost it.
+    let creators: Vec<String> = projects.iter().filter_map(|dao| dao.get_opt("creator")).collect();
+    let mut demotions = vec![];
+    let mut roles = vec![];
+
+    for (i, dao) in users.iter().enumerate() {
+        let name: String = dao.get("name");
+        let email: Option<String> = dao.get_opt("email");
+        let role = dao.get::<String>("role").parse().unwrap_or(Role::Backer);
+        let address = email.unwrap_or(format!("{}@example.invalid", name.to_lowercase()));
+        if i == 0 {
+            script.push_str(&line(&["register", &name, &address, "--admin"]));
+        } else {
+            script.push_str(&line(&["register", &name, &address]));
+        }
+
+        let needed = if creators.contains(&name) && role < Role::Creator { Role::Creator } else { role };
+        let given = if i == 0 { Role::Admin } else { Role::Backer };
+        if needed != given {
+            roles.push(line(&["role", &name, needed.as_str()]));
+        }
+        if needed != role {
+            demotions.push(line(&["role", &name, role.as_str()]));
+        }
+    }
+
+    if !roles.is_empty() {
+        script.push_str(&line(&["login", &admin]));
+        for role in &roles {
+            script.push_str(role);
+        }
+    }
+
+    // Each change, with when it happened and who made it.
+    let mut changes: Vec<(NaiveDateTime, String, String)> = vec![];
+
+    for dao in &projects {
+        let name: String = dao.get("name");
+        let goal: f64 = dao.get("goal");
+        let creator: Option<String> = dao.get_opt("creator");
+        changes.push((dao.get("date_created"), creator.unwrap_or(admin.clone()),
+                      line(&["project", &name, &format!("{:.2}", goal)])));
+
+        let closed: Option<NaiveDateTime> = dao.get_opt("date_closed");
+        if let Some(closed) = closed {
+            changes.push((closed, admin.clone(), line(&["close", &name])));
+        }
+    }
+
+    for dao in &rewards {
+        let project: String = dao.get("project");
+        let title: String = dao.get("title");
+        let creator: Option<String> = dao.get_opt("creator");
+        changes.push((dao.get("date_created"), creator.unwrap_or(admin.clone()),
+                      line(&["reward", &project, &title, &format!("{:.2}", dao.get::<f64>("minimum"))])));
+    }
+
+    for dao in &pledges {
+        let amount: f64 = dao.get("amount");
+        let card: String = dao.get("card");
+        let project: String = dao.get("project");
+        changes.push((dao.get("date_created"), dao.get("backer"),
+                      line(&["back", &project, &card, &format!("{:.2}", amount)])));
+    }
+
+    for dao in &refunds {
+        let project: String = dao.get("project");
+        let backer: String = dao.get("backer");
+        let kind: String = dao.get("kind");
+        let amount = format!("{:.2}", dao.get::<f64>("amount"));
+        let reason = format!("--reason={}", dao.get::<String>("reason"));
+        let card: Option<String> = dao.get_opt("card");
+        let reference: Option<String> = dao.get_opt("reference");
+
+        let text = match (&kind[..], card) {
+            ("chargeback", Some(card)) => {
+                let mut words = vec!["chargeback".to_owned(), project, card, amount, reason];
+                if let Some(reference) = reference {
+                    words.push(format!("--reference={}", reference));
+                }
+                line(&words.iter().map(|w| &w[..]).collect::<Vec<&str>>())
+            }
+            ("chargeback", None) => format!("# skipped: a chargeback of ${} against {} for {}, \
+                                             whose pledge no longer exists\n", amount, project, backer),
+            _ => line(&["refund", &backer, &project, &amount, &reason]),
+        };
+        changes.push((dao.get("date_created"), admin.clone(), text));
+    }
+
+    // Sorting is stable, so closings stay after anything that happened at the same moment.
+    changes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut current = if roles.is_empty() { None } else { Some(admin.clone()) };
+    for (_, actor, text) in changes {
+        if current.as_ref() != Some(&actor) {
+            script.push_str(&line(&["login", &actor]));
+            current = Some(actor);
+        }
+        script.push_str(&text);
+    }
+
+    for dao in &users {
+        let address: Option<String> = dao.get_opt("shipping_address");
+        if let Some(address) = address {
+            script.push_str(&line(&["login", &dao.get::<String>("name")]));
+            script.push_str(&line(&["ship-to", &address]));
+        }
+    }
+
+    // Accounts without an email address were registered with a placeholder.
+    for dao in &users {
+        let email: Option<String> = dao.get_opt("email");
+        if email.is_none() {
+            script.push_str(&line(&["login", &dao.get::<String>("name")]));
+            script.push_str(&line(&["email"]));
+        }
+    }
+
+    if !demotions.is_empty() {
+        script.push_str(&line(&["login", &admin]));
+        for demotion in &demotions {
+            script.push_str(demotion);
+        }
+    }
+
+    Ok(script)
+}
+
+/// Returns a script line of quoted words.
+fn line(words: &[&str]) -> String {
+    let words: Vec<String> = words.iter().map(|word| repl::quote(word)).collect();
+    format!("{}\n", words.join(" "))
+}
+
+/// Returns the schema's tables in the order they were created, so that each comes after
+/// the tables it references.
+fn list_tables(client: &Client) -> Result<Vec<Table>> {
+    let sql = "SELECT c.relname::text AS name, \
+                      array_to_string(ARRAY(SELECT a.attname FROM pg_index i \
+                                            INNER JOIN pg_attribute a ON a.attrelid = i.indrelid \
+                                                                     AND a.attnum = ANY(i.indkey) \
+                                            WHERE i.indrelid = c.oid AND i.indisprimary \
+                                            ORDER BY a.attnum), ',') AS keys, \
+                      array_to_string(ARRAY(SELECT a.attname FROM pg_attribute a \
+                                            INNER JOIN pg_attrdef d ON d.adrelid = a.attrelid \
+                                                                   AND d.adnum = a.attnum \
+                                            WHERE a.attrelid = c.oid \
+                                              AND pg_get_expr(d.adbin, d.adrelid) LIKE 'nextval(%' \
+                                            ORDER BY a.attnum), ',') AS serials \
+               FROM pg_class c \
+               INNER JOIN pg_namespace n ON n.oid = c.relnamespace \
+               WHERE n.nspname = $1 AND c.relkind = 'r' \
+               ORDER BY c.oid";
+
+    let daos = try!(client.db().execute_sql_with_return(sql, &vec![Value::String(client.schema.clone())]));
+    let columns = |list: String| -> Vec<String> {
+        list.split(',').filter(|c| !c.is_empty()).map(|c| c.to_owned()).collect()
+    };
+
+    Ok(daos.iter().map(|dao| Table {
+        name: dao.get("name"),
+        keys: columns(dao.get("keys")),
+        serials: columns(dao.get("serials")),
+    }).collect())
+}
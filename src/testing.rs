@@ -0,0 +1,82 @@
+//! Module for running against throwaway schemas, so that test suites can run in parallel
+//! against a single database.
+//!
+//! An `Ephemeral` client works on a schema of its own, named at random and built from the
+//! configured .sql file, instead of rebuilding the configured schema that every test shares.
+//! The schema is dropped when the client is, along with any scratch schemas it handed out
+//! for other clients, such as targets to replay or restore into.
+use {Client, Result};
+
+use rand::{self, Rng};
+
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+
+/// The prefix of every ephemeral schema's name.
+pub const PREFIX: &'static str = "ksr_test_";
+
+/// A client on a schema of its own, which is dropped along with the client.
+pub struct Ephemeral {
+    client: Client,
+    /// Schemas named for other clients, dropped along with this one.
+    scratch: RefCell<Vec<String>>,
+}
+
+impl Ephemeral {
+
+    /// Connects to the database at the uri and builds an ephemeral schema from the default .sql file.
+    pub fn new(uri: &str) -> Result<Ephemeral> {
+        Ephemeral::from_client(try!(Client::new(uri, PREFIX)))
+    }
+
+    /// Connects with a .toml configuration file and builds an ephemeral schema in place of
+    /// the configured one, keeping the rest of the configuration.
+    pub fn with_config(filename: &str) -> Result<Ephemeral> {
+        Ephemeral::from_client(try!(Client::with_config(filename)))
+    }
+
+    /// Moves a client onto a new ephemeral schema and builds it.
+    pub fn from_client(mut client: Client) -> Result<Ephemeral> {
+        client.schema = schema_name();
+        let ephemeral = Ephemeral { client: client, scratch: RefCell::new(vec![]) };
+
+        // A schema left half built is dropped with the client.
+        try!(ephemeral.client.build(None, false));
+        Ok(ephemeral)
+    }
+
+    /// Returns a new schema name for another client to use, which is dropped along with this client.
+    pub fn scratch_schema(&self) -> String {
+        let schema = schema_name();
+        self.scratch.borrow_mut().push(schema.clone());
+        schema
+    }
+}
+
+impl Deref for Ephemeral {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl DerefMut for Ephemeral {
+    fn deref_mut(&mut self) -> &mut Client {
+        &mut self.client
+    }
+}
+
+impl Drop for Ephemeral {
+    fn drop(&mut self) {
+        for schema in self.scratch.borrow().iter().chain(Some(&self.client.schema)) {
+            let sql = format!("DROP SCHEMA IF EXISTS {} CASCADE", schema);
+            let _ = self.client.db().execute_sql(&sql, &vec![]);
+        }
+    }
+}
+
+/// Returns a schema name that no other test will use.
+pub fn schema_name() -> String {
+    format!("{}{:016x}", PREFIX, rand::thread_rng().gen::<u64>())
+}
@@ -1,26 +1,189 @@
 //! Module for interacting with Kickstarter users.
 pub use models::User;
 
-use {Client, Result};
-use db::table;
-use models::{Pledge, Project};
+use {validate, Client, Result};
+use account::Role;
+use db::{column, table};
+use event::{self, DomainEvent};
+use models::{Audit, Event, Pledge, Project};
 
-use rustorm::dao::Value;
+use rustc_serialize::json::{Json, ToJson};
+use rustorm::dao::{FromValue, IsDao, Value};
 use rustorm::query::{Equality, Query};
 use std::cmp::Ordering;
+use std::convert::From;
 
 impl User {
     /// Upserts a user and returns the resultant ID as a Value.
+    /// A user created here is audited on behalf of the client's actor, like any other change.
     pub fn upsert(client: &Client, user: &str) -> Result<Value> {
+        if let Ok(existing) = User::get(client, user) {
+            return Ok(Value::I32(existing.user_id));
+        }
+
         let u_result = try!(Query::select()
                             .column("upsert_user")
-                            .from_table(&format!("upsert_user('{}')", user))
+                            .from_table(&format!("{}('{}')", client.table("upsert_user"), user))
                             .retrieve_one(client.db()));
         
         let uid = u_result.values.get("upsert_user").unwrap();
+        let created = try!(User::get(client, user));
+        try!(Audit::record(client, "create", table::user, &created.user_id.to_string(),
+                           None, Some(created.redacted_json())));
+        try!(Event::record(client, DomainEvent::UserCreated(event::UserCreated {
+            user_id: FromValue::from_type(uid.clone()),
+            name: user.to_owned(),
+            password_hash: None,
+        })));
+
         Ok(uid.clone())
     }
 
+    /// Retrieve a user by name.
+    pub fn get(client: &Client, user: &str) -> Result<User> {
+        let mut users: Vec<User> = try!(Query::select_all()
+            .from_table(&client.table(table::user))
+            .filter(column::name, Equality::EQ, &user)
+            .collect(client.db()));
+
+        users.pop().ok_or(From::from(validate::Error::UserDoesNotExist))
+    }
+
+    /// Retrieves a list of all users.
+    pub fn list_all(client: &Client) -> Result<Vec<User>> {
+        let results: Vec<User> = try!(Query::select_all()
+            .from_table(&client.table(table::user))
+            .collect(client.db()));
+
+        Ok(results)
+    }
+
+    /// Returns the user's role. Unrecognized roles are treated as the least privileged.
+    pub fn role(&self) -> Role {
+        self.role.parse().unwrap_or(Role::Backer)
+    }
+
+    /// Returns true if any user has the admin role.
+    pub fn has_admin(client: &Client) -> Result<bool> {
+        let admins: Vec<User> = try!(Query::select_all()
+            .from_table(&client.table(table::user))
+            .filter(column::role, Equality::EQ, &Role::Admin.as_str())
+            .collect(client.db()));
+
+        Ok(!admins.is_empty())
+    }
+
+    /// Sets a user's role, creating the user if necessary.
+    pub fn set_role(client: &Client, user: &str, role: Role) -> Result<User> {
+        try!(validate::length(user, 4, 20));
+        try!(validate::alphanumeric(user));
+
+        let sql = format!("UPDATE {} SET role = $1 WHERE user_id = $2 RETURNING *",
+                          client.table(table::user));
+
+        client.transaction(|| {
+            let uid = try!(User::upsert(client, user));
+            let before = try!(User::get(client, user));
+
+            let mut daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::String(role.as_str().to_owned()), uid]));
+            let after = match daos.pop() {
+                Some(dao) => User::from_dao(&dao),
+                None => return Err(From::from(validate::Error::UserDoesNotExist)),
+            };
+
+            try!(Audit::record(client, "set_role", table::user, &after.user_id.to_string(),
+                               Some(before.redacted_json()), Some(after.redacted_json())));
+            try!(Event::record(client, DomainEvent::RoleChanged(event::RoleChanged {
+                user_id: after.user_id,
+                role: after.role.clone(),
+            })));
+            Ok(after)
+        })
+    }
+
+    /// Sets or clears the address that mail is sent to, creating the user if necessary.
+    pub fn set_email(client: &Client, user: &str, email: Option<&str>) -> Result<User> {
+        try!(validate::length(user, 4, 20));
+        try!(validate::alphanumeric(user));
+
+        if let Some(email) = email {
+            try!(validate::email(email));
+        }
+
+        let sql = format!("UPDATE {} SET email = $1 WHERE user_id = $2 RETURNING *",
+                          client.table(table::user));
+
+        let email = email.map_or(Value::Null, |e| Value::String(e.to_owned()));
+
+        client.transaction(|| {
+            let uid = try!(User::upsert(client, user));
+            let before = try!(User::get(client, user));
+
+            let mut daos = try!(client.db().execute_sql_with_return(&sql, &vec![email, uid]));
+            let after = match daos.pop() {
+                Some(dao) => User::from_dao(&dao),
+                None => return Err(From::from(validate::Error::UserDoesNotExist)),
+            };
+
+            try!(Audit::record(client, "set_email", table::user, &after.user_id.to_string(),
+                               Some(before.redacted_json()), Some(after.redacted_json())));
+            try!(Event::record(client, DomainEvent::EmailChanged(event::EmailChanged {
+                user_id: after.user_id,
+                email: after.email.clone(),
+            })));
+            Ok(after)
+        })
+    }
+
+    /// Sets or clears the address that rewards are shipped to, creating the user if necessary.
+    pub fn set_shipping_address(client: &Client, user: &str, address: Option<&str>) -> Result<User> {
+        try!(validate::length(user, 4, 20));
+        try!(validate::alphanumeric(user));
+
+        if let Some(address) = address {
+            try!(validate::not_blank(address));
+            try!(validate::single_line(address));
+            try!(validate::length(address, 1, 500));
+        }
+
+        let sql = format!("UPDATE {} SET shipping_address = $1 WHERE user_id = $2 RETURNING *",
+                          client.table(table::user));
+
+        let address = address.map_or(Value::Null, |a| Value::String(a.to_owned()));
+
+        client.transaction(|| {
+            let uid = try!(User::upsert(client, user));
+            let before = try!(User::get(client, user));
+
+            let mut daos = try!(client.db().execute_sql_with_return(&sql, &vec![address, uid]));
+            let after = match daos.pop() {
+                Some(dao) => User::from_dao(&dao),
+                None => return Err(From::from(validate::Error::UserDoesNotExist)),
+            };
+
+            try!(Audit::record(client, "set_shipping_address", table::user, &after.user_id.to_string(),
+                               Some(before.redacted_json()), Some(after.redacted_json())));
+            try!(Event::record(client, DomainEvent::ShippingAddressChanged(event::ShippingAddressChanged {
+                user_id: after.user_id,
+                shipping_address: after.shipping_address.clone(),
+            })));
+            Ok(after)
+        })
+    }
+
+    /// Returns true if the user has pledged towards the project.
+    pub fn has_backed(client: &Client, user: &str, project_id: i32) -> Result<bool> {
+        let result = try!(Query::select()
+            .column(&"pl.user_id")
+            .from_table(&client.table_abbr(table::pledge))
+            .inner_join_table(&client.table_abbr(table::user), &"pl.user_id", &"us.user_id")
+            .filter(&"us.name", Equality::EQ, &user)
+            .filter(&"pl.project_id", Equality::EQ, &project_id)
+            .retrieve(client.db()));
+
+        Ok(!result.dao.is_empty())
+    }
+
     /// Retrieve a map of all pledges that a user has made to Kickstarter projects.
     /// Returns a map of projects to Pledge objects.
     pub fn list_pledges(client: &Client, user: &str) -> Result<Vec<Pledge>> {
@@ -45,6 +208,48 @@ impl User {
 
         Ok(pledges)
     }
+
+    /// Returns one page of the pledges that a user has made, in the order they were made, along
+    /// with how many pledges they have made in all. Each pledge comes with its project.
+    pub fn list_pledges_page(client: &Client, user: &str, offset: usize,
+                             limit: usize) -> Result<(Vec<Pledge>, usize)> {
+        let name = vec![Value::String(user.to_owned())];
+        let count_sql = format!("SELECT COUNT(*) AS total FROM {} \
+                                 INNER JOIN {} ON us.user_id = pl.user_id WHERE us.name = $1",
+                                client.table_abbr(table::pledge), client.table_abbr(table::user));
+        let page_sql = format!("SELECT pl.* FROM {} \
+                                INNER JOIN {} ON us.user_id = pl.user_id WHERE us.name = $1 \
+                                ORDER BY pl.date_created, pl.project_id LIMIT {} OFFSET {}",
+                               client.table_abbr(table::pledge), client.table_abbr(table::user), limit, offset);
+
+        let mut daos = try!(client.db().execute_sql_with_return(&count_sql, &name));
+        let total: i64 = daos.pop().map_or(0, |dao| dao.get("total"));
+        let daos = try!(client.db().execute_sql_with_return(&page_sql, &name));
+        let mut pledges: Vec<Pledge> = daos.iter().map(Pledge::from_dao).collect();
+
+        let ids: Vec<String> = pledges.iter().map(|p| p.project_id.to_string()).collect();
+        if !ids.is_empty() {
+            let projects_sql = format!("SELECT * FROM {} WHERE project_id IN ({})",
+                                       client.table(table::project), ids.join(", "));
+            let projects: Vec<Project> = try!(client.db().execute_sql_with_return(&projects_sql, &vec![]))
+                .iter().map(Project::from_dao).collect();
+            for pledge in &mut pledges {
+                pledge.project = projects.iter().find(|p| p.project_id == pledge.project_id).cloned();
+            }
+        }
+
+        Ok((pledges, total as usize))
+    }
+
+    /// Returns the user as JSON for the audit log, without their credentials or unsubscribe token.
+    pub fn redacted_json(&self) -> Json {
+        let mut json = self.to_json();
+        if let Json::Object(ref mut map) = json {
+            map.remove(column::password_hash);
+            map.remove(column::unsubscribe_token);
+        }
+        json
+    }
 }    
 
 impl Ord for User {
@@ -16,12 +16,84 @@ pub enum Error {
     NotNumeric(String),
     /// The string did not pass the Luhn-10 test.
     NotLuhn10(String),
+    /// The string was empty or contained only whitespace.
+    Blank,
+    /// The string contained control characters.
+    NotPrintable(String),
     /// The pledged amount was not more than zero dollars.
     InvalidAmount,
     /// The credit card has already been used to back the desired project.
     CardExists,
     /// The project being searched for does not exist.
     ProjectDoesNotExist,
+    /// The user being searched for does not exist.
+    UserDoesNotExist,
+    /// The comment being searched for does not exist, or has been deleted.
+    CommentDoesNotExist,
+    /// Only the author of a comment may change it.
+    NotCommentAuthor,
+    /// The action is reserved for moderators.
+    NotModerator,
+    /// The project only accepts comments from its backers.
+    BackersOnly,
+    /// The pledge being searched for does not exist.
+    PledgeDoesNotExist,
+    /// The amount to return was more than what remains of the pledge.
+    ExceedsPledge,
+    /// The refund reason code was not recognized.
+    InvalidReason(String),
+    /// The string was not a date or timestamp.
+    InvalidTimestamp(String),
+    /// The string was not an http or https URL.
+    InvalidUrl(String),
+    /// The webhook being searched for does not exist.
+    WebhookDoesNotExist,
+    /// The string was not an email address.
+    InvalidEmail(String),
+    /// The mail kind was not recognized.
+    InvalidMailKind(String),
+    /// The unsubscribe token did not match any user.
+    InvalidToken,
+    /// The project's campaign has already ended.
+    ProjectClosed,
+    /// The password was too short or too long.
+    InvalidPassword,
+    /// An account with the name already exists.
+    UserExists,
+    /// An account with the email address already exists.
+    EmailExists,
+    /// The user name or password was wrong.
+    InvalidCredentials,
+    /// The API token was not recognized, or has expired or been revoked.
+    InvalidSession,
+    /// The action requires logging in first.
+    NotAuthenticated,
+    /// The role was not recognized.
+    InvalidRole(String),
+    /// The GraphQL document could not be parsed or executed.
+    InvalidQuery(String),
+    /// The output format was not recognized.
+    InvalidFormat(String),
+    /// A quoted word was not closed before the end of the line.
+    UnclosedQuote,
+    /// The schema already has data, so a snapshot can't be restored into it.
+    SchemaNotEmpty,
+    /// A CSV file to import has no column for a required field.
+    MissingColumn(String),
+    /// A column mapping was not written as `field=column`, or named an unknown field.
+    InvalidMapping(String),
+    /// Rows of a CSV file failed validation, so none of it was imported.
+    RowsRejected(usize),
+    /// More pledges were asked for than there are users and projects to make them, one per pair.
+    TooManyPledges(usize),
+    /// An operation mix was not written as `op=weight` pairs of known operations.
+    InvalidMix(String),
+    /// The database has drifted from the generated models in this many ways.
+    SchemaDrift(usize),
+    /// The project already offers a reward tier with the title.
+    RewardExists,
+    /// The data changed while a command was running, so it changed nothing and can be run again.
+    ConcurrentChange,
 }
 
 impl fmt::Display for Error {
@@ -32,9 +104,56 @@ impl fmt::Display for Error {
             Error::Length(ref s, min, max) => write!(fmt, "{} must be between {} and {} characters.", s, min, max),
             Error::NotNumeric(ref s)       => write!(fmt, "{} must be numeric.", s),
             Error::NotLuhn10(ref s)        => write!(fmt, "{} failed the Luhn-10 test.", s),
+            Error::NotPrintable(ref s)     => write!(fmt, "{:?} contains unprintable characters.", s),
+            Error::InvalidTimestamp(ref s) => write!(fmt, "{} must be formatted as YYYY-MM-DD \
+                                                           or YYYY-MM-DD HH:MM:SS.", s),
+            Error::InvalidUrl(ref s)       => write!(fmt, "{} must be an http:// or https:// URL.", s),
+            Error::InvalidEmail(ref s)     => write!(fmt, "{} is not a valid email address.", s),
+            Error::InvalidMailKind(ref s)  => write!(fmt, "{} is not a kind of mail. Use one of: receipt, \
+                                                           new_backer, campaign_succeeded, campaign_failed.", s),
+            Error::InvalidRole(ref s)      => write!(fmt, "{} is not a role. Use one of: backer, creator, \
+                                                           moderator, admin.", s),
+            Error::InvalidQuery(ref s)     => write!(fmt, "Invalid GraphQL: {}", s),
+            Error::InvalidFormat(ref s)    => write!(fmt, "{} is not an output format. Use one of: text, table, \
+                                                           json, csv, tsv.", s),
+            Error::MissingColumn(ref s)    => write!(fmt, "The file has no {} column. Name it in the header, \
+                                                           or map another column to it with --map.", s),
+            Error::InvalidMapping(ref s)   => write!(fmt, "{} is not a column mapping. Write field=column, \
+                                                           naming a field of the rows being imported.", s),
+            Error::RowsRejected(n)         => write!(fmt, "{} rows were rejected, so nothing was imported. Fix them, \
+                                                           or pass --skip-rejected to import the rest.", n),
+            Error::TooManyPledges(n)       => write!(fmt, "At most {} pledges can be made, one for each user and \
+                                                           project. Ask for more users or projects.", n),
+            Error::InvalidMix(ref s)       => write!(fmt, "{} is not an operation mix. Write op=weight pairs \
+                                                           of create, back and list, such as back=8,list=2.", s),
+            Error::SchemaDrift(n)          => write!(fmt, "The database differs from the generated models in {} ways. \
+                                                           Rebuild the schema or regenerate the models.", n),
+            Error::InvalidReason(ref s)    => write!(fmt, "{} is not a valid reason. Use one of: requested, \
+                                                           duplicate, fraudulent, cancelled, disputed, other.", s),
+            Error::Blank                   => write!(fmt, "{}", self.description()),
             Error::InvalidAmount           => write!(fmt, "{}", self.description()),
             Error::ProjectDoesNotExist     => write!(fmt, "{}", self.description()),
+            Error::UserDoesNotExist        => write!(fmt, "{}", self.description()),
+            Error::CommentDoesNotExist     => write!(fmt, "{}", self.description()),
+            Error::NotCommentAuthor        => write!(fmt, "{}", self.description()),
+            Error::NotModerator            => write!(fmt, "{}", self.description()),
+            Error::BackersOnly             => write!(fmt, "{}", self.description()),
+            Error::PledgeDoesNotExist      => write!(fmt, "{}", self.description()),
+            Error::ExceedsPledge           => write!(fmt, "{}", self.description()),
+            Error::WebhookDoesNotExist     => write!(fmt, "{}", self.description()),
+            Error::InvalidToken            => write!(fmt, "{}", self.description()),
+            Error::ProjectClosed           => write!(fmt, "{}", self.description()),
+            Error::InvalidPassword         => write!(fmt, "{}", self.description()),
+            Error::UserExists              => write!(fmt, "{}", self.description()),
+            Error::EmailExists             => write!(fmt, "{}", self.description()),
+            Error::InvalidCredentials      => write!(fmt, "{}", self.description()),
+            Error::InvalidSession          => write!(fmt, "{}", self.description()),
+            Error::NotAuthenticated        => write!(fmt, "{}", self.description()),
+            Error::UnclosedQuote           => write!(fmt, "{}", self.description()),
+            Error::SchemaNotEmpty          => write!(fmt, "{}", self.description()),
             Error::CardExists              => write!(fmt, "{}", self.description()),
+            Error::RewardExists            => write!(fmt, "{}", self.description()),
+            Error::ConcurrentChange        => write!(fmt, "{}", self.description()),
         }
     }
 }
@@ -47,9 +166,46 @@ impl ErrorTrait for Error {
             Error::Length(..)          => "Argument length was not within the desired bounds.",
             Error::NotNumeric(_)       => "Argument must be numeric.",
             Error::NotLuhn10(_)        => "Argument failed the Luhn-10 test.",
+            Error::Blank               => "Argument must not be blank.",
+            Error::NotPrintable(_)     => "Argument contains unprintable characters.",
             Error::InvalidAmount       => "Amounts must be greater than 0 dollars.",
             Error::ProjectDoesNotExist => "The project you are looking for does not exist. Go make it!",
+            Error::UserDoesNotExist    => "The user you are looking for does not exist.",
+            Error::CommentDoesNotExist => "The comment you are looking for does not exist.",
+            Error::NotCommentAuthor    => "Only the author of a comment can change it.",
+            Error::NotModerator        => "Only moderators can do that.",
+            Error::BackersOnly         => "Only backers can comment on this project.",
+            Error::PledgeDoesNotExist  => "The pledge you are looking for does not exist.",
+            Error::ExceedsPledge       => "Cannot return more than what remains of the pledge.",
+            Error::InvalidReason(_)    => "Argument is not a valid refund reason.",
+            Error::InvalidTimestamp(_) => "Argument is not a valid date or timestamp.",
+            Error::InvalidUrl(_)       => "Argument is not a valid http or https URL.",
+            Error::WebhookDoesNotExist => "The webhook you are looking for does not exist.",
+            Error::InvalidEmail(_)     => "Argument is not a valid email address.",
+            Error::InvalidMailKind(_)  => "Argument is not a valid kind of mail.",
+            Error::InvalidToken        => "The unsubscribe token does not match any user.",
+            Error::ProjectClosed       => "The project's campaign has already ended.",
+            Error::InvalidPassword     => "Passwords must be at least 8 characters and at most 72 bytes long.",
+            Error::UserExists          => "An account with that name already exists.",
+            Error::EmailExists         => "An account with that email address already exists.",
+            Error::InvalidCredentials  => "The user name or password is incorrect.",
+            Error::InvalidSession      => "The token is not valid. It may have expired or been revoked; log in again.",
+            Error::NotAuthenticated    => "You need to log in first.",
+            Error::InvalidRole(_)      => "Argument is not a valid role.",
+            Error::InvalidQuery(_)     => "The GraphQL document is not valid.",
+            Error::InvalidFormat(_)    => "Argument is not a valid output format.",
+            Error::UnclosedQuote       => "A quote was not closed before the end of the line.",
+            Error::MissingColumn(_)    => "The file has no column for a required field.",
+            Error::InvalidMapping(_)   => "Argument is not a valid column mapping.",
+            Error::RowsRejected(_)     => "Some rows were rejected, so nothing was imported.",
+            Error::TooManyPledges(_)   => "Each user can only back each project once.",
+            Error::InvalidMix(_)       => "Argument is not a valid operation mix.",
+            Error::SchemaDrift(_)      => "The database differs from the generated models.",
+            Error::SchemaNotEmpty      => "The schema already has data. Restore snapshots into a freshly built schema.",
             Error::CardExists          => "The credit card number has already been used to back this project.",
+            Error::RewardExists        => "The project already offers a reward with that title.",
+            Error::ConcurrentChange    => "The data changed while the command was running, so nothing was changed. \
+                                           Check your input again and retry.",
         }
     }
 
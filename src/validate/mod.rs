@@ -2,11 +2,16 @@
 pub mod error;
 pub use self::error::{Error, Result};
 
+use chrono::naive::date::NaiveDate;
+use chrono::naive::datetime::NaiveDateTime;
+use rand::Rng;
 use regex::Regex;
 
 lazy_static! {
     static ref ALPHANUM: Regex = Regex::new(r"^[a-zA-Z0-9_-]+$").unwrap();
     static ref NUMTEXT: Regex = Regex::new(r"^[0-9]+$").unwrap();
+    static ref EMAIL: Regex = Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap();
+    static ref URL: Regex = Regex::new(r"^https?://[^\s/]+\S*$").unwrap();
 }
 
 /// Validates that the float is positive, and rounds it to two decimal places.
@@ -31,6 +36,66 @@ pub fn numtext(s: &str) -> Result<()> {
     regex(&NUMTEXT, s, Error::NotNumeric(s.to_owned()))
 }
 
+/// Validates that the string looks like an email address.
+pub fn email(s: &str) -> Result<()> {
+    regex(&EMAIL, s, Error::InvalidEmail(s.to_owned()))
+}
+
+/// Validates that a password is long enough to resist guessing, and short enough for bcrypt,
+/// which ignores everything past 72 bytes. The password itself is never included in the error.
+pub fn password(s: &str) -> Result<()> {
+    if s.chars().count() < 8 || s.len() > 72 {
+        Err(Error::InvalidPassword)
+    } else {
+        Ok(())
+    }
+}
+
+/// Validates that the string is an http or https URL with a host.
+pub fn url(s: &str) -> Result<()> {
+    regex(&URL, s, Error::InvalidUrl(s.to_owned()))
+}
+
+/// Validates that the string contains at least one non-whitespace character.
+pub fn not_blank(s: &str) -> Result<()> {
+    if s.trim().is_empty() {
+        Err(Error::Blank)
+    } else {
+        Ok(())
+    }
+}
+
+/// Validates that the string contains no control characters other than newlines and tabs.
+pub fn printable(s: &str) -> Result<()> {
+    if s.chars().any(|ch| ch.is_control() && ch != '\n' && ch != '\t') {
+        Err(Error::NotPrintable(s.to_owned()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Validates that the string contains no control characters, including newlines and tabs.
+pub fn single_line(s: &str) -> Result<()> {
+    if s.chars().any(|ch| ch.is_control()) {
+        Err(Error::NotPrintable(s.to_owned()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Parses a date (YYYY-MM-DD) or timestamp (YYYY-MM-DD HH:MM:SS).
+/// Dates are interpreted as midnight at the start of the day.
+pub fn timestamp(s: &str) -> Result<NaiveDateTime> {
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S") {
+        return Ok(datetime);
+    }
+
+    match NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        Ok(date) => Ok(date.and_hms(0, 0, 0)),
+        Err(_) => Err(Error::InvalidTimestamp(s.to_owned())),
+    }
+}
+
 /// Validates that the string matches the provided regex.
 pub fn regex(reg: &Regex, s: &str, error: Error) -> Result<()> {
     if reg.is_match(s) {
@@ -40,9 +105,10 @@ pub fn regex(reg: &Regex, s: &str, error: Error) -> Result<()> {
     }
 }
 
-/// Validates that the string length is between min and max, inclusive.
+/// Validates that the string length is between min and max, inclusive. Length is counted in
+/// characters rather than bytes, as `char_length` counts it in the schema's checks.
 pub fn length(s: &str, min: i32, max: i32) -> Result<()> {
-    let len = s.len();
+    let len = s.chars().count();
     if min as usize <= len && len <= max as usize {
         Ok(())
     } else {
@@ -83,3 +149,89 @@ pub fn luhn10(s: &str) -> Result<()> {
         Err(Error::NotLuhn10(s.to_owned()))
     }
 }
+
+/// Returns the check digit that makes a numerical string pass the Luhn-10 test when appended to it.
+pub fn luhn10_check_digit(s: &str) -> Result<u8> {
+    try!(numtext(s));
+
+    // The check digit takes the rightmost place, so doubling starts with the last digit given.
+    let mut alt = true;
+    let mut sum = 0u32;
+    for ch in s.chars().rev() {
+        let mut luhn = ch.to_digit(10).unwrap();
+        if alt {
+            luhn *= 2;
+            if luhn > 9 { luhn -= 9; }
+        }
+        sum += luhn;
+        alt = !alt;
+    }
+
+    Ok(((10 - sum % 10) % 10) as u8)
+}
+
+/// A credit card brand, which decides how its card numbers start and how long they are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Brand {
+    Visa,
+    Mastercard,
+    Amex,
+    Discover,
+}
+
+impl Brand {
+    /// Returns every brand, from the most to the least common.
+    pub fn all() -> &'static [Brand] {
+        &[Brand::Visa, Brand::Mastercard, Brand::Amex, Brand::Discover]
+    }
+
+    /// Returns the number of digits in the brand's card numbers.
+    pub fn length(&self) -> usize {
+        match *self {
+            Brand::Amex => 15,
+            _ => 16,
+        }
+    }
+
+    /// Returns the brand that issues a card number, judging by its prefix and length.
+    pub fn of(card: &str) -> Option<Brand> {
+        if numtext(card).is_err() {
+            return None;
+        }
+
+        let prefix = |n: usize| if card.len() >= n { card[..n].parse::<u32>().unwrap() } else { 0 };
+        let brand = match (prefix(1), prefix(2), prefix(4)) {
+            (4, _, _) => Brand::Visa,
+            (_, 51...55, _) | (_, _, 2221...2720) => Brand::Mastercard,
+            (_, 34, _) | (_, 37, _) => Brand::Amex,
+            (_, 65, _) | (_, _, 6011) => Brand::Discover,
+            _ => return None,
+        };
+
+        if card.len() == brand.length() { Some(brand) } else { None }
+    }
+
+    /// Returns a prefix that the brand issues card numbers under.
+    fn prefix<R: Rng>(&self, rng: &mut R) -> String {
+        match *self {
+            Brand::Visa => "4".to_owned(),
+            Brand::Mastercard if rng.gen_weighted_bool(5) => rng.gen_range(2221, 2721).to_string(),
+            Brand::Mastercard => rng.gen_range(51, 56).to_string(),
+            Brand::Amex => if rng.gen() { "34" } else { "37" }.to_owned(),
+            Brand::Discover => if rng.gen() { "6011" } else { "65" }.to_owned(),
+        }
+    }
+}
+
+/// Generates a random card number of the brand that passes the Luhn-10 test, for test and demo data.
+/// This is the counterpart to `luhn10`: every number it makes is accepted there.
+pub fn luhn10_card<R: Rng>(rng: &mut R, brand: Brand) -> String {
+    let mut card = brand.prefix(rng);
+    while card.len() < brand.length() - 1 {
+        card.push((b'0' + rng.gen_range(0, 10)) as char);
+    }
+
+    let check = luhn10_check_digit(&card).unwrap();
+    card.push((b'0' + check) as char);
+    card
+}
@@ -0,0 +1,345 @@
+//! Module for notifying integrators of changes through signed webhooks.
+//!
+//! Webhooks are registered for a single project, or for every project. Changes queue a
+//! delivery for each matching webhook in the same transaction as the change itself, so
+//! that nothing is lost if delivery fails. Pending deliveries are sent with
+//! `WebhookDelivery::deliver_pending`, and retried with exponential backoff. Each run claims
+//! the deliveries it sends before sending them, so that runs at the same time never send one twice.
+//!
+//! Each delivery is a JSON body of the form `{"event": ..., "data": {...}}`, signed with
+//! an HMAC-SHA256 of the body using the webhook's shared secret:
+//!
+//! ```text
+//! X-Kickstarter-Event: pledge.created
+//! X-Kickstarter-Delivery: 42
+//! X-Kickstarter-Signature: sha256=<hex digest>
+//! ```
+pub use models::{Webhook, WebhookAttempt, WebhookDelivery};
+
+use {validate, Client, Result};
+use db::{column, table};
+use models::{Audit, Pledge, Project};
+
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::Sha256;
+use hyper;
+use hyper::header::Headers;
+use hyper::status::StatusClass;
+use rustc_serialize::hex::ToHex;
+use rustc_serialize::json::{Json, ToJson};
+use rustorm::dao::{IsDao, Value};
+use rustorm::query::{Equality, Query};
+
+use std::cmp;
+use std::collections::BTreeMap;
+use std::convert::From;
+use std::io::Read;
+use std::time::Duration;
+
+/// Sent whenever a project is created. Only global webhooks can receive it.
+pub const PROJECT_CREATED:   &'static str = "project.created";
+/// Sent when a pledge first brings a project's total to its goal.
+pub const PROJECT_FUNDED:    &'static str = "project.funded";
+/// Sent when a campaign closes having reached its goal.
+pub const PROJECT_SUCCEEDED: &'static str = "project.succeeded";
+/// Sent when a campaign closes short of its goal.
+pub const PROJECT_FAILED:    &'static str = "project.failed";
+/// Sent whenever a project is backed.
+pub const PLEDGE_CREATED:    &'static str = "pledge.created";
+/// Sent whenever money is returned from a pledge, through a refund or chargeback.
+pub const PLEDGE_REFUNDED:   &'static str = "pledge.refunded";
+/// Sent whenever a pledge is withdrawn.
+pub const PLEDGE_CANCELLED:  &'static str = "pledge.cancelled";
+
+/// Header carrying the name of the event.
+pub const EVENT_HEADER:      &'static str = "X-Kickstarter-Event";
+/// Header carrying the delivery ID, which stays the same across retries.
+pub const DELIVERY_HEADER:   &'static str = "X-Kickstarter-Delivery";
+/// Header carrying the HMAC-SHA256 signature of the body.
+pub const SIGNATURE_HEADER:  &'static str = "X-Kickstarter-Signature";
+
+/// Deliveries are abandoned after this many failed attempts.
+pub const MAX_ATTEMPTS: i32 = 8;
+/// Seconds to wait before the first retry. Each retry after that waits twice as long.
+const BASE_BACKOFF: i64 = 30;
+/// Longest response body kept in the delivery log for failed attempts.
+const MAX_ERROR_LEN: usize = 200;
+/// Seconds to wait for a receiver to accept a delivery, or to answer it, before giving up.
+const SEND_TIMEOUT: u64 = 10;
+/// Seconds that a claimed delivery is held by the run sending it. If that run dies before
+/// recording an attempt, the delivery is picked up again once the claim runs out.
+const CLAIM_TIMEOUT: i64 = 300;
+
+impl Webhook {
+
+    /// Registers a webhook for a single project, or for every project if none is provided.
+    /// The secret is shared with the receiver, who uses it to verify signatures.
+    pub fn register(client: &Client, url: &str, secret: &str, project_name: Option<&str>) -> Result<Webhook> {
+        try!(validate::url(url));
+        try!(validate::length(secret, 16, 256));
+
+        let project_id = match project_name {
+            Some(name) => Some(try!(Project::get(client, name)).project_id),
+            None => None,
+        };
+
+        client.transaction(|| {
+            let mut query = Query::insert();
+            query.set(column::url, &url)
+                .set(column::secret, &secret);
+
+            if let Some(ref project_id) = project_id {
+                query.set(column::project_id, project_id);
+            }
+
+            let webhook: Webhook = try!(query
+                .into_table(&client.table(table::webhook))
+                .return_all()
+                .collect_one(client.db()));
+
+            try!(Audit::record(client, "create", table::webhook, &webhook.webhook_id.to_string(),
+                               None, Some(webhook.redacted_json())));
+            Ok(webhook)
+        })
+    }
+
+    /// Removes a webhook, along with its pending deliveries and delivery log.
+    pub fn remove(client: &Client, webhook_id: i32) -> Result<Webhook> {
+        let sql = format!("DELETE FROM {} WHERE webhook_id = $1 RETURNING *", client.table(table::webhook));
+
+        client.transaction(|| {
+            let mut daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::I32(webhook_id)]));
+            let webhook = match daos.pop() {
+                Some(dao) => Webhook::from_dao(&dao),
+                None => return Err(From::from(validate::Error::WebhookDoesNotExist)),
+            };
+
+            try!(Audit::record(client, "delete", table::webhook, &webhook_id.to_string(),
+                               Some(webhook.redacted_json()), None));
+            Ok(webhook)
+        })
+    }
+
+    /// Returns every registered webhook, oldest first.
+    pub fn list(client: &Client) -> Result<Vec<Webhook>> {
+        let sql = format!("SELECT * FROM {} ORDER BY webhook_id", client.table(table::webhook));
+        let daos = try!(client.db().execute_sql_with_return(&sql, &vec![]));
+        Ok(daos.iter().map(Webhook::from_dao).collect())
+    }
+
+    /// Signs a body with the shared secret, in the form sent in the signature header.
+    pub fn sign(secret: &str, body: &str) -> String {
+        let mut hmac = Hmac::new(Sha256::new(), secret.as_bytes());
+        hmac.input(body.as_bytes());
+        format!("sha256={}", hmac.result().code().to_hex())
+    }
+
+    /// Queues a delivery of the event to every active webhook for the project, and to every
+    /// global webhook. Callers should run this in the same transaction as the change being
+    /// described. Returns the number of deliveries queued.
+    pub fn enqueue(client: &Client, project_id: i32, event: &str, data: Json) -> Result<usize> {
+        let mut body = BTreeMap::new();
+        body.insert("event".to_owned(), event.to_json());
+        body.insert("data".to_owned(), data);
+
+        let sql = format!("INSERT INTO {} (webhook_id, event, payload) \
+                           SELECT webhook_id, $1, $2 FROM {} \
+                           WHERE active AND (project_id IS NULL OR project_id = $3)",
+                          client.table(table::webhook_delivery),
+                          client.table(table::webhook));
+
+        let count = try!(client.db().execute_sql(&sql, &vec![Value::String(event.to_owned()),
+                                                            Value::String(Json::Object(body).to_string()),
+                                                            Value::I32(project_id)]));
+        Ok(count)
+    }
+
+    /// Queues a project event.
+    pub fn enqueue_project(client: &Client, event: &str, project: &Project) -> Result<usize> {
+        let mut data = BTreeMap::new();
+        data.insert("project_id".to_owned(), project.project_id.to_json());
+        data.insert("project".to_owned(), project.name.to_json());
+        data.insert("goal".to_owned(), project.goal.to_json());
+        Webhook::enqueue(client, project.project_id, event, Json::Object(data))
+    }
+
+    /// Queues a pledge event for the amount of money that moved. Card numbers are never sent.
+    pub fn enqueue_pledge(client: &Client, event: &str, pledge: &Pledge, amount: f64) -> Result<usize> {
+        let mut data = BTreeMap::new();
+        data.insert("project_id".to_owned(), pledge.project_id.to_json());
+        data.insert("user_id".to_owned(), pledge.user_id.to_json());
+        data.insert("amount".to_owned(), amount.to_json());
+        data.insert("pledged".to_owned(), pledge.net_amount().to_json());
+        Webhook::enqueue(client, pledge.project_id, event, Json::Object(data))
+    }
+
+    /// Queues a funded event if the pledge just brought its project's total up to the goal.
+    pub fn enqueue_if_funded(client: &Client, pledge: &Pledge) -> Result<usize> {
+        let sql = format!("SELECT pr.*, SUM(pl.amount - pl.refunded) AS total FROM {} \
+                           INNER JOIN {} ON pl.project_id = pr.project_id \
+                           WHERE pr.project_id = $1 GROUP BY pr.project_id",
+                          client.table_abbr(table::project),
+                          client.table_abbr(table::pledge));
+
+        let mut daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::I32(pledge.project_id)]));
+        let dao = match daos.pop() {
+            Some(dao) => dao,
+            None => return Ok(0),
+        };
+
+        let project = Project::from_dao(&dao);
+        let total: f64 = dao.get("total");
+
+        if total >= project.goal && total - pledge.net_amount() < project.goal {
+            Webhook::enqueue_project(client, PROJECT_FUNDED, &project)
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// Returns the webhook as JSON with its secret removed, for the audit log and other readers.
+    pub fn redacted_json(&self) -> Json {
+        let mut json = self.to_json();
+        if let Json::Object(ref mut map) = json {
+            map.remove(column::secret);
+        }
+        json
+    }
+}
+
+impl WebhookDelivery {
+
+    /// Sends every pending delivery that is due, oldest first, and records each attempt.
+    /// Failed deliveries are retried with exponential backoff until `MAX_ATTEMPTS` is reached.
+    /// Returns the deliveries that were attempted, as updated.
+    pub fn deliver_pending(client: &Client) -> Result<Vec<WebhookDelivery>> {
+        // Claim the due deliveries by putting off their next attempt, skipping any that another
+        // run is claiming at the same moment, and commit that before anything is sent.
+        let sql = format!("WITH due AS ( \
+                               SELECT delivery_id FROM {delivery} \
+                               WHERE status = 'pending' AND next_attempt <= localtimestamp \
+                               ORDER BY delivery_id FOR UPDATE SKIP LOCKED) \
+                           UPDATE {delivery} de SET next_attempt = localtimestamp + $1 * interval '1 second' \
+                           FROM due, {webhook} we \
+                           WHERE de.delivery_id = due.delivery_id AND we.webhook_id = de.webhook_id \
+                           RETURNING de.*, we.url, we.secret",
+                          delivery = client.table(table::webhook_delivery),
+                          webhook = client.table(table::webhook));
+
+        let daos = try!(client.transaction(|| {
+            Ok(try!(client.db().execute_sql_with_return(&sql, &vec![Value::F64(CLAIM_TIMEOUT as f64)])))
+        }));
+
+        let mut claimed: Vec<(WebhookDelivery, String, String)> = daos.iter()
+            .map(|dao| (WebhookDelivery::from_dao(dao), dao.get(column::url), dao.get(column::secret)))
+            .collect();
+        claimed.sort_by(|a, b| a.0.delivery_id.cmp(&b.0.delivery_id));
+
+        let mut attempted = vec![];
+        for &(ref delivery, ref url, ref secret) in &claimed {
+            let (status_code, error) = delivery.send(url, secret);
+            attempted.push(try!(delivery.record_attempt(client, status_code, error)));
+        }
+
+        Ok(attempted)
+    }
+
+    /// Posts the delivery to the URL. Returns the response status, if one was received,
+    /// and an error message if the delivery failed.
+    fn send(&self, url: &str, secret: &str) -> (Option<i32>, Option<String>) {
+        let mut headers = Headers::new();
+        headers.set_raw("Content-Type", vec![b"application/json".to_vec()]);
+        headers.set_raw(EVENT_HEADER, vec![self.event.clone().into_bytes()]);
+        headers.set_raw(DELIVERY_HEADER, vec![self.delivery_id.to_string().into_bytes()]);
+        headers.set_raw(SIGNATURE_HEADER, vec![Webhook::sign(secret, &self.payload).into_bytes()]);
+
+        let mut http = hyper::Client::new();
+        http.set_read_timeout(Some(Duration::from_secs(SEND_TIMEOUT)));
+        http.set_write_timeout(Some(Duration::from_secs(SEND_TIMEOUT)));
+        match http.post(url).headers(headers).body(&self.payload[..]).send() {
+            Ok(mut res) => {
+                let code = res.status.to_u16() as i32;
+                if res.status.class() == StatusClass::Success {
+                    (Some(code), None)
+                } else {
+                    let mut body = String::new();
+                    let _ = res.read_to_string(&mut body);
+                    let body: String = body.chars().take(MAX_ERROR_LEN).collect();
+                    (Some(code), Some(format!("{}: {}", res.status, body.trim())))
+                }
+            }
+            Err(err) => (None, Some(err.to_string())),
+        }
+    }
+
+    /// Logs an attempt and schedules the delivery's next step.
+    fn record_attempt(&self, client: &Client, status_code: Option<i32>, error: Option<String>) -> Result<WebhookDelivery> {
+        client.transaction(|| {
+            let mut query = Query::insert();
+            query.set(column::delivery_id, &self.delivery_id);
+
+            if let Some(ref status_code) = status_code {
+                query.set(column::status_code, status_code);
+            }
+
+            if let Some(ref error) = error {
+                query.set(column::error, error);
+            }
+
+            let _: WebhookAttempt = try!(query
+                .into_table(&client.table(table::webhook_attempt))
+                .return_all()
+                .collect_one(client.db()));
+
+            let attempts = self.attempts + 1;
+            let (sql, params) = if error.is_none() {
+                (format!("UPDATE {} SET status = 'delivered', attempts = $1, date_delivered = localtimestamp \
+                          WHERE delivery_id = $2 RETURNING *", client.table(table::webhook_delivery)),
+                 vec![Value::I32(attempts), Value::I32(self.delivery_id)])
+            } else if attempts >= MAX_ATTEMPTS {
+                (format!("UPDATE {} SET status = 'failed', attempts = $1 \
+                          WHERE delivery_id = $2 RETURNING *", client.table(table::webhook_delivery)),
+                 vec![Value::I32(attempts), Value::I32(self.delivery_id)])
+            } else {
+                (format!("UPDATE {} SET attempts = $1, next_attempt = localtimestamp + $2 * interval '1 second' \
+                          WHERE delivery_id = $3 RETURNING *", client.table(table::webhook_delivery)),
+                 vec![Value::I32(attempts), Value::F64(WebhookDelivery::backoff(attempts) as f64),
+                      Value::I32(self.delivery_id)])
+            };
+
+            let mut daos = try!(client.db().execute_sql_with_return(&sql, &params));
+            Ok(WebhookDelivery::from_dao(&daos.pop().unwrap()))
+        })
+    }
+
+    /// Returns the number of seconds to wait before retrying a delivery that has failed
+    /// the provided number of times.
+    pub fn backoff(attempts: i32) -> i64 {
+        BASE_BACKOFF << (cmp::max(attempts, 1) - 1) as u32
+    }
+
+    /// Returns every delivery made to a webhook, oldest first, along with its attempts.
+    pub fn list(client: &Client, webhook_id: i32) -> Result<Vec<WebhookDelivery>> {
+        let mut deliveries: Vec<WebhookDelivery> = try!(Query::select_all()
+            .from_table(&client.table(table::webhook_delivery))
+            .filter(column::webhook_id, Equality::EQ, &webhook_id)
+            .collect(client.db()));
+
+        let sql = format!("SELECT wa.* FROM {} wa \
+                           INNER JOIN {} de ON de.delivery_id = wa.delivery_id \
+                           WHERE de.webhook_id = $1 ORDER BY wa.attempt_id",
+                          client.table(table::webhook_attempt),
+                          client.table(table::webhook_delivery));
+
+        let daos = try!(client.db().execute_sql_with_return(&sql, &vec![Value::I32(webhook_id)]));
+        for attempt in daos.iter().map(WebhookAttempt::from_dao) {
+            if let Some(delivery) = deliveries.iter_mut().find(|d| d.delivery_id == attempt.delivery_id) {
+                delivery.webhook_attempt.push(attempt);
+            }
+        }
+
+        deliveries.sort_by(|a, b| a.delivery_id.cmp(&b.delivery_id));
+        Ok(deliveries)
+    }
+}
@@ -0,0 +1,201 @@
+//! Module for writing simple spreadsheets in the Office Open XML (.xlsx) format.
+//!
+//! A workbook is a zip archive of XML parts. Only what a single sheet of text and numbers
+//! needs is written, with strings stored inline, and the archive entries are stored without
+//! compression, so that no zip or deflate dependency is needed.
+
+/// A spreadsheet cell.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cell {
+    Text(String),
+    Number(f64),
+    Empty,
+}
+
+const CONTENT_TYPES: &'static str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+<Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+<Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+<Override PartName=\"/xl/workbook.xml\" \
+ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml\"/>\
+<Override PartName=\"/xl/worksheets/sheet1.xml\" \
+ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml\"/>\
+</Types>";
+
+const ROOT_RELS: &'static str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+<Relationship Id=\"rId1\" \
+Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" \
+Target=\"xl/workbook.xml\"/>\
+</Relationships>";
+
+const WORKBOOK_RELS: &'static str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+<Relationship Id=\"rId1\" \
+Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet\" \
+Target=\"worksheets/sheet1.xml\"/>\
+</Relationships>";
+
+/// Returns a workbook with a single sheet holding the rows.
+pub fn workbook(sheet_name: &str, rows: &[Vec<Cell>]) -> Vec<u8> {
+    let workbook = format!("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+                            <workbook xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" \
+                            xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\">\
+                            <sheets><sheet name=\"{}\" sheetId=\"1\" r:id=\"rId1\"/></sheets></workbook>",
+                           escape(&sheet_name.chars().take(31).collect::<String>()));
+
+    let mut zip = Zip::new();
+    zip.add("[Content_Types].xml", CONTENT_TYPES.as_bytes());
+    zip.add("_rels/.rels", ROOT_RELS.as_bytes());
+    zip.add("xl/workbook.xml", workbook.as_bytes());
+    zip.add("xl/_rels/workbook.xml.rels", WORKBOOK_RELS.as_bytes());
+    zip.add("xl/worksheets/sheet1.xml", sheet(rows).as_bytes());
+    zip.finish()
+}
+
+/// Returns the XML of a worksheet.
+fn sheet(rows: &[Vec<Cell>]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+                                <worksheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\">\
+                                <sheetData>");
+
+    for (i, row) in rows.iter().enumerate() {
+        xml.push_str(&format!("<row r=\"{}\">", i + 1));
+        for (j, cell) in row.iter().enumerate() {
+            let reference = format!("{}{}", column_name(j), i + 1);
+            match *cell {
+                Cell::Text(ref text) => xml.push_str(&format!("<c r=\"{}\" t=\"inlineStr\"><is><t>{}</t></is></c>",
+                                                              reference, escape(text))),
+                Cell::Number(n) => xml.push_str(&format!("<c r=\"{}\"><v>{}</v></c>", reference, n)),
+                Cell::Empty => (),
+            }
+        }
+        xml.push_str("</row>");
+    }
+
+    xml.push_str("</sheetData></worksheet>");
+    xml
+}
+
+/// Returns the letters naming a column, counting from 0: A, B, ..., Z, AA, AB, ...
+pub fn column_name(mut index: usize) -> String {
+    let mut name = vec![];
+    loop {
+        name.push((b'A' + (index % 26) as u8) as char);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.into_iter().rev().collect()
+}
+
+/// Escapes text for XML, dropping control characters that XML can't hold.
+fn escape(s: &str) -> String {
+    s.chars()
+        .filter(|&c| !c.is_control() || c == '\n' || c == '\t' || c == '\r')
+        .map(|c| match c {
+            '&' => "&amp;".to_owned(),
+            '<' => "&lt;".to_owned(),
+            '>' => "&gt;".to_owned(),
+            '"' => "&quot;".to_owned(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// A zip archive whose entries are stored uncompressed.
+struct Zip {
+    data: Vec<u8>,
+    directory: Vec<u8>,
+    entries: u16,
+}
+
+impl Zip {
+    fn new() -> Zip {
+        Zip { data: vec![], directory: vec![], entries: 0 }
+    }
+
+    /// Adds a file to the archive.
+    fn add(&mut self, name: &str, contents: &[u8]) {
+        let offset = self.data.len() as u32;
+        let crc = crc32(contents);
+        let size = contents.len() as u32;
+
+        // Local file header: version 2.0, no flags, stored, with a fixed 1980-01-01 timestamp.
+        push_u32(&mut self.data, 0x04034b50);
+        push_u16(&mut self.data, 20);
+        push_u16(&mut self.data, 0);
+        push_u16(&mut self.data, 0);
+        push_u16(&mut self.data, 0);
+        push_u16(&mut self.data, 0x21);
+        push_u32(&mut self.data, crc);
+        push_u32(&mut self.data, size);
+        push_u32(&mut self.data, size);
+        push_u16(&mut self.data, name.len() as u16);
+        push_u16(&mut self.data, 0);
+        self.data.extend(name.as_bytes());
+        self.data.extend(contents);
+
+        // Central directory entry.
+        push_u32(&mut self.directory, 0x02014b50);
+        push_u16(&mut self.directory, 20);
+        push_u16(&mut self.directory, 20);
+        push_u16(&mut self.directory, 0);
+        push_u16(&mut self.directory, 0);
+        push_u16(&mut self.directory, 0);
+        push_u16(&mut self.directory, 0x21);
+        push_u32(&mut self.directory, crc);
+        push_u32(&mut self.directory, size);
+        push_u32(&mut self.directory, size);
+        push_u16(&mut self.directory, name.len() as u16);
+        push_u16(&mut self.directory, 0);
+        push_u16(&mut self.directory, 0);
+        push_u16(&mut self.directory, 0);
+        push_u16(&mut self.directory, 0);
+        push_u32(&mut self.directory, 0);
+        push_u32(&mut self.directory, offset);
+        self.directory.extend(name.as_bytes());
+
+        self.entries += 1;
+    }
+
+    /// Returns the archive, with the central directory and its end record after the files.
+    fn finish(mut self) -> Vec<u8> {
+        let offset = self.data.len() as u32;
+        let size = self.directory.len() as u32;
+        self.data.extend(&self.directory);
+
+        push_u32(&mut self.data, 0x06054b50);
+        push_u16(&mut self.data, 0);
+        push_u16(&mut self.data, 0);
+        push_u16(&mut self.data, self.entries);
+        push_u16(&mut self.data, self.entries);
+        push_u32(&mut self.data, size);
+        push_u32(&mut self.data, offset);
+        push_u16(&mut self.data, 0);
+        self.data
+    }
+}
+
+fn push_u16(buf: &mut Vec<u8>, n: u16) {
+    buf.push(n as u8);
+    buf.push((n >> 8) as u8);
+}
+
+fn push_u32(buf: &mut Vec<u8>, n: u32) {
+    push_u16(buf, n as u16);
+    push_u16(buf, (n >> 16) as u16);
+}
+
+/// Returns the CRC-32 checksum that zip archives use.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffffffffu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
@@ -1,11 +1,17 @@
+extern crate hyper;
 extern crate kickstarter;
 extern crate postgres;
+extern crate rand;
+extern crate rustc_serialize;
 extern crate rustorm;
+extern crate rustyline;
 
 mod models;
 
 use kickstarter::Client;
+use kickstarter::account::Principal;
 use kickstarter::models::{Pledge, Project};
+use kickstarter::testing::Ephemeral;
 
 const DEFAULT_CONFIG: &'static str = "tests/data/config.toml";
 
@@ -25,8 +31,14 @@ static CARDS: &'static [&'static str] = &["341468752760899",
 
 static CONTRIBUTIONS: &'static [f64] = &[100f64, 200f64, 3000f64];
 
-pub fn init_client() -> Client {
-    Client::with_config(DEFAULT_CONFIG, true, false).unwrap_or_else(|e| {
+// Test Admin
+static ADMIN: &'static str = "Overseer";
+static PASSWORD: &'static str = "correct horse battery";
+
+// Returns a client on a freshly built schema of its own, which is dropped along with it,
+// so that tests can run in parallel.
+pub fn init_client() -> Ephemeral {
+    Ephemeral::with_config(DEFAULT_CONFIG).unwrap_or_else(|e| {
         panic!("ERROR: {}\n\
                 Could not connect to the test database. \
                 Make sure that '{}' exists and is pointing to an existing database.", 
@@ -35,7 +47,7 @@ pub fn init_client() -> Client {
 }
 
 // Returns the client and a list of created projects.
-fn init_test_projects() -> (Client, Vec<Project>) {
+fn init_test_projects() -> (Ephemeral, Vec<Project>) {
     let client = init_client();
 
     let mut projects = vec![];
@@ -57,3 +69,11 @@ fn init_test_pledges(client: &Client) -> Vec<Pledge> {
 
     pledges
 }
+
+// Registers the schema's first admin and returns its principal.
+fn init_admin(client: &Client) -> Principal {
+    let email = format!("{}@example.com", ADMIN.to_lowercase());
+    let _ = client.register_admin(ADMIN, &email, PASSWORD).unwrap();
+    let (principal, _) = client.login(ADMIN, PASSWORD).unwrap();
+    principal
+}
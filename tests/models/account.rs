@@ -0,0 +1,189 @@
+use ::{init_client, init_test_projects, NAMES, CARDS, CONTRIBUTIONS};
+
+use kickstarter::{validate, Client, Error};
+use kickstarter::account::{self, Principal, Role};
+use kickstarter::audit::Filter;
+use kickstarter::db::table;
+use kickstarter::models::User;
+
+const USER: &'static str = "Johnnyboy";
+const EMAIL: &'static str = "johnnyboy@example.com";
+const PASSWORD: &'static str = "correct horse battery";
+
+/// Registers the test user and logs in.
+fn init_account(client: &Client) -> (Principal, String) {
+    let _ = client.register(USER, EMAIL, PASSWORD).unwrap();
+    client.login(USER, PASSWORD).unwrap()
+}
+
+#[test]
+fn hash_and_verify() {
+    let hash = account::hash_password(PASSWORD).unwrap();
+    assert!(hash.starts_with("bcrypt$"));
+    assert!(!hash.contains(PASSWORD));
+
+    assert!(account::verify_password(PASSWORD, &hash));
+    assert!(!account::verify_password("wrong horse battery", &hash));
+    assert!(!account::verify_password(PASSWORD, "bcrypt$10$nothex$nothex"));
+
+    // Each hash is salted differently.
+    assert!(hash != account::hash_password(PASSWORD).unwrap());
+}
+
+#[test]
+fn register_account() {
+    let client = init_client();
+    let user = client.register(USER, EMAIL, PASSWORD).unwrap();
+    assert_eq!(USER, user.name);
+    assert_eq!(Some(EMAIL.to_owned()), user.email);
+    assert!(user.password_hash.is_some());
+
+    let result = client.register(USER, "other@example.com", PASSWORD);
+    match result {
+        Err(Error::InvalidData(validate::Error::UserExists)) => (),
+        _ => panic!(result),
+    }
+
+    let result = client.register("Margie", EMAIL, PASSWORD);
+    match result {
+        Err(Error::InvalidData(validate::Error::EmailExists)) => (),
+        _ => panic!(result),
+    }
+
+    let result = client.register("Margie", "margie@example.com", "short");
+    match result {
+        Err(Error::InvalidData(validate::Error::InvalidPassword)) => (),
+        _ => panic!(result),
+    }
+}
+
+#[test]
+fn credentials_are_not_audited() {
+    let client = init_client();
+    let _ = client.register_admin(USER, EMAIL, PASSWORD).unwrap();
+    let (principal, _) = client.login(USER, PASSWORD).unwrap();
+
+    let entries = client.audit_log(&principal, &Filter { entity: Some(table::user.to_owned()), ..Filter::default() }).unwrap();
+    assert_eq!(1, entries.len());
+
+    let after = entries[0].after_value.clone().unwrap();
+    assert!(after.contains(USER));
+    assert!(!after.contains("password_hash"));
+    assert!(!after.contains("unsubscribe_token"));
+}
+
+#[test]
+fn login_and_authenticate() {
+    let client = init_client();
+    let (principal, token) = init_account(&client);
+    assert_eq!(USER, principal.name());
+
+    // Even the first account on a new schema is a backer.
+    assert_eq!(Role::Backer, principal.role());
+
+    let authenticated = client.authenticate(&token).unwrap();
+    assert_eq!(principal, authenticated);
+
+    let tokens = client.list_tokens(&principal).unwrap();
+    assert_eq!(1, tokens.len());
+    assert!(tokens[0].digest != token);
+    assert!(tokens[0].date_used.is_some());
+}
+
+#[test]
+fn register_admin_once() {
+    let client = init_client();
+    let _ = init_account(&client);
+
+    let admin = client.register_admin("Margie", "margie@example.com", PASSWORD).unwrap();
+    assert_eq!(Role::Admin, admin.role());
+
+    let result = client.register_admin("Shakey_Graves", "shakey@example.com", PASSWORD);
+    match result {
+        Err(Error::PermissionDenied(_)) => (),
+        _ => panic!(result),
+    }
+    assert!(client.get_user("Shakey_Graves").is_err());
+}
+
+#[test]
+fn login_invalid_credentials() {
+    let client = init_client();
+    let _ = init_account(&client);
+
+    for &(user, password) in &[(USER, "wrong horse battery"), ("Nobody", PASSWORD)] {
+        let result = client.login(user, password);
+        match result {
+            Err(Error::InvalidData(validate::Error::InvalidCredentials)) => (),
+            _ => panic!(result),
+        }
+    }
+
+    // Users created implicitly have no password, and cannot log in.
+    let _ = User::upsert(&client, "Margie").unwrap();
+    let result = client.login("Margie", PASSWORD);
+    match result {
+        Err(Error::InvalidData(validate::Error::InvalidCredentials)) => (),
+        _ => panic!(result),
+    }
+}
+
+#[test]
+fn logout_revokes_token() {
+    let client = init_client();
+    let (principal, token) = init_account(&client);
+    let (_, other) = client.login(USER, PASSWORD).unwrap();
+
+    let revoked = client.logout(&token).unwrap();
+    assert!(revoked.date_revoked.is_some());
+
+    let result = client.authenticate(&token);
+    match result {
+        Err(Error::InvalidData(validate::Error::InvalidSession)) => (),
+        _ => panic!(result),
+    }
+
+    // Other tokens keep working until they are revoked too.
+    assert!(client.authenticate(&other).is_ok());
+    assert_eq!(1, client.logout_everywhere(&principal).unwrap().len());
+    assert!(client.authenticate(&other).is_err());
+    assert!(client.list_tokens(&principal).unwrap().is_empty());
+}
+
+#[test]
+fn expired_token() {
+    let client = init_client();
+    let (_, token) = init_account(&client);
+
+    let sql = format!("UPDATE {} SET date_expires = localtimestamp - interval '1 day'",
+                      client.table(table::token));
+    let _ = client.db().execute_sql(&sql, &vec![]).unwrap();
+
+    let result = client.authenticate(&token);
+    match result {
+        Err(Error::InvalidData(validate::Error::InvalidSession)) => (),
+        _ => panic!(result),
+    }
+}
+
+#[test]
+fn back_as_principal() {
+    let (client, _) = init_test_projects();
+    let (principal, _) = init_account(&client);
+
+    let pledge = client.back_project(&principal, NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+    assert_eq!(principal.user_id(), pledge.user_id);
+
+    let pledges = client.list_backed_projects(USER).unwrap();
+    assert_eq!(1, pledges.len());
+}
+
+#[test]
+fn unknown_token() {
+    let client = init_client();
+    let result = client.authenticate("not-a-token");
+    match result {
+        Err(Error::InvalidData(validate::Error::InvalidSession)) => (),
+        _ => panic!(result),
+    }
+}
@@ -0,0 +1,254 @@
+use ::{init_admin, init_client, init_test_projects, init_test_pledges, DEFAULT_CONFIG,
+        ADMIN, PASSWORD, NAMES, CARDS, NUM_PROJECTS, NUM_PLEDGES, USERS};
+
+use kickstarter::{validate, Client, Error};
+use kickstarter::api::{self, Reply, MAX_LIMIT};
+use kickstarter::audit::Filter;
+use kickstarter::db::table;
+use kickstarter::models::Audit;
+
+use hyper;
+use hyper::header::Headers;
+use hyper::method::Method;
+use hyper::status::StatusCode;
+use rustc_serialize::json::Json;
+
+use std::io::Read;
+
+/// Sends a request without a token.
+fn get(client: &Client, uri: &str) -> Reply {
+    api::handle(client, &Method::Get, uri, None, "")
+}
+
+/// Returns a string field of a JSON object.
+fn field<'a>(json: &'a Json, key: &str) -> &'a str {
+    json.find(key).and_then(|value| value.as_string()).unwrap()
+}
+
+/// Logs in as the admin and returns a token.
+fn admin_token(client: &Client) -> String {
+    let _ = init_admin(client);
+    client.login(ADMIN, PASSWORD).unwrap().1
+}
+
+#[test]
+fn list_projects_paginated() {
+    let (client, _) = init_test_projects();
+
+    let all = get(&client, "/projects");
+    assert_eq!(StatusCode::Ok, all.status);
+    assert_eq!(NUM_PROJECTS as u64, all.body.find("total").unwrap().as_u64().unwrap());
+    let all = all.body.find("items").unwrap().as_array().unwrap();
+    assert_eq!(NUM_PROJECTS, all.len());
+
+    let reply = get(&client, "/projects?offset=1&limit=2");
+    let items = reply.body.find("items").unwrap().as_array().unwrap();
+    assert_eq!(&all[1..3], &items[..]);
+
+    let reply = get(&client, "/projects?limit=100000");
+    assert_eq!(MAX_LIMIT as u64, reply.body.find("limit").unwrap().as_u64().unwrap());
+
+    let reply = get(&client, "/projects?offset=-1");
+    assert_eq!(StatusCode::BadRequest, reply.status);
+    assert!(field(&reply.body, "error").contains("numeric"));
+}
+
+#[test]
+fn project_pledges_mask_cards() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+
+    let reply = get(&client, &format!("/projects/{}", NAMES[0]));
+    assert_eq!(StatusCode::Ok, reply.status);
+    assert_eq!(NUM_PLEDGES as u64, reply.body.find("backers").unwrap().as_u64().unwrap());
+    assert_eq!(3300f64, reply.body.find("pledged").unwrap().as_f64().unwrap());
+
+    let reply = get(&client, &format!("/projects/{}/pledges", NAMES[0]));
+    let items = reply.body.find("items").unwrap().as_array().unwrap();
+    assert_eq!(NUM_PLEDGES, items.len());
+
+    let first = items.iter().find(|p| field(p, "user") == USERS[0]).unwrap();
+    assert_eq!("***********0899", field(first, "card"));
+    assert!(!reply.body.to_string().contains(CARDS[0]));
+
+    let reply = get(&client, &format!("/users/{}/pledges", USERS[0]));
+    let items = reply.body.find("items").unwrap().as_array().unwrap();
+    assert_eq!(NAMES[0], field(&items[0], "project"));
+}
+
+#[test]
+fn pledges_paginated() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+
+    let all = get(&client, &format!("/projects/{}/pledges", NAMES[0]));
+    let all = all.body.find("items").unwrap().as_array().unwrap();
+
+    let reply = get(&client, &format!("/projects/{}/pledges?offset=1&limit=1", NAMES[0]));
+    assert_eq!(NUM_PLEDGES as u64, reply.body.find("total").unwrap().as_u64().unwrap());
+    let items = reply.body.find("items").unwrap().as_array().unwrap();
+    assert_eq!(&all[1..2], &items[..]);
+
+    // The total is counted even past the last page.
+    let reply = get(&client, &format!("/projects/{}/pledges?offset=100", NAMES[0]));
+    assert_eq!(NUM_PLEDGES as u64, reply.body.find("total").unwrap().as_u64().unwrap());
+    assert!(reply.body.find("items").unwrap().as_array().unwrap().is_empty());
+
+    let reply = get(&client, &format!("/users/{}/pledges?limit=1", USERS[0]));
+    assert_eq!(1, reply.body.find("total").unwrap().as_u64().unwrap());
+    assert_eq!(StatusCode::NotFound, get(&client, "/projects/Missing_Project/pledges").status);
+}
+
+#[test]
+fn register_never_grants_admin() {
+    let client = init_client();
+
+    // Even on a schema without an admin, accounts registered over HTTP are backers.
+    let body = "{\"name\": \"Sally\", \"email\": \"sally@example.com\", \"password\": \"correct horse\"}";
+    let reply = api::handle(&client, &Method::Post, "/users", None, body);
+    assert_eq!(StatusCode::Created, reply.status);
+    assert_eq!("backer", field(&reply.body, "role"));
+}
+
+#[test]
+fn error_statuses() {
+    let client = init_client();
+
+    assert_eq!(StatusCode::NotFound, get(&client, "/projects/Missing_Project").status);
+    assert_eq!(StatusCode::NotFound, get(&client, "/users/Nobody").status);
+    assert_eq!(StatusCode::NotFound, get(&client, "/nowhere").status);
+    assert_eq!(StatusCode::MethodNotAllowed,
+               api::handle(&client, &Method::Put, "/projects", None, "").status);
+
+    let reply = api::handle(&client, &Method::Post, "/projects", None, "{\"name\": \"Nope\", \"goal\": 1}");
+    assert_eq!(StatusCode::Unauthorized, reply.status);
+
+    let reply = api::handle(&client, &Method::Post, "/users", None, "{\"name\": \"Sally\"}");
+    assert_eq!(StatusCode::BadRequest, reply.status);
+
+    let reply = api::handle(&client, &Method::Post, "/users", None,
+                            "{\"name\": \"Sally\", \"email\": \"sally\", \"password\": \"correct horse\"}");
+    assert_eq!(StatusCode::BadRequest, reply.status);
+
+    assert_eq!(StatusCode::Forbidden, api::status(&Error::PermissionDenied(String::new())));
+    assert_eq!(StatusCode::Conflict, api::status(&Error::InvalidData(validate::Error::CardExists)));
+    assert_eq!(StatusCode::Conflict, api::status(&Error::InvalidData(validate::Error::ConcurrentChange)));
+}
+
+#[test]
+fn register_login_and_back() {
+    let client = init_client();
+    let token = admin_token(&client);
+
+    let body = format!("{{\"name\": \"{}\", \"goal\": 500}}", NAMES[0]);
+    let reply = api::handle(&client, &Method::Post, "/projects", Some(&token), &body);
+    assert_eq!(StatusCode::Created, reply.status);
+    assert_eq!(NAMES[0], field(&reply.body, "name"));
+
+    let reply = api::handle(&client, &Method::Post, "/projects", Some(&token), &body);
+    assert_eq!(StatusCode::Conflict, reply.status);
+
+    let body = "{\"name\": \"Sally\", \"email\": \"sally@example.com\", \"password\": \"correct horse\"}";
+    let reply = api::handle(&client, &Method::Post, "/users", None, body);
+    assert_eq!(StatusCode::Created, reply.status);
+    assert_eq!("backer", field(&reply.body, "role"));
+    assert!(reply.body.find("password_hash").is_none());
+
+    let body = "{\"name\": \"Sally\", \"password\": \"correct horse\"}";
+    let reply = api::handle(&client, &Method::Post, "/tokens", None, body);
+    assert_eq!(StatusCode::Created, reply.status);
+    let sally = field(&reply.body, "token").to_owned();
+
+    // Backers can't create projects.
+    let body = "{\"name\": \"Sallys_Project\", \"goal\": 500}";
+    let reply = api::handle(&client, &Method::Post, "/projects", Some(&sally), body);
+    assert_eq!(StatusCode::Forbidden, reply.status);
+
+    let uri = format!("/projects/{}/pledges", NAMES[0]);
+    let body = format!("{{\"card\": \"{}\", \"amount\": 50}}", CARDS[0]);
+    let reply = api::handle(&client, &Method::Post, &uri, Some(&sally), &body);
+    assert_eq!(StatusCode::Created, reply.status);
+    assert_eq!("Sally", field(&reply.body, "user"));
+
+    let reply = api::handle(&client, &Method::Delete, &format!("{}/{}", uri, ADMIN), Some(&sally), "");
+    assert_eq!(StatusCode::Forbidden, reply.status);
+
+    let reply = api::handle(&client, &Method::Delete, &format!("{}/Sally", uri), Some(&sally), "");
+    assert_eq!(StatusCode::Ok, reply.status);
+
+    let reply = api::handle(&client, &Method::Delete, "/tokens", Some(&sally), "");
+    assert_eq!(StatusCode::NoContent, reply.status);
+    assert_eq!(Json::Null, reply.body);
+    assert!(client.authenticate(&sally).is_err());
+}
+
+#[test]
+fn audit_records_caller() {
+    let client = init_client();
+    let token = admin_token(&client);
+
+    let body = format!("{{\"name\": \"{}\", \"goal\": 500}}", NAMES[0]);
+    let reply = api::handle(&client, &Method::Post, "/projects", Some(&token), &body);
+    assert_eq!(StatusCode::Created, reply.status);
+
+    // Anonymous requests aren't blamed on whoever the client acted for last.
+    let body = "{\"name\": \"Sally\", \"email\": \"sally@example.com\", \"password\": \"correct horse\"}";
+    let _ = api::handle(&client, &Method::Post, "/users", None, body);
+    let body = "{\"name\": \"Sally\", \"password\": \"correct horse\"}";
+    let reply = api::handle(&client, &Method::Post, "/tokens", None, body);
+    let sally = field(&reply.body, "token").to_owned();
+
+    let uri = format!("/projects/{}/pledges", NAMES[0]);
+    let body = format!("{{\"card\": \"{}\", \"amount\": 50}}", CARDS[0]);
+    let _ = api::handle(&client, &Method::Post, &uri, Some(&sally), &body);
+
+    let actor = |entity: &str| {
+        let filter = Filter { entity: Some(entity.to_owned()), ..Filter::default() };
+        Audit::search(&client, &filter).unwrap().pop().unwrap().actor
+    };
+    assert_eq!(ADMIN, actor(table::project));
+    assert_eq!("Sally", actor(table::pledge));
+    client.act_as(None);
+    assert_eq!(client.actor(), actor(table::user));
+}
+
+#[test]
+fn openapi_describes_routes() {
+    let client = init_client();
+    let reply = get(&client, "/openapi.json");
+    assert_eq!(StatusCode::Ok, reply.status);
+
+    let paths = reply.body.find("paths").unwrap().as_object().unwrap();
+    for path in &["/users", "/users/{name}", "/users/{name}/pledges", "/tokens", "/projects",
+                  "/projects/{name}", "/projects/{name}/pledges", "/projects/{name}/pledges/{user}"] {
+        assert!(paths.contains_key(*path), "{} is not described", path);
+    }
+}
+
+#[test]
+fn serve_over_http() {
+    let (client, _) = init_test_projects();
+    let mut listening = api::serve_schema(DEFAULT_CONFIG, &client.schema, "127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listening.socket);
+
+    let http = hyper::Client::new();
+    let mut res = http.get(&format!("{}/projects?limit=1", base)).send().unwrap();
+    assert_eq!(StatusCode::Ok, res.status);
+
+    let mut body = String::new();
+    res.read_to_string(&mut body).unwrap();
+    let json = Json::from_str(&body).unwrap();
+    assert_eq!(1, json.find("items").unwrap().as_array().unwrap().len());
+
+    let mut headers = Headers::new();
+    headers.set_raw("Authorization", vec![b"Bearer not-a-token".to_vec()]);
+    let res = http.delete(&format!("{}/tokens", base)).headers(headers).send().unwrap();
+    assert_eq!(StatusCode::Unauthorized, res.status);
+    assert!(res.headers.get_raw("WWW-Authenticate").is_some());
+
+    let body = vec![b' '; api::MAX_BODY as usize + 1];
+    let res = http.post(&format!("{}/users", base)).body(&body[..]).send().unwrap();
+    assert_eq!(StatusCode::PayloadTooLarge, res.status);
+
+    listening.close().unwrap();
+}
@@ -0,0 +1,90 @@
+use ::{init_client, init_test_projects, init_test_pledges, NAMES, NUM_PROJECTS, NUM_PLEDGES, USERS};
+
+use kickstarter::audit::Filter;
+use kickstarter::models::{Audit, Comment, Project, Refund};
+use kickstarter::refund::Reason;
+use kickstarter::db::table;
+use kickstarter::validate;
+
+#[test]
+fn records_creation() {
+    let (client, projects) = init_test_projects();
+    let _ = init_test_pledges(&client);
+
+    let entries = Audit::search(&client, &Filter::default()).unwrap();
+    assert_eq!(NUM_PROJECTS + USERS.len() + NUM_PLEDGES, entries.len());
+
+    let first = &entries[0];
+    assert_eq!(client.actor(), first.actor);
+    assert_eq!("create", first.action);
+    assert_eq!(table::project, first.entity);
+    assert_eq!(projects[0].project_id.to_string(), first.entity_id);
+    assert!(first.before().is_none());
+
+    let after = first.after().unwrap();
+    assert_eq!(Some(NAMES[0]), after.find("name").and_then(|name| name.as_string()));
+}
+
+#[test]
+fn records_implicit_users() {
+    let (mut client, _) = init_test_projects();
+    client.set_actor("auditor");
+    let _ = init_test_pledges(&client);
+
+    let filter = Filter { entity: Some(table::user.to_owned()), ..Filter::default() };
+    let entries = Audit::search(&client, &filter).unwrap();
+    assert_eq!(USERS.len(), entries.len());
+
+    let first = &entries[0];
+    assert_eq!("auditor", first.actor);
+    assert_eq!("create", first.action);
+    let after = first.after().unwrap();
+    assert_eq!(Some(USERS[0]), after.find("name").and_then(|name| name.as_string()));
+    assert!(after.find("password_hash").is_none());
+}
+
+#[test]
+fn records_before_and_after() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+    let _ = Refund::refund(&client, USERS[0], NAMES[0], Some(10f64), Reason::Requested).unwrap();
+
+    let filter = Filter { entity: Some(table::pledge.to_owned()), ..Filter::default() };
+    let entries = Audit::search(&client, &filter).unwrap();
+    let refund = entries.last().unwrap();
+
+    assert_eq!("refund", refund.action);
+    let before = refund.before().unwrap();
+    let after = refund.after().unwrap();
+    assert_eq!(Some(0f64), before.find("refunded").and_then(|v| v.as_f64()));
+    assert_eq!(Some(10f64), after.find("refunded").and_then(|v| v.as_f64()));
+}
+
+#[test]
+fn failed_mutation_not_recorded() {
+    let (client, _) = init_test_projects();
+    assert!(Project::create(&client, NAMES[0], 100f64).is_err());
+    assert!(Comment::create(&client, USERS[0], "I_DONT_EXIST", "Hello").is_err());
+
+    let entries = Audit::search(&client, &Filter::default()).unwrap();
+    assert_eq!(NUM_PROJECTS, entries.len());
+}
+
+#[test]
+fn filter_by_actor_and_time() {
+    let mut client = init_client();
+    client.set_actor("auditor");
+    let _ = Project::create(&client, NAMES[0], 100f64).unwrap();
+
+    let filter = Filter { actor: Some("auditor".to_owned()), ..Filter::default() };
+    assert_eq!(1, Audit::search(&client, &filter).unwrap().len());
+
+    let filter = Filter { actor: Some("somebody_else".to_owned()), ..Filter::default() };
+    assert!(Audit::search(&client, &filter).unwrap().is_empty());
+
+    let filter = Filter { until: Some(validate::timestamp("2000-01-01").unwrap()), ..Filter::default() };
+    assert!(Audit::search(&client, &filter).unwrap().is_empty());
+
+    let filter = Filter { since: Some(validate::timestamp("2000-01-01").unwrap()), ..Filter::default() };
+    assert_eq!(1, Audit::search(&client, &filter).unwrap().len());
+}
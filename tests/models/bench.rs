@@ -0,0 +1,73 @@
+use ::{init_admin, init_client};
+
+use kickstarter::{seed, validate, Client, Error};
+use kickstarter::bench::{self, Mix, Op};
+use kickstarter::models::Project;
+
+#[test]
+fn parse_mix() {
+    assert_eq!(Mix { create: 0, back: 8, list: 2 }, Mix::parse("back=8, list=2").unwrap());
+    assert_eq!(Mix::default(), Mix::parse("create=1,back=6,list=3").unwrap());
+
+    for invalid in &["back", "back=-1", "close=1", "back=0,list=0", ""] {
+        match Mix::parse(invalid) {
+            Err(validate::Error::InvalidMix(_)) => (),
+            other => panic!("expected {:?} to be rejected, got {:?}", invalid, other),
+        }
+    }
+}
+
+#[test]
+fn percentiles() {
+    let latencies: Vec<f64> = (1..101).map(|n| n as f64).collect();
+    assert_eq!(50f64, bench::percentile(&latencies, 50f64));
+    assert_eq!(95f64, bench::percentile(&latencies, 95f64));
+    assert_eq!(99f64, bench::percentile(&latencies, 99f64));
+    assert_eq!(7f64, bench::percentile(&[7f64], 99f64));
+    assert_eq!(0f64, bench::percentile(&[], 50f64));
+}
+
+#[test]
+fn run_bench() {
+    let client = init_client();
+    let admin = init_admin(&client);
+
+    // So few users and projects that workers collide on cards and backers.
+    let seed = seed::Options { projects: 2, users: 4, pledges: 0, seed: 3 };
+    let options = bench::Options { workers: 4, operations: 200, mix: Mix::default(), seed: 3 };
+    let schema = client.scratch_schema();
+    let report = client.bench(&admin, &schema, &seed, &options).unwrap();
+
+    assert_eq!(4, report.workers);
+    assert_eq!(200, report.count());
+    assert_eq!(vec![Op::Create, Op::Back, Op::List], report.operations.iter().map(|s| s.op).collect::<Vec<Op>>());
+
+    let back = &report.operations[1];
+    assert!(back.failed() > 0);
+    for error in back.errors.keys() {
+        assert!(error == "pledge_project_card" || error == "pledge_pkey", "unexpected error {}", error);
+    }
+    assert_eq!(0, report.operations[2].failed());
+
+    for summary in &report.operations {
+        assert!(summary.p50 <= summary.p95 && summary.p95 <= summary.p99);
+    }
+
+    let benched = Client::new(&client.uri, &schema).unwrap();
+    let created = report.operations[0].count - report.operations[0].failed();
+    assert_eq!(2 + created, Project::list_all(&benched).unwrap().len());
+}
+
+#[test]
+fn bench_rejects_own_schema() {
+    let client = init_client();
+    let admin = init_admin(&client);
+    let schema = client.schema.clone();
+
+    let seed = seed::Options { projects: 1, users: 1, pledges: 0, seed: 1 };
+    let options = bench::Options { workers: 1, operations: 1, mix: Mix::default(), seed: 1 };
+    match client.bench(&admin, &schema, &seed, &options) {
+        Err(Error::Config(_)) => (),
+        _ => panic!("expected benchmarking the client's own schema to fail"),
+    }
+}
@@ -0,0 +1,131 @@
+use ::{init_test_projects, init_test_pledges, NAMES, USERS};
+
+use kickstarter::{validate, Error};
+use kickstarter::account::Role;
+use kickstarter::models::{Comment, Project, User};
+
+#[test]
+fn create_comment() {
+    let (client, _) = init_test_projects();
+    let comment = Comment::create(&client, USERS[0], NAMES[0], "Applesauce for everyone!").unwrap();
+    assert_eq!("Applesauce for everyone!", comment.body);
+    assert_eq!(None, comment.parent_id);
+
+    let found = Comment::get(&client, comment.comment_id).unwrap();
+    assert_eq!(comment.body, found.body);
+}
+
+#[test]
+fn create_comment_invalid_body() {
+    let (client, _) = init_test_projects();
+    let result = Comment::create(&client, USERS[0], NAMES[0], "   ");
+
+    match result {
+        Err(Error::InvalidData(validate::Error::Blank)) => (),
+        _ => panic!(result),
+    }
+}
+
+#[test]
+fn create_comment_non_ascii() {
+    let (client, _) = init_test_projects();
+
+    // The limit is 2000 characters, not 2000 bytes.
+    let body: String = ::std::iter::repeat("ü").take(2000).collect();
+    let comment = Comment::create(&client, USERS[0], NAMES[0], &body).unwrap();
+    assert_eq!(2000, comment.body.chars().count());
+    assert!(Comment::create(&client, USERS[0], NAMES[0], &format!("{}ü", body)).is_err());
+}
+
+#[test]
+fn list_threads() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+
+    let root = Comment::create(&client, USERS[0], NAMES[0], "When does it ship?").unwrap();
+    let _ = Comment::create(&client, "Stranger", NAMES[0], "Looks tasty.").unwrap();
+    let reply = Comment::reply(&client, USERS[1], root.comment_id, "Next spring.").unwrap();
+    let _ = Comment::reply(&client, USERS[0], reply.comment_id, "Thanks!").unwrap();
+
+    let threads = Comment::list(&client, NAMES[0]).unwrap();
+    assert_eq!(2, threads.len());
+
+    assert_eq!(USERS[0], threads[0].author);
+    assert!(threads[0].backer);
+    assert_eq!(1, threads[0].replies.len());
+    assert_eq!(1, threads[0].replies[0].replies.len());
+
+    assert_eq!("Stranger", threads[1].author);
+    assert!(!threads[1].backer);
+    assert!(threads[1].replies.is_empty());
+}
+
+#[test]
+fn edit_and_delete_by_author() {
+    let (client, _) = init_test_projects();
+    let comment = Comment::create(&client, USERS[0], NAMES[0], "Frist!").unwrap();
+
+    let result = Comment::edit(&client, USERS[1], comment.comment_id, "Hijacked");
+    match result {
+        Err(Error::InvalidData(validate::Error::UserDoesNotExist)) => (),
+        _ => panic!(result),
+    }
+
+    let edited = Comment::edit(&client, USERS[0], comment.comment_id, "First!").unwrap();
+    assert_eq!("First!", edited.body);
+    assert!(edited.date_edited.is_some());
+
+    let deleted = Comment::delete(&client, USERS[0], comment.comment_id).unwrap();
+    assert!(deleted.deleted);
+    assert_eq!("[deleted]", deleted.visible_body());
+}
+
+#[test]
+fn edit_not_author() {
+    let (client, _) = init_test_projects();
+    let comment = Comment::create(&client, USERS[0], NAMES[0], "Mine").unwrap();
+    let _ = Comment::create(&client, USERS[1], NAMES[0], "Also mine").unwrap();
+
+    let result = Comment::delete(&client, USERS[1], comment.comment_id);
+    match result {
+        Err(Error::InvalidData(validate::Error::NotCommentAuthor)) => (),
+        _ => panic!(result),
+    }
+}
+
+#[test]
+fn moderate_comment() {
+    let (client, _) = init_test_projects();
+    let comment = Comment::create(&client, USERS[0], NAMES[0], "Buy my mixtape").unwrap();
+
+    let result = Comment::hide(&client, USERS[0], comment.comment_id, true);
+    match result {
+        Err(Error::InvalidData(validate::Error::NotModerator)) => (),
+        _ => panic!(result),
+    }
+
+    let _ = User::set_role(&client, "Moddy", Role::Moderator).unwrap();
+    let flagged = Comment::flag(&client, "Moddy", comment.comment_id, true).unwrap();
+    assert!(flagged.flagged);
+
+    let hidden = Comment::hide(&client, "Moddy", comment.comment_id, true).unwrap();
+    assert!(hidden.hidden);
+    assert_eq!("[hidden by a moderator]", hidden.visible_body());
+}
+
+#[test]
+fn backers_only() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+
+    let project = Project::set_comments_backers_only(&client, NAMES[0], true).unwrap();
+    assert!(project.comments_backers_only);
+
+    let result = Comment::create(&client, "Stranger", NAMES[0], "Hello?");
+    match result {
+        Err(Error::InvalidData(validate::Error::BackersOnly)) => (),
+        _ => panic!(result),
+    }
+
+    assert!(Comment::create(&client, USERS[0], NAMES[0], "Hello!").is_ok());
+}
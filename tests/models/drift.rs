@@ -0,0 +1,69 @@
+use ::init_client;
+
+use kickstarter::{db, drift, output, validate, Error};
+use kickstarter::drift::Kind;
+
+#[test]
+fn fresh_schema_has_no_drift() {
+    let client = init_client();
+    assert_eq!(vec![] as Vec<drift::Drift>, client.check_schema().unwrap());
+    assert_eq!(db::get_all_tables().len(), drift::tables(&client).unwrap().len());
+}
+
+#[test]
+fn detect_drift() {
+    let client = init_client();
+    let sql = format!("ALTER TABLE {project} ALTER COLUMN goal TYPE numeric(30,2); \
+                       ALTER TABLE {project} ADD COLUMN tagline text; \
+                       ALTER TABLE {pledge} ALTER COLUMN card DROP NOT NULL; \
+                       ALTER TABLE {pledge} DROP COLUMN refunded; \
+                       ALTER TABLE {comment} DROP CONSTRAINT comment_parent_fkey; \
+                       DROP TABLE {token}; \
+                       CREATE TABLE {stretch_goal} (stretch_goal_id integer)",
+                      project = client.table("project"), pledge = client.table("pledge"),
+                      comment = client.table("comment"), token = client.table("token"),
+                      stretch_goal = client.table("stretch_goal"));
+    client.db().execute_sql(&sql, &vec![]).unwrap();
+
+    let drift = client.check_schema().unwrap();
+    let found: Vec<(Kind, &str, Option<&str>)> = drift.iter().map(|d| {
+        (d.kind, &d.table[..], d.column.as_ref().map(|c| &c[..]))
+    }).collect();
+
+    assert_eq!(vec![(Kind::ForeignKey, "comment", Some("parent_id")),
+                    (Kind::Nullability, "pledge", Some("card")),
+                    (Kind::MissingColumn, "pledge", Some("refunded")),
+                    (Kind::Type, "project", Some("goal")),
+                    (Kind::ExtraColumn, "project", Some("tagline")),
+                    (Kind::MissingTable, "token", None),
+                    (Kind::ExtraTable, "stretch_goal", None)], found);
+
+    assert_eq!(Some("comment.comment_id".to_owned()), drift[0].expected);
+    assert_eq!(None, drift[0].found);
+    assert_eq!("Column project.goal is double precision in the models but numeric(30,2) in the database.",
+               drift[3].to_string());
+}
+
+#[test]
+fn compare_ignores_schema_name() {
+    let models = db::get_all_tables();
+    let mut live = db::get_all_tables();
+    for table in &mut live {
+        table.schema = "elsewhere".to_owned();
+        for column in &mut table.columns {
+            if let Some(ref mut foreign) = column.foreign {
+                foreign.schema = "elsewhere".to_owned();
+            }
+        }
+    }
+    assert!(drift::compare(&models, &live).is_empty());
+
+    live[0].columns.pop();
+    assert_eq!(1, drift::compare(&models, &live).len());
+}
+
+#[test]
+fn drift_exit_code() {
+    let err = Error::InvalidData(validate::Error::SchemaDrift(2));
+    assert_eq!(output::EXIT_CONFLICT, output::exit_code(&err));
+}
@@ -0,0 +1,110 @@
+use ::{init_admin, init_test_projects, init_test_pledges, ADMIN, NAMES, NUM_PROJECTS, NUM_PLEDGES, PASSWORD, USERS};
+
+use kickstarter::{validate, Client};
+use kickstarter::event::{self, DomainEvent};
+use kickstarter::models::{Event, Pledge, Project, Refund, User};
+use kickstarter::refund::Reason;
+
+#[test]
+fn records_events() {
+    let (client, projects) = init_test_projects();
+    let _ = init_test_pledges(&client);
+
+    let events = Event::list(&client, None).unwrap();
+
+    // Every pledge also creates its backer.
+    assert_eq!(NUM_PROJECTS + 2 * NUM_PLEDGES, events.len());
+
+    let expected = DomainEvent::ProjectCreated(event::ProjectCreated {
+        project_id: projects[0].project_id,
+        name: NAMES[0].to_owned(),
+        goal: projects[0].goal,
+        creator_id: None,
+    });
+    assert_eq!(expected, events[0].domain_event().unwrap());
+    assert_eq!("UserCreated", events[NUM_PROJECTS].kind);
+    assert_eq!("PledgeMade", events[NUM_PROJECTS + 1].kind);
+}
+
+#[test]
+fn decode_unknown_kind() {
+    assert!(DomainEvent::decode("ProjectExploded", "{}").is_err());
+    assert!(DomainEvent::decode("ProjectCreated", "{\"name\": 1}").is_err());
+    assert!(DomainEvent::decode("ModeratorChanged", "{\"user_id\": 1, \"moderator\": true}").is_err());
+}
+
+#[test]
+fn replay_all() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+    let _ = Refund::refund(&client, USERS[0], NAMES[0], Some(25f64), Reason::Requested).unwrap();
+    let _ = Pledge::cancel(&client, USERS[1], NAMES[0]).unwrap();
+    let admin = init_admin(&client);
+
+    let schema = client.scratch_schema();
+    let count = client.replay(&admin, &schema, None).unwrap();
+    assert_eq!(Event::list(&client, None).unwrap().len(), count);
+
+    let replayed = Client::new(&client.uri, &schema).unwrap();
+    let projects = Project::list_all(&client).unwrap();
+    let replayed_projects = Project::list_all(&replayed).unwrap();
+    assert_eq!(projects.len(), replayed_projects.len());
+
+    for (project, replayed_project) in projects.iter().zip(replayed_projects.iter()) {
+        assert_eq!(project.project_id, replayed_project.project_id);
+        assert_eq!(project.name, replayed_project.name);
+        assert_eq!(project.goal, replayed_project.goal);
+    }
+
+    let (pledges, _) = Project::list_pledges(&client, NAMES[0]).unwrap();
+    let (replayed_pledges, _) = Project::list_pledges(&replayed, NAMES[0]).unwrap();
+    assert_eq!(NUM_PLEDGES - 1, replayed_pledges.len());
+
+    for (pledge, replayed_pledge) in pledges.iter().zip(replayed_pledges.iter()) {
+        assert_eq!(pledge.card, replayed_pledge.card);
+        assert_eq!(pledge.net_amount(), replayed_pledge.net_amount());
+        assert_eq!(pledge.date_created, replayed_pledge.date_created);
+    }
+
+    // New rows in the replayed schema continue after the replayed IDs.
+    let project = Project::create(&replayed, "Replayed_Project", 10f64).unwrap();
+    assert_eq!(NUM_PROJECTS as i32 + 1, project.project_id);
+}
+
+#[test]
+fn replay_keeps_passwords() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+    let admin = init_admin(&client);
+
+    let schema = client.scratch_schema();
+    let _ = client.replay(&admin, &schema, None).unwrap();
+
+    let replayed = Client::new(&client.uri, &schema).unwrap();
+    let (principal, _) = replayed.login(ADMIN, PASSWORD).unwrap();
+    assert!(principal.is_admin());
+    assert_eq!(Some(format!("{}@example.com", ADMIN.to_lowercase())), User::get(&replayed, ADMIN).unwrap().email);
+
+    // Users created by their first pledge still have no password.
+    assert!(replayed.login(USERS[0], PASSWORD).is_err());
+}
+
+#[test]
+fn replay_point_in_time() {
+    let (client, _) = init_test_projects();
+    let admin = init_admin(&client);
+    let until = validate::timestamp("2000-01-01").unwrap();
+
+    let schema = client.scratch_schema();
+    assert_eq!(0, client.replay(&admin, &schema, Some(until)).unwrap());
+
+    let replayed = Client::new(&client.uri, &schema).unwrap();
+    assert!(Project::list_all(&replayed).unwrap().is_empty());
+}
+
+#[test]
+fn replay_into_self() {
+    let (client, _) = init_test_projects();
+    let admin = init_admin(&client);
+    assert!(client.replay(&admin, &client.schema, None).is_err());
+}
@@ -0,0 +1,99 @@
+use ::{init_admin, init_test_projects, init_test_pledges, NAMES, USERS, PASSWORD};
+
+use kickstarter::Error;
+use kickstarter::fulfillment;
+use kickstarter::models::{Refund, User};
+use kickstarter::refund::Reason;
+use kickstarter::xlsx;
+
+#[test]
+fn export_backers() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+    let _ = Refund::refund(&client, USERS[1], NAMES[0], None, Reason::Requested).unwrap();
+    let _ = Refund::refund(&client, USERS[2], NAMES[0], Some(500f64), Reason::Requested).unwrap();
+    let admin = init_admin(&client);
+
+    // Fully refunded pledges are left out.
+    let backers = client.export_backers(&admin, NAMES[0]).unwrap();
+    let names: Vec<&str> = backers.iter().map(|b| &b.name[..]).collect();
+    assert_eq!(vec![USERS[0], USERS[2]], names);
+    assert_eq!(2500f64, backers[1].amount);
+    assert_eq!(3000f64, backers[1].pledged);
+
+    let csv = fulfillment::csv(&backers);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!("backer,email,shipping_address,reward,amount,pledged,refunded,date_pledged,card", lines[0]);
+    assert!(lines[1].starts_with("Johnnyboy,,,,100.00,100.00,0.00,"));
+    assert!(lines[1].ends_with(",***********0899"));
+    assert!(lines[2].ends_with(",************5649"));
+    assert!(!csv.contains("341468752760899"));
+
+    assert!(client.export_backers(&admin, NAMES[1]).unwrap().is_empty());
+}
+
+#[test]
+fn export_rewards_and_addresses() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+    let admin = init_admin(&client);
+    let _ = client.add_reward(&admin, NAMES[0], "Sticker pack", 50f64).unwrap();
+    let _ = client.add_reward(&admin, NAMES[0], "Signed print", 3000f64).unwrap();
+    let _ = User::set_shipping_address(&client, USERS[2], Some("12 Birch Lane, Leeds")).unwrap();
+
+    let backers = client.export_backers(&admin, NAMES[0]).unwrap();
+    let last = backers.len() - 1;
+    assert_eq!(Some("Sticker pack".to_owned()), backers[0].reward);
+    assert_eq!(None, backers[0].shipping_address);
+    assert_eq!(Some("Signed print".to_owned()), backers[last].reward);
+    assert_eq!(Some("12 Birch Lane, Leeds".to_owned()), backers[last].shipping_address);
+
+    let csv = fulfillment::csv(&backers);
+    assert!(csv.contains(&format!("{},,\"12 Birch Lane, Leeds\",Signed print,3000.00,", USERS[2])));
+
+    // A partial refund moves the backer down a tier.
+    let _ = Refund::refund(&client, USERS[2], NAMES[0], Some(500f64), Reason::Requested).unwrap();
+    let backers = client.export_backers(&admin, NAMES[0]).unwrap();
+    assert_eq!(Some("Sticker pack".to_owned()), backers[last].reward);
+}
+
+#[test]
+fn export_requires_creator() {
+    let (client, _) = init_test_projects();
+    let _ = init_admin(&client);
+    let _ = client.register("Nosy_Parker", "nosy@example.com", PASSWORD).unwrap();
+    let (backer, _) = client.login("Nosy_Parker", PASSWORD).unwrap();
+
+    let result = client.export_backers(&backer, NAMES[0]);
+    match result {
+        Err(Error::PermissionDenied(_)) => (),
+        _ => panic!("expected the export to be denied"),
+    }
+}
+
+#[test]
+fn export_xlsx() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+    let admin = init_admin(&client);
+
+    let backers = client.export_backers(&admin, NAMES[0]).unwrap();
+    let workbook = fulfillment::xlsx(NAMES[0], &backers);
+    let text = String::from_utf8_lossy(&workbook);
+
+    assert!(workbook.starts_with(b"PK\x03\x04"));
+    assert!(text.contains("xl/worksheets/sheet1.xml"));
+    assert!(text.contains("<sheet name=\"GoGo_Applesauce\""));
+    assert!(text.contains("<c r=\"A2\" t=\"inlineStr\"><is><t>Johnnyboy</t></is></c>"));
+    assert!(text.contains("<c r=\"E4\"><v>3000</v></c>"));
+    assert!(!text.contains("6011168468345649"));
+}
+
+#[test]
+fn xlsx_helpers() {
+    assert_eq!("A", xlsx::column_name(0));
+    assert_eq!("Z", xlsx::column_name(25));
+    assert_eq!("AA", xlsx::column_name(26));
+    assert_eq!("BA", xlsx::column_name(52));
+    assert_eq!(0xcbf43926, xlsx::crc32(b"123456789"));
+}
@@ -0,0 +1,88 @@
+use kickstarter::golden::{self, Command};
+
+const SCRIPT: &'static str = "# Setup
+login Amalia
+project Attack_Owl 55000
+  # expect: Added project
+list Attack_Owl
+# expect: -- Amalia backed
+# expect: needs $48000.00 more
+
+listall
+";
+
+fn commands() -> Vec<Command> {
+    golden::parse(SCRIPT, |line| !line.trim().is_empty() && !line.trim().starts_with("#"))
+}
+
+fn outputs() -> Vec<Vec<String>> {
+    let stdout = "> login Amalia\nLogged in as Amalia.\n\n\
+                  > project Attack_Owl 55000\nAdded project 'Attack_Owl' with a target goal of $55000.00.\n\n\
+                  > list Attack_Owl\n-- Amalia backed for $5000.00\n-- Jill backed for $2000.00\n\
+                  Attack_Owl needs $48000.00 more dollars to be successful!\n\n\
+                  > listall\n\n\
+                  Summary:\n    ok         2  login Amalia\n";
+    golden::outputs(&commands(), stdout)
+}
+
+#[test]
+fn parse_expectations() {
+    let commands = commands();
+    assert_eq!(4, commands.len());
+    assert_eq!(2, commands[0].line);
+    assert_eq!("login Amalia", commands[0].text);
+    assert!(commands[0].expected.is_empty());
+    assert_eq!(vec!["Added project".to_owned()], commands[1].expected);
+    assert_eq!(vec!["-- Amalia backed".to_owned(), "needs $48000.00 more".to_owned()], commands[2].expected);
+    assert_eq!(9, commands[3].line);
+}
+
+#[test]
+fn split_and_match_outputs() {
+    let outputs = outputs();
+    assert_eq!(vec!["Logged in as Amalia.".to_owned()], outputs[0]);
+    assert_eq!(3, outputs[2].len());
+    assert!(outputs[3].is_empty());
+    assert!(golden::mismatches(&commands(), &outputs).is_empty());
+
+    // Expectations must appear in order.
+    let mut commands = commands();
+    commands[2].expected.reverse();
+    let mismatches = golden::mismatches(&commands, &outputs);
+    assert_eq!(1, mismatches.len());
+    assert!(mismatches[0].starts_with("line 5: > list Attack_Owl\n- -- Amalia backed\n"));
+    assert!(mismatches[0].contains("+ -- Jill backed for $2000.00\n"));
+}
+
+#[test]
+fn bless_and_transcript() {
+    let blessed = golden::bless(SCRIPT, &commands(), &outputs());
+    assert_eq!("# Setup
+login Amalia
+# expect: Logged in as Amalia.
+project Attack_Owl 55000
+# expect: Added project 'Attack_Owl' with a target goal of $55000.00.
+list Attack_Owl
+# expect: -- Amalia backed for $5000.00
+# expect: -- Jill backed for $2000.00
+# expect: Attack_Owl needs $48000.00 more dollars to be successful!
+
+listall
+", blessed);
+
+    // Blessing is idempotent.
+    let reparsed = golden::parse(&blessed, |line| !line.trim().is_empty() && !line.trim().starts_with("#"));
+    assert_eq!(blessed, golden::bless(&blessed, &reparsed, &outputs()));
+    assert!(golden::mismatches(&reparsed, &outputs()).is_empty());
+
+    let transcript = golden::transcript(&commands(), &outputs());
+    assert!(transcript.starts_with("> login Amalia\nLogged in as Amalia.\n\n> project"));
+    assert!(transcript.ends_with("> listall\n\n"));
+}
+
+#[test]
+fn diff_lines() {
+    assert_eq!(None, golden::diff("a\nb\n", "a\nb\n"));
+    assert_eq!(Some("  a\n- b\n+ c\n  d\n+ e\n".to_owned()), golden::diff("a\nb\nd\n", "a\nc\nd\ne\n"));
+    assert!(golden::diff("a\n", "a").unwrap().contains("final newlines"));
+}
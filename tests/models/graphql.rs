@@ -0,0 +1,242 @@
+use ::{init_admin, init_client, init_test_projects, init_test_pledges,
+        ADMIN, PASSWORD, NAMES, GOALS, CARDS, CONTRIBUTIONS, NUM_PROJECTS, NUM_PLEDGES, USERS};
+
+use kickstarter::{api, graphql, validate};
+use kickstarter::graphql::parser::{self, OperationKind, Selection, Value};
+use kickstarter::models::Pledge;
+
+use hyper::method::Method;
+use hyper::status::StatusCode;
+use rustc_serialize::json::Json;
+
+use std::time::Duration;
+
+/// Returns the JSON at a path of keys.
+fn at<'a>(json: &'a Json, path: &[&str]) -> &'a Json {
+    json.find_path(path).unwrap()
+}
+
+#[test]
+fn parse_document() {
+    let document = parser::parse("
+        # Comments and commas are ignored.
+        query Funding($name: String!, $limit: Int = 10) {
+            project(name: $name) { ...Totals, owner: creator @include(if: true) { name } }
+            projects(limit: $limit) { ... on Project { name } }
+        }
+
+        fragment Totals on Project { pledged backers note: name(tag: \"a \\\"b\\\"\\n\") }
+    ").unwrap();
+
+    assert_eq!(1, document.operations.len());
+    let operation = &document.operations[0];
+    assert_eq!(OperationKind::Query, operation.kind);
+    assert_eq!(Some("Funding".to_owned()), operation.name);
+    assert_eq!("String!", operation.variables[0].type_name);
+    assert_eq!(Some(Value::Int(10)), operation.variables[1].default);
+
+    match operation.selection[0] {
+        Selection::Field(ref field) => {
+            assert_eq!("project", field.name);
+            assert_eq!(vec![("name".to_owned(), Value::Variable("name".to_owned()))], field.arguments);
+            assert_eq!(Selection::FragmentSpread("Totals".to_owned(), vec![]), field.selection[0]);
+            match field.selection[1] {
+                Selection::Field(ref owner) => {
+                    assert_eq!("owner", owner.key());
+                    assert_eq!("include", owner.directives[0].name);
+                }
+                _ => panic!("Expected a field."),
+            }
+        }
+        _ => panic!("Expected a field."),
+    }
+
+    let fragment = document.fragments.get("Totals").unwrap();
+    assert_eq!("Project", fragment.type_condition);
+    match fragment.selection[2] {
+        Selection::Field(ref field) => assert_eq!(Value::String("a \"b\"\n".to_owned()), field.arguments[0].1),
+        _ => panic!("Expected a field."),
+    }
+
+    for bad in &["", "{ }", "{ projects { name }", "query ($x) { projects }", "{ name(x: \"unterminated) }",
+                 "fragment on on Project { name } { projects }"] {
+        let result = parser::parse(bad);
+        match result {
+            Err(validate::Error::InvalidQuery(_)) => (),
+            _ => panic!("{:?} parsed as {:?}", bad, result),
+        }
+    }
+}
+
+#[test]
+fn nested_query_is_batched() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+    let _ = Pledge::create(&client, USERS[0], NAMES[1], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+
+    let query = "{
+        projects {
+            name pledged backers
+            pledges { amount card user { name pledges { project { name } } } }
+        }
+    }";
+    let response = graphql::execute(&client, None, query, None, &Json::Null);
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+    // One query per level, however many projects and pledges there are:
+    // projects, totals, pledges, their users, the users' pledges and those pledges' projects.
+    assert_eq!(6, response.queries);
+
+    let projects = at(&response.data, &["projects"]).as_array().unwrap();
+    assert_eq!(NUM_PROJECTS, projects.len());
+
+    let first = &projects[0];
+    assert_eq!(NAMES[0], at(first, &["name"]).as_string().unwrap());
+    assert_eq!(3300f64, at(first, &["pledged"]).as_f64().unwrap());
+    assert_eq!(NUM_PLEDGES as i64, at(first, &["backers"]).as_i64().unwrap());
+
+    let pledges = at(first, &["pledges"]).as_array().unwrap();
+    let johnny = pledges.iter()
+        .find(|p| at(p, &["user", "name"]).as_string() == Some(USERS[0]))
+        .unwrap();
+    assert_eq!("***********0899", at(johnny, &["card"]).as_string().unwrap());
+    assert_eq!(2, at(johnny, &["user", "pledges"]).as_array().unwrap().len());
+    assert!(!response.data.to_string().contains(CARDS[0]));
+
+    // Projects without pledges have empty lists and zero totals.
+    assert_eq!(0f64, at(&projects[3], &["pledged"]).as_f64().unwrap());
+    assert!(at(&projects[3], &["pledges"]).as_array().unwrap().is_empty());
+}
+
+#[test]
+fn variables_fragments_and_directives() {
+    let (client, _) = init_test_projects();
+    let query = "
+        query One($name: String!, $full: Boolean = false) {
+            project(name: $name) { __typename ...Basics goal @include(if: $full) }
+            missing: project(name: \"Nope_Nope\") { name }
+        }
+        query Other { projects(limit: 1) { name } }
+
+        fragment Basics on Project { name dateClosed }
+    ";
+
+    let variables = Json::from_str(&format!("{{\"name\": \"{}\"}}", NAMES[2])).unwrap();
+    let response = graphql::execute(&client, None, query, Some("One"), &variables);
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    assert_eq!("Project", at(&response.data, &["project", "__typename"]).as_string().unwrap());
+    assert_eq!(NAMES[2], at(&response.data, &["project", "name"]).as_string().unwrap());
+    assert_eq!(&Json::Null, at(&response.data, &["project", "dateClosed"]));
+    assert!(response.data.find_path(&["project", "goal"]).is_none());
+    assert_eq!(&Json::Null, at(&response.data, &["missing"]));
+
+    let variables = Json::from_str(&format!("{{\"name\": \"{}\", \"full\": true}}", NAMES[2])).unwrap();
+    let response = graphql::execute(&client, None, query, Some("One"), &variables);
+    assert_eq!(GOALS[2], at(&response.data, &["project", "goal"]).as_f64().unwrap());
+
+    // Several operations need a name, and required variables must be given.
+    let response = graphql::execute(&client, None, query, None, &Json::Null);
+    assert_eq!(Json::Null, response.data);
+    let response = graphql::execute(&client, None, query, Some("One"), &Json::Null);
+    assert_eq!(Json::Null, response.data);
+    assert!(response.errors[0].message.contains("$name"));
+}
+
+#[test]
+fn field_errors_leave_other_fields() {
+    let (client, _) = init_test_projects();
+    let query = "{ projects(limit: 2) { name } user(name: \"Nobody\") { name } bogus { name } }";
+    let response = graphql::execute(&client, None, query, None, &Json::Null);
+
+    assert_eq!(2, at(&response.data, &["projects"]).as_array().unwrap().len());
+    assert_eq!(&Json::Null, at(&response.data, &["user"]));
+    assert_eq!(&Json::Null, at(&response.data, &["bogus"]));
+    assert_eq!(1, response.errors.len());
+    assert_eq!(vec!["bogus".to_owned()], response.errors[0].path);
+
+    let response = graphql::execute(&client, None, "{ projects }", None, &Json::Null);
+    assert!(response.errors[0].message.contains("selection"));
+
+    let response = graphql::execute(&client, None, "{ projects { name { first } } }", None, &Json::Null);
+    assert!(response.errors[0].message.contains("scalar"));
+}
+
+#[test]
+fn mutations_need_a_principal() {
+    let client = init_client();
+    let admin = init_admin(&client);
+    let mutation = format!("mutation {{ createProject(name: \"{}\", goal: 500) {{ name creator {{ name role }} }} }}",
+                           NAMES[0]);
+
+    let response = graphql::execute(&client, None, &mutation, None, &Json::Null);
+    assert_eq!(&Json::Null, at(&response.data, &["createProject"]));
+    assert_eq!(validate::Error::NotAuthenticated.to_string(), response.errors[0].message);
+
+    let response = graphql::execute(&client, Some(&admin), &mutation, None, &Json::Null);
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    assert_eq!(ADMIN, at(&response.data, &["createProject", "creator", "name"]).as_string().unwrap());
+    assert_eq!("ADMIN", at(&response.data, &["createProject", "creator", "role"]).as_string().unwrap());
+
+    // Mutations run in order, so the second sees the first's pledge.
+    let mutation = "mutation Back($project: String!, $card: String!) {
+        backProject(project: $project, card: $card, amount: 25) { amount }
+        again: backProject(project: $project, card: $card, amount: 25) { amount }
+        cancelPledge(project: $project) { project { pledged backers } }
+    }";
+    let variables = Json::from_str(&format!("{{\"project\": \"{}\", \"card\": \"{}\"}}", NAMES[0], CARDS[0])).unwrap();
+    let response = graphql::execute(&client, Some(&admin), mutation, None, &variables);
+
+    assert_eq!(25f64, at(&response.data, &["backProject", "amount"]).as_f64().unwrap());
+    assert_eq!(&Json::Null, at(&response.data, &["again"]));
+    assert_eq!(vec!["again".to_owned()], response.errors[0].path);
+    assert_eq!(0, at(&response.data, &["cancelPledge", "project", "backers"]).as_i64().unwrap());
+}
+
+#[test]
+fn subscribe_to_pledges() {
+    let (client, _) = init_test_projects();
+    let query = format!("subscription {{ pledges(project: \"{}\") {{ kind amount user {{ name }} \
+                         project {{ name pledged }} }} }}", NAMES[0]);
+    let stream = graphql::subscribe(&client, &query, None, &Json::Null).unwrap();
+
+    // Changes to other projects are skipped.
+    let _ = Pledge::create(&client, USERS[1], NAMES[1], CARDS[1], CONTRIBUTIONS[1]).unwrap();
+    let _ = Pledge::create(&client, USERS[0], NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+
+    let response = stream.wait(Duration::from_secs(5)).unwrap().unwrap();
+    assert_eq!("CREATED", at(&response.data, &["pledges", "kind"]).as_string().unwrap());
+    assert_eq!(CONTRIBUTIONS[0], at(&response.data, &["pledges", "amount"]).as_f64().unwrap());
+    assert_eq!(USERS[0], at(&response.data, &["pledges", "user", "name"]).as_string().unwrap());
+    assert_eq!(CONTRIBUTIONS[0], at(&response.data, &["pledges", "project", "pledged"]).as_f64().unwrap());
+
+    assert!(stream.wait(Duration::from_millis(100)).is_none());
+
+    // Subscriptions can't be executed, and queries can't be subscribed to.
+    let response = graphql::execute(&client, None, &query, None, &Json::Null);
+    assert_eq!(Json::Null, response.data);
+    assert!(graphql::subscribe(&client, "{ projects { name } }", None, &Json::Null).is_err());
+}
+
+#[test]
+fn graphql_over_api() {
+    let client = init_client();
+    let _ = init_admin(&client);
+    let token = client.login(ADMIN, PASSWORD).unwrap().1;
+
+    let body = format!("{{\"query\": \"mutation($goal: Float!) {{ createProject(name: \\\"{}\\\", goal: $goal) \
+                        {{ name goal }} }}\", \"variables\": {{\"goal\": 750}}}}", NAMES[3]);
+    let reply = api::handle(&client, &Method::Post, "/graphql", Some(&token), &body);
+    assert_eq!(StatusCode::Ok, reply.status);
+    assert_eq!(750f64, at(&reply.body, &["data", "createProject", "goal"]).as_f64().unwrap());
+    assert!(reply.body.find_path(&["extensions", "queries"]).is_some());
+
+    let reply = api::handle(&client, &Method::Post, "/graphql", None, "{\"query\": \"{ projects \"}");
+    assert_eq!(StatusCode::BadRequest, reply.status);
+    assert!(reply.body.find("errors").is_some());
+
+    let reply = api::handle(&client, &Method::Post, "/graphql", None, "{\"variables\": {}}");
+    assert_eq!(StatusCode::BadRequest, reply.status);
+
+    assert_eq!(StatusCode::MethodNotAllowed,
+               api::handle(&client, &Method::Get, "/graphql", None, "").status);
+}
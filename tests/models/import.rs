@@ -0,0 +1,132 @@
+use ::{init_admin, init_client, init_test_projects, init_test_pledges, CARDS, NAMES, NUM_PLEDGES, USERS};
+
+use kickstarter::{validate, Error};
+use kickstarter::import::{self, Kind, Mapping};
+use kickstarter::models::{Event, Project, User};
+
+#[test]
+fn parse_csv() {
+    let csv = "name,goal\r\nAttack_Owl,55000\n\n\"Banana, Bread\",\"10\"\n\"Say \"\"hi\"\"\nagain\",5";
+    let records = import::parse_csv(csv).unwrap();
+
+    assert_eq!(vec![(1, vec!["name".to_owned(), "goal".to_owned()]),
+                    (2, vec!["Attack_Owl".to_owned(), "55000".to_owned()]),
+                    (4, vec!["Banana, Bread".to_owned(), "10".to_owned()]),
+                    (5, vec!["Say \"hi\"\nagain".to_owned(), "5".to_owned()])],
+               records);
+
+    assert_eq!(Err(validate::Error::UnclosedQuote), import::parse_csv("name\n\"Attack_Owl"));
+}
+
+#[test]
+fn parse_mapping() {
+    let mapping = Mapping::parse(Kind::Projects, "name=Title, goal=Target").unwrap();
+    assert_eq!("Title", mapping.column("name"));
+    assert_eq!("creator", mapping.column("creator"));
+
+    assert!(Mapping::parse(Kind::Projects, "card=Number").is_err());
+    assert!(Mapping::parse(Kind::Pledges, "card").is_err());
+    assert_eq!(Mapping::default(), Mapping::parse(Kind::Pledges, "").unwrap());
+}
+
+#[test]
+fn reject_rows() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+    let _ = Project::close(&client, NAMES[3]).unwrap();
+    let admin = init_admin(&client);
+
+    let csv = format!("User,Project,Card,Amount\n\
+                       Amalia,{p0},1234567803121,10\n\
+                       Amalia,{p1},1234567803122,10\n\
+                       Amalia,Nonexistent,1234567803121,10\n\
+                       {u0},{p0},12345678031217,5\n\
+                       John,{p0},1234567803121,10\n\
+                       John,{p2},12345678031217,-5\n\
+                       Jill,{p3},4090165776584306,20\n\
+                       Jill,{p0},{c0},20\n\
+                       Jill,{p1}\n",
+                      p0 = NAMES[0], p1 = NAMES[1], p2 = NAMES[2], p3 = NAMES[3], u0 = USERS[0], c0 = CARDS[0]);
+
+    let checked = client.check_import(&admin, Kind::Pledges, &csv, &Mapping::default()).unwrap();
+    assert_eq!(vec![2], checked.accepted.iter().map(|&(line, _)| line).collect::<Vec<usize>>());
+
+    let rejected: Vec<(usize, &str)> = checked.rejected.iter().map(|r| (r.line, &r.error[..])).collect();
+    assert_eq!(vec![(3, "1234567803122 failed the Luhn-10 test."),
+                    (4, "The project you are looking for does not exist. Go make it!"),
+                    (5, "User 'Johnnyboy' has already backed project 'GoGo_Applesauce'."),
+                    (6, "Credit card '1234567803121' for 'GoGo_Applesauce' is already on line 2."),
+                    (7, "Amounts must be greater than 0 dollars."),
+                    (8, "The project's campaign has already ended."),
+                    (9, "Credit card '341468752760899' has already been used to back project 'GoGo_Applesauce'."),
+                    (10, "Expected 4 columns, but found 2.")],
+               rejected);
+
+    let rejects = import::rejects_csv(&checked);
+    assert!(rejects.starts_with("User,Project,Card,Amount,error\n"));
+    assert!(rejects.contains("Jill,Exquisite_Banana,\"Expected 4 columns, but found 2.\"\n"));
+
+    match client.check_import(&admin, Kind::Pledges, "user,project,amount\n", &Mapping::default()) {
+        Err(Error::InvalidData(validate::Error::MissingColumn(ref column))) => assert_eq!("card", column),
+        other => panic!("expected a missing column, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn import_projects() {
+    let client = init_client();
+    let admin = init_admin(&client);
+    let before = Event::list(&client, None).unwrap().len();
+
+    let csv = "Title,Target,Creator\nAttack_Owl,55000,Amalia\nBanana_Bread,100.004,\n";
+    let mapping = Mapping::parse(Kind::Projects, "name=Title,goal=Target").unwrap();
+    let checked = client.check_import(&admin, Kind::Projects, csv, &mapping).unwrap();
+    assert!(checked.rejected.is_empty());
+    assert_eq!(2, client.import(&admin, &checked).unwrap());
+
+    let owl = Project::get(&client, "Attack_Owl").unwrap();
+    let bread = Project::get(&client, "Banana_Bread").unwrap();
+    assert_eq!(100f64, bread.goal);
+    assert_eq!(Some(User::get(&client, "Amalia").unwrap().user_id), owl.creator_id);
+    assert_eq!(None, bread.creator_id);
+
+    // The same events are recorded as for projects created one at a time.
+    let events = Event::list(&client, None).unwrap();
+    let kinds: Vec<&str> = events[before..].iter().map(|e| &e.kind[..]).collect();
+    assert_eq!(vec!["UserCreated", "ProjectCreated", "ProjectCreated"], kinds);
+
+    // Importing the same file again rejects every row.
+    let checked = client.check_import(&admin, Kind::Projects, csv, &mapping).unwrap();
+    assert_eq!(2, checked.rejected.len());
+}
+
+#[test]
+fn import_pledges() {
+    let (client, _) = init_test_projects();
+    let admin = init_admin(&client);
+    let before = Event::list(&client, None).unwrap().len();
+
+    let mut csv = String::from("user,project,card,amount\n");
+    for i in 0..NUM_PLEDGES {
+        csv.push_str(&format!("{},{},{},{}\n", USERS[i], NAMES[0], CARDS[i], 10 * (i + 1)));
+    }
+
+    let checked = client.check_import(&admin, Kind::Pledges, &csv, &Mapping::default()).unwrap();
+    assert_eq!(NUM_PLEDGES, client.import(&admin, &checked).unwrap());
+
+    let (pledges, _) = Project::list_pledges(&client, NAMES[0]).unwrap();
+    assert_eq!(NUM_PLEDGES, pledges.len());
+    for pledge in &pledges {
+        let i = USERS.iter().position(|u| *u == pledge.get_user().name).unwrap();
+        assert_eq!(CARDS[i], pledge.card);
+        assert_eq!(10f64 * (i + 1) as f64, pledge.amount);
+    }
+
+    let events = Event::list(&client, None).unwrap();
+    let events = &events[before..];
+    assert_eq!(NUM_PLEDGES, events.iter().filter(|e| e.kind == "UserCreated").count());
+    assert_eq!(NUM_PLEDGES, events.iter().filter(|e| e.kind == "PledgeMade").count());
+    for event in events {
+        assert!(event.domain_event().is_ok());
+    }
+}
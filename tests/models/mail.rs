@@ -0,0 +1,206 @@
+use ::{init_client, init_test_projects, NAMES, GOALS, USERS, CARDS, CONTRIBUTIONS, NUM_PLEDGES};
+
+use kickstarter::{validate, Client, Error, Result};
+use kickstarter::db::table;
+use kickstarter::mail::{self, Kind, Maildir, Transport};
+use kickstarter::models::{Mail, MailPreference, Pledge, Project, User};
+use kickstarter::testing::Ephemeral;
+
+use rustorm::query::Query;
+
+use std::env;
+use std::fs;
+
+const FROM: &'static str = "noreply@example.com";
+const CREATOR: &'static str = "Ilya";
+
+/// A transport that keeps every message it's asked to send.
+struct Outbox {
+    sent: Vec<String>,
+}
+
+impl Transport for Outbox {
+    fn send(&mut self, from: &str, mail: &Mail) -> Result<()> {
+        self.sent.push(mail.to_message(from));
+        Ok(())
+    }
+}
+
+/// Returns all queued mail, oldest first.
+fn queued(client: &Client) -> Vec<Mail> {
+    let mut mail: Vec<Mail> = Query::select_all()
+        .from_table(&client.table(table::mail))
+        .collect(client.db())
+        .unwrap();
+
+    mail.sort_by(|a, b| a.mail_id.cmp(&b.mail_id));
+    mail
+}
+
+/// Creates the first test project with a creator, and gives everyone an address.
+fn init_mail_project() -> Ephemeral {
+    let client = init_client();
+    let _ = Project::create_by(&client, Some(CREATOR), NAMES[0], GOALS[0]).unwrap();
+
+    for name in USERS.iter().chain(Some(&CREATOR)) {
+        let address = format!("{}@example.com", name.to_lowercase());
+        let _ = User::set_email(&client, name, Some(&address)).unwrap();
+    }
+
+    client
+}
+
+#[test]
+fn pledge_queues_receipt_and_notice() {
+    let client = init_mail_project();
+    let _ = Pledge::create(&client, USERS[0], NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+
+    let mail = queued(&client);
+    assert_eq!(2, mail.len());
+
+    assert_eq!(Kind::Receipt.as_str(), mail[0].kind);
+    assert_eq!("johnnyboy@example.com", mail[0].recipient);
+    assert!(mail[0].body_text.contains(NAMES[0]));
+    assert!(mail[0].body_text.contains("100.00"));
+
+    assert_eq!(Kind::NewBacker.as_str(), mail[1].kind);
+    assert_eq!("ilya@example.com", mail[1].recipient);
+    assert!(mail[1].body_text.contains(USERS[0]));
+
+    assert!(mail.iter().all(|m| m.status == "pending" && !m.subject.is_empty()));
+}
+
+#[test]
+fn no_mail_without_address() {
+    let (client, _) = init_test_projects();
+    let _ = Pledge::create(&client, USERS[0], NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+    assert!(queued(&client).is_empty());
+}
+
+#[test]
+fn muted_kind_is_skipped() {
+    let client = init_mail_project();
+    let _ = MailPreference::set(&client, CREATOR, Kind::NewBacker, false).unwrap();
+    let _ = Pledge::create(&client, USERS[0], NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+
+    let mail = queued(&client);
+    assert_eq!(1, mail.len());
+    assert_eq!(Kind::Receipt.as_str(), mail[0].kind);
+
+    // Turning it back on resumes the notices.
+    let _ = MailPreference::set(&client, CREATOR, Kind::NewBacker, true).unwrap();
+    let _ = Pledge::create(&client, USERS[1], NAMES[0], CARDS[1], CONTRIBUTIONS[1]).unwrap();
+    assert_eq!(3, queued(&client).len());
+}
+
+#[test]
+fn unsubscribe_by_token() {
+    let client = init_mail_project();
+    let user = User::get(&client, USERS[0]).unwrap();
+
+    // Tokens are 16 random bytes, in hex.
+    assert_eq!(32, user.unsubscribe_token.len());
+    assert!(user.unsubscribe_token.chars().all(|c| c.is_digit(16)));
+
+    let unsubscribed = MailPreference::unsubscribe(&client, &user.unsubscribe_token, None).unwrap();
+    assert_eq!(user.user_id, unsubscribed.user_id);
+
+    for kind in Kind::all() {
+        assert!(!MailPreference::enabled(&client, user.user_id, kind).unwrap());
+    }
+
+    let _ = Pledge::create(&client, USERS[0], NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+    assert!(queued(&client).iter().all(|m| m.user_id != user.user_id));
+
+    let result = MailPreference::unsubscribe(&client, "not-a-token", None);
+    match result {
+        Err(Error::InvalidData(validate::Error::InvalidToken)) => (),
+        _ => panic!(result),
+    }
+}
+
+#[test]
+fn close_sends_outcome() {
+    let client = init_mail_project();
+    for i in 0..NUM_PLEDGES {
+        let _ = Pledge::create(&client, USERS[i], NAMES[0], CARDS[i], CONTRIBUTIONS[i]).unwrap();
+    }
+
+    let (project, total) = Project::close(&client, NAMES[0]).unwrap();
+    assert!(project.date_closed.is_some());
+    assert_eq!(3300f64, total);
+
+    let outcomes: Vec<Mail> = queued(&client).into_iter()
+        .filter(|m| m.kind == Kind::CampaignFailed.as_str())
+        .collect();
+    assert_eq!(NUM_PLEDGES, outcomes.len());
+
+    let result = Project::close(&client, NAMES[0]);
+    match result {
+        Err(Error::InvalidData(validate::Error::ProjectClosed)) => (),
+        _ => panic!(result),
+    }
+
+    let result = Pledge::create(&client, "Jorge", NAMES[0], "4298708533045499", 10f64);
+    match result {
+        Err(Error::InvalidData(validate::Error::ProjectClosed)) => (),
+        _ => panic!(result),
+    }
+}
+
+#[test]
+fn send_pending_mail() {
+    let client = init_mail_project();
+    let _ = Pledge::create(&client, USERS[0], NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+
+    let mut outbox = Outbox { sent: vec![] };
+    let sent = Mail::send_pending(&client, &mut outbox, FROM).unwrap();
+    assert_eq!(2, sent.len());
+    assert!(sent.iter().all(|m| m.status == "sent" && m.attempts == 1 && m.date_sent.is_some()));
+
+    let message = &outbox.sent[0];
+    assert!(message.contains("From: noreply@example.com\r\n"));
+    assert!(message.contains("To: johnnyboy@example.com\r\n"));
+    assert!(message.contains("Content-Type: text/plain"));
+    assert!(message.contains("Content-Type: text/html"));
+
+    // Nothing is left to send.
+    assert!(Mail::send_pending(&client, &mut outbox, FROM).unwrap().is_empty());
+    assert_eq!(2, outbox.sent.len());
+}
+
+#[test]
+fn maildir_transport() {
+    let client = init_mail_project();
+    let _ = Pledge::create(&client, USERS[0], NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+
+    let path = env::temp_dir().join("ksr_test_maildir");
+    let _ = fs::remove_dir_all(&path);
+
+    let mut maildir = Maildir::new(path.to_str().unwrap());
+    let _ = Mail::send_pending(&client, &mut maildir, FROM).unwrap();
+
+    assert_eq!(2, fs::read_dir(path.join("new")).unwrap().count());
+    assert_eq!(0, fs::read_dir(path.join("tmp")).unwrap().count());
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn render_escapes_html() {
+    let vars = [("project", "<b>Tom & Jerry</b>".to_owned())];
+    assert_eq!("Back <b>Tom & Jerry</b>", mail::render("Back {{project}}", &vars, false));
+    assert_eq!("Back &lt;b&gt;Tom &amp; Jerry&lt;/b&gt;", mail::render("Back {{project}}", &vars, true));
+}
+
+#[test]
+fn parse_kind() {
+    for kind in Kind::all() {
+        assert_eq!(kind, kind.as_str().parse::<Kind>().unwrap());
+    }
+
+    let result = "newsletter".parse::<Kind>();
+    match result {
+        Err(validate::Error::InvalidMailKind(_)) => (),
+        _ => panic!(result),
+    }
+}
@@ -1,4 +1,27 @@
+mod account;
+mod api;
+mod audit;
+mod bench;
+mod comment;
+mod config;
+mod drift;
+mod event;
+mod fulfillment;
+mod golden;
+mod graphql;
+mod import;
+mod mail;
+mod notify;
+mod output;
 mod pledge;
 mod project;
+mod refund;
+mod repl;
+mod reward;
+mod role;
+mod rpc;
+mod seed;
+mod snapshot;
 mod user;
 mod validate;
+mod webhook;
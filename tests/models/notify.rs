@@ -0,0 +1,54 @@
+use ::{init_client, NAMES, GOALS, USERS, CARDS, CONTRIBUTIONS};
+
+use kickstarter::models::{Pledge, Project, Refund};
+use kickstarter::notify::Notice;
+use kickstarter::refund::Reason;
+
+use std::time::Duration;
+
+#[test]
+fn subscribe_to_changes() {
+    let client = init_client();
+    let subscription = client.subscribe().unwrap();
+
+    let _ = Project::create(&client, NAMES[0], GOALS[0]).unwrap();
+    let _ = Pledge::create(&client, USERS[0], NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+    let _ = Refund::refund(&client, USERS[0], NAMES[0], Some(40f64), Reason::Requested).unwrap();
+    let _ = Pledge::cancel(&client, USERS[0], NAMES[0]).unwrap();
+
+    let mut notices = vec![];
+    for _ in 0..4 {
+        notices.push(subscription.wait(Duration::from_secs(5)).unwrap().unwrap());
+    }
+
+    let project = NAMES[0].to_owned();
+    let user = USERS[0].to_owned();
+
+    assert_eq!(vec![
+        Notice::ProjectCreated { project: project.clone(), goal: GOALS[0] },
+        Notice::PledgeCreated { project: project.clone(), user: user.clone(), amount: CONTRIBUTIONS[0] },
+        Notice::PledgeRefunded { project: project.clone(), user: user.clone(), amount: 40f64 },
+        Notice::PledgeCancelled { project: project.clone(), user: user.clone(), amount: CONTRIBUTIONS[0] - 40f64 },
+    ], notices);
+
+    let total = notices.iter().fold(0f64, |total, n| total + n.funding_change());
+    assert_eq!(0f64, total);
+}
+
+#[test]
+fn no_notices_without_changes() {
+    let client = init_client();
+    let subscription = client.subscribe().unwrap();
+    assert!(subscription.wait(Duration::from_millis(100)).is_none());
+}
+
+#[test]
+fn decode_notice() {
+    let notice = Notice::decode("{\"event\": \"PledgeCreated\", \"project\": \"Alt_Party_Owl\", \
+                                 \"user\": \"Margie\", \"amount\": 25.5}").unwrap();
+
+    assert_eq!(Notice::PledgeCreated { project: "Alt_Party_Owl".to_owned(),
+                                       user: "Margie".to_owned(), amount: 25.5 }, notice);
+    assert_eq!("Alt_Party_Owl", notice.project());
+    assert!(Notice::decode("{\"event\": \"ProjectExploded\"}").is_err());
+}
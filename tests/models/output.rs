@@ -0,0 +1,94 @@
+use ::{init_test_projects, init_test_pledges, NAMES, GOALS, USERS, NUM_PLEDGES};
+
+use kickstarter::{output, validate, Error};
+use kickstarter::output::{Format, Table};
+
+use rustc_serialize::json::Json;
+
+#[test]
+fn currency() {
+    assert_eq!("$0.00", output::currency(0f64));
+    assert_eq!("$1.50", output::currency(1.5f64));
+    assert_eq!("$999.99", output::currency(999.99f64));
+    assert_eq!("$1,000.00", output::currency(1000f64));
+    assert_eq!("$250,000.00", output::currency(250000f64));
+    assert_eq!("-$1,234,567.89", output::currency(-1234567.89f64));
+}
+
+#[test]
+fn parse_format() {
+    assert_eq!(Format::Text, "text".parse().unwrap());
+    assert_eq!(Format::Table, "table".parse().unwrap());
+    assert_eq!(Format::Json, "json".parse().unwrap());
+    assert_eq!(Format::Csv, "csv".parse().unwrap());
+    assert_eq!(Format::Tsv, "tsv".parse().unwrap());
+    assert_eq!(Err(validate::Error::InvalidFormat("xml".to_owned())), "xml".parse::<Format>());
+}
+
+#[test]
+fn render_table() {
+    let json = Json::from_str("[{\"name\": \"Banana\", \"goal\": 1.0, \"note\": \"a, \\\"b\\\"\\tc\"},
+                                {\"name\": \"Applesauce\", \"goal\": 250000.0, \"id\": 7}]").unwrap();
+    let table = Table::new(&["name", "goal", "note"], &json);
+    assert_eq!(2, table.rows.len());
+    assert_eq!(Json::Null, table.rows[1][2]);
+
+    assert_eq!("NAME        GOAL         NOTE\n\
+                Banana            $1.00  a, \"b\"\tc\n\
+                Applesauce  $250,000.00\n",
+               table.render(Format::Table));
+
+    assert_eq!("name,goal,note\n\
+                Banana,1.00,\"a, \"\"b\"\"\tc\"\n\
+                Applesauce,250000.00,\n",
+               table.render(Format::Csv));
+
+    assert_eq!("name\tgoal\tnote\n\
+                Banana\t1.00\ta, \"b\" c\n\
+                Applesauce\t250000.00\t\n",
+               table.render(Format::Tsv));
+
+    // JSON keeps every member, not just the table's columns.
+    let rendered = output::render(Format::Json, &json, &table);
+    assert_eq!(json, Json::from_str(&rendered).unwrap());
+}
+
+#[test]
+fn bench_table() {
+    let json = Json::from_str("[{\"operation\": \"back\", \"count\": 1200, \"throughput\": 1523.456,
+                                 \"p50_ms\": 2.5, \"p99_ms\": 41.25}]").unwrap();
+    let table = Table::new(&["operation", "count", "throughput", "p50_ms", "p99_ms"], &json);
+
+    // Throughputs and latencies aren't amounts of money.
+    assert_eq!("OPERATION  COUNT  THROUGHPUT  P50_MS  P99_MS\n\
+                back        1200     1523.46    2.50   41.25\n",
+               table.render(Format::Table));
+}
+
+#[test]
+fn backers_table() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+    let (pledges, goal) = client.list_backers(NAMES[0]).unwrap();
+
+    let json = output::backers_json(NAMES[0], &pledges, goal);
+    assert_eq!(GOALS[0], json["goal"].as_f64().unwrap());
+    assert_eq!(Some(false), json["funded"].as_boolean());
+
+    let table = Table::new(&["user", "amount"], &json["pledges"]);
+    assert_eq!(NUM_PLEDGES, table.rows.len());
+    assert!(table.render(Format::Csv).lines().any(|line| line == format!("{},100.00", USERS[0])));
+}
+
+#[test]
+fn exit_codes() {
+    let code = |err: validate::Error| output::exit_code(&Error::InvalidData(err));
+    assert_eq!(output::EXIT_INVALID, code(validate::Error::InvalidAmount));
+    assert_eq!(output::EXIT_INVALID, code(validate::Error::InvalidFormat("xml".to_owned())));
+    assert_eq!(output::EXIT_NOT_FOUND, code(validate::Error::ProjectDoesNotExist));
+    assert_eq!(output::EXIT_CONFLICT, code(validate::Error::CardExists));
+    assert_eq!(output::EXIT_DENIED, code(validate::Error::NotAuthenticated));
+    assert_eq!(output::EXIT_DENIED, code(validate::Error::NotModerator));
+    assert_eq!(output::EXIT_FAILURE, output::exit_code(&Error::Config("missing".to_owned())));
+    assert_eq!(output::EXIT_RETRY, code(validate::Error::ConcurrentChange));
+}
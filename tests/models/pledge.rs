@@ -1,7 +1,7 @@
 use ::{init_test_projects, init_test_pledges, 
        NAMES, USERS, CARDS, CONTRIBUTIONS, NUM_PLEDGES};
 
-use kickstarter::Error;
+use kickstarter::{validate, Error};
 use kickstarter::models::Pledge;
 use kickstarter::db::table;
 
@@ -54,3 +54,18 @@ fn pledge_once_card() {
         assert_eq!(Some(SqlState::UniqueViolation), err.code);
     }    
 }
+
+#[test]
+fn cancel_pledge() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+
+    let pledge = Pledge::cancel(&client, USERS[0], NAMES[0]).unwrap();
+    assert_eq!(CARDS[0], pledge.card);
+
+    let result = Pledge::cancel(&client, USERS[0], NAMES[0]);
+    match result {
+        Err(Error::InvalidData(validate::Error::PledgeDoesNotExist)) => (),
+        _ => panic!(result),
+    }
+}
@@ -0,0 +1,111 @@
+use ::{init_test_projects, init_test_pledges, NAMES, USERS, CARDS, CONTRIBUTIONS};
+
+use kickstarter::{validate, Error};
+use kickstarter::models::{Pledge, Project, Refund};
+use kickstarter::refund::Reason;
+
+#[test]
+fn partial_refund() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+
+    let refund = Refund::refund(&client, USERS[0], NAMES[0], Some(40f64), Reason::Requested).unwrap();
+    assert_eq!(40f64, refund.amount);
+    assert_eq!("refund", refund.kind);
+    assert_eq!("requested", refund.reason);
+
+    let (pledges, _) = Project::list_pledges(&client, NAMES[0]).unwrap();
+    let pledge = pledges.iter().find(|p| p.get_user().name == USERS[0]).unwrap();
+    assert_eq!(40f64, pledge.refunded);
+    assert_eq!(CONTRIBUTIONS[0] - 40f64, pledge.net_amount());
+}
+
+#[test]
+fn full_refund() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+
+    let refund = Refund::refund(&client, USERS[1], NAMES[0], None, Reason::Duplicate).unwrap();
+    assert_eq!(CONTRIBUTIONS[1], refund.amount);
+
+    // Nothing is left to refund.
+    let result = Refund::refund(&client, USERS[1], NAMES[0], None, Reason::Duplicate);
+    match result {
+        Err(Error::InvalidData(validate::Error::ExceedsPledge)) => (),
+        _ => panic!(result),
+    }
+}
+
+#[test]
+fn refund_remainder_in_cents() {
+    let (client, _) = init_test_projects();
+    let _ = Pledge::create(&client, USERS[0], NAMES[1], CARDS[0], 0.30f64).unwrap();
+
+    let _ = Refund::refund(&client, USERS[0], NAMES[1], Some(0.10f64), Reason::Requested).unwrap();
+    let rest = Refund::refund(&client, USERS[0], NAMES[1], Some(0.20f64), Reason::Requested).unwrap();
+    assert_eq!(0.20f64, rest.amount);
+
+    // 0.10 + 0.20 is stored as 0.30, not 0.30000000000000004, so the pledge is exactly used up.
+    let (pledges, _) = Project::list_pledges(&client, NAMES[1]).unwrap();
+    assert_eq!(0.30f64, pledges[0].refunded);
+    assert_eq!(0f64, pledges[0].net_amount());
+
+    let result = Refund::refund(&client, USERS[0], NAMES[1], None, Reason::Requested);
+    match result {
+        Err(Error::InvalidData(validate::Error::ExceedsPledge)) => (),
+        _ => panic!(result),
+    }
+}
+
+#[test]
+fn refund_exceeds_pledge() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+
+    let result = Refund::refund(&client, USERS[0], NAMES[0], Some(CONTRIBUTIONS[0] + 1f64), Reason::Other);
+    match result {
+        Err(Error::InvalidData(validate::Error::ExceedsPledge)) => (),
+        _ => panic!(result),
+    }
+
+    // The failed refund must not leave a record behind.
+    assert!(Refund::list(&client, NAMES[0]).unwrap().is_empty());
+}
+
+#[test]
+fn refund_missing_pledge() {
+    let (client, _) = init_test_projects();
+    let result = Refund::refund(&client, USERS[0], NAMES[1], None, Reason::Requested);
+
+    match result {
+        Err(Error::InvalidData(validate::Error::PledgeDoesNotExist)) => (),
+        _ => panic!(result),
+    }
+}
+
+#[test]
+fn chargeback_by_card() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+
+    let chargeback = Refund::chargeback(&client, NAMES[0], CARDS[2], Some(500f64),
+                                        Reason::Disputed, Some("DSP-1001")).unwrap();
+    assert_eq!("chargeback", chargeback.kind);
+    assert_eq!(Some("DSP-1001".to_owned()), chargeback.reference);
+    assert_eq!(USERS[2], chargeback.get_user().name);
+
+    let _ = Refund::refund(&client, USERS[2], NAMES[0], Some(100f64), Reason::Requested).unwrap();
+
+    let refunds = Refund::list(&client, NAMES[0]).unwrap();
+    assert_eq!(2, refunds.len());
+    assert_eq!(USERS[2], refunds[0].get_user().name);
+    assert_eq!(500f64, refunds[0].amount);
+    assert_eq!(100f64, refunds[1].amount);
+}
+
+#[test]
+fn parse_reason() {
+    assert_eq!(Reason::Fraudulent, "fraudulent".parse::<Reason>().unwrap());
+    assert_eq!(validate::Error::InvalidReason("bored".to_owned()),
+               "bored".parse::<Reason>().unwrap_err());
+}
@@ -0,0 +1,136 @@
+use ::{init_test_projects, init_test_pledges, NAMES, USERS};
+
+use kickstarter::{repl, validate};
+use rustyline::completion::Completer;
+
+use std::cell::RefCell;
+use std::env;
+use std::fs::{self, File};
+use std::io::Read;
+use std::rc::Rc;
+
+const USAGE: &'static str = "
+Usage:
+    ksr list    <name>                [--config=<cfile>] [--format=<f>]
+    ksr back    <name> <card> <amount> [--token=<t>] [--config=<cfile>]
+    ksr backer  <user>                [--config=<cfile>]
+    ksr (-h | --help)
+
+Options:
+    -h --help          Show this message
+    --config=<cfile>   The .toml configuration file
+    --token=<t>        The API token
+    --format=<f>       Print results as text, table, json,
+                       csv, or tsv
+
+Commands:
+    list       List all pledges towards a project
+    back       Back a project
+    backer     List all pledges that a backer has made
+";
+
+#[test]
+fn split_words() {
+    let split = |line: &str| repl::split(line).unwrap();
+    assert_eq!(vec!["back", "GoGo", "4111", "25"], split("  back\tGoGo   4111 25 "));
+    assert_eq!(vec!["comment", "1", "Can't wait!"], split("comment 1 \"Can't wait!\""));
+    assert_eq!(vec!["a b", "c\"d", "e\\f", "g h"], split("'a b' \"c\\\"d\" 'e\\f' g\\ h"));
+    assert_eq!(vec!["", "xy"], split("'' x'y'"));
+    assert!(split("").is_empty());
+
+    // Comments start at a word, not within one.
+    assert_eq!(vec!["reply", "1", "issue#4", "#5"], split("reply 1 issue#4 '#5' # not part of it"));
+    assert!(split("   # just a comment").is_empty());
+
+    assert_eq!(Err(validate::Error::UnclosedQuote), repl::split("comment 1 \"oops"));
+    assert_eq!(Err(validate::Error::UnclosedQuote), repl::split("comment 1 'oops"));
+}
+
+#[test]
+fn unscriptable_commands() {
+    let words = |line: &str| repl::split(line).unwrap();
+    assert!(repl::scriptable(&words("back GoGo 4111111111111111 25")));
+    assert!(repl::scriptable(&words("# nothing to run")));
+
+    for line in &["replay ksr_replay --until=2016-01-01", "restore snapshot.json", "import projects p.csv",
+                  "seed --projects=5", "bench ksr_bench", "run script.ksr", "serve", "watch", "rpc"] {
+        assert!(!repl::scriptable(&words(line)), "{}", line);
+    }
+}
+
+#[test]
+fn usage_commands_and_help() {
+    assert_eq!(vec!["list", "back", "backer"], repl::commands(USAGE));
+    assert_eq!(vec!["--help", "--config=", "--token=", "--format="], repl::options(USAGE));
+
+    let help = repl::help(USAGE, "list").unwrap();
+    assert!(help.contains("    list    <name>"));
+    assert!(help.contains("List all pledges towards a project"));
+    assert!(help.contains("--format=<f>"));
+    assert!(help.contains("csv, or tsv"));
+    assert!(!help.contains("--token"));
+    assert!(!help.contains("backer"));
+
+    assert_eq!(None, repl::help(USAGE, "bogus"));
+}
+
+#[test]
+fn complete_names() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+
+    let names = repl::names(&client).unwrap();
+    assert!(names.iter().any(|n| n == NAMES[0]));
+    assert!(names.iter().any(|n| n == USERS[0]));
+
+    let completer = repl::Completer::new(USAGE, Rc::new(RefCell::new(names)));
+    assert_eq!((0, vec!["back".to_owned(), "backer".to_owned()]), completer.complete("ba", 2).unwrap());
+    assert_eq!((5, vec!["back".to_owned(), "backer".to_owned()]), completer.complete("help ba", 7).unwrap());
+    assert_eq!((5, vec![NAMES[0].to_owned()]), completer.complete("list Go", 7).unwrap());
+    assert_eq!((10, vec!["--token=".to_owned()]), completer.complete("list GoGo --to", 14).unwrap());
+}
+
+#[test]
+fn quote_round_trips() {
+    let words = ["plain", "two words", "it's", "\"quoted\"", "back\\slash", "#5", "issue#5", ""];
+    for word in &words {
+        assert_eq!(vec![word.to_string()], repl::split(&repl::quote(word)).unwrap());
+    }
+    assert_eq!("plain", repl::quote("plain"));
+    assert_eq!("'it'\\''s'", repl::quote("it's"));
+}
+
+#[test]
+fn record_session() {
+    let path = env::temp_dir().join("ksr_record_session.ksr");
+    let _ = fs::remove_file(&path);
+    let words = |line: &str| repl::split(line).unwrap();
+
+    {
+        let mut recorder = repl::Recorder::open(path.to_str().unwrap(), false, true).unwrap();
+        recorder.record(&words("login Amalia"), None).unwrap();
+        recorder.record(&words("comment Attack_Owl \"Can't wait\" --token=abc"), None).unwrap();
+        recorder.record(&words("back Attack_Owl 4111 25 --token abc --format=json"), Some("Bad card.\nTry again.")).unwrap();
+    }
+
+    // Without outcomes, failures are left out; recordings append.
+    {
+        let mut recorder = repl::Recorder::open(path.to_str().unwrap(), false, false).unwrap();
+        recorder.record(&words("listall"), Some("Nope.")).unwrap();
+        recorder.record(&words("listall"), None).unwrap();
+    }
+
+    let mut script = String::new();
+    File::open(&path).unwrap().read_to_string(&mut script).unwrap();
+    assert_eq!("login Amalia\n\
+                comment Attack_Owl 'Can'\\''t wait'\n\
+                # failed: back Attack_Owl 4111 25 --format=json\n\
+                #   Bad card. Try again.\n\
+                listall\n", script);
+
+    // Every recorded line replays as it was typed, and failures are only comments.
+    let replayed: Vec<Vec<String>> = script.lines().map(&words).filter(|w| !w.is_empty()).collect();
+    assert_eq!(vec![words("login Amalia"), words("comment Attack_Owl \"Can't wait\""), words("listall")], replayed);
+
+    let _ = fs::remove_file(&path);
+}
@@ -0,0 +1,86 @@
+use ::{init_admin, init_test_projects, NAMES, PASSWORD};
+
+use kickstarter::{validate, Error};
+use kickstarter::models::{Project, Reward};
+
+#[test]
+fn add_rewards() {
+    let (client, _) = init_test_projects();
+    let admin = init_admin(&client);
+
+    let _ = client.add_reward(&admin, NAMES[0], "Signed print", 250f64).unwrap();
+    let reward = client.add_reward(&admin, NAMES[0], "Sticker pack", 10f64).unwrap();
+    assert_eq!("Sticker pack", reward.title);
+    assert_eq!(10f64, reward.minimum);
+
+    // Tiers are listed cheapest first.
+    let rewards = client.list_rewards(NAMES[0]).unwrap();
+    let titles: Vec<&str> = rewards.iter().map(|r| &r.title[..]).collect();
+    assert_eq!(vec!["Sticker pack", "Signed print"], titles);
+    assert!(client.list_rewards(NAMES[1]).unwrap().is_empty());
+}
+
+#[test]
+fn duplicate_title() {
+    let (client, _) = init_test_projects();
+    let admin = init_admin(&client);
+    let _ = client.add_reward(&admin, NAMES[0], "Sticker pack", 10f64).unwrap();
+
+    let result = client.add_reward(&admin, NAMES[0], "Sticker pack", 20f64);
+    match result {
+        Err(Error::InvalidData(validate::Error::RewardExists)) => (),
+        _ => panic!(result),
+    }
+
+    // Another project can offer the same title.
+    let _ = client.add_reward(&admin, NAMES[1], "Sticker pack", 10f64).unwrap();
+}
+
+#[test]
+fn invalid_rewards() {
+    let (client, _) = init_test_projects();
+    let admin = init_admin(&client);
+
+    assert!(client.add_reward(&admin, NAMES[0], "", 10f64).is_err());
+    assert!(client.add_reward(&admin, NAMES[0], "Two\nlines", 10f64).is_err());
+    assert!(client.add_reward(&admin, NAMES[0], "Nothing", 0f64).is_err());
+    assert!(client.add_reward(&admin, "No_Such_Project", "Sticker pack", 10f64).is_err());
+
+    let _ = Project::close(&client, NAMES[0]).unwrap();
+    let result = client.add_reward(&admin, NAMES[0], "Sticker pack", 10f64);
+    match result {
+        Err(Error::InvalidData(validate::Error::ProjectClosed)) => (),
+        _ => panic!(result),
+    }
+}
+
+#[test]
+fn reward_requires_creator() {
+    let (client, _) = init_test_projects();
+    let _ = init_admin(&client);
+    let _ = client.register("Nosy_Parker", "nosy@example.com", PASSWORD).unwrap();
+    let (backer, _) = client.login("Nosy_Parker", PASSWORD).unwrap();
+
+    let result = client.add_reward(&backer, NAMES[0], "Sticker pack", 10f64);
+    match result {
+        Err(Error::PermissionDenied(_)) => (),
+        _ => panic!("expected the reward to be denied"),
+    }
+}
+
+#[test]
+fn tiers() {
+    let (client, _) = init_test_projects();
+    let admin = init_admin(&client);
+    let _ = client.add_reward(&admin, NAMES[0], "Sticker pack", 10f64).unwrap();
+    let _ = client.add_reward(&admin, NAMES[0], "Signed print", 250f64).unwrap();
+    let rewards = client.list_rewards(NAMES[0]).unwrap();
+
+    let tier = |amount| Reward::tier(&rewards, amount).map(|r| &r.title[..]);
+    assert_eq!(None, tier(9.99f64));
+    assert_eq!(Some("Sticker pack"), tier(10f64));
+    assert_eq!(Some("Sticker pack"), tier(249.99f64));
+    assert_eq!(Some("Signed print"), tier(1000f64));
+    // What's left after refunds counts to the cent.
+    assert_eq!(Some("Sticker pack"), tier(10.30f64 - 0.30f64));
+}
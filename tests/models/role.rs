@@ -0,0 +1,191 @@
+use ::{init_admin, init_client, ADMIN, NAMES, GOALS, CARDS, CONTRIBUTIONS, PASSWORD};
+
+use kickstarter::{validate, Client, Error};
+use kickstarter::account::{Principal, Role};
+use kickstarter::audit::Filter;
+use kickstarter::event::{self, DomainEvent};
+use kickstarter::models::{Event, User};
+
+/// Registers and logs in a user with the provided role.
+fn init_user(client: &Client, admin: &Principal, user: &str, role: Role) -> Principal {
+    let email = format!("{}@example.com", user.to_lowercase());
+    let _ = client.register(user, &email, PASSWORD).unwrap();
+    let _ = client.set_role(admin, user, role).unwrap();
+    let (principal, _) = client.login(user, PASSWORD).unwrap();
+    principal
+}
+
+#[test]
+fn parse_role() {
+    for role in &[Role::Backer, Role::Creator, Role::Moderator, Role::Admin] {
+        assert_eq!(*role, role.as_str().parse::<Role>().unwrap());
+    }
+
+    let result = "owner".parse::<Role>();
+    match result {
+        Err(validate::Error::InvalidRole(_)) => (),
+        _ => panic!(result),
+    }
+}
+
+#[test]
+fn roles_are_ordered() {
+    let client = init_client();
+    let admin = init_admin(&client);
+    let moderator = init_user(&client, &admin, "Moddy", Role::Moderator);
+
+    assert!(moderator.is_moderator());
+    assert!(!moderator.is_admin());
+    assert!(moderator.require(Role::Creator).is_ok());
+
+    let result = moderator.require(Role::Admin);
+    match result {
+        Err(Error::PermissionDenied(_)) => (),
+        _ => panic!(result),
+    }
+}
+
+#[test]
+fn set_role() {
+    let client = init_client();
+    let admin = init_admin(&client);
+    let backer = init_user(&client, &admin, "Margie", Role::Backer);
+
+    let result = client.set_role(&backer, "Margie", Role::Admin);
+    match result {
+        Err(Error::PermissionDenied(_)) => (),
+        _ => panic!(result),
+    }
+
+    let user = client.set_role(&admin, "Margie", Role::Creator).unwrap();
+    assert_eq!(Role::Creator, user.role());
+    assert_eq!(Role::Creator, client.login("Margie", PASSWORD).unwrap().0.role());
+
+    let expected = DomainEvent::RoleChanged(event::RoleChanged {
+        user_id: user.user_id,
+        role: Role::Creator.as_str().to_owned(),
+    });
+    let events = Event::list(&client, None).unwrap();
+    assert_eq!(expected, events.last().unwrap().domain_event().unwrap());
+}
+
+#[test]
+fn only_creators_create_projects() {
+    let client = init_client();
+    let admin = init_admin(&client);
+    let backer = init_user(&client, &admin, "Margie", Role::Backer);
+    let creator = init_user(&client, &admin, "Ilya", Role::Creator);
+
+    let result = client.create_project(&backer, NAMES[0], GOALS[0]);
+    match result {
+        Err(Error::PermissionDenied(_)) => (),
+        _ => panic!(result),
+    }
+
+    let project = client.create_project(&creator, NAMES[0], GOALS[0]).unwrap();
+    assert_eq!(Some(creator.user_id()), project.creator_id);
+}
+
+#[test]
+fn only_owner_or_admin_manages_project() {
+    let client = init_client();
+    let admin = init_admin(&client);
+    let creator = init_user(&client, &admin, "Ilya", Role::Creator);
+    let rival = init_user(&client, &admin, "Rival", Role::Creator);
+    let backer = init_user(&client, &admin, "Margie", Role::Backer);
+
+    let _ = client.create_project(&creator, NAMES[0], GOALS[0]).unwrap();
+    let _ = client.create_project(&creator, NAMES[1], GOALS[1]).unwrap();
+    let _ = client.back_project(&backer, NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+
+    let result = client.list_refunds(&rival, NAMES[0]);
+    match result {
+        Err(Error::PermissionDenied(_)) => (),
+        _ => panic!(result),
+    }
+
+    let result = client.close_project(&backer, NAMES[0]);
+    match result {
+        Err(Error::PermissionDenied(_)) => (),
+        _ => panic!(result),
+    }
+
+    assert!(client.list_refunds(&creator, NAMES[0]).unwrap().is_empty());
+    assert!(client.close_project(&creator, NAMES[0]).is_ok());
+    assert!(client.close_project(&admin, NAMES[1]).is_ok());
+}
+
+#[test]
+fn moderators_restrict_any_project() {
+    let client = init_client();
+    let admin = init_admin(&client);
+    let creator = init_user(&client, &admin, "Ilya", Role::Creator);
+    let moderator = init_user(&client, &admin, "Moddy", Role::Moderator);
+    let backer = init_user(&client, &admin, "Margie", Role::Backer);
+
+    let _ = client.create_project(&creator, NAMES[0], GOALS[0]).unwrap();
+    let comment = client.post_comment(&backer, NAMES[0], "Ship it!").unwrap();
+
+    let result = client.restrict_comments(&backer, NAMES[0], true);
+    match result {
+        Err(Error::PermissionDenied(_)) => (),
+        _ => panic!(result),
+    }
+
+    let result = client.hide_comment(&creator, comment.comment_id, true);
+    match result {
+        Err(Error::PermissionDenied(_)) => (),
+        _ => panic!(result),
+    }
+
+    assert!(client.restrict_comments(&moderator, NAMES[0], true).unwrap().comments_backers_only);
+    assert!(client.hide_comment(&moderator, comment.comment_id, true).unwrap().hidden);
+}
+
+#[test]
+fn admin_only_operations() {
+    let client = init_client();
+    let admin = init_admin(&client);
+    let moderator = init_user(&client, &admin, "Moddy", Role::Moderator);
+
+    let results = vec![
+        client.audit_log(&moderator, &Filter::default()).map(|_| ()),
+        client.list_webhooks(&moderator).map(|_| ()),
+        client.deliver_webhooks(&moderator).map(|_| ()),
+        client.send_mail(&moderator).map(|_| ()),
+        client.replay(&moderator, "ksr_test_replay", None).map(|_| ()),
+        client.sync(&moderator),
+        client.build(Some(&moderator), false),
+    ];
+
+    for result in results {
+        match result {
+            Err(Error::PermissionDenied(_)) => (),
+            _ => panic!(result),
+        }
+    }
+
+    assert!(client.list_webhooks(&admin).unwrap().is_empty());
+}
+
+#[test]
+fn build_requires_admin_once_built() {
+    let client = init_client();
+    let _ = init_admin(&client);
+    let (admin, _) = client.login(ADMIN, PASSWORD).unwrap();
+
+    // Rebuilding as the admin wipes every account, but the schema still has its tables.
+    client.build(Some(&admin), false).unwrap();
+    assert!(!User::has_admin(&client).unwrap());
+
+    let result = client.build(None, false);
+    match result {
+        Err(Error::PermissionDenied(_)) => (),
+        _ => panic!(result),
+    }
+
+    // Only a schema without tables can be built without a principal.
+    let drop = format!("DROP SCHEMA {} CASCADE", client.schema);
+    client.db().execute_sql(&drop, &vec![]).unwrap();
+    client.build(None, false).unwrap();
+}
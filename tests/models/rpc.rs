@@ -0,0 +1,139 @@
+use ::{init_admin, init_client, init_test_projects, init_test_pledges, DEFAULT_CONFIG,
+        ADMIN, PASSWORD, NAMES, CARDS, NUM_PLEDGES, USERS};
+
+use kickstarter::rpc;
+use kickstarter::rpc::Session;
+
+use rustc_serialize::json::Json;
+
+/// Sends a line to the session and returns every message written in reply.
+fn send(session: &mut Session, line: &str) -> Vec<Json> {
+    let mut messages = vec![];
+    session.handle(line, &mut |message: Json| { messages.push(message); Ok(()) }).unwrap();
+    messages
+}
+
+/// Calls a method and returns its single response.
+fn call(session: &mut Session, method: &str, params: &str) -> Json {
+    let line = format!("{{\"jsonrpc\": \"2.0\", \"id\": 7, \"method\": \"{}\", \"params\": {}}}", method, params);
+    let mut messages = send(session, &line);
+    assert_eq!(1, messages.len());
+    let response = messages.pop().unwrap();
+    assert_eq!(Some(&Json::U64(7)), response.find("id"));
+    response
+}
+
+/// Returns the error code of a response.
+fn code(response: &Json) -> i64 {
+    response.find_path(&["error", "code"]).and_then(|c| c.as_i64()).unwrap()
+}
+
+/// Returns the result of a successful response.
+fn result(response: &Json) -> &Json {
+    response.find("result").unwrap_or_else(|| panic!("Expected a result: {}", response))
+}
+
+#[test]
+fn malformed_requests() {
+    let client = init_client();
+    let mut session = Session::new(&client, DEFAULT_CONFIG, None);
+
+    assert_eq!(rpc::PARSE_ERROR, code(&send(&mut session, "{\"jsonrpc\"")[0]));
+    assert_eq!(rpc::INVALID_REQUEST, code(&send(&mut session, "{\"id\": 1, \"method\": \"listall\"}")[0]));
+    assert_eq!(rpc::INVALID_REQUEST, code(&send(&mut session, "[]")[0]));
+    assert_eq!(rpc::METHOD_NOT_FOUND, code(&call(&mut session, "fly", "{}")));
+    assert_eq!(rpc::INVALID_PARAMS, code(&call(&mut session, "list", "[\"GoGo_Applesauce\"]")));
+    assert_eq!(rpc::INVALID_PARAMS, code(&call(&mut session, "list", "{}")));
+
+    // Notifications are never answered.
+    assert!(send(&mut session, "{\"jsonrpc\": \"2.0\", \"method\": \"listall\"}").is_empty());
+}
+
+#[test]
+fn list_pledges() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+    let mut session = Session::new(&client, DEFAULT_CONFIG, None);
+
+    let response = call(&mut session, "list", &format!("{{\"name\": \"{}\"}}", NAMES[0]));
+    let list = result(&response);
+    assert_eq!(Some(3300f64), list.find("pledged").and_then(|p| p.as_f64()));
+    assert_eq!(NUM_PLEDGES, list.find("pledges").unwrap().as_array().unwrap().len());
+    assert!(!list.to_string().contains(CARDS[0]));
+
+    let response = call(&mut session, "backer", &format!("{{\"user\": \"{}\"}}", USERS[0]));
+    let pledges = result(&response).find("pledges").unwrap().as_array().unwrap();
+    assert_eq!(Some(NAMES[0]), pledges[0].find("project").and_then(|p| p.as_string()));
+
+    assert_eq!(404, code(&call(&mut session, "list", "{\"name\": \"Missing_Project\"}")));
+
+    let batch = format!("[{{\"jsonrpc\": \"2.0\", \"id\": 1, \"method\": \"listall\"}}, \
+                         {{\"jsonrpc\": \"2.0\", \"method\": \"listall\"}}, \
+                         {{\"jsonrpc\": \"2.0\", \"id\": 2, \"method\": \"backer\", \"params\": {{\"user\": \"{}\"}}}}]",
+                        USERS[1]);
+    let messages = send(&mut session, &batch);
+    assert_eq!(1, messages.len());
+    assert_eq!(2, messages[0].as_array().unwrap().len());
+}
+
+#[test]
+fn login_acts_for_the_session() {
+    let client = init_client();
+    let _ = init_admin(&client);
+    let mut session = Session::new(&client, DEFAULT_CONFIG, None);
+
+    assert_eq!(401, code(&call(&mut session, "whoami", "{}")));
+
+    let params = format!("{{\"user\": \"{}\", \"password\": \"{}\"}}", ADMIN, PASSWORD);
+    let response = call(&mut session, "login", &params);
+    let token = result(&response).find("token").and_then(|t| t.as_string()).unwrap().to_owned();
+
+    let response = call(&mut session, "whoami", "{}");
+    assert_eq!(Some("admin"), result(&response).find("role").and_then(|r| r.as_string()));
+
+    let params = format!("{{\"name\": \"{}\", \"amount\": 500}}", NAMES[0]);
+    assert!(call(&mut session, "project", &params).find("result").is_some());
+    assert_eq!(409, code(&call(&mut session, "project", &params)));
+
+    let params = format!("{{\"name\": \"{}\", \"body\": \"First!\"}}", NAMES[0]);
+    let response = call(&mut session, "comment", &params);
+    let id = result(&response).find("comment_id").and_then(|c| c.as_i64()).unwrap();
+
+    let response = call(&mut session, "hide", &format!("{{\"id\": {}}}", id));
+    assert!(!result(&response).to_string().contains("First!"));
+
+    call(&mut session, "logout", "{}");
+    assert!(client.authenticate(&token).is_err());
+    assert_eq!(401, code(&call(&mut session, "whoami", "{}")));
+}
+
+#[test]
+fn file_commands() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+    let _ = init_admin(&client);
+    let mut session = Session::new(&client, DEFAULT_CONFIG, None);
+    call(&mut session, "login", &format!("{{\"user\": \"{}\", \"password\": \"{}\"}}", ADMIN, PASSWORD));
+
+    let response = call(&mut session, "dump", "{}");
+    let script = result(&response).find("script").and_then(|s| s.as_string()).unwrap();
+    assert!(script.contains(&format!("register {}", USERS[0])));
+
+    // Rejected rows hold back the whole import unless they are skipped.
+    let csv = "name,goal\\nFresh_Project,100\\nx,1\\n";
+    let response = call(&mut session, "import", &format!("{{\"kind\": \"projects\", \"csv\": \"{}\"}}", csv));
+    assert_eq!(1, result(&response).find("rejected").and_then(|r| r.as_array()).unwrap().len());
+    assert_eq!(Some(0), result(&response).find("imported").and_then(|i| i.as_u64()));
+    let params = format!("{{\"kind\": \"projects\", \"csv\": \"{}\", \"skip_rejected\": true}}", csv);
+    assert_eq!(Some(1), result(&call(&mut session, "import", &params)).find("imported").and_then(|i| i.as_u64()));
+    assert_eq!(rpc::INVALID_PARAMS, code(&call(&mut session, "import", "{\"kind\": \"rewards\", \"csv\": \"\"}")));
+
+    let response = call(&mut session, "export-backers", &format!("{{\"name\": \"{}\"}}", NAMES[0]));
+    let backers = result(&response).as_array().unwrap();
+    assert_eq!(NUM_PLEDGES, backers.len());
+    assert!(!response.to_string().contains(CARDS[0]));
+
+    let response = call(&mut session, "schema check", "{}");
+    assert!(result(&response).find("differences").and_then(|d| d.as_array()).unwrap().is_empty());
+    assert!(result(&call(&mut session, "config check", "{}")).find("uri").is_some());
+}
@@ -0,0 +1,98 @@
+use ::{init_admin, init_client, PASSWORD};
+
+use kickstarter::{validate, Error};
+use kickstarter::audit::Filter;
+use kickstarter::db::table;
+use kickstarter::models::{Event, Project};
+use kickstarter::seed::{self, Options};
+
+use std::collections::HashSet;
+
+const OPTIONS: Options = Options { projects: 5, users: 40, pledges: 120, seed: 7 };
+
+#[test]
+fn generate_is_deterministic() {
+    let dataset = seed::generate(&OPTIONS).unwrap();
+    assert_eq!(dataset, seed::generate(&OPTIONS).unwrap());
+    assert!(dataset != seed::generate(&Options { seed: 8, ..OPTIONS }).unwrap());
+
+    assert_eq!(40, dataset.users.len());
+    assert_eq!(5, dataset.projects.len());
+    assert_eq!(120, dataset.pledges.len());
+}
+
+#[test]
+fn generate_valid_data() {
+    let dataset = seed::generate(&OPTIONS).unwrap();
+
+    let mut names = HashSet::new();
+    for &(ref name, ref card) in &dataset.users {
+        assert!(validate::length(name, 4, 20).is_ok());
+        assert!(validate::alphanumeric(name).is_ok());
+        assert!(validate::luhn10(card).is_ok());
+        assert!(names.insert(name.clone()));
+    }
+
+    for &(ref name, goal, _) in &dataset.projects {
+        assert!(validate::length(name, 4, 20).is_ok());
+        assert!(validate::alphanumeric(name).is_ok());
+        assert!(100f64 <= goal && goal <= 5000000f64);
+        assert!(names.insert(name.clone()));
+    }
+
+    let mut backed = HashSet::new();
+    for &(ref user, ref project, _, amount) in &dataset.pledges {
+        assert!(amount > 0f64);
+        assert!(backed.insert((user.clone(), project.clone())));
+    }
+
+    // Every pair can be asked for, but no more.
+    let small = Options { projects: 3, users: 4, pledges: 12, seed: 1 };
+    assert_eq!(12, seed::generate(&small).unwrap().pledges.len());
+    assert_eq!(Err(validate::Error::TooManyPledges(12)), seed::generate(&Options { pledges: 13, ..small }));
+}
+
+#[test]
+fn seed_database() {
+    let client = init_client();
+    let admin = init_admin(&client);
+    let before = Event::list(&client, None).unwrap().len();
+    let users = Filter { entity: Some(table::user.to_owned()), ..Filter::default() };
+    let audited = client.audit_log(&admin, &users).unwrap().len();
+
+    let dataset = client.seed(&admin, &OPTIONS).unwrap();
+
+    let projects = Project::list_all(&client).unwrap();
+    assert_eq!(5, projects.len());
+
+    let mut pledged = 0;
+    for &(ref name, goal, _) in &dataset.projects {
+        let (pledges, project_goal) = Project::list_pledges(&client, name).unwrap();
+        assert_eq!(goal, project_goal);
+        pledged += pledges.len();
+    }
+    assert_eq!(120, pledged);
+
+    let events = Event::list(&client, None).unwrap();
+    let events = &events[before..];
+    assert_eq!(40, events.iter().filter(|e| e.kind == "UserCreated").count());
+    assert_eq!(5, events.iter().filter(|e| e.kind == "ProjectCreated").count());
+    assert_eq!(120, events.iter().filter(|e| e.kind == "PledgeMade").count());
+
+    // Seeded users are audited like any other.
+    let entries = client.audit_log(&admin, &users).unwrap();
+    assert_eq!(40, entries.len() - audited);
+}
+
+#[test]
+fn seed_requires_admin() {
+    let client = init_client();
+    let _ = init_admin(&client);
+    let _ = client.register("Nosy_Parker", "nosy@example.com", PASSWORD).unwrap();
+    let (backer, _) = client.login("Nosy_Parker", PASSWORD).unwrap();
+
+    match client.seed(&backer, &OPTIONS) {
+        Err(Error::PermissionDenied(_)) => (),
+        _ => panic!("expected seeding to be denied"),
+    }
+}
@@ -0,0 +1,128 @@
+use ::{init_admin, init_test_projects, init_test_pledges, ADMIN, CARDS, NAMES, NUM_PROJECTS, NUM_PLEDGES,
+        PASSWORD, USERS};
+
+use kickstarter::{snapshot, validate, Client, Error};
+use kickstarter::models::{Pledge, Project, Refund};
+use kickstarter::refund::Reason;
+
+use rustc_serialize::json::{Json, ToJson};
+
+#[test]
+fn dump_snapshot() {
+    let (client, projects) = init_test_projects();
+    let _ = init_test_pledges(&client);
+    let admin = init_admin(&client);
+
+    let snapshot = client.dump(&admin).unwrap();
+    assert_eq!(Some(client.schema.as_ref()), snapshot.find("schema").and_then(|s| s.as_string()));
+
+    let rows = snapshot.find_path(&["tables", "project"]).and_then(|t| t.as_array()).unwrap();
+    assert_eq!(NUM_PROJECTS, rows.len());
+    for (row, project) in rows.iter().zip(projects.iter()) {
+        assert_eq!(Some(project.project_id as i64), row.find("project_id").and_then(|id| id.as_i64()));
+        assert_eq!(Some(&project.name[..]), row.find("name").and_then(|name| name.as_string()));
+    }
+
+    let pledges = snapshot.find_path(&["tables", "pledge"]).and_then(|t| t.as_array()).unwrap();
+    assert_eq!(NUM_PLEDGES, pledges.len());
+}
+
+#[test]
+fn dump_requires_admin() {
+    let (client, _) = init_test_projects();
+    let _ = init_admin(&client);
+    let _ = client.register(USERS[0], "johnnyboy@example.com", PASSWORD).unwrap();
+    let (backer, _) = client.login(USERS[0], PASSWORD).unwrap();
+
+    match client.dump(&backer) {
+        Err(Error::PermissionDenied(_)) => (),
+        other => panic!("expected permission to be denied, got {:?}", other.map(|_| ())),
+    }
+    assert!(client.dump_script(&backer).is_err());
+}
+
+#[test]
+fn restore_snapshot() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+    let _ = Refund::refund(&client, USERS[0], NAMES[0], Some(25f64), Reason::Requested).unwrap();
+    let admin = init_admin(&client);
+    let (_, token) = client.login(ADMIN, PASSWORD).unwrap();
+    let snapshot = client.dump(&admin).unwrap();
+
+    let restored = Client::new(&client.uri, &client.scratch_schema()).unwrap();
+    restored.build(None, false).unwrap();
+    let count = restored.restore(&snapshot).unwrap();
+    assert!(count >= NUM_PROJECTS + NUM_PLEDGES);
+
+    let projects = Project::list_all(&client).unwrap();
+    let restored_projects = Project::list_all(&restored).unwrap();
+    assert_eq!(projects.len(), restored_projects.len());
+    for (project, restored_project) in projects.iter().zip(restored_projects.iter()) {
+        assert_eq!(project.project_id, restored_project.project_id);
+        assert_eq!(project.name, restored_project.name);
+        assert_eq!(project.date_created, restored_project.date_created);
+    }
+
+    let (pledges, _) = Project::list_pledges(&client, NAMES[0]).unwrap();
+    let (restored_pledges, _) = Project::list_pledges(&restored, NAMES[0]).unwrap();
+    assert_eq!(pledges.len(), restored_pledges.len());
+    for (pledge, restored_pledge) in pledges.iter().zip(restored_pledges.iter()) {
+        assert_eq!(pledge.card, restored_pledge.card);
+        assert_eq!(pledge.net_amount(), restored_pledge.net_amount());
+        assert_eq!(pledge.date_created, restored_pledge.date_created);
+    }
+
+    // Accounts keep their passwords but not their tokens, and new rows continue after the restored IDs.
+    assert!(restored.authenticate(&token).is_err());
+    assert!(restored.login(admin.name(), PASSWORD).is_ok());
+    let project = Project::create(&restored, "Restored_Project", 10f64).unwrap();
+    assert_eq!(NUM_PROJECTS as i32 + 1, project.project_id);
+
+    // Restoring twice would duplicate every row.
+    match restored.restore(&snapshot) {
+        Err(Error::InvalidData(validate::Error::SchemaNotEmpty)) => (),
+        other => panic!("expected the schema to be rejected, got {:?}", other),
+    }
+
+    let mut newer = snapshot.clone();
+    if let Json::Object(ref mut map) = newer {
+        map.insert("version".to_owned(), (snapshot::VERSION + 1).to_json());
+    }
+    let empty = Client::new(&client.uri, &client.scratch_schema()).unwrap();
+    empty.build(None, false).unwrap();
+    assert!(empty.restore(&newer).is_err());
+}
+
+#[test]
+fn dump_script() {
+    let (client, _) = init_test_projects();
+    let _ = init_test_pledges(&client);
+    let _ = Refund::refund(&client, USERS[0], NAMES[0], Some(25f64), Reason::Requested).unwrap();
+    let _ = Pledge::create(&client, USERS[1], NAMES[1], CARDS[1], 5f64).unwrap();
+    let _ = Project::close(&client, NAMES[1]).unwrap();
+    let admin = init_admin(&client);
+
+    let script = client.dump_script(&admin).unwrap();
+    let lines: Vec<&str> = script.lines().filter(|l| !l.starts_with('#')).collect();
+    let position = |line: &str| lines.iter().position(|l| *l == line)
+        .unwrap_or_else(|| panic!("{:?} is not in the script:\n{}", line, script));
+
+    // The admin registers first, so that they administer the rebuilt schema.
+    assert_eq!(0, position("register Overseer overseer@example.com --admin"));
+    let johnnyboy = position("register Johnnyboy johnnyboy@example.invalid");
+
+    let project = position("project GoGo_Applesauce 250000.00");
+    let login = position("login Johnnyboy");
+    let back = position("back GoGo_Applesauce 341468752760899 100.00");
+    let refund = position("refund Johnnyboy GoGo_Applesauce 25.00 --reason=requested");
+    let second_back = position("back Exquisite_Banana 351149395124027 5.00");
+    let close = position("close Exquisite_Banana");
+
+    assert!(johnnyboy < project);
+    assert!(project < login && login < back && back < refund);
+    assert!(second_back < close);
+
+    // Accounts made without an email address have their placeholder cleared at the end.
+    assert_eq!(Some(&"email"), lines.last());
+}
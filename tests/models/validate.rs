@@ -1,4 +1,6 @@
-use kickstarter::validate;
+use kickstarter::validate::{self, Brand};
+
+use rand::{SeedableRng, XorShiftRng};
 
 #[test]
 #[should_panic]
@@ -107,6 +109,13 @@ fn length_low() {
     }
 }
 
+#[test]
+fn length_in_characters() {
+    // Ten characters, but twenty bytes.
+    assert!(validate::length("éééééééééé", 6, 10).is_ok());
+    assert!(validate::length("éééééééééééé", 6, 10).is_err());
+}
+
 #[test]
 fn length_high() {
     let invalid = vec![
@@ -169,3 +178,194 @@ fn luhn10_valid() {
         assert!(result.is_ok());
     }
 }
+
+#[test]
+fn luhn10_check_digit() {
+    assert_eq!(3, validate::luhn10_check_digit("7992739871").unwrap());
+    assert_eq!(9, validate::luhn10_check_digit("34146875276089").unwrap());
+    assert!(validate::luhn10_check_digit("4298 7085").is_err());
+}
+
+#[test]
+fn luhn10_card() {
+    let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+    for brand in Brand::all() {
+        for _ in 0..50 {
+            let card = validate::luhn10_card(&mut rng, *brand);
+            assert!(validate::luhn10(&card).is_ok());
+            assert_eq!(Some(*brand), Brand::of(&card));
+        }
+    }
+
+    assert_eq!(Some(Brand::Amex), Brand::of("341468752760899"));
+    assert_eq!(Some(Brand::Discover), Brand::of("6011168468345649"));
+    assert_eq!(None, Brand::of("351149395124027"));
+}
+
+#[test]
+fn not_blank_blank() {
+    let invalid = vec![
+        "",
+        " ",
+        "\t\n  ",
+    ];
+
+    for s in &invalid {
+        let result = validate::not_blank(s);
+        assert!(result.is_err());
+    }
+}
+
+#[test]
+fn not_blank_valid() {
+    let result = validate::not_blank("  hello  ");
+    assert!(result.is_ok());
+}
+
+#[test]
+fn printable_control_chars() {
+    let invalid = vec![
+        "bell\x07",
+        "\x1b[31mred",
+        "null\0byte",
+    ];
+
+    for s in &invalid {
+        let result = validate::printable(s);
+        assert!(result.is_err());
+    }
+}
+
+#[test]
+fn printable_valid() {
+    let valid = vec![
+        "plain text",
+        "multiple\nlines\tand tabs",
+        "ünïcödé ✓",
+    ];
+
+    for s in &valid {
+        let result = validate::printable(s);
+        assert!(result.is_ok());
+    }
+}
+
+#[test]
+fn single_line() {
+    assert!(validate::single_line("12 Mill Lane, Bristol BS1 4DJ, UK").is_ok());
+    assert!(validate::single_line("ünïcödé ✓").is_ok());
+
+    for s in &["two\nlines", "a\ttab", "carriage\rreturn", "bell\x07"] {
+        assert!(validate::single_line(s).is_err());
+    }
+}
+
+#[test]
+fn timestamp_invalid() {
+    let invalid = vec![
+        "",
+        "yesterday",
+        "2015-13-01",
+        "2015-10-01T12:00:00",
+        "2015-10-01 25:00:00",
+    ];
+
+    for s in &invalid {
+        let result = validate::timestamp(s);
+        assert!(result.is_err());
+    }
+}
+
+#[test]
+fn timestamp_valid() {
+    let date = validate::timestamp("2015-10-01").unwrap();
+    assert_eq!("2015-10-01 00:00:00", date.format("%Y-%m-%d %H:%M:%S").to_string());
+
+    let datetime = validate::timestamp("2015-10-01 13:37:00").unwrap();
+    assert_eq!("2015-10-01 13:37:00", datetime.format("%Y-%m-%d %H:%M:%S").to_string());
+}
+
+#[test]
+fn url_invalid() {
+    let invalid = vec![
+        "",
+        "localhost:8080/hooks",
+        "ftp://example.com/hooks",
+        "http://",
+        "http:// example.com",
+    ];
+
+    for s in &invalid {
+        let result = validate::url(s);
+        assert_eq!(Err(validate::Error::InvalidUrl(s.to_string())), result);
+    }
+}
+
+#[test]
+fn url_valid() {
+    let valid = vec![
+        "http://127.0.0.1:8080/hooks",
+        "https://example.com",
+        "https://example.com/hooks?token=abc",
+    ];
+
+    for s in &valid {
+        let result = validate::url(s);
+        assert!(result.is_ok());
+    }
+}
+
+#[test]
+fn email_invalid() {
+    let invalid = vec![
+        "",
+        "margie",
+        "margie@",
+        "margie@localhost",
+        "mar gie@example.com",
+        "margie@@example.com",
+    ];
+
+    for s in &invalid {
+        let result = validate::email(s);
+        assert_eq!(Err(validate::Error::InvalidEmail(s.to_string())), result);
+    }
+}
+
+#[test]
+fn email_valid() {
+    let valid = vec![
+        "margie@example.com",
+        "shakey.graves+ksr@mail.example.org",
+    ];
+
+    for s in &valid {
+        let result = validate::email(s);
+        assert!(result.is_ok());
+    }
+}
+
+#[test]
+fn password_invalid() {
+    let long = "a".repeat(73);
+    let invalid = vec!["", "hunter2", &long[..]];
+
+    for s in &invalid {
+        let result = validate::password(s);
+        assert_eq!(Err(validate::Error::InvalidPassword), result);
+    }
+}
+
+#[test]
+fn password_valid() {
+    let valid = vec![
+        "hunter22",
+        "correct horse battery staple",
+        "ünïcødé!",
+    ];
+
+    for s in &valid {
+        let result = validate::password(s);
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,265 @@
+use ::{init_test_projects, NAMES, USERS, CARDS, CONTRIBUTIONS};
+
+use kickstarter::{validate, Client, Error};
+use kickstarter::models::{Pledge, Webhook, WebhookDelivery};
+use kickstarter::webhook;
+
+use rustc_serialize::json::Json;
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SECRET: &'static str = "correct-horse-battery-staple";
+
+/// A request received by the test listener.
+struct Request {
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+/// Starts a local HTTP listener that answers the next `count` requests with the provided
+/// status line, and forwards each request it receives. Returns the listener's URL.
+fn listen(status: &'static str, count: usize) -> (String, Receiver<Request>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hooks", listener.local_addr().unwrap());
+    let (tx, rx) = channel();
+
+    thread::spawn(move || {
+        for stream in listener.incoming().take(count) {
+            let mut stream = stream.unwrap();
+            let mut headers = HashMap::new();
+            let mut body = String::new();
+
+            {
+                let mut reader = BufReader::new(&mut stream);
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+
+                    let mut parts = line.splitn(2, ':');
+                    let name = parts.next().unwrap().trim().to_lowercase();
+                    let value = parts.next().unwrap_or("").trim().to_owned();
+                    headers.insert(name, value);
+                }
+
+                let len = headers.get("content-length").map_or(0, |l| l.parse().unwrap());
+                reader.take(len).read_to_string(&mut body).unwrap();
+            }
+
+            let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+            stream.write_all(response.as_bytes()).unwrap();
+            let _ = tx.send(Request { headers: headers, body: body });
+        }
+    });
+
+    (url, rx)
+}
+
+#[test]
+fn deliver_signed_payload() {
+    let (client, _) = init_test_projects();
+    let (url, requests) = listen("200 OK", 1);
+
+    let hook = Webhook::register(&client, &url, SECRET, None).unwrap();
+    let _ = Pledge::create(&client, USERS[0], NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+
+    let deliveries = WebhookDelivery::deliver_pending(&client).unwrap();
+    assert_eq!(1, deliveries.len());
+    assert_eq!("delivered", deliveries[0].status);
+    assert_eq!(1, deliveries[0].attempts);
+    assert!(deliveries[0].date_delivered.is_some());
+
+    let request = requests.recv().unwrap();
+    assert_eq!(Some(&webhook::PLEDGE_CREATED.to_owned()), request.headers.get("x-kickstarter-event"));
+    assert_eq!(Some(&Webhook::sign(SECRET, &request.body)), request.headers.get("x-kickstarter-signature"));
+
+    let body = Json::from_str(&request.body).unwrap();
+    assert_eq!(Some(webhook::PLEDGE_CREATED), body.find("event").and_then(|e| e.as_string()));
+    assert_eq!(Some(CONTRIBUTIONS[0]), body.find_path(&["data", "amount"]).and_then(|a| a.as_f64()));
+    assert!(!request.body.contains(CARDS[0]));
+
+    // Nothing is left to deliver.
+    assert!(WebhookDelivery::deliver_pending(&client).unwrap().is_empty());
+
+    let log = WebhookDelivery::list(&client, hook.webhook_id).unwrap();
+    assert_eq!(1, log.len());
+    assert_eq!(Some(200), log[0].webhook_attempt[0].status_code);
+}
+
+#[test]
+fn project_scoped_webhook() {
+    let (client, _) = init_test_projects();
+    let (url, _) = listen("200 OK", 0);
+
+    let _ = Webhook::register(&client, &url, SECRET, Some(NAMES[2])).unwrap();
+    let _ = Pledge::create(&client, USERS[0], NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+
+    assert!(WebhookDelivery::deliver_pending(&client).unwrap().is_empty());
+}
+
+#[test]
+fn project_funded() {
+    let (client, _) = init_test_projects();
+    let (url, _) = listen("200 OK", 0);
+
+    // The second project's goal is a single dollar.
+    let hook = Webhook::register(&client, &url, SECRET, Some(NAMES[1])).unwrap();
+    let _ = Pledge::create(&client, USERS[0], NAMES[1], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+    let _ = Pledge::create(&client, USERS[1], NAMES[1], CARDS[1], CONTRIBUTIONS[1]).unwrap();
+
+    let events: Vec<String> = WebhookDelivery::list(&client, hook.webhook_id).unwrap()
+        .into_iter().map(|d| d.event).collect();
+
+    assert_eq!(vec![webhook::PLEDGE_CREATED, webhook::PROJECT_FUNDED, webhook::PLEDGE_CREATED], events);
+}
+
+#[test]
+fn retry_failed_delivery() {
+    let (client, _) = init_test_projects();
+    let (url, _) = listen("500 Internal Server Error", 1);
+
+    let hook = Webhook::register(&client, &url, SECRET, None).unwrap();
+    let _ = Pledge::create(&client, USERS[0], NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+
+    let deliveries = WebhookDelivery::deliver_pending(&client).unwrap();
+    assert_eq!(1, deliveries.len());
+    assert_eq!("pending", deliveries[0].status);
+    assert_eq!(1, deliveries[0].attempts);
+    assert!(deliveries[0].next_attempt > deliveries[0].date_created);
+
+    // The retry isn't due yet.
+    assert!(WebhookDelivery::deliver_pending(&client).unwrap().is_empty());
+
+    let log = WebhookDelivery::list(&client, hook.webhook_id).unwrap();
+    let attempt = &log[0].webhook_attempt[0];
+    assert_eq!(Some(500), attempt.status_code);
+    assert!(attempt.error.is_some());
+}
+
+#[test]
+fn unreachable_webhook() {
+    let (client, _) = init_test_projects();
+
+    // Bind and release a port, so that nothing is listening on it.
+    let url = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}/hooks", listener.local_addr().unwrap())
+    };
+
+    let hook = Webhook::register(&client, &url, SECRET, None).unwrap();
+    let _ = Pledge::create(&client, USERS[0], NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+    let _ = WebhookDelivery::deliver_pending(&client).unwrap();
+
+    let log = WebhookDelivery::list(&client, hook.webhook_id).unwrap();
+    let attempt = &log[0].webhook_attempt[0];
+    assert_eq!(None, attempt.status_code);
+    assert!(attempt.error.is_some());
+}
+
+#[test]
+fn skip_claimed_delivery() {
+    let (client, _) = init_test_projects();
+    let (url, rx) = listen("200 OK", 1);
+    let _ = Webhook::register(&client, &url, SECRET, None).unwrap();
+    let _ = Pledge::create(&client, USERS[0], NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+
+    // Another run holds the delivery while it claims it, so this one leaves it alone.
+    let other = Client::new(&client.uri, &client.schema).unwrap();
+    let sql = format!("SELECT * FROM {} FOR UPDATE", other.table("webhook_delivery"));
+    other.db().execute_sql("BEGIN", &vec![]).unwrap();
+    other.db().execute_sql_with_return(&sql, &vec![]).unwrap();
+    assert!(WebhookDelivery::deliver_pending(&client).unwrap().is_empty());
+    other.db().execute_sql("ROLLBACK", &vec![]).unwrap();
+
+    let deliveries = WebhookDelivery::deliver_pending(&client).unwrap();
+    assert_eq!(1, deliveries.len());
+    assert_eq!("delivered", deliveries[0].status);
+    assert!(rx.recv().is_ok());
+    assert!(WebhookDelivery::deliver_pending(&client).unwrap().is_empty());
+}
+
+#[test]
+fn silent_receiver_times_out() {
+    let (client, _) = init_test_projects();
+
+    // Accept the connection, but never answer.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hooks", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        let _stream = listener.accept().unwrap();
+        thread::sleep(Duration::from_secs(60));
+    });
+
+    let hook = Webhook::register(&client, &url, SECRET, None).unwrap();
+    let _ = Pledge::create(&client, USERS[0], NAMES[0], CARDS[0], CONTRIBUTIONS[0]).unwrap();
+
+    let started = Instant::now();
+    let deliveries = WebhookDelivery::deliver_pending(&client).unwrap();
+    assert!(started.elapsed() < Duration::from_secs(30));
+    assert_eq!("pending", deliveries[0].status);
+
+    let log = WebhookDelivery::list(&client, hook.webhook_id).unwrap();
+    assert_eq!(None, log[0].webhook_attempt[0].status_code);
+}
+
+#[test]
+fn backoff_doubles() {
+    assert_eq!(30, WebhookDelivery::backoff(1));
+    assert_eq!(60, WebhookDelivery::backoff(2));
+    assert_eq!(120, WebhookDelivery::backoff(3));
+}
+
+#[test]
+fn sign_known_digest() {
+    // RFC 4231, test case 2.
+    assert_eq!("sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+               Webhook::sign("Jefe", "what do ya want for nothing?"));
+}
+
+#[test]
+fn register_invalid() {
+    let (client, _) = init_test_projects();
+
+    let result = Webhook::register(&client, "localhost/hooks", SECRET, None);
+    match result {
+        Err(Error::InvalidData(validate::Error::InvalidUrl(_))) => (),
+        _ => panic!(result),
+    }
+
+    let result = Webhook::register(&client, "http://localhost/hooks", "short", None);
+    match result {
+        Err(Error::InvalidData(validate::Error::Length(..))) => (),
+        _ => panic!(result),
+    }
+
+    let result = Webhook::register(&client, "http://localhost/hooks", SECRET, Some("Nonexistent_Project"));
+    match result {
+        Err(Error::InvalidData(validate::Error::ProjectDoesNotExist)) => (),
+        _ => panic!(result),
+    }
+}
+
+#[test]
+fn remove_webhook() {
+    let (client, _) = init_test_projects();
+    let hook = Webhook::register(&client, "http://localhost/hooks", SECRET, None).unwrap();
+
+    let _ = Webhook::remove(&client, hook.webhook_id).unwrap();
+    assert!(Webhook::list(&client).unwrap().is_empty());
+
+    let result = Webhook::remove(&client, hook.webhook_id);
+    match result {
+        Err(Error::InvalidData(validate::Error::WebhookDoesNotExist)) => (),
+        _ => panic!(result),
+    }
+}